@@ -0,0 +1,81 @@
+use std::convert::TryInto;
+
+use meilisearch_core::{DocumentId, Number, RankedMap};
+use meilisearch_schema::FieldId;
+use ordered_float::OrderedFloat;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+/// The number of bytes of an entry of the hand-rolled encoding,
+/// the document id, the field id and the value as an `f64`.
+const ENTRY_LEN: usize = 4 + 2 + 8;
+
+fn entries(count: usize, attributes: u16) -> Vec<(DocumentId, FieldId, f64)> {
+    (0..count)
+        .map(|i| {
+            let document = DocumentId((i / attributes as usize) as u32);
+            let field = FieldId((i % attributes as usize) as u16);
+            (document, field, (i as f64) * 0.5)
+        })
+        .collect()
+}
+
+fn ranked_map(entries: &[(DocumentId, FieldId, f64)]) -> RankedMap {
+    let mut ranked_map = RankedMap::default();
+    for (document, field, value) in entries {
+        ranked_map.insert(*document, *field, Number::Float(OrderedFloat(*value)));
+    }
+    ranked_map
+}
+
+fn bincode_round_trip(ranked_map: &RankedMap) -> RankedMap {
+    let mut bytes = Vec::new();
+    ranked_map.write_to_bin(&mut bytes).unwrap();
+    RankedMap::read_from_bin(bytes.as_slice()).unwrap()
+}
+
+/// Encodes the entries as a flat array of fixed size entries, the values are all
+/// stored as `f64`, and decodes them back into a `RankedMap`.
+fn f64_array_round_trip(entries: &[(DocumentId, FieldId, f64)]) -> RankedMap {
+    let mut bytes = Vec::with_capacity(entries.len() * ENTRY_LEN);
+    for (document, field, value) in entries {
+        bytes.extend_from_slice(&document.0.to_be_bytes());
+        bytes.extend_from_slice(&field.0.to_be_bytes());
+        bytes.extend_from_slice(&value.to_bits().to_be_bytes());
+    }
+
+    let mut ranked_map = RankedMap::default();
+    for entry in bytes.chunks_exact(ENTRY_LEN) {
+        let document = DocumentId(u32::from_be_bytes(entry[..4].try_into().unwrap()));
+        let field = FieldId(u16::from_be_bytes(entry[4..6].try_into().unwrap()));
+        let value = f64::from_bits(u64::from_be_bytes(entry[6..].try_into().unwrap()));
+        ranked_map.insert(document, field, Number::Float(OrderedFloat(value)));
+    }
+    ranked_map
+}
+
+pub fn ranked_map_round_trip(c: &mut Criterion) {
+    let mut group = c.benchmark_group("ranked_map_round_trip");
+    group.sample_size(10);
+
+    for &count in &[100_000, 1_000_000, 10_000_000] {
+        for &attributes in &[1, 4, 16] {
+            let entries = entries(count, attributes);
+            let ranked_map = ranked_map(&entries);
+            let parameter = format!("{}-entries/{}-attributes", count, attributes);
+
+            group.throughput(Throughput::Elements(count as u64));
+            group.bench_with_input(BenchmarkId::new("bincode", &parameter), &ranked_map, |b, ranked_map| {
+                b.iter(|| bincode_round_trip(ranked_map))
+            });
+            group.bench_with_input(BenchmarkId::new("f64_array", &parameter), &entries, |b, entries| {
+                b.iter(|| f64_array_round_trip(entries))
+            });
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, ranked_map_round_trip);
+criterion_main!(benches);
@@ -0,0 +1,87 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::mpsc;
+
+use meilisearch_core::settings::Settings;
+use meilisearch_core::{Database, DatabaseOptions, ProcessedUpdateResult, UpdateStatus};
+use meilisearch_schema::Schema;
+use serde_json::json;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+const NUMBER_OF_DOCUMENTS: usize = 20_000;
+
+/// Creates an index where "new" is in all the documents and the multi-word synonyms of "ny"
+/// are made of "new" and of words that are not indexed, they can't match any document.
+fn prepare_database(path: &Path) -> Database {
+    let database = Database::open_or_create(path, DatabaseOptions::default()).unwrap();
+    let db = &database;
+
+    let (sender, receiver) = mpsc::sync_channel(100);
+    let update_fn = move |_name: &str, update: ProcessedUpdateResult| {
+        sender.send(update.update_id).unwrap()
+    };
+    let index = database.create_index("bench").unwrap();
+
+    database.set_update_callback(Box::new(update_fn));
+
+    let mut writer = db.main_write_txn().unwrap();
+    index.main.put_schema(&mut writer, &Schema::with_primary_key("id")).unwrap();
+    writer.commit().unwrap();
+
+    let mut synonyms = BTreeMap::new();
+    let alternatives = vec!["new york", "new york city", "new amsterdam", "new yorker"];
+    synonyms.insert("ny".to_string(), alternatives.into_iter().map(String::from).collect());
+    let settings = Settings { synonyms: Some(Some(synonyms)), ..Settings::default() };
+
+    let mut update_writer = db.update_write_txn().unwrap();
+    index.settings_update(&mut update_writer, settings.into_update().unwrap()).unwrap();
+    update_writer.commit().unwrap();
+
+    let mut additions = index.documents_addition();
+    for id in 0..NUMBER_OF_DOCUMENTS {
+        let title = format!("new station {} line {}", id % 100, id % 7);
+        additions.update_document(json!({ "id": id, "title": title }));
+    }
+
+    let mut update_writer = db.update_write_txn().unwrap();
+    let update_id = additions.finalize(&mut update_writer).unwrap();
+    update_writer.commit().unwrap();
+
+    // block until the transaction is processed
+    let _ = receiver.into_iter().find(|id| *id == update_id);
+
+    let update_reader = db.update_read_txn().unwrap();
+    let result = index.update_status(&update_reader, update_id).unwrap();
+    match result {
+        Some(UpdateStatus::Processed { content }) => assert!(content.error.is_none()),
+        status => panic!("unexpected update status {:?}", status),
+    }
+
+    database
+}
+
+/// The queries of the synonyms with missing words are compared to
+/// the same queries without them, their groups should cost nothing.
+pub fn criterion_benchmark(c: &mut Criterion) {
+    let dir = tempfile::tempdir().unwrap();
+    let database = prepare_database(dir.path());
+
+    let reader = database.main_read_txn().unwrap();
+    let index = database.open_index("bench").unwrap();
+
+    let mut group = c.benchmark_group(format!("sparse synonyms ({} docs)", NUMBER_OF_DOCUMENTS));
+    group.sample_size(20);
+
+    for query in &["ny", "line", "ny line", "station ny"] {
+        let bench_name = BenchmarkId::from_parameter(format!("{:?}", query));
+        group.bench_with_input(bench_name, query, |b, query| b.iter(|| {
+            let builder = index.query_builder();
+            builder.query(&reader, query, 0..20).unwrap();
+        }));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -5,6 +5,10 @@ static LEVDIST0: OnceCell<LevBuilder> = OnceCell::new();
 static LEVDIST1: OnceCell<LevBuilder> = OnceCell::new();
 static LEVDIST2: OnceCell<LevBuilder> = OnceCell::new();
 
+/// The default number of characters below which the query words are not
+/// considered as prefixes, the shortest prefixes match nearly every word.
+pub const DEFAULT_MIN_PREFIX_LEN: usize = 3;
+
 #[derive(Copy, Clone)]
 enum PrefixSetting {
     Prefix,
@@ -39,7 +43,12 @@ fn build_dfa_with_setting(query: &str, setting: PrefixSetting) -> DFA {
     }
 }
 
-pub fn build_prefix_dfa(query: &str) -> DFA {
+/// Builds a DFA matching the words starting with the query, the queries shorter
+/// than `min_prefix_len` characters only match the words equal to them.
+pub fn build_prefix_dfa(query: &str, min_prefix_len: usize) -> DFA {
+    if query.chars().count() < min_prefix_len {
+        return build_dfa(query);
+    }
     build_dfa_with_setting(query, PrefixSetting::Prefix)
 }
 
@@ -47,6 +56,13 @@ pub fn build_dfa(query: &str) -> DFA {
     build_dfa_with_setting(query, PrefixSetting::NoPrefix)
 }
 
+/// Builds a DFA matching the words at most two typos away from the query,
+/// whatever its length, it is used to look for spelling corrections.
+pub fn build_correction_dfa(query: &str) -> DFA {
+    let builder = LEVDIST2.get_or_init(|| LevBuilder::new(2, true));
+    builder.build_dfa(query)
+}
+
 pub fn build_exact_dfa(query: &str) -> DFA {
     let builder = LEVDIST0.get_or_init(|| LevBuilder::new(0, true));
     builder.build_dfa(query)
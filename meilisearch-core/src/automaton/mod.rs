@@ -1,15 +1,80 @@
 mod dfa;
 
 use meilisearch_tokenizer::is_cjk;
+use serde::{Deserialize, Serialize};
+use unicode_normalization::UnicodeNormalization;
 
-pub use self::dfa::{build_dfa, build_prefix_dfa, build_exact_dfa};
+use crate::query_tree::split_weighted_query;
 
-pub fn normalize_str(string: &str) -> String {
-    let mut string = string.to_lowercase();
+pub use self::dfa::{build_dfa, build_prefix_dfa, build_exact_dfa, build_correction_dfa, DEFAULT_MIN_PREFIX_LEN};
+
+/// How the words are normalized once lowercased, before being indexed and searched.
+///
+/// The transliteration into ASCII is lossy, the words of the other scripts are better
+/// kept intact with one of the Unicode normalization forms, the new indexes use `NFKC`.
+/// The indexes without this setting, created before it existed, keep transliterating
+/// the words, they have been indexed this way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NormalizationMode {
+    #[serde(rename = "transliterate")]
+    Transliterate,
+    #[serde(rename = "NFC")]
+    Nfc,
+    #[serde(rename = "NFD")]
+    Nfd,
+    #[serde(rename = "NFKC")]
+    Nfkc,
+    #[serde(rename = "NFKD")]
+    Nfkd,
+}
+
+impl Default for NormalizationMode {
+    fn default() -> NormalizationMode {
+        NormalizationMode::Transliterate
+    }
+}
+
+impl NormalizationMode {
+    /// Lowercases the string and normalizes it according to the mode,
+    /// the strings containing CJK characters are never transliterated.
+    pub fn normalize(self, string: &str) -> String {
+        let string = string.to_lowercase();
 
-    if !string.contains(is_cjk) {
-        string = deunicode::deunicode_with_tofu(&string, "");
+        match self {
+            NormalizationMode::Transliterate if !string.contains(is_cjk) => {
+                deunicode::deunicode_with_tofu(&string, "")
+            }
+            _ => self.normalize_lowercased(string),
+        }
     }
 
-    string
+    /// Applies the Unicode normalization form of the mode to an already lowercased word,
+    /// the transliteration of the words is indexed besides the words themselves.
+    pub fn normalize_lowercased(self, word: String) -> String {
+        match self {
+            NormalizationMode::Transliterate => word,
+            NormalizationMode::Nfc => word.nfc().collect(),
+            NormalizationMode::Nfd => word.nfd().collect(),
+            NormalizationMode::Nfkc => word.nfkc().collect(),
+            NormalizationMode::Nfkd => word.nfkd().collect(),
+        }
+    }
+}
+
+/// Normalizes a string the same way the engine does for the indexed words and
+/// the query words of the indexes using the default [`NormalizationMode`]: the
+/// string is lowercased and, if it does not contain CJK characters, transliterated into ASCII.
+///
+/// [`NormalizationMode`]: enum.NormalizationMode.html
+pub fn normalize_str(string: &str) -> String {
+    NormalizationMode::Transliterate.normalize(string)
+}
+
+/// Splits a query into words and normalizes them with [`normalize_str`],
+/// this is the preprocessing applied to the query before searching it,
+/// the `^` boost suffixes of the words are removed.
+///
+/// [`normalize_str`]: fn.normalize_str.html
+pub fn normalize_query_string(query: &str) -> Vec<String> {
+    split_weighted_query(query).map(|(w, _)| normalize_str(w)).collect()
 }
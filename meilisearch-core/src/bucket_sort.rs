@@ -15,13 +15,13 @@ use sdset::{Set, SetBuf, exponential_search, SetOperation, Counter, duo::OpBuild
 use slice_group_by::{GroupBy, GroupByMut};
 
 use crate::error::Error;
-use crate::criterion::{Criteria, Context, ContextMut};
+use crate::criterion::{Criteria, Context, ContextMut, WordPrefixPair};
 use crate::distinct_map::{BufferedDistinctMap, DistinctMap};
 use crate::raw_document::RawDocument;
 use crate::{database::MainT, reordered_attrs::ReorderedAttrs};
-use crate::{store, Document, DocumentId, MResult};
-use crate::query_tree::{create_query_tree, traverse_query_tree};
-use crate::query_tree::{Operation, QueryResult, QueryKind, QueryId, PostingsKey};
+use crate::{store, Document, DocumentId, MResult, SearchCursor, Stemmer};
+use crate::query_tree::{create_query_tree, minimum_should_match_docids, traverse_query_tree, word_prefix_pair};
+use crate::query_tree::{AutomatonGenerationStats, Operation, QueryResult, QueryKind, QueryId, PostingsKey};
 use crate::query_tree::Context as QTContext;
 
 #[derive(Debug, Default)]
@@ -31,6 +31,23 @@ pub struct SortResult {
     pub exhaustive_nb_hit: bool,
     pub facets: Option<HashMap<String, HashMap<String, usize>>>,
     pub exhaustive_facet_count: Option<bool>,
+    /// The cursor of the last document returned, to be given to the next search.
+    pub next_cursor: Option<SearchCursor>,
+    /// What has been explored to create the query tree.
+    pub automaton_stats: AutomatonGenerationStats,
+    /// The number of groups of the query tree skipped because one of their words is not indexed.
+    pub skipped_groups: usize,
+}
+
+/// Moves the range after the documents already returned up to the cursor.
+fn range_after_cursor(range: Range<usize>, cursor: Option<SearchCursor>) -> Range<usize> {
+    let offset = cursor.map_or(0, |c| c.position as usize);
+    (range.start + offset)..(range.end + offset)
+}
+
+fn next_cursor(documents: &[Document], range: &Range<usize>) -> Option<SearchCursor> {
+    let position = (range.start + documents.len()) as u64;
+    documents.last().map(|document| SearchCursor::new(document.id, position))
 }
 
 pub fn bucket_sort<'c, FI>(
@@ -48,6 +65,14 @@ pub fn bucket_sort<'c, FI>(
     synonyms_store: store::Synonyms,
     prefix_documents_cache_store: store::PrefixDocumentsCache,
     prefix_postings_lists_cache_store: store::PrefixPostingsListsCache,
+    word_frequency_store: store::WordFrequency,
+    word_prefix_pair_store: Option<store::WordPrefixPairProximityDocIds>,
+    max_automaton_count: usize,
+    min_prefix_len: usize,
+    stemmer: Option<&dyn Stemmer>,
+    phrase_slop: usize,
+    minimum_should_match: Option<f32>,
+    search_after: Option<SearchCursor>,
 ) -> MResult<SortResult>
 where
     FI: Fn(DocumentId) -> bool,
@@ -74,6 +99,14 @@ where
             synonyms_store,
             prefix_documents_cache_store,
             prefix_postings_lists_cache_store,
+            word_frequency_store,
+            word_prefix_pair_store,
+            max_automaton_count,
+            min_prefix_len,
+            stemmer,
+            phrase_slop,
+            minimum_should_match,
+            search_after,
         );
     }
 
@@ -84,7 +117,10 @@ where
         None => return Ok(SortResult::default()),
     };
 
-    let stop_words = main_store.stop_words_fst(reader)?.unwrap_or_default();
+    let stop_words = main_store.all_stop_words_fst(reader)?;
+    let short_prefixes_stale = main_store.short_prefixes_stale(reader)?;
+    let shortest_cached_prefix_len = main_store.shortest_cached_prefix_len(reader)?;
+    let normalization_mode = main_store.normalization_mode(reader)?.unwrap_or_default();
 
     let context = QTContext {
         words_set,
@@ -92,9 +128,22 @@ where
         synonyms: synonyms_store,
         postings_lists: postings_lists_store,
         prefix_postings_lists: prefix_postings_lists_cache_store,
+        word_frequency: word_frequency_store,
+        max_automaton_count,
+        min_prefix_len,
+        short_prefixes_stale,
+        shortest_cached_prefix_len,
+        stemmer,
+        phrase_slop,
+        normalization_mode,
     };
 
-    let (operation, mapping) = create_query_tree(reader, &context, query)?;
+    let (operation, mapping, weights, automaton_stats) = create_query_tree(reader, &context, query)?;
+    let word_prefix_pair = match word_prefix_pair_store {
+        Some(store) => word_prefix_pair(&context, query).map(|(word, prefix)| (store, word, prefix)),
+        None => None,
+    };
+    result.automaton_stats = automaton_stats;
     debug!("operation:\n{:?}", operation);
     debug!("mapping:\n{:?}", mapping);
 
@@ -109,8 +158,15 @@ where
     let mut queries_kinds = HashMap::new();
     recurs_operation(&mut queries_kinds, &operation);
 
-    let QueryResult { mut docids, queries } = traverse_query_tree(reader, &context, &operation)?;
+    let QueryResult { mut docids, queries, skipped_groups } = traverse_query_tree(reader, &context, &operation)?;
+    result.skipped_groups = skipped_groups;
     debug!("found {} documents", docids.len());
+
+    if let Some(fraction) = minimum_should_match {
+        let relaxed = minimum_should_match_docids(&queries, &mapping, fraction);
+        docids = Cow::Owned(sdset::duo::OpBuilder::new(docids.as_ref(), relaxed.as_set()).union().into_set_buf());
+        debug!("found {} documents matching enough words", docids.len());
+    }
     debug!("number of postings {:?}", queries.len());
 
     if let Some(facets_docids) = facets_docids {
@@ -144,6 +200,13 @@ where
         before_raw_documents_building.elapsed(),
     );
 
+    // the search resumes after the document of the cursor, or at the position
+    // of the cursor when this document does not match the query anymore
+    let (cursor_document, sort_range) = match search_after {
+        Some(cursor) if raw_documents.iter().any(|d| d.id == cursor.document_id) => (Some(cursor.document_id), range.clone()),
+        cursor => (None, range_after_cursor(range.clone(), cursor)),
+    };
+
     let before_criterion_loop = Instant::now();
     let proximity_count = AtomicUsize::new(0);
 
@@ -152,6 +215,7 @@ where
     'criteria: for criterion in criteria.as_ref() {
         let tmp_groups = mem::replace(&mut groups, Vec::new());
         let mut documents_seen = 0;
+        let mut cursor_group_seen = cursor_document.is_none();
 
         for mut group in tmp_groups {
             let before_criterion_preparation = Instant::now();
@@ -160,7 +224,11 @@ where
                 reader,
                 postings_lists: &mut arena,
                 query_mapping: &mapping,
+                query_weights: &weights,
                 documents_fields_counts_store,
+                word_prefix_pair: word_prefix_pair.as_ref().map(|(store, word, prefix)| {
+                    WordPrefixPair { word, prefix: *prefix, store: *store }
+                }),
             };
 
             criterion.prepare(ctx, &mut group)?;
@@ -169,6 +237,7 @@ where
             let ctx = Context {
                 postings_lists: &arena,
                 query_mapping: &mapping,
+                query_weights: &weights,
             };
 
             let before_criterion_sort = Instant::now();
@@ -178,12 +247,22 @@ where
             for group in group.binary_group_by_mut(|a, b| criterion.eq(&ctx, a, b)) {
                 debug!("{:?} produced a group of size {}", criterion.name(), group.len());
 
+                // the groups ranked before the one of the cursor document are not sorted further,
+                // the documents of the cursor group are not counted as they can be before it
+                if !cursor_group_seen {
+                    if group.iter().any(|document| Some(document.id) == cursor_document) {
+                        cursor_group_seen = true;
+                        groups.push(group);
+                    }
+                    continue;
+                }
+
                 documents_seen += group.len();
                 groups.push(group);
 
                 // we have sort enough documents if the last document sorted is after
                 // the end of the requested range, we can continue to the next criterion
-                if documents_seen >= range.end {
+                if documents_seen >= sort_range.end {
                     continue 'criteria;
                 }
             }
@@ -193,13 +272,28 @@ where
     debug!("criterion loop took {:.02?}", before_criterion_loop.elapsed());
     debug!("proximity evaluation called {} times", proximity_count.load(Ordering::Relaxed));
 
+    let start = match cursor_document {
+        Some(id) => raw_documents.iter().position(|document| document.id == id).map_or(0, |pos| pos + 1),
+        None => 0,
+    };
+
     let schema = main_store.schema(reader)?.ok_or(Error::SchemaMissing)?;
-    let iter = raw_documents.into_iter().skip(range.start).take(range.len());
-    let iter = iter.map(|rd| Document::from_raw(rd, &queries_kinds, &arena, searchable_attrs.as_ref(), &schema));
-    let documents = iter.collect();
+    let iter = raw_documents.into_iter().skip(start + sort_range.start).take(sort_range.len());
+    let ctx = Context {
+        postings_lists: &arena,
+        query_mapping: &mapping,
+        query_weights: &weights,
+    };
+    let iter = iter.map(|rd| {
+        let ranking_score = criteria.ranking_score(&ctx, &rd);
+        let document = Document::from_raw(rd, &queries_kinds, &arena, searchable_attrs.as_ref(), &schema);
+        Document { ranking_score, ..document }
+    });
+    let documents: Vec<_> = iter.collect();
 
     debug!("bucket sort took {:.02?}", before_bucket_sort.elapsed());
 
+    result.next_cursor = next_cursor(&documents, &range_after_cursor(range, search_after));
     result.documents = documents;
     result.nb_hits = docids.len();
 
@@ -223,11 +317,22 @@ pub fn bucket_sort_with_distinct<'c, FI, FD>(
     synonyms_store: store::Synonyms,
     _prefix_documents_cache_store: store::PrefixDocumentsCache,
     prefix_postings_lists_cache_store: store::PrefixPostingsListsCache,
+    word_frequency_store: store::WordFrequency,
+    word_prefix_pair_store: Option<store::WordPrefixPairProximityDocIds>,
+    max_automaton_count: usize,
+    min_prefix_len: usize,
+    stemmer: Option<&dyn Stemmer>,
+    phrase_slop: usize,
+    minimum_should_match: Option<f32>,
+    search_after: Option<SearchCursor>,
 ) -> MResult<SortResult>
 where
     FI: Fn(DocumentId) -> bool,
     FD: Fn(DocumentId) -> Option<u64>,
 {
+    // the distinct documents can't be resumed after a document,
+    // the cursor is used as the offset of the requested range
+    let range = range_after_cursor(range, search_after);
     let mut result = SortResult::default();
 
     let words_set = match unsafe { main_store.static_words_fst(reader)? } {
@@ -235,7 +340,10 @@ where
         None => return Ok(SortResult::default()),
     };
 
-    let stop_words = main_store.stop_words_fst(reader)?.unwrap_or_default();
+    let stop_words = main_store.all_stop_words_fst(reader)?;
+    let short_prefixes_stale = main_store.short_prefixes_stale(reader)?;
+    let shortest_cached_prefix_len = main_store.shortest_cached_prefix_len(reader)?;
+    let normalization_mode = main_store.normalization_mode(reader)?.unwrap_or_default();
 
     let context = QTContext {
         words_set,
@@ -243,9 +351,22 @@ where
         synonyms: synonyms_store,
         postings_lists: postings_lists_store,
         prefix_postings_lists: prefix_postings_lists_cache_store,
+        word_frequency: word_frequency_store,
+        max_automaton_count,
+        min_prefix_len,
+        short_prefixes_stale,
+        shortest_cached_prefix_len,
+        stemmer,
+        phrase_slop,
+        normalization_mode,
     };
 
-    let (operation, mapping) = create_query_tree(reader, &context, query)?;
+    let (operation, mapping, weights, automaton_stats) = create_query_tree(reader, &context, query)?;
+    let word_prefix_pair = match word_prefix_pair_store {
+        Some(store) => word_prefix_pair(&context, query).map(|(word, prefix)| (store, word, prefix)),
+        None => None,
+    };
+    result.automaton_stats = automaton_stats;
     debug!("operation:\n{:?}", operation);
     debug!("mapping:\n{:?}", mapping);
 
@@ -260,8 +381,15 @@ where
     let mut queries_kinds = HashMap::new();
     recurs_operation(&mut queries_kinds, &operation);
 
-    let QueryResult { mut docids, queries } = traverse_query_tree(reader, &context, &operation)?;
+    let QueryResult { mut docids, queries, skipped_groups } = traverse_query_tree(reader, &context, &operation)?;
+    result.skipped_groups = skipped_groups;
     debug!("found {} documents", docids.len());
+
+    if let Some(fraction) = minimum_should_match {
+        let relaxed = minimum_should_match_docids(&queries, &mapping, fraction);
+        docids = Cow::Owned(sdset::duo::OpBuilder::new(docids.as_ref(), relaxed.as_set()).union().into_set_buf());
+        debug!("found {} documents matching enough words", docids.len());
+    }
     debug!("number of postings {:?}", queries.len());
 
     if let Some(facets_docids) = facets_docids {
@@ -321,7 +449,11 @@ where
                 reader,
                 postings_lists: &mut arena,
                 query_mapping: &mapping,
+                query_weights: &weights,
                 documents_fields_counts_store,
+                word_prefix_pair: word_prefix_pair.as_ref().map(|(store, word, prefix)| {
+                    WordPrefixPair { word, prefix: *prefix, store: *store }
+                }),
             };
 
             let before_criterion_preparation = Instant::now();
@@ -331,6 +463,7 @@ where
             let ctx = Context {
                 postings_lists: &arena,
                 query_mapping: &mapping,
+                query_weights: &weights,
             };
 
             let before_criterion_sort = Instant::now();
@@ -403,13 +536,21 @@ where
             };
 
             if distinct_accepted && seen.len() > range.start {
-                documents.push(Document::from_raw(raw_document, &queries_kinds, &arena, searchable_attrs.as_ref(), &schema));
+                let ctx = Context {
+                    postings_lists: &arena,
+                    query_mapping: &mapping,
+                    query_weights: &weights,
+                };
+                let ranking_score = criteria.ranking_score(&ctx, &raw_document);
+                let document = Document::from_raw(raw_document, &queries_kinds, &arena, searchable_attrs.as_ref(), &schema);
+                documents.push(Document { ranking_score, ..document });
                 if documents.len() == range.len() {
                     break;
                 }
             }
         }
     }
+    result.next_cursor = next_cursor(&documents, &range);
     result.documents = documents;
     result.nb_hits = docids.len();
 
@@ -425,7 +566,7 @@ fn cleanup_bare_matches<'tag, 'txn>(
     let docidslen = docids.len() as f32;
     let mut bare_matches = Vec::new();
 
-    for (PostingsKey { query, input, distance, is_exact }, matches) in queries {
+    for (PostingsKey { query, input, distance, is_exact, slop }, matches) in queries {
         let postings_list_view = PostingsListView::original(Rc::from(input), Rc::new(matches));
         let pllen = postings_list_view.len() as f32;
 
@@ -442,6 +583,7 @@ fn cleanup_bare_matches<'tag, 'txn>(
                         query_index: query.id,
                         distance,
                         is_exact,
+                        slop,
                         postings_list: posting_list_index,
                     };
 
@@ -473,6 +615,7 @@ fn cleanup_bare_matches<'tag, 'txn>(
                         query_index: query.id,
                         distance,
                         is_exact,
+                        slop,
                         postings_list: posting_list_index,
                     };
 
@@ -494,6 +637,8 @@ pub struct BareMatch<'tag> {
     pub query_index: usize,
     pub distance: u8,
     pub is_exact: bool,
+    /// The number of words matched between the words of a phrase.
+    pub slop: u8,
     pub postings_list: Idx32<'tag>,
 }
 
@@ -504,6 +649,7 @@ impl fmt::Debug for BareMatch<'_> {
             .field("query_index", &self.query_index)
             .field("distance", &self.distance)
             .field("is_exact", &self.is_exact)
+            .field("slop", &self.slop)
             .finish()
     }
 }
@@ -515,6 +661,7 @@ pub struct SimpleMatch {
     pub attribute: u16,
     pub word_index: u16,
     pub is_exact: bool,
+    pub slop: u8,
 }
 
 #[derive(Clone)]
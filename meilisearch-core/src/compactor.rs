@@ -0,0 +1,127 @@
+use std::collections::BTreeSet;
+
+use fst::{set::OpBuilder, SetBuilder};
+use sdset::SetBuf;
+
+use crate::database::MainT;
+use crate::{store, DocIndex, MResult};
+
+/// Metrics about the postings lists cleaned by the [`Compactor`].
+///
+/// [`Compactor`]: struct.Compactor.html
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CompactorStats {
+    /// The number of postings lists read.
+    pub postings_lists_checked: usize,
+    /// The number of postings lists rewritten without their orphaned entries.
+    pub postings_lists_rewritten: usize,
+    /// The number of postings lists removed because all their entries were orphaned.
+    pub postings_lists_removed: usize,
+    /// The number of entries removed, those referencing documents that do not exist anymore.
+    pub removed_entries: usize,
+}
+
+/// Removes the postings lists entries of the documents that are not in the
+/// internal ids of an index anymore, it is meant to be run as an offline
+/// maintenance task, e.g. after a mass deletion.
+pub struct Compactor;
+
+impl Compactor {
+    pub fn run(writer: &mut heed::RwTxn<MainT>, index: &store::Index) -> MResult<CompactorStats> {
+        let mut stats = CompactorStats::default();
+        let internal_docids = index.main.internal_docids(writer)?.into_owned();
+
+        // the entries can't be written while iterating over the store
+        let mut cleaned_postings = Vec::new();
+        for result in index.postings_lists.postings_lists.iter(writer)? {
+            let (word, postings) = result?;
+            stats.postings_lists_checked += 1;
+
+            let is_orphan = |di: &DocIndex| internal_docids.binary_search(&di.document_id).is_err();
+            if postings.matches.iter().any(is_orphan) {
+                let matches: Vec<_> = postings.matches.iter().filter(|di| !is_orphan(di)).copied().collect();
+                let removed = postings.matches.len() - matches.len();
+                // removing elements keeps the set sorted and deduplicated
+                cleaned_postings.push((word.to_vec(), SetBuf::new_unchecked(matches), removed));
+            }
+        }
+
+        let mut removed_words = BTreeSet::new();
+        for (word, matches, removed) in cleaned_postings {
+            stats.removed_entries += removed;
+
+            if let Ok(word) = std::str::from_utf8(&word) {
+                index.word_frequency.decrease_frequency(writer, word, removed as u64)?;
+            }
+
+            if matches.is_empty() {
+                index.postings_lists.del_postings_list(writer, &word)?;
+                stats.postings_lists_removed += 1;
+                removed_words.insert(word);
+            } else {
+                index.postings_lists.put_postings_list(writer, &word, &matches)?;
+                stats.postings_lists_rewritten += 1;
+            }
+        }
+
+        if !removed_words.is_empty() {
+            let removed_words = fst::Set::from_iter(removed_words)?;
+            if let Some(words) = index.main.words_fst(writer)? {
+                let op = OpBuilder::new()
+                    .add(words.stream())
+                    .add(removed_words.stream())
+                    .difference();
+
+                let mut words_builder = SetBuilder::memory();
+                words_builder.extend_stream(op)?;
+                let words = words_builder.into_inner().and_then(fst::Set::from_bytes)?;
+                index.main.put_words_fst(writer, &words)?;
+            }
+        }
+
+        // the prefixes cache is computed from the postings lists
+        if stats.removed_entries != 0 {
+            index.main.put_short_prefixes_stale(writer)?;
+        }
+
+        Ok(stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::update::test_helpers::{add_documents, with_index};
+
+    #[test]
+    fn compactor_removes_orphaned_postings() {
+        with_index(|writer, index| {
+            add_documents(writer, index, 0, serde_json::json!([
+                { "id": 1, "title": "hello world" },
+                { "id": 2, "title": "hello again" },
+            ]));
+
+            let stats = Compactor::run(writer, index).unwrap();
+            assert_eq!(stats.removed_entries, 0);
+
+            // the second document is forgotten by the ids map but not by the postings lists
+            let orphan = index.main.external_to_internal_docid(writer, "2").unwrap().unwrap();
+            index.main.remove_internal_docids(writer, &SetBuf::from_dirty(vec![orphan])).unwrap();
+
+            let stats = Compactor::run(writer, index).unwrap();
+            assert_eq!(stats.postings_lists_rewritten, 1);
+            assert!(stats.postings_lists_removed >= 1);
+            assert!(stats.removed_entries >= 2);
+
+            let words = index.main.words_fst(writer).unwrap().unwrap();
+            assert!(words.contains("hello"));
+            assert!(!words.contains("again"));
+
+            let postings = index.postings_lists.postings_list(writer, b"hello").unwrap().unwrap();
+            assert!(postings.matches.iter().all(|di| di.document_id != orphan));
+            assert!(postings.docids.binary_search(&orphan).is_err());
+            assert!(index.main.short_prefixes_stale(writer).unwrap());
+        });
+    }
+}
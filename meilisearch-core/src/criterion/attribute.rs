@@ -1,4 +1,6 @@
 use std::cmp::Ordering;
+use std::collections::HashMap;
+use meilisearch_schema::Schema;
 use slice_group_by::GroupBy;
 use crate::{RawDocument, MResult};
 use crate::bucket_sort::SimpleMatch;
@@ -20,18 +22,82 @@ impl Criterion for Attribute {
     }
 
     fn evaluate(&self, _ctx: &Context, lhs: &RawDocument, rhs: &RawDocument) -> Ordering {
-        #[inline]
-        fn sum_of_attribute(matches: &[SimpleMatch]) -> usize {
-            let mut sum_of_attribute = 0;
-            for group in matches.linear_group_by_key(|bm| bm.query_index) {
-                sum_of_attribute += group[0].attribute as usize;
-            }
-            sum_of_attribute
-        }
-
         let lhs = sum_of_attribute(&lhs.processed_matches);
         let rhs = sum_of_attribute(&rhs.processed_matches);
 
         lhs.cmp(&rhs)
     }
+
+    fn score(&self, _ctx: &Context, document: &RawDocument) -> Option<f64> {
+        Some(sum_of_attribute(&document.processed_matches) as f64)
+    }
+}
+
+#[inline]
+fn sum_of_attribute(matches: &[SimpleMatch]) -> usize {
+    let mut sum_of_attribute = 0;
+    for group in matches.linear_group_by_key(|bm| bm.query_index) {
+        sum_of_attribute += group[0].attribute as usize;
+    }
+    sum_of_attribute
+}
+
+/// An attribute criterion that sorts the documents by the sum of the weights
+/// of the attributes of each match, the attributes without weight count for 1.0.
+/// The documents with the same weighted sum are sorted like the `Attribute` criterion.
+pub struct WeightedAttribute {
+    weights: HashMap<u16, f32>,
+}
+
+impl WeightedAttribute {
+    pub fn new(schema: &Schema, weights: &HashMap<String, f32>) -> WeightedAttribute {
+        let weights = weights
+            .iter()
+            .filter_map(|(name, weight)| {
+                let field_id = schema.id(name)?;
+                let pos = schema.is_indexed(field_id)?;
+                Some((pos.0, *weight))
+            })
+            .collect();
+
+        WeightedAttribute { weights }
+    }
+
+    fn weighted_sum(&self, matches: &[SimpleMatch]) -> f32 {
+        matches.iter().map(|m| self.weights.get(&m.attribute).copied().unwrap_or(1.0)).sum()
+    }
+}
+
+impl Criterion for WeightedAttribute {
+    fn name(&self) -> &str { "attribute" }
+
+    fn prepare<'h, 'p, 'tag, 'txn, 'q, 'r>(
+        &self,
+        ctx: ContextMut<'h, 'p, 'tag, 'txn, 'q>,
+        documents: &mut [RawDocument<'r, 'tag>],
+    ) -> MResult<()>
+    {
+        prepare_bare_matches(documents, ctx.postings_lists, ctx.query_mapping);
+        Ok(())
+    }
+
+    fn evaluate(&self, _ctx: &Context, lhs: &RawDocument, rhs: &RawDocument) -> Ordering {
+        let lhs_sum = self.weighted_sum(&lhs.processed_matches);
+        let rhs_sum = self.weighted_sum(&rhs.processed_matches);
+
+        // a higher weighted sum is better
+        match rhs_sum.partial_cmp(&lhs_sum) {
+            Some(Ordering::Equal) | None => {
+                let lhs = sum_of_attribute(&lhs.processed_matches);
+                let rhs = sum_of_attribute(&rhs.processed_matches);
+                lhs.cmp(&rhs)
+            }
+            Some(ordering) => ordering,
+        }
+    }
+
+    /// Only the weighted sum is scored, the ties are broken by the next criteria.
+    fn score(&self, _ctx: &Context, document: &RawDocument) -> Option<f64> {
+        Some(-self.weighted_sum(&document.processed_matches) as f64)
+    }
 }
@@ -55,17 +55,6 @@ impl Criterion for Exactness {
     }
 
     fn evaluate(&self, _ctx: &Context, lhs: &RawDocument, rhs: &RawDocument) -> Ordering {
-        #[inline]
-        fn sum_exact_query_words(matches: &[BareMatch]) -> usize {
-            let mut sum_exact_query_words = 0;
-
-            for group in matches.linear_group_by_key(|bm| bm.query_index) {
-                sum_exact_query_words += group[0].is_exact as usize;
-            }
-
-            sum_exact_query_words
-        }
-
         // does it contains a "one word field"
         lhs.contains_one_word_field.cmp(&rhs.contains_one_word_field).reverse()
         // if not, with document contains the more exact words
@@ -75,4 +64,21 @@ impl Criterion for Exactness {
             lhs.cmp(&rhs).reverse()
         })
     }
+
+    fn score(&self, _ctx: &Context, document: &RawDocument) -> Option<f64> {
+        // the number of exact query words is never bigger than the number of query words
+        let one_word_field = if document.contains_one_word_field { f64::from(u16::max_value()) } else { 0.0 };
+        Some(-(one_word_field + sum_exact_query_words(&document.bare_matches) as f64))
+    }
+}
+
+#[inline]
+fn sum_exact_query_words(matches: &[BareMatch]) -> usize {
+    let mut sum_exact_query_words = 0;
+
+    for group in matches.linear_group_by_key(|bm| bm.query_index) {
+        sum_exact_query_words += group[0].is_exact as usize;
+    }
+
+    sum_exact_query_words
 }
@@ -3,6 +3,7 @@ use std::collections::HashMap;
 use std::ops::Range;
 
 use compact_arena::SmallArena;
+use ordered_float::OrderedFloat;
 use sdset::SetBuf;
 use slice_group_by::GroupBy;
 
@@ -23,7 +24,7 @@ mod sort_by_attr;
 pub use self::typo::Typo;
 pub use self::words::Words;
 pub use self::proximity::Proximity;
-pub use self::attribute::Attribute;
+pub use self::attribute::{Attribute, WeightedAttribute};
 pub use self::words_position::WordsPosition;
 pub use self::exactness::Exactness;
 pub use self::document_id::DocumentId;
@@ -48,6 +49,18 @@ pub trait Criterion {
         rhs: &RawDocument<'r, 'tag>,
     ) -> Ordering;
 
+    /// The score of the document, the documents sorted by ascending scores are in the order
+    /// given by `evaluate`. Unlike the ordering, the scores can be compared between indexes
+    /// ranked by the same criteria, the criteria that can't be scored return `None`.
+    fn score<'p, 'tag, 'txn, 'q, 'r>(
+        &self,
+        _ctx: &Context<'p, 'tag, 'txn, 'q>,
+        _document: &RawDocument<'r, 'tag>,
+    ) -> Option<f64>
+    {
+        None
+    }
+
     #[inline]
     fn eq<'p, 'tag, 'txn, 'q, 'r>(
         &self,
@@ -64,12 +77,26 @@ pub struct ContextMut<'h, 'p, 'tag, 'txn, 'q> {
     pub reader: &'h heed::RoTxn<MainT>,
     pub postings_lists: &'p mut SmallArena<'tag, PostingsListView<'txn>>,
     pub query_mapping: &'q HashMap<QueryId, Range<usize>>,
+    pub query_weights: &'q [f32],
     pub documents_fields_counts_store: store::DocumentsFieldsCounts,
+    /// The word followed by a prefix the query is made of, if any.
+    pub word_prefix_pair: Option<WordPrefixPair<'q>>,
+}
+
+/// A query word followed by a short prefix, the documents
+/// where they are adjacent are read from the index.
+#[derive(Copy, Clone)]
+pub struct WordPrefixPair<'q> {
+    pub word: &'q str,
+    pub prefix: [u8; 4],
+    pub store: store::WordPrefixPairProximityDocIds,
 }
 
 pub struct Context<'p, 'tag, 'txn, 'q> {
     pub postings_lists: &'p SmallArena<'tag, PostingsListView<'txn>>,
     pub query_mapping: &'q HashMap<QueryId, Range<usize>>,
+    /// The weights of the query words, given by their `^` boost suffix.
+    pub query_weights: &'q [f32],
 }
 
 #[derive(Default)]
@@ -130,6 +157,19 @@ impl<'a> Default for Criteria<'a> {
     }
 }
 
+impl<'a> Criteria<'a> {
+    /// The scores of the document given by the criteria that can score it, the documents
+    /// of many indexes ranked by the same criteria are merged by comparing them in order.
+    pub fn ranking_score<'p, 'tag, 'txn, 'q, 'r>(
+        &self,
+        ctx: &Context<'p, 'tag, 'txn, 'q>,
+        document: &RawDocument<'r, 'tag>,
+    ) -> Vec<OrderedFloat<f64>>
+    {
+        self.inner.iter().filter_map(|criterion| criterion.score(ctx, document)).map(OrderedFloat).collect()
+    }
+}
+
 impl<'a> AsRef<[Box<dyn Criterion + 'a>]> for Criteria<'a> {
     fn as_ref(&self) -> &[Box<dyn Criterion + 'a>] {
         &self.inner
@@ -185,6 +225,7 @@ fn prepare_bare_matches<'a, 'tag, 'txn>(
                     attribute: di.attribute,
                     word_index: di.word_index,
                     is_exact: m.is_exact,
+                    slop: m.slop,
                 };
                 processed.push(simple_match);
             }
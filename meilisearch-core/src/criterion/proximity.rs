@@ -18,51 +18,86 @@ impl Criterion for Proximity {
     ) -> MResult<()>
     {
         prepare_bare_matches(documents, ctx.postings_lists, ctx.query_mapping);
+
+        let pair = match ctx.word_prefix_pair {
+            Some(pair) => pair,
+            None => return Ok(()),
+        };
+
+        let docids = match pair.store.pair_docids(ctx.reader, pair.word.as_bytes(), pair.prefix, 1)? {
+            Some(docids) => docids,
+            None => return Ok(()),
+        };
+
+        // the documents where the word is followed by the prefix have the smallest proximity,
+        // when they only match the two query words and not the words made of both of them
+        for document in documents {
+            let single_words = document.bare_matches.iter().all(|m| ctx.query_mapping[&m.query_index].len() == 1);
+            let words_count = document.processed_matches.linear_group_by_key(|m| m.query_index).count();
+            if single_words && words_count == 2 && docids.binary_search(&document.id).is_ok() {
+                document.proximity = Some(1);
+            }
+        }
+
         Ok(())
     }
 
     fn evaluate(&self, _ctx: &Context, lhs: &RawDocument, rhs: &RawDocument) -> Ordering {
-        fn index_proximity(lhs: u16, rhs: u16) -> u16 {
-            if lhs < rhs {
-                cmp::min(rhs - lhs, MAX_DISTANCE)
-            } else {
-                cmp::min(lhs - rhs, MAX_DISTANCE) + 1
-            }
-        }
+        let lhs = document_proximity(lhs);
+        let rhs = document_proximity(rhs);
 
-        fn attribute_proximity(lhs: SimpleMatch, rhs: SimpleMatch) -> u16 {
-            if lhs.attribute != rhs.attribute { MAX_DISTANCE }
-            else { index_proximity(lhs.word_index, rhs.word_index) }
-        }
+        lhs.cmp(&rhs)
+    }
 
-        fn min_proximity(lhs: &[SimpleMatch], rhs: &[SimpleMatch]) -> u16 {
-            let mut min_prox = u16::max_value();
-            for a in lhs {
-                for b in rhs {
-                    let prox = attribute_proximity(*a, *b);
-                    min_prox = cmp::min(min_prox, prox);
-                }
-            }
-            min_prox
-        }
+    fn score(&self, _ctx: &Context, document: &RawDocument) -> Option<f64> {
+        Some(document_proximity(document) as f64)
+    }
+}
 
-        fn matches_proximity(matches: &[SimpleMatch],) -> u16 {
-            let mut proximity = 0;
-            let mut iter = matches.linear_group_by_key(|m| m.query_index);
+fn document_proximity(document: &RawDocument) -> u16 {
+    document.proximity.unwrap_or_else(|| matches_proximity(&document.processed_matches))
+}
 
-            // iterate over groups by windows of size 2
-            let mut last = iter.next();
-            while let (Some(lhs), Some(rhs)) = (last, iter.next()) {
-                proximity += min_proximity(lhs, rhs);
-                last = Some(rhs);
-            }
+fn index_proximity(lhs: u16, rhs: u16) -> u16 {
+    if lhs < rhs {
+        cmp::min(rhs - lhs, MAX_DISTANCE)
+    } else {
+        cmp::min(lhs - rhs, MAX_DISTANCE) + 1
+    }
+}
+
+fn attribute_proximity(lhs: SimpleMatch, rhs: SimpleMatch) -> u16 {
+    if lhs.attribute != rhs.attribute { MAX_DISTANCE }
+    else { index_proximity(lhs.word_index, rhs.word_index) }
+}
 
-            proximity
+fn min_proximity(lhs: &[SimpleMatch], rhs: &[SimpleMatch]) -> u16 {
+    let mut min_prox = u16::max_value();
+    for a in lhs {
+        for b in rhs {
+            let prox = attribute_proximity(*a, *b);
+            min_prox = cmp::min(min_prox, prox);
         }
+    }
+    min_prox
+}
+
+// the words matched between the words of a phrase
+fn min_slop(matches: &[SimpleMatch]) -> u16 {
+    matches.iter().map(|m| m.slop as u16).min().unwrap_or(0)
+}
 
-        let lhs = matches_proximity(&lhs.processed_matches);
-        let rhs = matches_proximity(&rhs.processed_matches);
+fn matches_proximity(matches: &[SimpleMatch],) -> u16 {
+    let mut proximity = 0;
+    let mut iter = matches.linear_group_by_key(|m| m.query_index);
 
-        lhs.cmp(&rhs)
+    // iterate over groups by windows of size 2
+    let mut last = iter.next();
+    proximity += last.map_or(0, min_slop);
+    while let (Some(lhs), Some(rhs)) = (last, iter.next()) {
+        proximity += min_proximity(lhs, rhs) + min_slop(rhs);
+        last = Some(rhs);
     }
+
+    proximity
 }
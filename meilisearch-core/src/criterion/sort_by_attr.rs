@@ -2,7 +2,7 @@ use std::cmp::Ordering;
 use std::error::Error;
 use std::fmt;
 use meilisearch_schema::{Schema, FieldId};
-use crate::{RankedMap, RawDocument};
+use crate::{Number, RankedMap, RawDocument};
 use super::{Criterion, Context};
 
 /// An helper struct that permit to sort documents by
@@ -108,6 +108,19 @@ impl Criterion for SortByAttr<'_> {
             (None, None) => Ordering::Equal,
         }
     }
+
+    fn score(&self, _ctx: &Context, document: &RawDocument) -> Option<f64> {
+        let value = match self.ranked_map.get(document.id, self.field_id) {
+            Some(Number::Unsigned(n)) => n as f64,
+            Some(Number::Signed(n)) => n as f64,
+            Some(Number::Float(n)) => n.into_inner(),
+            // the null values are sorted after the numbers, the documents without value after them
+            Some(Number::Null) => f64::MAX,
+            None => return Some(f64::INFINITY),
+        };
+
+        Some(if self.reversed { -value } else { value })
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
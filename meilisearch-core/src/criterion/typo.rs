@@ -17,39 +17,45 @@ impl Criterion for Typo {
         Ok(())
     }
 
-    fn evaluate(&self, _ctx: &Context, lhs: &RawDocument, rhs: &RawDocument) -> Ordering {
-        // This function is a wrong logarithmic 10 function.
-        // It is safe to panic on input number higher than 3,
-        // the number of typos is never bigger than that.
-        #[inline]
-        fn custom_log10(n: u8) -> f32 {
-            match n {
-                0 => 0.0,     // log(1)
-                1 => 0.30102, // log(2)
-                2 => 0.47712, // log(3)
-                3 => 0.60205, // log(4)
-                _ => panic!("invalid number"),
-            }
-        }
-
-        #[inline]
-        fn compute_typos(distances: &[Option<u8>]) -> usize {
-            let mut number_words: usize = 0;
-            let mut sum_typos = 0.0;
+    fn evaluate(&self, ctx: &Context, lhs: &RawDocument, rhs: &RawDocument) -> Ordering {
+        let lhs = compute_typos(&lhs.processed_distances, ctx.query_weights);
+        let rhs = compute_typos(&rhs.processed_distances, ctx.query_weights);
 
-            for distance in distances {
-                if let Some(distance) = distance {
-                    sum_typos += custom_log10(*distance);
-                    number_words += 1;
-                }
-            }
+        lhs.cmp(&rhs).reverse()
+    }
 
-            (number_words as f32 / (sum_typos + 1.0) * 1000.0) as usize
-        }
+    fn score(&self, ctx: &Context, document: &RawDocument) -> Option<f64> {
+        Some(-(compute_typos(&document.processed_distances, ctx.query_weights) as f64))
+    }
+}
 
-        let lhs = compute_typos(&lhs.processed_distances);
-        let rhs = compute_typos(&rhs.processed_distances);
+// This function is a wrong logarithmic 10 function.
+// It is safe to panic on input number higher than 3,
+// the number of typos is never bigger than that.
+#[inline]
+fn custom_log10(n: u8) -> f32 {
+    match n {
+        0 => 0.0,     // log(1)
+        1 => 0.30102, // log(2)
+        2 => 0.47712, // log(3)
+        3 => 0.60205, // log(4)
+        _ => panic!("invalid number"),
+    }
+}
 
-        lhs.cmp(&rhs).reverse()
+/// The words and their typos count as much as their weight, 1.0 by default.
+#[inline]
+fn compute_typos(distances: &[Option<u8>], weights: &[f32]) -> usize {
+    let mut number_words = 0.0;
+    let mut sum_typos = 0.0;
+
+    for (i, distance) in distances.iter().enumerate() {
+        if let Some(distance) = distance {
+            let weight = weights.get(i).copied().unwrap_or(1.0);
+            sum_typos += custom_log10(*distance) * weight;
+            number_words += weight;
+        }
     }
+
+    (number_words / (sum_typos + 1.0) * 1000.0) as usize
 }
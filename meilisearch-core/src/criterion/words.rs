@@ -17,15 +17,25 @@ impl Criterion for Words {
         Ok(())
     }
 
-    fn evaluate(&self, _ctx: &Context, lhs: &RawDocument, rhs: &RawDocument) -> Ordering {
-        #[inline]
-        fn number_of_query_words(distances: &[Option<u8>]) -> usize {
-            distances.iter().cloned().filter(Option::is_some).count()
-        }
+    fn evaluate(&self, ctx: &Context, lhs: &RawDocument, rhs: &RawDocument) -> Ordering {
+        let lhs = weighted_number_of_query_words(&lhs.processed_distances, ctx.query_weights);
+        let rhs = weighted_number_of_query_words(&rhs.processed_distances, ctx.query_weights);
 
-        let lhs = number_of_query_words(&lhs.processed_distances);
-        let rhs = number_of_query_words(&rhs.processed_distances);
+        lhs.partial_cmp(&rhs).unwrap_or(Ordering::Equal).reverse()
+    }
 
-        lhs.cmp(&rhs).reverse()
+    fn score(&self, ctx: &Context, document: &RawDocument) -> Option<f64> {
+        Some(-weighted_number_of_query_words(&document.processed_distances, ctx.query_weights) as f64)
     }
 }
+
+/// The sum of the weights of the matched query words, the words weigh 1.0 by default.
+#[inline]
+fn weighted_number_of_query_words(distances: &[Option<u8>], weights: &[f32]) -> f32 {
+    distances
+        .iter()
+        .enumerate()
+        .filter(|(_, distance)| distance.is_some())
+        .map(|(i, _)| weights.get(i).copied().unwrap_or(1.0))
+        .sum()
+}
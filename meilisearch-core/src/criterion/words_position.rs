@@ -20,18 +20,22 @@ impl Criterion for WordsPosition {
     }
 
     fn evaluate(&self, _ctx: &Context, lhs: &RawDocument, rhs: &RawDocument) -> Ordering {
-        #[inline]
-        fn sum_words_position(matches: &[SimpleMatch]) -> usize {
-            let mut sum_words_position = 0;
-            for group in matches.linear_group_by_key(|bm| bm.query_index) {
-                sum_words_position += group[0].word_index as usize;
-            }
-            sum_words_position
-        }
-
         let lhs = sum_words_position(&lhs.processed_matches);
         let rhs = sum_words_position(&rhs.processed_matches);
 
         lhs.cmp(&rhs)
     }
+
+    fn score(&self, _ctx: &Context, document: &RawDocument) -> Option<f64> {
+        Some(sum_words_position(&document.processed_matches) as f64)
+    }
+}
+
+#[inline]
+fn sum_words_position(matches: &[SimpleMatch]) -> usize {
+    let mut sum_words_position = 0;
+    for group in matches.linear_group_by_key(|bm| bm.query_index) {
+        sum_words_position += group[0].word_index as usize;
+    }
+    sum_words_position
 }
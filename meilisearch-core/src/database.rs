@@ -1,20 +1,29 @@
 use std::collections::hash_map::{Entry, HashMap};
 use std::fs::File;
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
 use std::{fs, thread};
 
 use crossbeam_channel::{Receiver, Sender};
+use fst::Streamer;
 use heed::types::{Str, Unit};
 use heed::{CompactionOption, Result as ZResult};
 use log::debug;
 use meilisearch_schema::Schema;
 
-use crate::{store, update, Index, MResult};
+use crate::raw_indexer::TokenLimits;
+use crate::read_txn_pool::{PooledReadTxn, ReadTxnPool, ReadTxnPoolStats};
+use crate::serde::FieldCipher;
+use crate::{store, update, CoreTransaction, Index, MResult, MigrateError, NormalizationMode};
 
 pub type BoxUpdateFn = Box<dyn Fn(&str, update::ProcessedUpdateResult) + Send + Sync + 'static>;
 type ArcSwapFn = arc_swap::ArcSwapOption<BoxUpdateFn>;
 
+pub type BoxIndexingHooks = Box<dyn update::IndexingHooks>;
+type ArcSwapHooks = arc_swap::ArcSwapOption<BoxIndexingHooks>;
+
 pub struct MainT;
 pub struct UpdateT;
 
@@ -25,11 +34,28 @@ pub struct Database {
     indexes_store: heed::Database<Str, Unit>,
     indexes: RwLock<HashMap<String, (Index, thread::JoinHandle<MResult<()>>)>>,
     update_fn: Arc<ArcSwapFn>,
+    indexing_hooks: Arc<ArcSwapHooks>,
+    stopping: Arc<AtomicBool>,
+    token_limits: TokenLimits,
+    checkpoint_interval: Option<usize>,
+    field_cipher: Option<FieldCipher>,
+    read_txn_pool: Arc<ReadTxnPool>,
 }
 
 pub struct DatabaseOptions {
     pub main_map_size: usize,
     pub update_map_size: usize,
+    /// The maximum number of tokens indexed by field and by document.
+    pub token_limits: TokenLimits,
+    /// The key of the AES-256-GCM encryption of the encrypted fields.
+    pub field_encryption_key: Option<[u8; 32]>,
+    /// The number of main read transactions recycled by the searches, none by default.
+    pub read_txn_pool_size: usize,
+    /// The age above which the pooled read transactions are replaced.
+    pub read_txn_max_age: Duration,
+    /// The number of words after which a documents addition commits the postings lists it has
+    /// written and continues in a new transaction, the additions are written at once by default.
+    pub checkpoint_interval: Option<usize>,
 }
 
 impl Default for DatabaseOptions {
@@ -37,6 +63,11 @@ impl Default for DatabaseOptions {
         DatabaseOptions {
             main_map_size: 100 * 1024 * 1024 * 1024, //100Gb
             update_map_size: 100 * 1024 * 1024 * 1024, //100Gb
+            token_limits: TokenLimits::default(),
+            field_encryption_key: None,
+            read_txn_pool_size: 0,
+            read_txn_max_age: Duration::from_secs(1),
+            checkpoint_interval: None,
         }
     }
 }
@@ -56,19 +87,73 @@ macro_rules! r#break_try {
 pub enum UpdateEvent {
     NewUpdate,
     MustClear,
+    MustStop,
 }
 
 pub type UpdateEvents = Receiver<UpdateEvent>;
 pub type UpdateEventsEmitter = Sender<UpdateEvent>;
 
+/// Rebuilds the short prefixes cache of an index each time it is notified,
+/// the notifications are coalesced while a rebuild is running.
+fn prefixes_rebuilder(receiver: Receiver<()>, env: heed::Env, index_uid: &str, index: Index) {
+    for () in receiver {
+        let mut writer = match env.typed_write_txn::<MainT>() {
+            Ok(writer) => writer,
+            Err(e) => {
+                log::error!("LMDB write transaction begin failed: {}", e);
+                continue;
+            }
+        };
+
+        let start = Instant::now();
+        match update::rebuild_short_prefixes(&mut writer, &index) {
+            Ok(true) => match writer.commit() {
+                Ok(()) => debug!("short prefixes of {} rebuilt in {:.02?}", index_uid, start.elapsed()),
+                Err(e) => log::error!("short prefixes commit failed: {}", e),
+            },
+            Ok(false) => writer.abort(),
+            Err(e) => {
+                log::error!("short prefixes rebuild failed: {}", e);
+                writer.abort();
+            }
+        }
+    }
+
+    debug!("prefixes rebuilder of {} stopped", index_uid);
+}
+
 fn update_awaiter(
     receiver: UpdateEvents,
     env: heed::Env,
     update_env: heed::Env,
     index_uid: &str,
     update_fn: Arc<ArcSwapFn>,
+    indexing_hooks: Arc<ArcSwapHooks>,
+    stopping: Arc<AtomicBool>,
+    read_txn_pool: Arc<ReadTxnPool>,
     index: Index,
 ) -> MResult<()> {
+    // the short prefixes are rebuilt by a dedicated thread to avoid blocking
+    // the updates processing, the search falls back to the words fst meanwhile
+    let (prefixes_notifier, prefixes_receiver) = crossbeam_channel::bounded(1);
+    let prefixes_handle = {
+        let env = env.clone();
+        let index = index.clone();
+        let index_uid = index_uid.to_owned();
+        thread::spawn(move || prefixes_rebuilder(prefixes_receiver, env, &index_uid, index))
+    };
+
+    // the cache could have been left stale by a previous run
+    let _ = prefixes_notifier.try_send(());
+
+    // a reindexation interrupted by a previous run is finished before the updates are processed
+    if let Err(e) = resume_interrupted_reindex(&env, &index) {
+        log::error!("resuming the reindexation of {} failed: {}", index_uid, e);
+    }
+
+    // the documents modified by the updates pending together
+    let mut conflicts = update::ConflictsTracker::default();
+
     let mut receiver = receiver.into_iter();
     while let Some(event) = receiver.next() {
 
@@ -81,13 +166,31 @@ fn update_awaiter(
 
             writer.commit()?;
             update_writer.commit()?;
+            read_txn_pool.invalidate();
 
             debug!("store {} cleared", index_uid);
 
             break
         }
 
+        // stop the loop once the update being processed, if any, has been committed
+        if let UpdateEvent::MustStop = event {
+            break
+        }
+
         loop {
+            if stopping.load(Ordering::Relaxed) {
+                debug!("update loop of {} is stopping", index_uid);
+                break;
+            }
+
+            // updates stay enqueued while the index is read-only,
+            // the loop is woken up again when the read-only window ends
+            if index.is_read_only() {
+                debug!("index {} is read-only, updates processing paused", index_uid);
+                break;
+            }
+
             // We instantiate a *write* transaction to *block* the thread
             // until the *other*, notifiying, thread commits
             let result = update_env.typed_write_txn::<UpdateT>();
@@ -99,6 +202,7 @@ fn update_awaiter(
                 Some(value) => value,
                 None => {
                     debug!("no more updates");
+                    conflicts.clear();
                     break;
                 }
             };
@@ -107,16 +211,34 @@ fn update_awaiter(
             update_reader.abort();
 
             // instantiate a transaction to touch to the main env
-            let result = env.typed_write_txn::<MainT>();
+            let result = CoreTransaction::<MainT>::reopenable(&env);
             let mut main_writer = break_try!(result, "LMDB nested write transaction failed");
 
+            // retrieve the documents modified by the update before it is consumed
+            let result = index.main.schema(&main_writer);
+            let schema = break_try!(result, "schema retrieval failed");
+            let documents_ids = schema
+                .as_ref()
+                .and_then(|schema| schema.primary_key())
+                .and_then(|primary_key| update::ConflictsTracker::documents_ids(&update, primary_key));
+
             // try to apply the update to the database using the main transaction
-            let result = update::update_task(&mut main_writer, &index, update_id, update);
-            let status = break_try!(result, "update task failed");
+            let hooks = indexing_hooks.load_full().map(|hooks| hooks as Arc<dyn update::IndexingHooks>);
+            let result = update::update_task(&mut main_writer, &index, update_id, update, hooks);
+            let mut status = break_try!(result, "update task failed");
 
             // commit the main transaction if the update was successful, abort it otherwise
             if status.error.is_none() {
                 break_try!(main_writer.commit(), "commit nested transaction failed");
+                read_txn_pool.invalidate();
+                let _ = prefixes_notifier.try_send(());
+
+                if let update::UpdateType::ClearAll = status.update_type {
+                    conflicts.clear();
+                }
+                if let Some(ids) = documents_ids {
+                    status.conflicts_resolved = Some(conflicts.register(ids));
+                }
             } else {
                 main_writer.abort()
             }
@@ -142,23 +264,93 @@ fn update_awaiter(
                 (callback)(index_uid, status);
             }
         }
+
+        if stopping.load(Ordering::Relaxed) {
+            break;
+        }
     }
 
+    drop(prefixes_notifier);
+    let _ = prefixes_handle.join();
+
     debug!("update loop system stopped");
 
     Ok(())
 }
 
+fn resume_interrupted_reindex(env: &heed::Env, index: &Index) -> MResult<()> {
+    let reader = env.typed_read_txn::<MainT>()?;
+    let interrupted = index.main.reindex_checkpoint(&reader)?.is_some();
+    reader.abort();
+
+    if interrupted {
+        let mut writer = CoreTransaction::<MainT>::reopenable(env)?;
+        update::resume_reindex_from_checkpoint(&mut writer, index)?;
+        writer.commit()?;
+    }
+
+    Ok(())
+}
+
+/// Returns an error if the data of the index is stored with another version of the format,
+/// the indexes created before the versioning of the stores are tagged with the current version.
+fn check_store_version(env: &heed::Env, index: &Index) -> MResult<()> {
+    let mut writer = env.typed_write_txn::<MainT>()?;
+    match index.main.store_version(&writer)? {
+        Some(version) if version == store::STORE_VERSION => {
+            writer.abort();
+            Ok(())
+        }
+        Some(version) if version > store::STORE_VERSION => {
+            writer.abort();
+            let error = MigrateError::IncompatibleVersion { on_disk: version, expected: store::STORE_VERSION };
+            Err(error.into())
+        }
+        // the indexes without a version have been created before the versions were stored
+        _ => {
+            migrate_store(&mut writer, index)?;
+            index.main.put_store_version(&mut writer, store::STORE_VERSION)?;
+            Ok(writer.commit()?)
+        }
+    }
+}
+
+/// Migrates an index stored with a previous store version: the schema is rewritten in the
+/// current format and the words frequencies are counted when the index has none.
+fn migrate_store(writer: &mut heed::RwTxn<MainT>, index: &Index) -> MResult<()> {
+    if let Err(heed::Error::Decoding) = index.main.schema(writer) {
+        index.main.migrate_legacy_schema(writer)?;
+    }
+
+    if index.word_frequency.is_empty(writer)? {
+        if let Some(words) = index.main.words_fst(writer)? {
+            let mut stream = words.stream();
+            while let Some(word) = stream.next() {
+                let count = match index.postings_lists.postings_list(writer, word)? {
+                    Some(postings) => postings.matches.len() as u64,
+                    None => continue,
+                };
+                if let Ok(word) = std::str::from_utf8(word) {
+                    index.word_frequency.increase_frequency(writer, word, count)?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
 impl Database {
     pub fn open_or_create(path: impl AsRef<Path>, options: DatabaseOptions) -> MResult<Database> {
         let main_path = path.as_ref().join("main");
         let update_path = path.as_ref().join("update");
 
         fs::create_dir_all(&main_path)?;
-        let env = heed::EnvOpenOptions::new()
-            .map_size(options.main_map_size)
-            .max_dbs(3000)
-            .open(main_path)?;
+        let mut env_options = heed::EnvOpenOptions::new();
+        env_options.map_size(options.main_map_size).max_dbs(3000);
+        // the pooled read transactions are used by other threads than the one that created them
+        unsafe { env_options.flag(heed::flags::Flags::MdbNoTls) };
+        let env = env_options.open(main_path)?;
 
         fs::create_dir_all(&update_path)?;
         let update_env = heed::EnvOpenOptions::new()
@@ -169,6 +361,10 @@ impl Database {
         let common_store = env.create_poly_database(Some("common"))?;
         let indexes_store = env.create_database::<Str, Unit>(Some("indexes"))?;
         let update_fn = Arc::new(ArcSwapFn::empty());
+        let indexing_hooks = Arc::new(ArcSwapHooks::empty());
+        let stopping = Arc::new(AtomicBool::new(false));
+        let field_cipher = options.field_encryption_key.map(FieldCipher::new);
+        let read_txn_pool = Arc::new(ReadTxnPool::new(env.clone(), options.read_txn_pool_size, options.read_txn_max_age));
 
         // list all indexes that needs to be opened
         let mut must_open = Vec::new();
@@ -184,7 +380,7 @@ impl Database {
         let mut indexes = HashMap::new();
         for index_uid in must_open {
             let (sender, receiver) = crossbeam_channel::unbounded();
-            let index = match store::open(&env, &update_env, &index_uid, sender.clone())? {
+            let mut index = match store::open(&env, &update_env, &index_uid, sender.clone())? {
                 Some(index) => index,
                 None => {
                     log::warn!(
@@ -195,11 +391,19 @@ impl Database {
                 }
             };
 
+            check_store_version(&env, &index)?;
+            index.token_limits = options.token_limits;
+            index.checkpoint_interval = options.checkpoint_interval;
+            index.field_cipher = field_cipher.clone();
+
             let env_clone = env.clone();
             let update_env_clone = update_env.clone();
             let index_clone = index.clone();
             let name_clone = index_uid.clone();
             let update_fn_clone = update_fn.clone();
+            let indexing_hooks_clone = indexing_hooks.clone();
+            let stopping_clone = stopping.clone();
+            let read_txn_pool_clone = read_txn_pool.clone();
 
             let handle = thread::spawn(move || {
                 update_awaiter(
@@ -208,6 +412,9 @@ impl Database {
                     update_env_clone,
                     &name_clone,
                     update_fn_clone,
+                    indexing_hooks_clone,
+                    stopping_clone,
+                    read_txn_pool_clone,
                     index_clone,
                 )
             });
@@ -230,6 +437,12 @@ impl Database {
             indexes_store,
             indexes: RwLock::new(indexes),
             update_fn,
+            indexing_hooks,
+            stopping,
+            token_limits: options.token_limits,
+            checkpoint_interval: options.checkpoint_interval,
+            field_cipher,
+            read_txn_pool,
         })
     }
 
@@ -249,21 +462,29 @@ impl Database {
             Entry::Occupied(_) => Err(crate::Error::IndexAlreadyExists),
             Entry::Vacant(entry) => {
                 let (sender, receiver) = crossbeam_channel::unbounded();
-                let index = store::create(&self.env, &self.update_env, name, sender)?;
+                let mut index = store::create(&self.env, &self.update_env, name, sender)?;
+                index.token_limits = self.token_limits;
+                index.checkpoint_interval = self.checkpoint_interval;
+                index.field_cipher = self.field_cipher.clone();
 
                 let mut writer = self.env.typed_write_txn::<MainT>()?;
                 self.indexes_store.put(&mut writer, name, &())?;
 
+                index.main.put_store_version(&mut writer, store::STORE_VERSION)?;
                 index.main.put_name(&mut writer, name)?;
                 index.main.put_created_at(&mut writer)?;
                 index.main.put_updated_at(&mut writer)?;
                 index.main.put_schema(&mut writer, &Schema::new())?;
+                index.main.put_normalization_mode(&mut writer, NormalizationMode::Nfkc)?;
 
                 let env_clone = self.env.clone();
                 let update_env_clone = self.update_env.clone();
                 let index_clone = index.clone();
                 let name_clone = name.to_owned();
                 let update_fn_clone = self.update_fn.clone();
+                let indexing_hooks_clone = self.indexing_hooks.clone();
+                let stopping_clone = self.stopping.clone();
+                let read_txn_pool_clone = self.read_txn_pool.clone();
 
                 let handle = thread::spawn(move || {
                     update_awaiter(
@@ -272,11 +493,16 @@ impl Database {
                         update_env_clone,
                         &name_clone,
                         update_fn_clone,
+                        indexing_hooks_clone,
+                        stopping_clone,
+                        read_txn_pool_clone,
                         index_clone,
                     )
                 });
 
                 writer.commit()?;
+                // the pooled transactions can't read the stores of the new index
+                self.read_txn_pool.invalidate();
                 entry.insert((index.clone(), handle));
 
                 Ok(index)
@@ -310,6 +536,48 @@ impl Database {
         }
     }
 
+    /// Blocks or unblocks the processing of the updates of an index,
+    /// returns `false` if the index does not exist.
+    pub fn set_read_only(&self, index_uid: &str, read_only: bool) -> bool {
+        match self.open_index(index_uid) {
+            Some(index) => {
+                index.set_read_only(read_only);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Stops the updates processing of every index, waiting at most `timeout` for the
+    /// updates being processed to be committed. The pending updates stay enqueued and
+    /// are processed the next time the database is opened, no update is processed anymore
+    /// by this database. Returns `false` if some updates loops did not stop in time.
+    pub fn close(&self, timeout: Duration) -> bool {
+        self.stopping.store(true, Ordering::Relaxed);
+
+        let indexes = std::mem::take(&mut *self.indexes.write().unwrap());
+        let (sender, receiver) = crossbeam_channel::bounded(1);
+
+        let mut handles = Vec::with_capacity(indexes.len());
+        for (index_uid, (index, handle)) in indexes {
+            let _ = index.updates_notifier.send(UpdateEvent::MustStop);
+            handles.push((index_uid, handle));
+        }
+
+        thread::spawn(move || {
+            for (index_uid, handle) in handles {
+                match handle.join() {
+                    Ok(Ok(())) => (),
+                    Ok(Err(e)) => log::error!("update loop of {} failed: {}", index_uid, e),
+                    Err(_) => log::error!("update loop of {} panicked", index_uid),
+                }
+            }
+            let _ = sender.send(());
+        });
+
+        receiver.recv_timeout(timeout).is_ok()
+    }
+
     pub fn set_update_callback(&self, update_fn: BoxUpdateFn) {
         let update_fn = Some(Arc::new(update_fn));
         self.update_fn.swap(update_fn);
@@ -319,10 +587,30 @@ impl Database {
         self.update_fn.swap(None);
     }
 
+    pub fn set_indexing_hooks(&self, hooks: BoxIndexingHooks) {
+        let hooks = Some(Arc::new(hooks));
+        self.indexing_hooks.swap(hooks);
+    }
+
+    pub fn unset_indexing_hooks(&self) {
+        self.indexing_hooks.swap(None);
+    }
+
     pub fn main_read_txn(&self) -> heed::Result<heed::RoTxn<MainT>> {
         self.env.typed_read_txn::<MainT>()
     }
 
+    /// Returns a main read transaction recycled by the pool of read transactions, it can
+    /// read a state of the database older than the `read_txn_max_age` option but not older
+    /// than the last update processed.
+    pub fn pooled_main_read_txn(&self) -> heed::Result<PooledReadTxn> {
+        self.read_txn_pool.read_txn()
+    }
+
+    pub fn read_txn_pool_stats(&self) -> ReadTxnPoolStats {
+        self.read_txn_pool.stats()
+    }
+
     pub fn main_write_txn(&self) -> heed::Result<heed::RwTxn<MainT>> {
         self.env.typed_write_txn::<MainT>()
     }
@@ -375,9 +663,22 @@ mod tests {
     use crate::criterion::{self, CriteriaBuilder};
     use crate::update::{ProcessedUpdateResult, UpdateStatus};
     use crate::settings::Settings;
-    use crate::{Document, DocumentId};
+    use crate::{Document, DocumentId, Error};
     use serde::de::IgnoredAny;
-    use std::sync::mpsc;
+    use std::collections::HashSet;
+    use std::sync::{mpsc, Mutex};
+
+    #[test]
+    fn close_stops_the_updates_loops() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        database.create_index("test").unwrap();
+        database.create_index("other").unwrap();
+
+        assert!(database.close(Duration::from_secs(10)));
+        assert!(database.open_index("test").is_none());
+    }
 
     #[test]
     fn valid_updates() {
@@ -1093,4 +1394,589 @@ mod tests {
         );
         assert_matches!(iter.next(), None);
     }
+
+    #[test]
+    fn null_field_values() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let db = &database;
+
+        let (sender, receiver) = mpsc::sync_channel(100);
+        let update_fn = move |_name: &str, update: ProcessedUpdateResult| {
+            sender.send(update.update_id).unwrap()
+        };
+        let index = database.create_index("test").unwrap();
+
+        database.set_update_callback(Box::new(update_fn));
+
+        let mut writer = db.main_write_txn().unwrap();
+        index.main.put_schema(&mut writer, &Schema::with_primary_key("id")).unwrap();
+        writer.commit().unwrap();
+
+        let settings = {
+            let data = r#"
+                {
+                    "searchableAttributes": ["name", "description"],
+                    "displayedAttributes": ["id", "name", "description"]
+                }
+            "#;
+            let settings: Settings = serde_json::from_str(data).unwrap();
+            settings.into_update().unwrap()
+        };
+
+        let mut writer = db.update_write_txn().unwrap();
+        let _update_id = index.settings_update(&mut writer, settings).unwrap();
+        writer.commit().unwrap();
+
+        let mut additions = index.documents_addition();
+
+        let doc1 = serde_json::json!({
+            "id": 123,
+            "name": "Marvin",
+            "description": null,
+        });
+
+        additions.update_document(doc1);
+
+        let mut writer = db.update_write_txn().unwrap();
+        let update_id = additions.finalize(&mut writer).unwrap();
+        writer.commit().unwrap();
+
+        // block until the transaction is processed
+        let _ = receiver.into_iter().find(|id| *id == update_id);
+
+        let update_reader = db.update_read_txn().unwrap();
+        let result = index.update_status(&update_reader, update_id).unwrap();
+        assert_matches!(result, Some(UpdateStatus::Processed { content }) if content.error.is_none());
+        update_reader.abort();
+
+        let reader = db.main_read_txn().unwrap();
+        let schema = index.main.schema(&reader).unwrap().unwrap();
+        let description = schema.id("description").unwrap();
+
+        // the field is present but empty
+        let bytes = index.document_attribute_bytes(&reader, DocumentId(0), description).unwrap();
+        assert_eq!(bytes, Some(&[][..]));
+
+        let value: Option<serde_json::Value> = index
+            .document_attribute(&reader, DocumentId(0), description)
+            .unwrap();
+        assert_eq!(value, Some(serde_json::Value::Null));
+
+        let document: Option<serde_json::Value> = index
+            .document(&reader, None, DocumentId(0))
+            .unwrap();
+        let expected = serde_json::json!({
+            "id": 123,
+            "name": "Marvin",
+            "description": null,
+        });
+        assert_eq!(document, Some(expected));
+
+        // null values are never indexed
+        let builder = index.query_builder();
+        let SortResult { documents, .. } = builder.query(&reader, "null", 0..20).unwrap();
+        assert!(documents.is_empty());
+    }
+
+    #[test]
+    fn timestamp_indexing_hook() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let db = &database;
+
+        let (sender, receiver) = mpsc::sync_channel(100);
+        let update_fn = move |_name: &str, update: ProcessedUpdateResult| {
+            sender.send(update.update_id).unwrap()
+        };
+        let index = database.create_index("test").unwrap();
+
+        database.set_update_callback(Box::new(update_fn));
+        database.set_indexing_hooks(Box::new(update::TimestampHook));
+
+        let mut writer = db.main_write_txn().unwrap();
+        index.main.put_schema(&mut writer, &Schema::with_primary_key("id")).unwrap();
+        writer.commit().unwrap();
+
+        let mut additions = index.documents_addition();
+
+        let doc1 = serde_json::json!({
+            "id": 123,
+            "name": "Marvin",
+        });
+
+        additions.update_document(doc1);
+
+        let mut writer = db.update_write_txn().unwrap();
+        let update_id = additions.finalize(&mut writer).unwrap();
+        writer.commit().unwrap();
+
+        // block until the transaction is processed
+        let _ = receiver.into_iter().find(|id| *id == update_id);
+
+        let update_reader = db.update_read_txn().unwrap();
+        let result = index.update_status(&update_reader, update_id).unwrap();
+        assert_matches!(result, Some(UpdateStatus::Processed { content }) if content.error.is_none());
+        update_reader.abort();
+
+        let reader = db.main_read_txn().unwrap();
+        let document: Option<HashMap<String, serde_json::Value>> = index
+            .document(&reader, None, DocumentId(0))
+            .unwrap();
+        let document = document.unwrap();
+        assert_matches!(document.get(update::TimestampHook::FIELD_NAME), Some(serde_json::Value::String(_)));
+    }
+
+    #[test]
+    fn read_only_index() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let db = &database;
+
+        let (sender, receiver) = mpsc::sync_channel(100);
+        let update_fn = move |_name: &str, update: ProcessedUpdateResult| {
+            sender.send(update.update_id).unwrap()
+        };
+        let index = database.create_index("test").unwrap();
+
+        database.set_update_callback(Box::new(update_fn));
+
+        let mut writer = db.main_write_txn().unwrap();
+        index.main.put_schema(&mut writer, &Schema::with_primary_key("id")).unwrap();
+        writer.commit().unwrap();
+
+        assert!(database.set_read_only("test", true));
+        assert!(!database.set_read_only("unknown", true));
+
+        let mut additions = index.documents_addition();
+        additions.update_document(serde_json::json!({ "id": 123, "name": "Marvin" }));
+
+        let mut writer = db.update_write_txn().unwrap();
+        let update_id = additions.finalize(&mut writer).unwrap();
+        writer.commit().unwrap();
+
+        // the update must not be processed while the index is read-only
+        thread::sleep(std::time::Duration::from_millis(200));
+        let update_reader = db.update_read_txn().unwrap();
+        let result = index.update_status(&update_reader, update_id).unwrap();
+        assert_matches!(result, Some(UpdateStatus::Enqueued { .. }));
+        update_reader.abort();
+
+        assert!(database.set_read_only("test", false));
+
+        // block until the transaction is processed
+        let _ = receiver.into_iter().find(|id| *id == update_id);
+
+        let update_reader = db.update_read_txn().unwrap();
+        let result = index.update_status(&update_reader, update_id).unwrap();
+        assert_matches!(result, Some(UpdateStatus::Processed { content }) if content.error.is_none());
+    }
+
+    #[test]
+    fn short_prefixes_rebuilt_in_background() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let db = &database;
+
+        let (sender, receiver) = mpsc::sync_channel(100);
+        let update_fn = move |_name: &str, update: ProcessedUpdateResult| {
+            sender.send(update.update_id).unwrap()
+        };
+        let index = database.create_index("test").unwrap();
+
+        database.set_update_callback(Box::new(update_fn));
+
+        let mut writer = db.main_write_txn().unwrap();
+        index.main.put_schema(&mut writer, &Schema::with_primary_key("id")).unwrap();
+        writer.commit().unwrap();
+
+        let mut additions = index.documents_addition();
+        additions.update_document(serde_json::json!({ "id": 123, "name": "Marvin" }));
+
+        let mut writer = db.update_write_txn().unwrap();
+        let update_id = additions.finalize(&mut writer).unwrap();
+        writer.commit().unwrap();
+
+        // block until the transaction is processed
+        let _ = receiver.into_iter().find(|id| *id == update_id);
+
+        // the documents are searchable by short prefixes during the rebuild
+        let mut builder = index.query_builder();
+        builder.with_min_prefix_len(1);
+        let reader = db.main_read_txn().unwrap();
+        let SortResult { documents, .. } = builder.query(&reader, "m", 0..20).unwrap();
+        assert_eq!(documents.len(), 1);
+        reader.abort();
+
+        // wait for the background rebuild to be committed
+        let mut rebuilt = false;
+        for _ in 0..100 {
+            let reader = db.main_read_txn().unwrap();
+            rebuilt = !index.main.short_prefixes_stale(&reader).unwrap();
+            reader.abort();
+            if rebuilt { break }
+            thread::sleep(std::time::Duration::from_millis(50));
+        }
+        assert!(rebuilt);
+
+        // the index is too small for its short prefixes to be cached
+        let reader = db.main_read_txn().unwrap();
+        let prefix = [b'm', 0, 0, 0];
+        assert!(index.prefix_postings_lists_cache.prefix_postings_list(&reader, prefix).unwrap().is_none());
+
+        let mut builder = index.query_builder();
+        builder.with_min_prefix_len(1);
+        let SortResult { documents, .. } = builder.query(&reader, "m", 0..20).unwrap();
+        assert_eq!(documents.len(), 1);
+    }
+
+    #[test]
+    fn conflicts_resolved_between_pending_additions() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let db = &database;
+
+        let (sender, receiver) = mpsc::sync_channel(100);
+        let update_fn = move |_name: &str, update: ProcessedUpdateResult| {
+            sender.send(update.update_id).unwrap()
+        };
+        let index = database.create_index("test").unwrap();
+
+        database.set_update_callback(Box::new(update_fn));
+
+        let mut writer = db.main_write_txn().unwrap();
+        index.main.put_schema(&mut writer, &Schema::with_primary_key("id")).unwrap();
+        writer.commit().unwrap();
+
+        // keep both updates pending together
+        assert!(database.set_read_only("test", true));
+
+        let mut additions = index.documents_addition();
+        additions.update_document(serde_json::json!({ "id": 1, "name": "Marvin" }));
+        additions.update_document(serde_json::json!({ "id": 2, "name": "Kevin" }));
+
+        let mut writer = db.update_write_txn().unwrap();
+        let first_update_id = additions.finalize(&mut writer).unwrap();
+        writer.commit().unwrap();
+
+        let mut additions = index.documents_addition();
+        additions.update_document(serde_json::json!({ "id": 2, "name": "Bob" }));
+        additions.update_document(serde_json::json!({ "id": 3, "name": "Arthur" }));
+
+        let mut writer = db.update_write_txn().unwrap();
+        let second_update_id = additions.finalize(&mut writer).unwrap();
+        writer.commit().unwrap();
+
+        assert!(database.set_read_only("test", false));
+
+        // block until the transactions are processed
+        let _ = receiver.into_iter().find(|id| *id == second_update_id);
+
+        let update_reader = db.update_read_txn().unwrap();
+        let result = index.update_status(&update_reader, first_update_id).unwrap();
+        assert_matches!(result, Some(UpdateStatus::Processed { content }) if content.conflicts_resolved == Some(0));
+        let result = index.update_status(&update_reader, second_update_id).unwrap();
+        assert_matches!(result, Some(UpdateStatus::Processed { content }) if content.conflicts_resolved == Some(1));
+        update_reader.abort();
+
+        // the last writer wins
+        let reader = db.main_read_txn().unwrap();
+        let docid = index.main.external_to_internal_docid(&reader, "2").unwrap().unwrap();
+        let document: Option<HashMap<String, serde_json::Value>> = index.document(&reader, None, docid).unwrap();
+        assert_eq!(document.unwrap().get("name"), Some(&serde_json::json!("Bob")));
+    }
+
+    #[test]
+    fn incompatible_store_version() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let index = database.create_index("test").unwrap();
+
+        let reader = database.main_read_txn().unwrap();
+        assert_eq!(index.main.store_version(&reader).unwrap(), Some(store::STORE_VERSION));
+        reader.abort();
+        check_store_version(&database.env, &index).unwrap();
+
+        let mut writer = database.main_write_txn().unwrap();
+        index.main.put_store_version(&mut writer, store::STORE_VERSION + 1).unwrap();
+        writer.commit().unwrap();
+
+        let result = check_store_version(&database.env, &index);
+        assert_matches!(
+            result,
+            Err(Error::Migrate(MigrateError::IncompatibleVersion { on_disk, expected }))
+                if on_disk == store::STORE_VERSION + 1 && expected == store::STORE_VERSION
+        );
+    }
+
+    /// Records the checkpoints of the additions and fails at the given checkpoint.
+    struct CheckpointsHook {
+        checkpoints: Arc<Mutex<Vec<update::IndexingProgress>>>,
+        fail_at: Option<usize>,
+    }
+
+    impl update::IndexingHooks for CheckpointsHook {
+        fn progress(&self, progress: update::IndexingProgress) -> MResult<()> {
+            let mut checkpoints = self.checkpoints.lock().unwrap();
+            checkpoints.push(progress);
+            if Some(checkpoints.len()) == self.fail_at {
+                return Err(Error::Io(std::io::Error::new(std::io::ErrorKind::Other, "interrupted")));
+            }
+            Ok(())
+        }
+    }
+
+    fn add_documents_with_checkpoints(fail_at: Option<usize>) -> (tempfile::TempDir, Database, Index, Vec<update::IndexingProgress>, Option<String>) {
+        let dir = tempfile::tempdir().unwrap();
+
+        let options = DatabaseOptions { checkpoint_interval: Some(2), ..DatabaseOptions::default() };
+        let database = Database::open_or_create(dir.path(), options).unwrap();
+
+        let (sender, receiver) = mpsc::sync_channel(100);
+        let update_fn = move |_name: &str, update: ProcessedUpdateResult| {
+            sender.send(update.update_id).unwrap()
+        };
+        let index = database.create_index("test").unwrap();
+
+        let checkpoints = Arc::new(Mutex::new(Vec::new()));
+        database.set_update_callback(Box::new(update_fn));
+        database.set_indexing_hooks(Box::new(CheckpointsHook { checkpoints: checkpoints.clone(), fail_at }));
+
+        // the first document replaces one already indexed
+        let mut writer = database.main_write_txn().unwrap();
+        index.main.put_schema(&mut writer, &Schema::with_primary_key("id")).unwrap();
+        let document = match serde_json::json!({ "id": 0, "name": "Arthur" }) {
+            serde_json::Value::Object(map) => map.into_iter().collect(),
+            _ => unreachable!(),
+        };
+        update::apply_documents_addition(&mut writer, &index, vec![document], Default::default(), None).unwrap();
+        writer.commit().unwrap();
+
+        let mut additions = index.documents_addition();
+        for (id, name) in ["Marvin", "Kevin", "Trillian", "Ford", "Zaphod"].iter().enumerate() {
+            additions.update_document(serde_json::json!({ "id": id, "name": name }));
+        }
+
+        let mut writer = database.update_write_txn().unwrap();
+        let update_id = additions.finalize(&mut writer).unwrap();
+        writer.commit().unwrap();
+
+        // block until the transaction is processed
+        let _ = receiver.into_iter().find(|id| *id == update_id);
+
+        let update_reader = database.update_read_txn().unwrap();
+        let error = match index.update_status(&update_reader, update_id).unwrap() {
+            Some(UpdateStatus::Processed { content }) => content.error,
+            Some(UpdateStatus::Failed { content }) => content.error,
+            status => panic!("unexpected update status {:?}", status),
+        };
+        update_reader.abort();
+
+        let checkpoints = checkpoints.lock().unwrap().clone();
+        (dir, database, index, checkpoints, error)
+    }
+
+    #[test]
+    fn documents_added_with_checkpoints() {
+        let (_dir, database, index, checkpoints, error) = add_documents_with_checkpoints(None);
+        assert_eq!(error, None);
+
+        // the names and the ids of the documents are indexed
+        let words_written: Vec<_> = checkpoints.iter().map(|update::IndexingProgress::Checkpoint { words_written }| *words_written).collect();
+        assert_eq!(words_written, vec![2, 4, 6, 8, 10]);
+
+        let reader = database.main_read_txn().unwrap();
+        assert_eq!(index.main.number_of_documents(&reader).unwrap(), 5);
+        let words = index.main.words_fst(&reader).unwrap().unwrap();
+        for word in &["marvin", "kevin", "trillian", "ford", "zaphod"] {
+            assert!(words.contains(word));
+        }
+        assert!(!words.contains("arthur"));
+    }
+
+    #[test]
+    fn checkpointed_documents_restored_on_failure() {
+        let (_dir, database, index, checkpoints, error) = add_documents_with_checkpoints(Some(2));
+        assert!(error.is_some());
+        assert_eq!(checkpoints.len(), 2);
+
+        // the documents committed before the failure are replaced by the documents they replaced
+        let reader = database.main_read_txn().unwrap();
+        assert_eq!(index.main.number_of_documents(&reader).unwrap(), 1);
+        let words = index.main.words_fst(&reader).unwrap().unwrap();
+        for word in &["marvin", "kevin", "trillian", "ford", "zaphod"] {
+            assert!(!words.contains(word));
+            assert!(index.postings_lists.postings_list(&reader, word.as_bytes()).unwrap().is_none());
+        }
+        for id in 1..5 {
+            assert_eq!(index.main.external_to_internal_docid(&reader, &id.to_string()).unwrap(), None);
+        }
+
+        let docid = index.main.external_to_internal_docid(&reader, "0").unwrap().unwrap();
+        let document: Option<HashMap<String, serde_json::Value>> = index.document(&reader, None, docid).unwrap();
+        assert_eq!(document.unwrap()["name"], serde_json::json!("Arthur"));
+        let postings = index.postings_lists.postings_list(&reader, b"arthur").unwrap().unwrap();
+        assert!(postings.matches.iter().all(|m| m.document_id == docid));
+    }
+
+    /// Creates the environments of a database holding a `movies` index only made
+    /// of the stores created by the versions preceding the addition of the others.
+    ///
+    /// heed remembers the types of the stores of the environments it opened, the stores are
+    /// created in a temporary environment which is then copied to the database path.
+    fn create_pre_series_database(path: &Path) {
+        let source = tempfile::tempdir().unwrap();
+        let mut options = heed::EnvOpenOptions::new();
+        options.map_size(100 * 1024 * 1024).max_dbs(3000);
+
+        fs::create_dir_all(source.path().join("main")).unwrap();
+        let env = options.open(source.path().join("main")).unwrap();
+        let indexes_store = env.create_database::<Str, Unit>(Some("indexes")).unwrap();
+        env.create_poly_database(Some("store-movies")).unwrap();
+        for name in &["postings-lists", "documents-fields", "documents-fields-counts", "synonyms",
+                      "docs-words", "prefix-documents-cache", "facets", "prefix-postings-lists-cache"]
+        {
+            let name = format!("store-movies-{}", name);
+            env.create_database::<Str, Unit>(Some(&name)).unwrap();
+        }
+        // the schema fields added since then are serialized at the end of the schema
+        let mut schema = bincode::serialize(&Schema::with_primary_key("id")).unwrap();
+        let new_fields_len = bincode::serialize(&(HashMap::<(), ()>::new(), HashSet::<()>::new(), 0u16)).unwrap().len();
+        schema.truncate(schema.len() - new_fields_len);
+
+        let main = env.open_poly_database(Some("store-movies")).unwrap().unwrap();
+        let mut writer = env.write_txn().unwrap();
+        indexes_store.put(&mut writer, "movies", &()).unwrap();
+        main.put::<_, Str, heed::types::ByteSlice>(&mut writer, "schema", &schema).unwrap();
+        writer.commit().unwrap();
+
+        fs::create_dir_all(source.path().join("update")).unwrap();
+        let update_env = options.open(source.path().join("update")).unwrap();
+        for name in &["store-movies-updates", "store-movies-updates-results"] {
+            update_env.create_database::<Str, Unit>(Some(name)).unwrap();
+        }
+
+        for (env, name) in &[(env, "main"), (update_env, "update")] {
+            fs::create_dir_all(path.join(name)).unwrap();
+            env.copy_to_path(path.join(name).join("data.mdb"), CompactionOption::Disabled).unwrap();
+        }
+    }
+
+    #[test]
+    fn open_pre_series_index() {
+        let dir = tempfile::tempdir().unwrap();
+        create_pre_series_database(dir.path());
+
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let index = database.open_index("movies").expect("the index is opened");
+
+        let reader = database.main_read_txn().unwrap();
+        assert_eq!(index.main.store_version(&reader).unwrap(), Some(store::STORE_VERSION));
+        let schema = index.main.schema(&reader).unwrap().unwrap();
+        assert_eq!(schema.primary_key(), Some("id"));
+        reader.abort();
+
+        let (sender, receiver) = mpsc::sync_channel(100);
+        database.set_update_callback(Box::new(move |_name: &str, update: ProcessedUpdateResult| {
+            sender.send(update.update_id).unwrap()
+        }));
+
+        let mut additions = index.documents_addition();
+        additions.update_document(serde_json::json!({ "id": 1, "name": "Marvin" }));
+        let mut update_writer = database.update_write_txn().unwrap();
+        let update_id = additions.finalize(&mut update_writer).unwrap();
+        update_writer.commit().unwrap();
+        let _ = receiver.iter().find(|id| *id == update_id);
+
+        let update_reader = database.update_read_txn().unwrap();
+        let result = index.update_status(&update_reader, update_id).unwrap();
+        assert_matches!(result, Some(UpdateStatus::Processed { content }) if content.error.is_none());
+
+        let reader = database.main_read_txn().unwrap();
+        assert_eq!(index.main.number_of_documents(&reader).unwrap(), 1);
+    }
+
+    #[test]
+    fn words_frequencies_counted_by_the_migration() {
+        let dir = tempfile::tempdir().unwrap();
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let index = database.create_index("test").unwrap();
+
+        let (sender, receiver) = mpsc::sync_channel(100);
+        database.set_update_callback(Box::new(move |_name: &str, update: ProcessedUpdateResult| {
+            sender.send(update.update_id).unwrap()
+        }));
+
+        let mut writer = database.main_write_txn().unwrap();
+        index.main.put_schema(&mut writer, &Schema::with_primary_key("id")).unwrap();
+        writer.commit().unwrap();
+
+        let mut additions = index.documents_addition();
+        additions.update_document(serde_json::json!({ "id": 1, "name": "Marvin Marvin" }));
+        let mut update_writer = database.update_write_txn().unwrap();
+        let update_id = additions.finalize(&mut update_writer).unwrap();
+        update_writer.commit().unwrap();
+        let _ = receiver.iter().find(|id| *id == update_id);
+
+        // the indexes of the first store version could have no words frequencies
+        let mut writer = database.main_write_txn().unwrap();
+        index.word_frequency.clear(&mut writer).unwrap();
+        index.main.put_store_version(&mut writer, 1).unwrap();
+        writer.commit().unwrap();
+
+        check_store_version(&database.env, &index).unwrap();
+
+        let reader = database.main_read_txn().unwrap();
+        assert_eq!(index.main.store_version(&reader).unwrap(), Some(store::STORE_VERSION));
+        assert_eq!(index.word_frequency.frequency(&reader, "marvin").unwrap(), Some(2));
+    }
+
+    #[test]
+    fn interrupted_reindex_resumed() {
+        let dir = tempfile::tempdir().unwrap();
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let index = database.create_index("test").unwrap();
+
+        let (sender, receiver) = mpsc::sync_channel(100);
+        database.set_update_callback(Box::new(move |_name: &str, update: ProcessedUpdateResult| {
+            sender.send(update.update_id).unwrap()
+        }));
+
+        let mut writer = database.main_write_txn().unwrap();
+        index.main.put_schema(&mut writer, &Schema::with_primary_key("id")).unwrap();
+        writer.commit().unwrap();
+
+        let mut additions = index.documents_addition();
+        additions.update_document(serde_json::json!({ "id": 1, "name": "Marvin" }));
+        additions.update_document(serde_json::json!({ "id": 2, "name": "Kevin" }));
+        let mut update_writer = database.update_write_txn().unwrap();
+        let update_id = additions.finalize(&mut update_writer).unwrap();
+        update_writer.commit().unwrap();
+        let _ = receiver.iter().find(|id| *id == update_id);
+
+        // a reindexation committed by a previous run before any document has been reindexed
+        let mut writer = database.main_write_txn().unwrap();
+        index.postings_lists.clear(&mut writer).unwrap();
+        index.main.put_words_fst(&mut writer, &fst::Set::default()).unwrap();
+        let first = index.main.internal_docids(&writer).unwrap().first().copied().unwrap();
+        let checkpoint = store::ReindexCheckpoint { last_processed_doc_id: u64::from(first.0) };
+        index.main.put_reindex_checkpoint(&mut writer, checkpoint).unwrap();
+        writer.commit().unwrap();
+
+        resume_interrupted_reindex(&database.env, &index).unwrap();
+
+        let reader = database.main_read_txn().unwrap();
+        assert!(index.main.reindex_checkpoint(&reader).unwrap().is_none());
+        let words = index.main.words_fst(&reader).unwrap().unwrap();
+        // only the documents following the checkpoint are reindexed
+        let marvin = index.main.external_to_internal_docid(&reader, "1").unwrap().unwrap();
+        assert_eq!(words.contains("marvin"), marvin > first);
+        assert_eq!(words.contains("kevin"), marvin == first);
+    }
 }
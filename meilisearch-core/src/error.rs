@@ -1,4 +1,4 @@
-use crate::serde::{DeserializerError, SerializerError};
+use crate::serde::{DeserializerError, FieldCipherError, SerializerError};
 use serde_json::Error as SerdeJsonError;
 use pest::error::Error as PestError;
 use crate::filters::Rule;
@@ -15,6 +15,7 @@ pub type MResult<T> = Result<T, Error>;
 pub enum Error {
     Io(io::Error),
     IndexAlreadyExists,
+    IndexReadOnly,
     MissingPrimaryKey,
     SchemaMissing,
     WordIndexMissing,
@@ -30,6 +31,12 @@ pub enum Error {
     UnsupportedOperation(UnsupportedOperation),
     FilterParseError(PestError<Rule>),
     FacetError(FacetError),
+    InvalidSnapshot(String),
+    QueryTooComplex { automaton_count: usize },
+    IndexDocumentLimitExceeded { current: u64, limit: u64, attempted: u64 },
+    DuplicateDocumentId { user_id: String },
+    Migrate(MigrateError),
+    FieldCipher(FieldCipherError),
 }
 
 impl From<io::Error> for Error {
@@ -108,6 +115,18 @@ impl From<DeserializerError> for Error {
     }
 }
 
+impl From<MigrateError> for Error {
+    fn from(error: MigrateError) -> Error {
+        Error::Migrate(error)
+    }
+}
+
+impl From<FieldCipherError> for Error {
+    fn from(error: FieldCipherError) -> Error {
+        Error::FieldCipher(error)
+    }
+}
+
 impl From<UnsupportedOperation> for Error {
     fn from(op: UnsupportedOperation) -> Error {
         Error::UnsupportedOperation(op)
@@ -120,6 +139,7 @@ impl fmt::Display for Error {
         match self {
             Io(e) => write!(f, "{}", e),
             IndexAlreadyExists => write!(f, "index already exists"),
+            IndexReadOnly => write!(f, "index is read-only, writes are blocked"),
             MissingPrimaryKey => write!(f, "schema cannot be built without a primary key"),
             SchemaMissing => write!(f, "this index does not have a schema"),
             WordIndexMissing => write!(f, "this index does not have a word index"),
@@ -135,11 +155,56 @@ impl fmt::Display for Error {
             UnsupportedOperation(op) => write!(f, "unsupported operation; {}", op),
             FilterParseError(e) => write!(f, "error parsing filter; {}", e),
             FacetError(e) => write!(f, "error processing facet filter: {}", e),
+            InvalidSnapshot(e) => write!(f, "invalid snapshot; {}", e),
+            QueryTooComplex { automaton_count } => {
+                write!(f, "query is too complex; it generates more than {} automatons", automaton_count)
+            },
+            IndexDocumentLimitExceeded { current, limit, attempted } => write!(
+                f,
+                "index document limit exceeded; the index contains {} documents, \
+                 adding {} new documents would exceed the limit of {}",
+                current, attempted, limit,
+            ),
+            DuplicateDocumentId { user_id } => {
+                write!(f, "the document id {} is present more than once in the addition", user_id)
+            },
+            Migrate(e) => write!(f, "migration error; {}", e),
+            FieldCipher(e) => write!(f, "field encryption error; {}", e),
         }
     }
 }
 
-impl error::Error for Error {}
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        use self::Error::*;
+        match self {
+            Io(e) => Some(e),
+            Schema(e) => Some(e),
+            Zlmdb(e) => Some(e),
+            Fst(e) => Some(e),
+            SerdeJson(e) => Some(e),
+            Bincode(e) => Some(e),
+            Serializer(e) => Some(e),
+            Deserializer(e) => Some(e),
+            FilterParseError(e) => Some(e),
+            FacetError(e) => Some(e),
+            UnsupportedOperation(e) => Some(e),
+            Migrate(e) => Some(e),
+            FieldCipher(e) => Some(e),
+            IndexAlreadyExists
+            | IndexReadOnly
+            | MissingPrimaryKey
+            | SchemaMissing
+            | WordIndexMissing
+            | MissingDocumentId
+            | MaxFieldsLimitExceeded
+            | InvalidSnapshot(_)
+            | QueryTooComplex { .. }
+            | IndexDocumentLimitExceeded { .. }
+            | DuplicateDocumentId { .. } => None,
+        }
+    }
+}
 
 #[derive(Debug)]
 pub enum UnsupportedOperation {
@@ -165,6 +230,8 @@ impl fmt::Display for UnsupportedOperation {
     }
 }
 
+impl error::Error for UnsupportedOperation {}
+
 #[derive(Debug)]
 pub enum FacetError {
     EmptyArray,
@@ -201,3 +268,45 @@ impl fmt::Display for FacetError {
         }
     }
 }
+
+impl error::Error for FacetError {}
+
+#[derive(Debug)]
+pub enum MigrateError {
+    IncompatibleVersion { on_disk: u32, expected: u32 },
+}
+
+impl fmt::Display for MigrateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::MigrateError::*;
+        match self {
+            IncompatibleVersion { on_disk, expected } => write!(
+                f,
+                "the data is stored with the version {} of the format, expected version {}",
+                on_disk, expected,
+            ),
+        }
+    }
+}
+
+impl error::Error for MigrateError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error as _;
+
+    #[test]
+    fn error_sources() {
+        let io_error = io::Error::new(io::ErrorKind::Other, "disk is full");
+        let error = Error::from(io_error);
+        assert_eq!(error.source().unwrap().to_string(), "disk is full");
+
+        let json_error = serde_json::from_str::<serde_json::Value>("{").unwrap_err();
+        let error = Error::from(DeserializerError::SerdeJson(json_error));
+        let source = error.source().unwrap();
+        assert!(source.source().is_some());
+
+        assert!(Error::IndexAlreadyExists.source().is_none());
+    }
+}
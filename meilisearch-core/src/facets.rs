@@ -14,6 +14,7 @@ use meilisearch_types::DocumentId;
 
 use crate::database::MainT;
 use crate::error::{FacetError, Error};
+use crate::serde::{field_json_bytes, is_encrypted_field};
 use crate::store::BEU16;
 
 /// Data structure used to represent a boolean expression in the form of nested arrays.
@@ -191,8 +192,9 @@ pub fn facet_map_from_docids(
             .document_fields(rtxn, *document_id)?
         {
             let (field_id, bytes) = result?;
-            if attributes_for_facetting.contains(&field_id) {
-                match serde_json::from_slice(bytes)? {
+            // the encrypted fields are never indexed, neither as facets
+            if attributes_for_facetting.contains(&field_id) && !is_encrypted_field(bytes) {
+                match serde_json::from_slice(field_json_bytes(bytes))? {
                     Value::Array(values) => {
                         for v in values {
                             add_to_facet_map(&mut facet_map, field_id, v, *document_id)?;
@@ -214,6 +216,7 @@ pub fn facet_map_from_docs(
     let mut facet_map = HashMap::new();
     let attributes_for_facetting = attributes_for_facetting
         .iter()
+        .filter(|&&id| !schema.is_encrypted(id))
         .filter_map(|&id| schema.name(id).map(|name| (id, name)))
         .collect::<Vec<_>>();
 
@@ -2,9 +2,10 @@ use std::str::FromStr;
 use std::cmp::Ordering;
 
 use crate::error::Error;
+use crate::serde::{FALSE_FIELD_BYTE, TRUE_FIELD_BYTE};
 use crate::{store::Index, DocumentId, MainT};
 use heed::RoTxn;
-use meilisearch_schema::{FieldId, Schema};
+use meilisearch_schema::{FieldId, FieldType, Schema};
 use pest::error::{Error as PestError, ErrorVariant};
 use pest::iterators::Pair;
 use serde_json::{Value, Number};
@@ -64,7 +65,13 @@ impl<'a> ConditionValue<'a> {
 pub struct Condition<'a> {
     field: FieldId,
     condition: ConditionType,
-    value: ConditionValue<'a>
+    value: ConditionValue<'a>,
+    /// The field contains booleans, stored as a single byte that can be compared directly.
+    boolean_field: bool,
+}
+
+fn is_boolean_field(schema: &Schema, field: FieldId) -> bool {
+    schema.field_type(field) == Some(FieldType::Bool)
 }
 
 fn get_field_value<'a>(schema: &Schema, pair: Pair<'a, Rule>) -> Result<(FieldId, ConditionValue<'a>), Error> {
@@ -105,7 +112,7 @@ impl<'a> Condition<'a> {
     ) -> Result<Self, Error> {
         let (field, value) = get_field_value(schema, item)?;
         let condition = ConditionType::Less;
-        Ok(Self { field, condition, value })
+        Ok(Self { field, condition, value, boolean_field: is_boolean_field(schema, field) })
     }
 
     pub fn greater(
@@ -114,7 +121,7 @@ impl<'a> Condition<'a> {
     ) -> Result<Self, Error> {
         let (field, value) = get_field_value(schema, item)?;
         let condition = ConditionType::Greater;
-        Ok(Self { field, condition, value })
+        Ok(Self { field, condition, value, boolean_field: is_boolean_field(schema, field) })
     }
 
     pub fn neq(
@@ -123,7 +130,7 @@ impl<'a> Condition<'a> {
     ) -> Result<Self, Error> {
         let (field, value) = get_field_value(schema, item)?;
         let condition = ConditionType::NotEqual;
-        Ok(Self { field, condition, value })
+        Ok(Self { field, condition, value, boolean_field: is_boolean_field(schema, field) })
     }
 
     pub fn geq(
@@ -132,7 +139,7 @@ impl<'a> Condition<'a> {
     ) -> Result<Self, Error> {
         let (field, value) = get_field_value(schema, item)?;
         let condition = ConditionType::GreaterEqual;
-        Ok(Self { field, condition, value })
+        Ok(Self { field, condition, value, boolean_field: is_boolean_field(schema, field) })
     }
 
     pub fn leq(
@@ -141,7 +148,7 @@ impl<'a> Condition<'a> {
     ) -> Result<Self, Error> {
         let (field, value) = get_field_value(schema, item)?;
         let condition = ConditionType::LessEqual;
-        Ok(Self { field, condition, value })
+        Ok(Self { field, condition, value, boolean_field: is_boolean_field(schema, field) })
     }
 
     pub fn eq(
@@ -150,7 +157,7 @@ impl<'a> Condition<'a> {
     ) -> Result<Self, Error> {
         let (field, value) = get_field_value(schema, item)?;
         let condition = ConditionType::Equal;
-        Ok(Self { field, condition, value })
+        Ok(Self { field, condition, value, boolean_field: is_boolean_field(schema, field) })
     }
 
     pub fn test(
@@ -159,6 +166,23 @@ impl<'a> Condition<'a> {
         index: &Index,
         document_id: DocumentId,
     ) -> Result<bool, Error> {
+        if let (true, Some(value)) = (self.boolean_field, self.value.as_bool()) {
+            let stored = match index.document_attribute_bytes(reader, document_id, self.field)? {
+                Some([FALSE_FIELD_BYTE]) => Some(false),
+                Some([TRUE_FIELD_BYTE]) => Some(true),
+                _ => None,
+            };
+
+            // the other values, e.g. arrays, are compared as JSON values
+            if let Some(stored) = stored {
+                return Ok(match self.condition {
+                    ConditionType::Equal => stored == value,
+                    ConditionType::NotEqual => stored != value,
+                    _ => false,
+                });
+            }
+        }
+
         match index.document_attribute::<Value>(reader, document_id, self.field)? {
             Some(Value::Array(values)) => Ok(values.iter().any(|v| self.match_value(Some(v)))),
             other => Ok(self.match_value(other.as_ref())),
@@ -6,6 +6,7 @@ extern crate pest_derive;
 
 mod automaton;
 mod bucket_sort;
+mod compactor;
 mod database;
 mod distinct_map;
 mod error;
@@ -17,31 +18,47 @@ mod query_tree;
 mod query_words_mapper;
 mod ranked_map;
 mod raw_document;
+mod read_txn_pool;
 mod reordered_attrs;
+mod reranker;
+mod search_cursor;
+mod stemmer;
+mod transaction;
 pub mod criterion;
 pub mod facets;
 pub mod raw_indexer;
 pub mod serde;
 pub mod settings;
+pub mod snapshot;
+pub mod stop_words;
 pub mod store;
 pub mod update;
 
-pub use self::database::{BoxUpdateFn, Database, DatabaseOptions, MainT, UpdateT};
-pub use self::error::{Error, HeedError, FstError, MResult, pest_error, FacetError};
+pub use self::automaton::{normalize_str, normalize_query_string, NormalizationMode, DEFAULT_MIN_PREFIX_LEN};
+pub use self::compactor::{Compactor, CompactorStats};
+pub use self::database::{BoxIndexingHooks, BoxUpdateFn, Database, DatabaseOptions, MainT, UpdateT};
+pub use self::error::{Error, HeedError, FstError, MResult, MigrateError, pest_error, FacetError};
 pub use self::filters::Filter;
 pub use self::number::{Number, ParseNumberError};
-pub use self::ranked_map::RankedMap;
+pub use self::query_tree::{stopped_words, AutomatonGenerationStats};
+pub use self::ranked_map::{RankedMap, RankedMapChanges};
 pub use self::raw_document::RawDocument;
+pub use self::read_txn_pool::{PooledReadTxn, ReadTxnPool, ReadTxnPoolStats};
+pub use self::reranker::{DocumentBoostReranker, Reranker};
+pub use self::search_cursor::{ParseSearchCursorError, SearchCursor};
+pub use self::stemmer::{SnowballStemmer, Stemmer, StemmerLanguage};
 pub use self::store::Index;
+pub use self::transaction::{CoreTransaction, UpdateWriter};
 pub use self::update::{EnqueuedUpdateResult, ProcessedUpdateResult, UpdateStatus, UpdateType};
 pub use meilisearch_types::{DocIndex, DocumentId, Highlight};
 pub use meilisearch_schema::Schema;
-pub use query_words_mapper::QueryWordsMapper;
+pub use query_words_mapper::{QueryWordsMapper, QueryWordsMapperError};
 
 use std::convert::TryFrom;
 use std::collections::HashMap;
 use compact_arena::SmallArena;
 use log::{error, trace};
+use ordered_float::OrderedFloat;
 
 use crate::bucket_sort::PostingsListView;
 use crate::levenshtein::prefix_damerau_levenshtein;
@@ -52,6 +69,8 @@ use crate::reordered_attrs::ReorderedAttrs;
 pub struct Document {
     pub id: DocumentId,
     pub highlights: Vec<Highlight>,
+    /// The scores given by the criteria, see `Criteria::ranking_score`.
+    pub ranking_score: Vec<OrderedFloat<f64>>,
 
     #[cfg(test)]
     pub matches: Vec<crate::bucket_sort::SimpleMatch>,
@@ -114,12 +133,12 @@ fn highlights_from_raw_document<'a, 'tag, 'txn>(
 impl Document {
     #[cfg(not(test))]
     pub fn from_highlights(id: DocumentId, highlights: &[Highlight]) -> Document {
-        Document { id, highlights: highlights.to_owned() }
+        Document { id, highlights: highlights.to_owned(), ranking_score: Vec::new() }
     }
 
     #[cfg(test)]
     pub fn from_highlights(id: DocumentId, highlights: &[Highlight]) -> Document {
-        Document { id, highlights: highlights.to_owned(), matches: Vec::new() }
+        Document { id, highlights: highlights.to_owned(), ranking_score: Vec::new(), matches: Vec::new() }
     }
 
     #[cfg(not(test))]
@@ -139,7 +158,7 @@ impl Document {
             schema,
         );
 
-        Document { id: raw_document.id, highlights }
+        Document { id: raw_document.id, highlights, ranking_score: Vec::new() }
     }
 
     #[cfg(test)]
@@ -180,7 +199,7 @@ impl Document {
         }
         matches.sort_unstable();
 
-        Document { id: raw_document.id, highlights, matches }
+        Document { id: raw_document.id, highlights, ranking_score: Vec::new(), matches }
     }
 }
 
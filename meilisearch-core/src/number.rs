@@ -1,5 +1,5 @@
 use std::cmp::Ordering;
-use std::fmt;
+use std::{error, fmt};
 use std::num::{ParseFloatError, ParseIntError};
 use std::str::FromStr;
 
@@ -118,3 +118,5 @@ impl fmt::Display for ParseNumberError {
         }
     }
 }
+
+impl error::Error for ParseNumberError {}
@@ -11,8 +11,10 @@ use meilisearch_schema::FieldId;
 use crate::database::MainT;
 use crate::bucket_sort::{bucket_sort, bucket_sort_with_distinct, SortResult};
 use crate::{criterion::Criteria, DocumentId};
-use crate::{reordered_attrs::ReorderedAttrs, store, MResult};
+use crate::{reordered_attrs::ReorderedAttrs, store, MResult, Reranker, SearchCursor, Stemmer};
 use crate::facets::FacetFilter;
+use crate::automaton::DEFAULT_MIN_PREFIX_LEN;
+use crate::query_tree::DEFAULT_MAX_AUTOMATON_COUNT;
 
 pub struct QueryBuilder<'c, 'f, 'd, 'i> {
     criteria: Criteria<'c>,
@@ -23,6 +25,13 @@ pub struct QueryBuilder<'c, 'f, 'd, 'i> {
     index: &'i store::Index,
     facet_filter: Option<FacetFilter>,
     facets: Option<Vec<(FieldId, String)>>,
+    max_automaton_count: usize,
+    min_prefix_len: usize,
+    minimum_should_match: Option<f32>,
+    search_after: Option<SearchCursor>,
+    reranker: Option<Box<dyn Reranker + 'f>>,
+    stemmer: Option<Box<dyn Stemmer + 'f>>,
+    phrase_slop: usize,
 }
 
 impl<'c, 'f, 'd, 'i> QueryBuilder<'c, 'f, 'd, 'i> {
@@ -56,6 +65,13 @@ impl<'c, 'f, 'd, 'i> QueryBuilder<'c, 'f, 'd, 'i> {
             index,
             facet_filter: None,
             facets: None,
+            max_automaton_count: DEFAULT_MAX_AUTOMATON_COUNT,
+            min_prefix_len: DEFAULT_MIN_PREFIX_LEN,
+            minimum_should_match: None,
+            search_after: None,
+            reranker: None,
+            stemmer: None,
+            phrase_slop: 0,
         }
     }
 
@@ -70,6 +86,52 @@ impl<'c, 'f, 'd, 'i> QueryBuilder<'c, 'f, 'd, 'i> {
         self.timeout = Some(timeout)
     }
 
+    /// Limits the number of automatons the query can generate, the query
+    /// fails with a `QueryTooComplex` error if the limit is exceeded.
+    pub fn with_max_automaton_count(&mut self, count: usize) {
+        self.max_automaton_count = count
+    }
+
+    /// The words of the query shorter than this number of characters
+    /// only match the words equal to them instead of being used as prefixes.
+    pub fn with_min_prefix_len(&mut self, len: usize) {
+        self.min_prefix_len = len
+    }
+
+    /// The documents matching at least this fraction, between 0 and 1, of the words
+    /// of the query are returned, not only the documents matching all of them.
+    pub fn with_minimum_should_match(&mut self, fraction: f32) {
+        self.minimum_should_match = Some(fraction)
+    }
+
+    /// Returns the documents ranked after the cursor, the range is relative to it.
+    pub fn with_search_after(&mut self, cursor: SearchCursor) {
+        self.search_after = Some(cursor)
+    }
+
+    /// Reorders the documents of the requested range once they have been sorted.
+    pub fn with_reranker<R>(&mut self, reranker: R)
+    where
+        R: Reranker + 'f,
+    {
+        self.reranker = Some(Box::new(reranker))
+    }
+
+    /// Also searches the words of the query by their stem.
+    pub fn with_stemmer<S>(&mut self, stemmer: S)
+    where
+        S: Stemmer + 'f,
+    {
+        self.stemmer = Some(Box::new(stemmer))
+    }
+
+    /// The number of words allowed between the words of a phrase, `0` by default,
+    /// the words must be adjacent. A phrase matched with words between its words
+    /// is ranked as if these words were between two words of the query.
+    pub fn with_phrase_slop(&mut self, slop: usize) {
+        self.phrase_slop = slop
+    }
+
     pub fn with_distinct<F>(&mut self, size: usize, function: F)
     where
         F: Fn(DocumentId) -> Option<u64> + 'd,
@@ -140,7 +202,28 @@ impl<'c, 'f, 'd, 'i> QueryBuilder<'c, 'f, 'd, 'i> {
             None => None,
         };
 
-        match self.distinct {
+        // the word prefix pairs are stored whatever their attribute,
+        // they are ignored when the search is restricted to some attributes
+        let word_prefix_pairs = match self.searchable_attrs {
+            Some(_) => None,
+            None => Some(self.index.word_prefix_pair_proximity_docids),
+        };
+
+        // the matches found in the attributes that are not searchable anymore are ignored
+        let searchable_attrs = match self.searchable_attrs {
+            Some(searchable_attrs) => Some(searchable_attrs),
+            None => self.index.main.schema(reader)?.map(|schema| {
+                let mut searchable_attrs = ReorderedAttrs::new();
+                for field_id in schema.searchable_attributes() {
+                    if let Some(indexed_pos) = schema.is_indexed(field_id) {
+                        searchable_attrs.insert_attribute(indexed_pos.0);
+                    }
+                }
+                searchable_attrs
+            }),
+        };
+
+        let mut result = match self.distinct {
             Some((distinct, distinct_size)) => bucket_sort_with_distinct(
                 reader,
                 query,
@@ -151,13 +234,21 @@ impl<'c, 'f, 'd, 'i> QueryBuilder<'c, 'f, 'd, 'i> {
                 distinct,
                 distinct_size,
                 self.criteria,
-                self.searchable_attrs,
+                searchable_attrs,
                 self.index.main,
                 self.index.postings_lists,
                 self.index.documents_fields_counts,
                 self.index.synonyms,
                 self.index.prefix_documents_cache,
                 self.index.prefix_postings_lists_cache,
+                self.index.word_frequency,
+                word_prefix_pairs,
+                self.max_automaton_count,
+                self.min_prefix_len,
+                self.stemmer.as_deref(),
+                self.phrase_slop,
+                self.minimum_should_match,
+                self.search_after,
             ),
             None => bucket_sort(
                 reader,
@@ -167,15 +258,30 @@ impl<'c, 'f, 'd, 'i> QueryBuilder<'c, 'f, 'd, 'i> {
                 facet_count_docids,
                 self.filter,
                 self.criteria,
-                self.searchable_attrs,
+                searchable_attrs,
                 self.index.main,
                 self.index.postings_lists,
                 self.index.documents_fields_counts,
                 self.index.synonyms,
                 self.index.prefix_documents_cache,
                 self.index.prefix_postings_lists_cache,
+                self.index.word_frequency,
+                word_prefix_pairs,
+                self.max_automaton_count,
+                self.min_prefix_len,
+                self.stemmer.as_deref(),
+                self.phrase_slop,
+                self.minimum_should_match,
+                self.search_after,
             ),
+        }?;
+
+        // the cursor keeps pointing to the last document sorted by the ranking rules
+        if let Some(reranker) = self.reranker {
+            result.documents = reranker.rerank(result.documents);
         }
+
+        Ok(result)
     }
 }
 
@@ -193,6 +299,9 @@ mod tests {
 
     use crate::DocIndex;
     use crate::Document;
+    use crate::DocumentBoostReranker;
+    use crate::Error;
+    use crate::{SnowballStemmer, StemmerLanguage};
     use crate::automaton::normalize_str;
     use crate::bucket_sort::SimpleMatch;
     use crate::database::{Database,DatabaseOptions};
@@ -307,6 +416,9 @@ mod tests {
             let db = &database;
             let mut writer = db.main_write_txn().unwrap();
 
+            // the words are given transliterated like in the indexes without a normalization mode
+            index.main.delete_normalization_mode(&mut writer).unwrap();
+
             let mut words_fst = BTreeSet::new();
             let mut postings_lists = HashMap::new();
             let mut fields_counts = HashMap::<_, u16>::new();
@@ -349,6 +461,12 @@ mod tests {
                     .postings_lists
                     .put_postings_list(&mut writer, &word, &postings_list)
                     .unwrap();
+
+                let word = std::str::from_utf8(&word).unwrap();
+                index
+                    .word_frequency
+                    .increase_frequency(&mut writer, word, postings_list.len() as u64)
+                    .unwrap();
             }
 
             for ((docid, attr, _), count) in fields_counts {
@@ -396,6 +514,383 @@ mod tests {
         assert_matches!(iter.next(), None);
     }
 
+    #[test]
+    fn short_prefixes_match_exactly() {
+        let store = TempDatabase::from_iter(vec![
+            ("iphone", &[doc_char_index(0, 0, 0)][..]),
+            ("ipho", &[doc_char_index(1, 0, 0)][..]),
+        ]);
+
+        let db = &store.database;
+        let reader = db.main_read_txn().unwrap();
+
+        let builder = store.query_builder();
+        let SortResult { documents, .. } = builder.query(&reader, "ipho", 0..20).unwrap();
+        assert_eq!(documents.len(), 2);
+
+        let mut builder = store.query_builder();
+        builder.with_min_prefix_len(5);
+        let SortResult { documents, .. } = builder.query(&reader, "ipho", 0..20).unwrap();
+        let mut iter = documents.into_iter();
+        assert_matches!(iter.next(), Some(Document { id: DocumentId(1), .. }));
+        assert_matches!(iter.next(), None);
+    }
+
+    #[test]
+    fn matches_restricted_to_searchable_attributes() {
+        let store = TempDatabase::from_iter(vec![
+            ("iphone", &[doc_index(0, 0), DocIndex { attribute: 1, ..doc_index(1, 0) }][..]),
+        ]);
+
+        let db = &store.database;
+        let mut writer = db.main_write_txn().unwrap();
+        let mut schema = store.index.main.schema(&writer).unwrap().unwrap();
+        schema.update_indexed(vec!["id", "0"]).unwrap();
+        store.index.main.put_schema(&mut writer, &schema).unwrap();
+        writer.commit().unwrap();
+
+        // the attribute is not searchable anymore but the documents have not been reindexed yet
+        let reader = db.main_read_txn().unwrap();
+        let builder = store.query_builder();
+        let SortResult { documents, .. } = builder.query(&reader, "iphone", 0..20).unwrap();
+        let mut iter = documents.into_iter();
+        assert_matches!(iter.next(), Some(Document { id: DocumentId(0), matches, highlights, .. }) => {
+            assert_eq!(matches.len(), 1);
+            assert_eq!(highlights.len(), 1);
+        });
+        assert_matches!(iter.next(), Some(Document { id: DocumentId(1), matches, highlights, .. }) => {
+            assert!(matches.is_empty());
+            assert!(highlights.is_empty());
+        });
+        assert_matches!(iter.next(), None);
+    }
+
+    #[test]
+    fn short_prefixes_length_counted_in_chars() {
+        let store = TempDatabase::from_iter(vec![
+            ("élan", &[doc_char_index(0, 0, 0)][..]),
+            ("é", &[doc_char_index(1, 0, 0)][..]),
+        ]);
+
+        let db = &store.database;
+        let reader = db.main_read_txn().unwrap();
+
+        // a single char of two bytes is shorter than the default minimum prefix length
+        let builder = store.query_builder();
+        let SortResult { documents, .. } = builder.query(&reader, "é", 0..20).unwrap();
+        let mut iter = documents.into_iter();
+        assert_matches!(iter.next(), Some(Document { id: DocumentId(1), .. }));
+        assert_matches!(iter.next(), None);
+
+        let builder = store.query_builder();
+        let SortResult { documents, .. } = builder.query(&reader, "éla", 0..20).unwrap();
+        let mut iter = documents.into_iter();
+        assert_matches!(iter.next(), Some(Document { id: DocumentId(0), .. }));
+        assert_matches!(iter.next(), None);
+    }
+
+    #[test]
+    fn documents_sorted_by_their_ranking_score() {
+        let store = TempDatabase::from_iter(vec![
+            ("hello", &[doc_char_index(0, 0, 0), doc_char_index(1, 0, 0)][..]),
+            ("world", &[doc_char_index(0, 1, 1), doc_char_index(1, 5, 5), doc_char_index(2, 1, 1)][..]),
+            ("hallo", &[doc_char_index(2, 0, 0)][..]),
+        ]);
+
+        let db = &store.database;
+        let reader = db.main_read_txn().unwrap();
+
+        let builder = store.query_builder();
+        let SortResult { documents, .. } = builder.query(&reader, "hello world", 0..20).unwrap();
+
+        let ids: Vec<_> = documents.iter().map(|document| document.id).collect();
+        assert_eq!(ids, vec![DocumentId(0), DocumentId(1), DocumentId(2)]);
+
+        // every criterion but the document id one scores the documents
+        let scores: Vec<_> = documents.iter().map(|document| document.ranking_score.clone()).collect();
+        assert!(scores.iter().all(|score| score.len() == 6));
+        assert!(scores.windows(2).all(|pair| pair[0] < pair[1]));
+        // the typos then the proximity of the words
+        assert_eq!(scores[0][0], scores[1][0]);
+        assert!(scores[0][2] < scores[1][2]);
+        assert!(scores[1][0] < scores[2][0]);
+    }
+
+    #[test]
+    fn proximity_read_from_the_word_prefix_pairs() {
+        let store = TempDatabase::from_iter(vec![
+            ("hello", &[doc_char_index(0, 0, 0), doc_char_index(1, 0, 0)][..]),
+            ("world", &[doc_char_index(0, 1, 1), doc_char_index(1, 5, 5)][..]),
+        ]);
+
+        let db = &store.database;
+        let pairs = store.index.word_prefix_pair_proximity_docids;
+
+        // the short prefixes are searched in the words fst
+        let mut writer = db.main_write_txn().unwrap();
+        store.index.main.put_shortest_cached_prefix_len(&mut writer, 3).unwrap();
+        writer.commit().unwrap();
+
+        let reader = db.main_read_txn().unwrap();
+        let mut builder = store.query_builder();
+        builder.with_min_prefix_len(1);
+        let SortResult { documents, .. } = builder.query(&reader, "hello w", 0..20).unwrap();
+        let ids: Vec<_> = documents.iter().map(|document| document.id).collect();
+        assert_eq!(ids, vec![DocumentId(0), DocumentId(1)]);
+        let expected_scores: Vec<_> = documents.iter().map(|document| document.ranking_score.clone()).collect();
+        reader.abort();
+
+        let mut writer = db.main_write_txn().unwrap();
+        let docids = SetBuf::from_dirty(vec![DocumentId(0)]);
+        pairs.put_pair_docids(&mut writer, b"hello", [b'w', 0, 0, 0], 1, &docids).unwrap();
+        writer.commit().unwrap();
+
+        // the pairs give the same ranking as the positions
+        let reader = db.main_read_txn().unwrap();
+        let mut builder = store.query_builder();
+        builder.with_min_prefix_len(1);
+        let SortResult { documents, .. } = builder.query(&reader, "hello w", 0..20).unwrap();
+        let scores: Vec<_> = documents.iter().map(|document| document.ranking_score.clone()).collect();
+        assert_eq!(scores, expected_scores);
+        reader.abort();
+
+        let mut writer = db.main_write_txn().unwrap();
+        let docids = SetBuf::from_dirty(vec![DocumentId(1)]);
+        pairs.put_pair_docids(&mut writer, b"hello", [b'w', 0, 0, 0], 1, &docids).unwrap();
+        writer.commit().unwrap();
+
+        // the positions of the documents of the pairs are not compared
+        let reader = db.main_read_txn().unwrap();
+        let mut builder = store.query_builder();
+        builder.with_min_prefix_len(1);
+        let SortResult { documents, .. } = builder.query(&reader, "hello w", 0..20).unwrap();
+        assert!(expected_scores[0][2] < expected_scores[1][2]);
+        assert_eq!(documents[0].ranking_score[2], documents[1].ranking_score[2]);
+
+        // the pairs are only read for the prefixes of one or two bytes
+        let mut builder = store.query_builder();
+        builder.with_min_prefix_len(1);
+        let SortResult { documents, .. } = builder.query(&reader, "hello world", 0..20).unwrap();
+        let ids: Vec<_> = documents.iter().map(|document| document.id).collect();
+        assert_eq!(ids, vec![DocumentId(0), DocumentId(1)]);
+    }
+
+    #[test]
+    fn words_searched_by_their_stem() {
+        let store = TempDatabase::from_iter(vec![
+            ("run", &[doc_char_index(0, 0, 0)][..]),
+            ("running", &[doc_char_index(1, 0, 0)][..]),
+        ]);
+
+        let db = &store.database;
+        let reader = db.main_read_txn().unwrap();
+
+        let builder = store.query_builder();
+        let SortResult { documents, .. } = builder.query(&reader, "running", 0..20).unwrap();
+        let mut iter = documents.into_iter();
+        assert_matches!(iter.next(), Some(Document { id: DocumentId(1), .. }));
+        assert_matches!(iter.next(), None);
+
+        // the exact word is ranked before its stem
+        let mut builder = store.query_builder();
+        builder.with_stemmer(SnowballStemmer::new(StemmerLanguage::English));
+        let SortResult { documents, .. } = builder.query(&reader, "running", 0..20).unwrap();
+        let mut iter = documents.into_iter();
+        assert_matches!(iter.next(), Some(Document { id: DocumentId(1), .. }));
+        assert_matches!(iter.next(), Some(Document { id: DocumentId(0), .. }));
+        assert_matches!(iter.next(), None);
+    }
+
+    #[test]
+    fn query_too_complex() {
+        let store = TempDatabase::from_iter(vec![
+            ("iphone", &[doc_char_index(0, 0, 0)][..]),
+            ("from", &[doc_char_index(0, 1, 1)][..]),
+            ("apple", &[doc_char_index(0, 2, 2)][..]),
+        ]);
+
+        let db = &store.database;
+        let reader = db.main_read_txn().unwrap();
+
+        let mut builder = store.query_builder();
+        builder.with_max_automaton_count(5);
+        let result = builder.query(&reader, "iphone from apple", 0..20);
+        assert_matches!(result, Err(Error::QueryTooComplex { automaton_count }) if automaton_count > 5);
+
+        let builder = store.query_builder();
+        let SortResult { documents, .. } = builder.query(&reader, "iphone from apple", 0..20).unwrap();
+        assert_eq!(documents.len(), 1);
+    }
+
+    #[test]
+    fn minimum_should_match() {
+        let store = TempDatabase::from_iter(vec![
+            ("iphone", &[doc_char_index(0, 0, 0), doc_char_index(1, 0, 0)][..]),
+            ("from", &[doc_char_index(0, 1, 1)][..]),
+            ("apple", &[doc_char_index(0, 2, 2), doc_char_index(1, 1, 1), doc_char_index(2, 0, 0)][..]),
+        ]);
+
+        let db = &store.database;
+        let reader = db.main_read_txn().unwrap();
+
+        let builder = store.query_builder();
+        let SortResult { documents, .. } = builder.query(&reader, "iphone from apple", 0..20).unwrap();
+        assert_eq!(documents.len(), 1);
+
+        let mut builder = store.query_builder();
+        builder.with_minimum_should_match(0.6);
+        let SortResult { documents, nb_hits, .. } = builder.query(&reader, "iphone from apple", 0..20).unwrap();
+        assert_eq!(nb_hits, 2);
+        let mut iter = documents.into_iter();
+        assert_matches!(iter.next(), Some(Document { id: DocumentId(0), .. }));
+        assert_matches!(iter.next(), Some(Document { id: DocumentId(1), .. }));
+        assert_matches!(iter.next(), None);
+
+        let mut builder = store.query_builder();
+        builder.with_minimum_should_match(0.3);
+        let SortResult { documents, .. } = builder.query(&reader, "iphone from apple", 0..20).unwrap();
+        assert_eq!(documents.len(), 3);
+    }
+
+    #[test]
+    fn search_after_cursor() {
+        let store = TempDatabase::from_iter(vec![
+            ("hello", &[doc_index(0, 0), doc_index(1, 0), doc_index(2, 0), doc_index(3, 0), doc_index(4, 0)][..]),
+        ]);
+
+        let db = &store.database;
+        let reader = db.main_read_txn().unwrap();
+
+        let builder = store.query_builder();
+        let SortResult { documents, next_cursor, .. } = builder.query(&reader, "hello", 0..2).unwrap();
+        let ids: Vec<_> = documents.iter().map(|d| d.id).collect();
+        assert_eq!(ids, vec![DocumentId(0), DocumentId(1)]);
+        assert_eq!(next_cursor, Some(SearchCursor::new(DocumentId(1), 2)));
+
+        let cursor: SearchCursor = next_cursor.unwrap().to_string().parse().unwrap();
+        let mut builder = store.query_builder();
+        builder.with_search_after(cursor);
+        let SortResult { documents, next_cursor, .. } = builder.query(&reader, "hello", 0..2).unwrap();
+        let ids: Vec<_> = documents.iter().map(|d| d.id).collect();
+        assert_eq!(ids, vec![DocumentId(2), DocumentId(3)]);
+        assert_eq!(next_cursor, Some(SearchCursor::new(DocumentId(3), 4)));
+
+        // the cursor document does not match anymore, the search resumes at its position
+        let mut builder = store.query_builder();
+        builder.with_search_after(SearchCursor::new(DocumentId(42), 4));
+        let SortResult { documents, next_cursor, .. } = builder.query(&reader, "hello", 0..2).unwrap();
+        let ids: Vec<_> = documents.iter().map(|d| d.id).collect();
+        assert_eq!(ids, vec![DocumentId(4)]);
+        assert_eq!(next_cursor, Some(SearchCursor::new(DocumentId(4), 5)));
+    }
+
+    #[test]
+    fn boosted_query_words() {
+        let store = TempDatabase::from_iter(vec![
+            ("hello", &[doc_char_index(0, 0, 0)][..]),
+            ("world", &[doc_char_index(1, 0, 0)][..]),
+        ]);
+
+        let db = &store.database;
+        let reader = db.main_read_txn().unwrap();
+
+        let mut builder = store.query_builder();
+        builder.with_minimum_should_match(0.5);
+        let SortResult { documents, .. } = builder.query(&reader, "hello world^3", 0..20).unwrap();
+        let mut iter = documents.into_iter();
+        assert_matches!(iter.next(), Some(Document { id: DocumentId(1), .. }));
+        assert_matches!(iter.next(), Some(Document { id: DocumentId(0), .. }));
+        assert_matches!(iter.next(), None);
+
+        let mut builder = store.query_builder();
+        builder.with_minimum_should_match(0.5);
+        let SortResult { documents, .. } = builder.query(&reader, "hello^2.5 world", 0..20).unwrap();
+        let mut iter = documents.into_iter();
+        assert_matches!(iter.next(), Some(Document { id: DocumentId(0), .. }));
+        assert_matches!(iter.next(), Some(Document { id: DocumentId(1), .. }));
+        assert_matches!(iter.next(), None);
+    }
+
+    #[test]
+    fn boosted_query_words_typos() {
+        let store = TempDatabase::from_iter(vec![
+            ("hello", &[doc_char_index(0, 0, 0)][..]),
+            ("warld", &[doc_char_index(0, 1, 1)][..]),
+            ("hallo", &[doc_char_index(1, 0, 0)][..]),
+            ("world", &[doc_char_index(1, 1, 1)][..]),
+        ]);
+
+        let db = &store.database;
+        let reader = db.main_read_txn().unwrap();
+
+        // all the words are required, the typos of the boosted words count more
+        let builder = store.query_builder();
+        let SortResult { documents, .. } = builder.query(&reader, "hello world^3", 0..20).unwrap();
+        let mut iter = documents.into_iter();
+        assert_matches!(iter.next(), Some(Document { id: DocumentId(1), .. }));
+        assert_matches!(iter.next(), Some(Document { id: DocumentId(0), .. }));
+        assert_matches!(iter.next(), None);
+
+        let builder = store.query_builder();
+        let SortResult { documents, .. } = builder.query(&reader, "hello^1.5 world", 0..20).unwrap();
+        let mut iter = documents.into_iter();
+        assert_matches!(iter.next(), Some(Document { id: DocumentId(0), .. }));
+        assert_matches!(iter.next(), Some(Document { id: DocumentId(1), .. }));
+        assert_matches!(iter.next(), None);
+    }
+
+    #[test]
+    fn query_stop_words() {
+        let store = TempDatabase::from_iter(vec![
+            ("the", &[doc_char_index(0, 0, 0)][..]),
+            ("quick", &[doc_char_index(1, 0, 0)][..]),
+        ]);
+
+        let db = &store.database;
+        let mut writer = db.main_write_txn().unwrap();
+        let stop_words = Set::from_iter(vec!["the"]).unwrap();
+        store.index.main.put_stop_words_fst(&mut writer, &stop_words).unwrap();
+        writer.commit().unwrap();
+
+        let reader = db.main_read_txn().unwrap();
+
+        let builder = store.query_builder();
+        let SortResult { documents, .. } = builder.query(&reader, "the quick", 0..20).unwrap();
+        let mut iter = documents.into_iter();
+        assert_matches!(iter.next(), Some(Document { id: DocumentId(1), .. }));
+        assert_matches!(iter.next(), None);
+
+        // the query only made of stop words is not emptied
+        let builder = store.query_builder();
+        let SortResult { documents, .. } = builder.query(&reader, "the", 0..20).unwrap();
+        let mut iter = documents.into_iter();
+        assert_matches!(iter.next(), Some(Document { id: DocumentId(0), .. }));
+        assert_matches!(iter.next(), None);
+
+        assert_eq!(crate::stopped_words(&stop_words, "The quick"), vec!["the".to_string()]);
+        assert!(crate::stopped_words(&stop_words, "The").is_empty());
+    }
+
+    #[test]
+    fn document_boost_reranker() {
+        let store = TempDatabase::from_iter(vec![
+            ("hello", &[doc_index(0, 0), doc_index(1, 0), doc_index(2, 0)][..]),
+        ]);
+
+        let db = &store.database;
+        let mut writer = db.main_write_txn().unwrap();
+        store.index.document_boosts.put_boost(&mut writer, DocumentId(2), 4.0).unwrap();
+        store.index.document_boosts.put_boost(&mut writer, DocumentId(0), 0.4).unwrap();
+        writer.commit().unwrap();
+
+        let reader = db.main_read_txn().unwrap();
+        let mut builder = store.query_builder();
+        builder.with_reranker(DocumentBoostReranker::new(&reader, store.index.document_boosts));
+        let SortResult { documents, .. } = builder.query(&reader, "hello", 0..20).unwrap();
+        let ids: Vec<_> = documents.iter().map(|d| d.id).collect();
+        assert_eq!(ids, vec![DocumentId(2), DocumentId(1), DocumentId(0)]);
+    }
+
     #[test]
     fn simple_synonyms() {
         let mut store = TempDatabase::from_iter(vec![("hello", &[doc_index(0, 0)][..])]);
@@ -1276,6 +1771,38 @@ mod tests {
         assert_matches!(iter.next(), None);
     }
 
+    #[test]
+    fn phrase_query_with_slop() {
+        let store = TempDatabase::from_iter(vec![
+            ("search", &[doc_index(0, 0)][..]),
+            ("engine", &[doc_index(0, 1)][..]),
+            ("search", &[doc_index(1, 0)][..]),
+            ("slow", &[doc_index(1, 1)][..]),
+            ("engine", &[doc_index(1, 2)][..]),
+            ("search", &[doc_index(2, 0)][..]),
+            ("very", &[doc_index(2, 1)][..]),
+            ("slow", &[doc_index(2, 2)][..]),
+            ("engine", &[doc_index(2, 3)][..]),
+        ]);
+
+        let db = &store.database;
+        let reader = db.main_read_txn().unwrap();
+
+        let mut builder = store.query_builder();
+        builder.with_phrase_slop(1);
+        let SortResult {documents, .. } = builder.query(&reader, "searchengine", 0..20).unwrap();
+        let mut iter = documents.into_iter();
+
+        assert_matches!(iter.next(), Some(Document { id: DocumentId(0), .. }));
+        assert_matches!(iter.next(), Some(Document { id: DocumentId(1), matches, .. }) => {
+            let mut iter = matches.into_iter();
+            assert_matches!(iter.next(), Some(SimpleMatch { query_index: 0, word_index: 0, slop: 1, .. })); // search
+            assert_matches!(iter.next(), Some(SimpleMatch { query_index: 0, word_index: 2, slop: 1, .. })); // engine
+            assert_matches!(iter.next(), None);
+        });
+        assert_matches!(iter.next(), None);
+    }
+
     #[test]
     fn harder_phrase_query_splitting() {
         let store = TempDatabase::from_iter(vec![
@@ -1313,4 +1840,54 @@ mod tests {
         });
         assert_matches!(iter.next(), None);
     }
+
+    #[test]
+    fn automaton_generation_stats() {
+        let mut store = TempDatabase::from_iter(vec![("hello", &[doc_index(0, 0)][..])]);
+
+        store.add_synonym("bonjour", SetBuf::from_dirty(vec!["hello"]));
+
+        let db = &store.database;
+        let reader = db.main_read_txn().unwrap();
+
+        let builder = store.query_builder();
+        let SortResult { automaton_stats, .. } = builder.query(&reader, "bonjour world", 0..20).unwrap();
+
+        // "bonjour", "world" and "bonjour world"
+        assert_eq!(automaton_stats.ngrams_explored, 3);
+        assert_eq!(automaton_stats.synonym_matches, 1);
+        assert_eq!(automaton_stats.split_frequency_used, 0);
+        assert!(automaton_stats.total_automatons > 0);
+    }
+
+    #[test]
+    fn multiword_synonyms_with_missing_words_skipped() {
+        let mut store = TempDatabase::from_iter(vec![
+            ("new", &[doc_char_index(0, 0, 0)][..]),
+            ("subway", &[doc_char_index(0, 1, 1)][..]),
+            ("subway", &[doc_char_index(1, 0, 0)][..]),
+        ]);
+
+        // "york" is not indexed, the synonym can't match any document
+        store.add_synonym("NY", SetBuf::from_dirty(vec!["new york"]));
+
+        let db = &store.database;
+        let reader = db.main_read_txn().unwrap();
+
+        let builder = store.query_builder();
+        let SortResult { documents, skipped_groups, .. } = builder.query(&reader, "NY", 0..20).unwrap();
+        assert!(documents.is_empty());
+        assert_eq!(skipped_groups, 1);
+
+        let builder = store.query_builder();
+        let SortResult { documents, skipped_groups, .. } = builder.query(&reader, "NY subway", 0..20).unwrap();
+        assert!(documents.is_empty());
+        assert_eq!(skipped_groups, 1);
+
+        // the groups of indexed words are not skipped
+        let builder = store.query_builder();
+        let SortResult { documents, skipped_groups, .. } = builder.query(&reader, "new subway", 0..20).unwrap();
+        assert_eq!(documents.len(), 1);
+        assert_eq!(skipped_groups, 0);
+    }
 }
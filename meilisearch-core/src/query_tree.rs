@@ -1,5 +1,5 @@
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::hash::{Hash, Hasher};
 use std::ops::Range;
 use std::time::Instant;
@@ -9,12 +9,14 @@ use fst::{IntoStreamer, Streamer};
 use itertools::{EitherOrBoth, merge_join_by};
 use meilisearch_tokenizer::split_query_string;
 use sdset::{Set, SetBuf, SetOperation};
-use log::debug;
+use slice_group_by::GroupBy;
+use log::{debug, trace};
+use serde::{Deserialize, Serialize};
 
 use crate::database::MainT;
-use crate::{store, DocumentId, DocIndex, MResult};
-use crate::automaton::{normalize_str, build_dfa, build_prefix_dfa, build_exact_dfa};
-use crate::QueryWordsMapper;
+use crate::{store, DocumentId, DocIndex, Error, MResult};
+use crate::automaton::{build_dfa, build_prefix_dfa, build_exact_dfa, NormalizationMode};
+use crate::{QueryWordsMapper, Stemmer};
 
 #[derive(Clone, PartialEq, Eq, Hash)]
 pub enum Operation {
@@ -112,12 +114,57 @@ pub struct PostingsList {
     matches: SetBuf<DocIndex>,
 }
 
-pub struct Context {
+pub struct Context<'s> {
     pub words_set: fst::Set,
     pub stop_words: fst::Set,
     pub synonyms: store::Synonyms,
     pub postings_lists: store::PostingsLists,
     pub prefix_postings_lists: store::PrefixPostingsListsCache,
+    pub word_frequency: store::WordFrequency,
+    /// The prefix postings lists cache is being rebuilt in the background
+    /// and must not be used, the words fst is scanned instead.
+    pub short_prefixes_stale: bool,
+    /// The length of the shortest prefixes in the prefix postings lists cache,
+    /// the shorter prefixes are searched in the words fst.
+    pub shortest_cached_prefix_len: usize,
+    /// The maximum number of automatons the query can generate.
+    pub max_automaton_count: usize,
+    /// The number of characters below which the words are not searched as prefixes.
+    pub min_prefix_len: usize,
+    /// The query words are also searched by their stem when the stem differs from them.
+    pub stemmer: Option<&'s dyn Stemmer>,
+    /// The number of words allowed between the words of a phrase, 0 means they must be adjacent.
+    pub phrase_slop: usize,
+    /// How the words have been normalized by the indexer, the query words are normalized the same way.
+    pub normalization_mode: NormalizationMode,
+}
+
+/// Returns the matches of the first word of a phrase followed in the same attribute by the matches
+/// of the second word, with at most `slop` words between them, and the number of words between them.
+fn phrase_matches<'m>(first: &'m [DocIndex], second: &'m [DocIndex], slop: usize) -> Vec<(u8, &'m DocIndex, &'m DocIndex)> {
+    let key = |m: &DocIndex| (m.document_id, m.attribute);
+    let firsts = first.linear_group_by_key(key);
+    let seconds = second.linear_group_by_key(key);
+
+    let mut pairs = Vec::new();
+    for (lefts, rights) in merge_join_by(firsts, seconds, |a, b| key(&a[0]).cmp(&key(&b[0]))).filter_map(EitherOrBoth::both) {
+        // the matches of an attribute are sorted by word index, the window of the
+        // second words following the first word only moves forward
+        let mut start = 0;
+        for a in lefts {
+            let next_index = a.word_index as usize + 1;
+            while start < rights.len() && (rights[start].word_index as usize) < next_index {
+                start += 1;
+            }
+
+            for b in rights[start..].iter().take_while(|b| b.word_index as usize - next_index <= slop) {
+                let words_between = cmp::min(b.word_index as usize - next_index, u8::max_value() as usize) as u8;
+                pairs.push((words_between, a, b));
+            }
+        }
+    }
+
+    pairs
 }
 
 fn split_best_frequency<'a>(reader: &heed::RoTxn<MainT>, ctx: &Context, word: &'a str) -> MResult<Option<(&'a str, &'a str)>> {
@@ -127,14 +174,8 @@ fn split_best_frequency<'a>(reader: &heed::RoTxn<MainT>, ctx: &Context, word: &'
     for (i, _) in chars {
         let (left, right) = word.split_at(i);
 
-        let left_freq = ctx.postings_lists
-            .postings_list(reader, left.as_bytes())?
-            .map(|p| p.docids.len())
-            .unwrap_or(0);
-        let right_freq = ctx.postings_lists
-            .postings_list(reader, right.as_bytes())?
-            .map(|p| p.docids.len())
-            .unwrap_or(0);
+        let left_freq = ctx.word_frequency.frequency(reader, left)?.unwrap_or(0);
+        let right_freq = ctx.word_frequency.frequency(reader, right)?.unwrap_or(0);
 
         let min_freq = cmp::min(left_freq, right_freq);
         if min_freq != 0 && best.map_or(true, |(old, _, _)| min_freq > old) {
@@ -146,7 +187,7 @@ fn split_best_frequency<'a>(reader: &heed::RoTxn<MainT>, ctx: &Context, word: &'
 }
 
 fn fetch_synonyms(reader: &heed::RoTxn<MainT>, ctx: &Context, words: &[&str]) -> MResult<Vec<Vec<String>>> {
-    let words = normalize_str(&words.join(" "));
+    let words = ctx.normalization_mode.normalize(&words.join(" "));
     let set = ctx.synonyms.synonyms(reader, words.as_bytes())?.unwrap_or_default();
 
     let mut strings = Vec::new();
@@ -174,16 +215,116 @@ where I: IntoIterator<Item=Operation>,
 
 const MAX_NGRAM: usize = 3;
 
+/// The default maximum number of automatons a query can generate, the number
+/// of alternatives grows exponentially with the number of words of the query.
+pub const DEFAULT_MAX_AUTOMATON_COUNT: usize = 100_000;
+
+fn automaton_count(operation: &Operation) -> usize {
+    match operation {
+        Operation::And(ops) | Operation::Or(ops) => ops.iter().map(automaton_count).sum(),
+        Operation::Query(_) => 1,
+    }
+}
+
+/// What has been explored to create the query tree of a query.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AutomatonGenerationStats {
+    /// The number of groups of consecutive words, up to the maximum ngram, given alternatives.
+    pub ngrams_explored: usize,
+    /// The number of synonyms alternatives found for these groups.
+    pub synonym_matches: usize,
+    /// The number of words split in the two most frequent words they are made of.
+    pub split_frequency_used: usize,
+    /// The number of queries of the tree, each one is an automaton searched in the words fst.
+    pub total_automatons: usize,
+    pub duration_us: u64,
+}
+
+/// Splits the `^` boost suffix of a query word, e.g. `word^3`, from the word.
+/// The words without a valid, strictly positive, boost have a weight of 1.0.
+pub fn split_query_weight(word: &str) -> (&str, f32) {
+    if let Some(pos) = word.rfind('^') {
+        let (word, boost) = (&word[..pos], &word[pos + 1..]);
+        match boost.parse::<f32>() {
+            Ok(weight) if !word.is_empty() && weight.is_finite() && weight > 0.0 => return (word, weight),
+            _ => (),
+        }
+    }
+
+    (word, 1.0)
+}
+
+/// Splits a query into words along with their weight, the `^` boost suffixes are removed
+/// before the query is tokenized, the boost of `word^2.5` must not be split on its dot.
+pub fn split_weighted_query(query: &str) -> impl Iterator<Item = (&str, f32)> {
+    query.split_whitespace().flat_map(|part| {
+        let (part, weight) = split_query_weight(part);
+        split_query_string(part).map(move |word| (word, weight))
+    })
+}
+
+/// Returns the words of the query that are ignored by the search
+/// because they are part of the stop words.
+pub fn stopped_words(stop_words: &fst::Set, query: &str) -> Vec<String> {
+    let words: Vec<_> = split_weighted_query(query)
+        .map(|(w, _)| w.to_lowercase())
+        .collect();
+
+    // a query only made of stop words is searched as is
+    if words.iter().all(|w| stop_words.contains(w)) {
+        return Vec::new();
+    }
+
+    words.into_iter().filter(|w| stop_words.contains(w)).collect()
+}
+
+/// Returns the normalized words of the query along with their weight.
+fn query_words(ctx: &Context, query: &str) -> (Vec<(usize, String)>, Vec<f32>) {
+    let words = split_weighted_query(query);
+    let mut words: Vec<_> = words.map(|(w, weight)| (w.to_lowercase(), weight)).collect();
+
+    // the stop words are ignored unless the query is only made of them
+    if !words.iter().all(|(w, _)| ctx.stop_words.contains(w)) {
+        words.retain(|(w, _)| !ctx.stop_words.contains(w));
+    }
+
+    words
+        .into_iter()
+        .enumerate()
+        .map(|(i, (w, weight))| ((i, ctx.normalization_mode.normalize_lowercased(w)), weight))
+        .unzip()
+}
+
+/// Returns the word and the prefix of a query made of a word followed by a prefix of one
+/// or two bytes, the documents where they are adjacent are stored by the index.
+pub fn word_prefix_pair(ctx: &Context, query: &str) -> Option<(String, [u8; 4])> {
+    if ctx.short_prefixes_stale {
+        return None;
+    }
+
+    let (words, _) = query_words(ctx, query);
+    match words.as_slice() {
+        [(_, word), (_, prefix)] if prefix.len() <= 2 && prefix.chars().count() >= ctx.min_prefix_len => {
+            let mut array = [0; 4];
+            array[..prefix.len()].copy_from_slice(prefix.as_bytes());
+            Some((word.clone(), array))
+        }
+        _ => None,
+    }
+}
+
+/// Creates the query tree of the query, the mapping of the queries to the query words,
+/// the weights of the query words, given by their `^` boost suffix, and what has been
+/// explored to create the tree.
 pub fn create_query_tree(
     reader: &heed::RoTxn<MainT>,
     ctx: &Context,
     query: &str,
-) -> MResult<(Operation, HashMap<QueryId, Range<usize>>)>
+) -> MResult<(Operation, HashMap<QueryId, Range<usize>>, Vec<f32>, AutomatonGenerationStats)>
 {
-    let words = split_query_string(query).map(str::to_lowercase);
-    let words = words.filter(|w| !ctx.stop_words.contains(w));
-    let words: Vec<_> = words.enumerate().collect();
-
+    let before = Instant::now();
+    let (words, weights) = query_words(ctx, query);
     let mut mapper = QueryWordsMapper::new(words.iter().map(|(_, w)| w));
 
     fn create_inner(
@@ -191,12 +332,14 @@ pub fn create_query_tree(
         ctx: &Context,
         mapper: &mut QueryWordsMapper,
         words: &[(usize, String)],
+        stats: &mut AutomatonGenerationStats,
     ) -> MResult<Vec<Operation>>
     {
         let mut alts = Vec::new();
 
         for ngram in 1..=MAX_NGRAM {
             if let Some(group) = words.get(..ngram) {
+                stats.ngrams_explored += 1;
                 let mut group_ops = Vec::new();
 
                 let tail = &words[ngram..];
@@ -216,7 +359,12 @@ pub fn create_query_tree(
                                 Operation::phrase2(id, is_last, ws)
                             });
 
-                        let synonyms = fetch_synonyms(reader, ctx, &[word])?
+                        stats.split_frequency_used += phrase.is_some() as usize;
+
+                        let synonyms = fetch_synonyms(reader, ctx, &[word])?;
+                        stats.synonym_matches += synonyms.len();
+
+                        let synonyms = synonyms
                             .into_iter()
                             .map(|alts| {
                                 let exact = alts.len() == 1;
@@ -237,6 +385,18 @@ pub fn create_query_tree(
 
                         group_alts.push(original);
                         group_alts.extend(synonyms.chain(phrase));
+
+                        // the stem is applied to the normalized word, it is not an exact match
+                        if let Some(stemmer) = ctx.stemmer {
+                            let normalized = ctx.normalization_mode.normalize(word);
+                            let stem = stemmer.stem(&normalized).into_owned();
+                            if !stem.is_empty() && stem != normalized && stem != *word {
+                                let id = idgen.next().unwrap();
+                                mapper.declare(range.clone(), id, &[&stem]);
+                                let kind = QueryKind::Tolerant(stem);
+                                group_alts.push(Operation::Query(Query { id, prefix: is_last, exact: false, kind }));
+                            }
+                        }
                     },
                     words => {
                         let id = words[0].0;
@@ -245,7 +405,10 @@ pub fn create_query_tree(
 
                         let words: Vec<_> = words.iter().map(|(_, s)| s.as_str()).collect();
 
-                        for synonym in fetch_synonyms(reader, ctx, &words)? {
+                        let synonyms = fetch_synonyms(reader, ctx, &words)?;
+                        stats.synonym_matches += synonyms.len();
+
+                        for synonym in synonyms {
                             let exact = synonym.len() == 1;
                             let id = idgen.next().unwrap();
                             mapper.declare(range.clone(), id, &synonym);
@@ -266,10 +429,15 @@ pub fn create_query_tree(
                     }
                 }
 
+                stats.total_automatons += group_alts.iter().map(automaton_count).sum::<usize>();
+                if stats.total_automatons > ctx.max_automaton_count {
+                    return Err(Error::QueryTooComplex { automaton_count: stats.total_automatons });
+                }
+
                 group_ops.push(create_operation(group_alts, Operation::Or));
 
                 if !tail.is_empty() {
-                    let tail_ops = create_inner(reader, ctx, mapper, tail)?;
+                    let tail_ops = create_inner(reader, ctx, mapper, tail, stats)?;
                     group_ops.push(create_operation(tail_ops, Operation::Or));
                 }
 
@@ -280,11 +448,15 @@ pub fn create_query_tree(
         Ok(alts)
     }
 
-    let alternatives = create_inner(reader, ctx, &mut mapper, &words)?;
+    let mut stats = AutomatonGenerationStats::default();
+    let alternatives = create_inner(reader, ctx, &mut mapper, &words, &mut stats)?;
     let operation = Operation::Or(alternatives);
     let mapping = mapper.mapping();
 
-    Ok((operation, mapping))
+    stats.duration_us = before.elapsed().as_micros() as u64;
+    trace!("query tree of {:?} created: {:?}", query, stats);
+
+    Ok((operation, mapping, weights, stats))
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -293,6 +465,8 @@ pub struct PostingsKey<'o> {
     pub input: Vec<u8>,
     pub distance: u8,
     pub is_exact: bool,
+    /// The number of words matched between the words of a phrase.
+    pub slop: u8,
 }
 
 pub type Postings<'o, 'txn> = HashMap<PostingsKey<'o>, Cow<'txn, Set<DocIndex>>>;
@@ -301,6 +475,52 @@ pub type Cache<'o, 'txn> = HashMap<&'o Operation, Cow<'txn, Set<DocumentId>>>;
 pub struct QueryResult<'o, 'txn> {
     pub docids: Cow<'txn, Set<DocumentId>>,
     pub queries: Postings<'o, 'txn>,
+    /// The number of AND groups matching no document because one of their words is not indexed.
+    pub skipped_groups: usize,
+}
+
+/// Returns the documents matching at least the given fraction of the query words, whatever
+/// the queries are, the words matched by a document are those mapped to the queries it matches.
+pub fn minimum_should_match_docids(
+    queries: &Postings,
+    mapping: &HashMap<QueryId, Range<usize>>,
+    fraction: f32,
+) -> SetBuf<DocumentId>
+{
+    let words_count = mapping.values().map(|range| range.end).max().unwrap_or(0);
+    let required = ((words_count as f32 * fraction).ceil() as usize).max(1);
+
+    let mut matched_words: HashMap<DocumentId, HashSet<usize>> = HashMap::new();
+    for (key, matches) in queries {
+        let range = match mapping.get(&key.query.id) {
+            Some(range) => range,
+            None => continue,
+        };
+
+        for group in matches.linear_group_by_key(|m| m.document_id) {
+            matched_words.entry(group[0].document_id).or_default().extend(range.clone());
+        }
+    }
+
+    let docids = matched_words
+        .into_iter()
+        .filter(|(_, words)| words.len() >= required)
+        .map(|(docid, _)| docid)
+        .collect();
+
+    SetBuf::from_dirty(docids)
+}
+
+/// Returns whether the operation is a query of words that must be found exactly
+/// in the words fst and at least one of them is missing from it.
+fn is_missing_word(ctx: &Context, operation: &Operation) -> bool {
+    match operation {
+        Operation::Query(Query { kind: QueryKind::NonTolerant(word), .. }) => !ctx.words_set.contains(word),
+        Operation::Query(Query { kind: QueryKind::Phrase(words), .. }) => {
+            words.iter().any(|word| !ctx.words_set.contains(word))
+        },
+        _ => false,
+    }
 }
 
 pub fn traverse_query_tree<'o, 'txn>(
@@ -314,6 +534,7 @@ pub fn traverse_query_tree<'o, 'txn>(
         ctx: &Context,
         cache: &mut Cache<'o, 'txn>,
         postings: &mut Postings<'o, 'txn>,
+        skipped: &mut usize,
         depth: usize,
         operations: &'o [Operation],
     ) -> MResult<Cow<'txn, Set<DocumentId>>>
@@ -323,11 +544,29 @@ pub fn traverse_query_tree<'o, 'txn>(
         let before = Instant::now();
         let mut results = Vec::new();
 
+        // the groups of the multi-words synonyms often contain words that are not indexed,
+        // they match no document and are skipped before any of their words is executed, the
+        // matches of their indexed words are kept to rank the documents of the other groups
+        if let Some(query) = operations.iter().find(|op| is_missing_word(ctx, op)) {
+            for op in operations {
+                if let Operation::Query(query @ Query { kind: QueryKind::NonTolerant(word), exact, .. }) = op {
+                    if let Some(result) = ctx.postings_lists.postings_list(reader, word.as_bytes())? {
+                        let key = PostingsKey { query, input: word.clone().into_bytes(), distance: 0, is_exact: *exact, slop: 0 };
+                        postings.insert(key, result.matches);
+                    }
+                }
+            }
+
+            *skipped += 1;
+            debug!("{:3$}--- AND skipped, {:?} is not indexed, in {:.02?}", "", query, before.elapsed(), depth * 2);
+            return Ok(Cow::default());
+        }
+
         for op in operations {
             if cache.get(op).is_none() {
                 let docids = match op {
-                    Operation::And(ops) => execute_and(reader, ctx, cache, postings, depth + 1, &ops)?,
-                    Operation::Or(ops) => execute_or(reader, ctx, cache, postings, depth + 1, &ops)?,
+                    Operation::And(ops) => execute_and(reader, ctx, cache, postings, skipped, depth + 1, &ops)?,
+                    Operation::Or(ops) => execute_or(reader, ctx, cache, postings, skipped, depth + 1, &ops)?,
                     Operation::Query(query) => execute_query(reader, ctx, postings, depth + 1, &query)?,
                 };
                 cache.insert(op, docids);
@@ -353,6 +592,7 @@ pub fn traverse_query_tree<'o, 'txn>(
         ctx: &Context,
         cache: &mut Cache<'o, 'txn>,
         postings: &mut Postings<'o, 'txn>,
+        skipped: &mut usize,
         depth: usize,
         operations: &'o [Operation],
     ) -> MResult<Cow<'txn, Set<DocumentId>>>
@@ -365,8 +605,8 @@ pub fn traverse_query_tree<'o, 'txn>(
         for op in operations {
             if cache.get(op).is_none() {
                 let docids = match op {
-                    Operation::And(ops) => execute_and(reader, ctx, cache, postings, depth + 1, &ops)?,
-                    Operation::Or(ops) => execute_or(reader, ctx, cache, postings, depth + 1, &ops)?,
+                    Operation::And(ops) => execute_and(reader, ctx, cache, postings, skipped, depth + 1, &ops)?,
+                    Operation::Or(ops) => execute_or(reader, ctx, cache, postings, skipped, depth + 1, &ops)?,
                     Operation::Query(query) => execute_query(reader, ctx, postings, depth + 1, &query)?,
                 };
                 cache.insert(op, docids);
@@ -400,7 +640,11 @@ pub fn traverse_query_tree<'o, 'txn>(
         let Query { prefix, kind, exact, .. } = query;
         let docids: Cow<Set<_>> = match kind {
             QueryKind::Tolerant(word) => {
-                if *prefix && word.len() <= 2 {
+                // the words shorter than the minimum prefix length only match the words equal to them
+                let prefix = *prefix && word.chars().count() >= ctx.min_prefix_len;
+                // the prefix postings lists cache is keyed by the first bytes of the words
+                let is_cached_prefix = word.len() <= 2 && word.len() >= ctx.shortest_cached_prefix_len;
+                if prefix && is_cached_prefix && !ctx.short_prefixes_stale {
                     let prefix = {
                         let mut array = [0; 4];
                         let bytes = word.as_bytes();
@@ -411,14 +655,14 @@ pub fn traverse_query_tree<'o, 'txn>(
                     // We retrieve the cached postings lists for all
                     // the words that starts with this short prefix.
                     let result = ctx.prefix_postings_lists.prefix_postings_list(reader, prefix)?.unwrap_or_default();
-                    let key = PostingsKey { query, input: word.clone().into_bytes(), distance: 0, is_exact: false };
+                    let key = PostingsKey { query, input: word.clone().into_bytes(), distance: 0, is_exact: false, slop: 0 };
                     postings.insert(key, result.matches);
                     let prefix_docids = &result.docids;
 
                     // We retrieve the exact postings list for the prefix,
                     // because we must consider these matches as exact.
                     let result = ctx.postings_lists.postings_list(reader, word.as_bytes())?.unwrap_or_default();
-                    let key = PostingsKey { query, input: word.clone().into_bytes(), distance: 0, is_exact: true };
+                    let key = PostingsKey { query, input: word.clone().into_bytes(), distance: 0, is_exact: true, slop: 0 };
                     postings.insert(key, result.matches);
                     let exact_docids = &result.docids;
 
@@ -430,7 +674,7 @@ pub fn traverse_query_tree<'o, 'txn>(
                     Cow::Owned(docids)
 
                 } else {
-                    let dfa = if *prefix { build_prefix_dfa(word) } else { build_dfa(word) };
+                    let dfa = if prefix { build_prefix_dfa(word, ctx.min_prefix_len) } else { build_dfa(word) };
 
                     let byte = word.as_bytes()[0];
                     let mut stream = if byte == u8::max_value() {
@@ -446,7 +690,7 @@ pub fn traverse_query_tree<'o, 'txn>(
                             let distance = dfa.eval(input).to_u8();
                             let is_exact = *exact && distance == 0 && input.len() == word.len();
                             results.push(result.docids);
-                            let key = PostingsKey { query, input: input.to_owned(), distance, is_exact };
+                            let key = PostingsKey { query, input: input.to_owned(), distance, is_exact, slop: 0 };
                             postings.insert(key, result.matches);
                         }
                     }
@@ -486,7 +730,7 @@ pub fn traverse_query_tree<'o, 'txn>(
                     if let Some(result) = ctx.postings_lists.postings_list(reader, input)? {
                         let distance = dfa.eval(input).to_u8();
                         results.push(result.docids);
-                        let key = PostingsKey { query, input: input.to_owned(), distance, is_exact: *exact };
+                        let key = PostingsKey { query, input: input.to_owned(), distance, is_exact: *exact, slop: 0 };
                         postings.insert(key, result.matches);
                     }
                 }
@@ -514,26 +758,25 @@ pub fn traverse_query_tree<'o, 'txn>(
                     let first = ctx.postings_lists.postings_list(reader, first.as_bytes())?.unwrap_or_default();
                     let second = ctx.postings_lists.postings_list(reader, second.as_bytes())?.unwrap_or_default();
 
-                    let iter = merge_join_by(first.matches.as_slice(), second.matches.as_slice(), |a, b| {
-                        let x = (a.document_id, a.attribute, (a.word_index as u32) + 1);
-                        let y = (b.document_id, b.attribute, b.word_index as u32);
-                        x.cmp(&y)
-                    });
-
-                    let matches: Vec<_> = iter
-                        .filter_map(EitherOrBoth::both)
-                        .flat_map(|(a, b)| once(*a).chain(Some(*b)))
-                        .collect();
+                    let pairs = phrase_matches(&first.matches, &second.matches, ctx.phrase_slop);
 
                     let before = Instant::now();
-                    let mut docids: Vec<_> = matches.iter().map(|m| m.document_id).collect();
+                    let mut docids: Vec<_> = pairs.iter().map(|(_, a, _)| a.document_id).collect();
                     docids.dedup();
                     let docids = SetBuf::new(docids).unwrap();
                     debug!("{:2$}docids construction took {:.02?}", "", before.elapsed(), depth * 2);
 
-                    let matches = Cow::Owned(SetBuf::from_dirty(matches));
-                    let key = PostingsKey { query, input: vec![], distance: 0, is_exact: true };
-                    postings.insert(key, matches);
+                    // the matches are ranked by the number of words between the words of the phrase
+                    let mut matches_by_slop = BTreeMap::new();
+                    for (slop, a, b) in pairs {
+                        matches_by_slop.entry(slop).or_insert_with(Vec::new).extend(once(*a).chain(Some(*b)));
+                    }
+
+                    for (slop, matches) in matches_by_slop {
+                        let matches = Cow::Owned(SetBuf::from_dirty(matches));
+                        let key = PostingsKey { query, input: vec![], distance: 0, is_exact: true, slop };
+                        postings.insert(key, matches);
+                    }
 
                     Cow::Owned(docids)
                 } else {
@@ -549,12 +792,13 @@ pub fn traverse_query_tree<'o, 'txn>(
 
     let mut cache = Cache::new();
     let mut postings = Postings::new();
+    let mut skipped_groups = 0;
 
     let docids = match tree {
-        Operation::And(ops) => execute_and(reader, ctx, &mut cache, &mut postings, 0, &ops)?,
-        Operation::Or(ops) => execute_or(reader, ctx, &mut cache, &mut postings, 0, &ops)?,
+        Operation::And(ops) => execute_and(reader, ctx, &mut cache, &mut postings, &mut skipped_groups, 0, &ops)?,
+        Operation::Or(ops) => execute_or(reader, ctx, &mut cache, &mut postings, &mut skipped_groups, 0, &ops)?,
         Operation::Query(query) => execute_query(reader, ctx, &mut postings, 0, &query)?,
     };
 
-    Ok(QueryResult { docids, queries: postings })
+    Ok(QueryResult { docids, queries: postings, skipped_groups })
 }
@@ -5,6 +5,12 @@ use intervaltree::{Element, IntervalTree};
 
 pub type QueryId = usize;
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryWordsMapperError {
+    /// The query id is already mapped to another range of the original words.
+    OverlappingRange { id: QueryId, range: Range<usize> },
+}
+
 pub struct QueryWordsMapper {
     originals: Vec<String>,
     mappings: HashMap<QueryId, (Range<usize>, Vec<String>)>,
@@ -19,7 +25,46 @@ impl QueryWordsMapper {
         QueryWordsMapper { originals, mappings: HashMap::new() }
     }
 
+    /// Declares the replacement of the original words in `range`, the words
+    /// of the replacement are given the query ids starting at `id`.
+    ///
+    /// Declaring a query id for another range or replacement is a bug, it panics
+    /// in debug builds and the first declaration is kept otherwise, see `try_declare`.
     pub fn declare<I, A>(&mut self, range: Range<usize>, id: QueryId, replacement: I)
+    where I: IntoIterator<Item = A>,
+          A: ToString,
+    {
+        let entries = self.entries(range, id, replacement);
+        debug_assert_eq!(self.overlapping(&entries), None, "query id declared for another range");
+        for (id, entry) in entries {
+            self.mappings.entry(id).or_insert(entry);
+        }
+    }
+
+    /// Like `declare` but returns an error if one of the query ids
+    /// has already been declared for another range or replacement.
+    pub fn try_declare<I, A>(&mut self, range: Range<usize>, id: QueryId, replacement: I) -> Result<(), QueryWordsMapperError>
+    where I: IntoIterator<Item = A>,
+          A: ToString,
+    {
+        let entries = self.entries(range, id, replacement);
+        if let Some((id, range)) = self.overlapping(&entries) {
+            return Err(QueryWordsMapperError::OverlappingRange { id, range });
+        }
+        self.mappings.extend(entries);
+        Ok(())
+    }
+
+    fn overlapping(&self, entries: &[(QueryId, (Range<usize>, Vec<String>))]) -> Option<(QueryId, Range<usize>)> {
+        entries.iter().find_map(|(id, entry)| {
+            match self.mappings.get(id) {
+                Some(old) if old != entry => Some((*id, old.0.clone())),
+                _ => None,
+            }
+        })
+    }
+
+    fn entries<I, A>(&self, range: Range<usize>, id: QueryId, replacement: I) -> Vec<(QueryId, (Range<usize>, Vec<String>))>
     where I: IntoIterator<Item = A>,
           A: ToString,
     {
@@ -43,26 +88,32 @@ impl QueryWordsMapper {
         let left = &self.originals[..range.start];
         let right = &self.originals[range.end..];
 
-        let common_left = longest_common_prefix(left, &replacement);
-        let common_right = longest_common_prefix(&replacement, right);
+        // at least one word of the replacement is mapped to the range, the callers
+        // only reserve the query ids of the words of the replacement
+        let common_left = longest_common_prefix(left, &replacement).min(replacement.len() - 1);
+        let common_right = longest_common_prefix(&replacement, right).min(replacement.len() - 1 - common_left);
+
+        let mut entries = Vec::with_capacity(common_left + 1 + common_right);
 
         for i in 0..common_left {
             let range = range.start - common_left + i..range.start - common_left + i + 1;
             let replacement = vec![replacement[i].clone()];
-            self.mappings.insert(id + i, (range, replacement));
+            entries.push((id + i, (range, replacement)));
         }
 
         {
             let replacement = replacement[common_left..replacement.len() - common_right].iter().cloned().collect();
-            self.mappings.insert(id + common_left, (range.clone(), replacement));
+            entries.push((id + common_left, (range.clone(), replacement)));
         }
 
         for i in 0..common_right {
             let id = id + replacement.len() - common_right + i;
             let range = range.end + i..range.end + i + 1;
             let replacement = vec![replacement[replacement.len() - common_right + i].clone()];
-            self.mappings.insert(id, (range, replacement));
+            entries.push((id, (range, replacement)));
         }
+
+        entries
     }
 
     pub fn mapping(self) -> HashMap<QueryId, Range<usize>> {
@@ -412,4 +463,55 @@ mod tests {
         assert_eq!(mapping[&10], 1..5); // NY
         assert_eq!(mapping[&11], 2..7); // metro
     }
+
+    #[test]
+    fn overlapping_query_ids() {
+        let query = ["new", "york", "city", "subway"];
+        let mut builder = QueryWordsMapper::new(&query);
+
+        builder.try_declare(0..2, 4, &["nyc"]).unwrap();
+
+        // declaring the same replacement twice is allowed
+        builder.try_declare(0..2, 4, &["nyc"]).unwrap();
+
+        let result = builder.try_declare(2..3, 4, &["town"]);
+        assert_eq!(result, Err(QueryWordsMapperError::OverlappingRange { id: 4, range: 0..2 }));
+
+        // the first range declared is kept
+        assert_eq!(builder.mapping()[&4], 0..2);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "query id declared for another range")]
+    fn overlapping_query_ids_declared() {
+        let query = ["new", "york", "city", "subway"];
+        let mut builder = QueryWordsMapper::new(&query);
+
+        builder.declare(0..2, 4, &["nyc"]);
+        builder.declare(0..2, 4, &["nyc"]);
+        builder.declare(2..3, 4, &["town"]);
+    }
+
+    #[test]
+    fn replacement_equal_to_a_neighbour() {
+        let query = ["walk", "walking"];
+        //              0        1
+        let mut builder = QueryWordsMapper::new(&query);
+
+        // walking = walk, the stem is the same as the previous word
+        builder.declare(1..2, 2, &["walk"]);
+        //                    ^      2
+
+        // walk = stroll
+        builder.declare(0..1, 3, &["stroll"]);
+        //                    ^      3
+
+        let mapping = builder.mapping();
+
+        assert_eq!(mapping[&0], 0..1); // walk
+        assert_eq!(mapping[&1], 1..2); // walking
+        assert_eq!(mapping[&2], 1..2); // walk
+        assert_eq!(mapping[&3], 0..1); // stroll
+    }
 }
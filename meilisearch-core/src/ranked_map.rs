@@ -1,16 +1,33 @@
 use std::io::{Read, Write};
 
-use hashbrown::HashMap;
 use meilisearch_schema::FieldId;
-use serde::{Deserialize, Serialize};
+use sdset::Set;
+use serde::{Deserialize, Deserializer, Serialize};
 
 use crate::{DocumentId, Number};
 
-#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+type Entry = ((DocumentId, FieldId), Number);
+
+/// The ranked values of the documents fields, sorted by document id and field id
+/// to be looked up by binary search.
+///
+/// It is serialized as a sequence of entries, the same layout bincode
+/// uses for a map, the entries are sorted again when deserialized.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
 #[serde(transparent)]
-pub struct RankedMap(HashMap<(DocumentId, FieldId), Number>);
+pub struct RankedMap(Vec<Entry>);
 
 impl RankedMap {
+    fn from_entries(mut entries: Vec<Entry>) -> RankedMap {
+        entries.sort_by_key(|(key, _)| *key);
+        entries.dedup_by_key(|(key, _)| *key);
+        RankedMap(entries)
+    }
+
+    fn position(&self, document: DocumentId, field: FieldId) -> Result<usize, usize> {
+        self.0.binary_search_by_key(&(document, field), |(key, _)| *key)
+    }
+
     pub fn len(&self) -> usize {
         self.0.len()
     }
@@ -20,22 +37,160 @@ impl RankedMap {
     }
 
     pub fn insert(&mut self, document: DocumentId, field: FieldId, number: Number) {
-        self.0.insert((document, field), number);
+        // the documents are mostly indexed with increasing ids, appending is cheap
+        match self.0.last() {
+            Some((key, _)) if *key >= (document, field) => (),
+            _ => return self.0.push(((document, field), number)),
+        }
+
+        match self.position(document, field) {
+            Ok(pos) => self.0[pos].1 = number,
+            Err(pos) => self.0.insert(pos, ((document, field), number)),
+        }
     }
 
     pub fn remove(&mut self, document: DocumentId, field: FieldId) {
-        self.0.remove(&(document, field));
+        if let Ok(pos) = self.position(document, field) {
+            self.0.remove(pos);
+        }
+    }
+
+    /// Removes the values of all the fields of the documents in a single pass.
+    pub fn remove_documents(&mut self, documents: &Set<DocumentId>) {
+        self.0.retain(|((document, _), _)| documents.binary_search(document).is_err());
+    }
+
+    /// Applies the changes collected while indexing, the map is rebuilt once
+    /// instead of moving its entries for every inserted or removed value.
+    pub fn apply(&mut self, changes: RankedMapChanges) {
+        let mut changes = changes.0;
+        if changes.is_empty() {
+            return;
+        }
+
+        // the sort is stable, only the last change of a field is kept
+        changes.sort_by_key(|(key, _)| *key);
+        let mut last_changes: Vec<(_, Option<Number>)> = Vec::with_capacity(changes.len());
+        for (key, change) in changes {
+            match last_changes.last_mut() {
+                Some((last_key, last_change)) if *last_key == key => *last_change = change,
+                _ => last_changes.push((key, change)),
+            }
+        }
+
+        let entries = std::mem::take(&mut self.0);
+        let mut merged = Vec::with_capacity(entries.len() + last_changes.len());
+        let mut entries = entries.into_iter().peekable();
+        for (key, change) in last_changes {
+            while let Some(entry) = entries.next_if(|(entry_key, _)| *entry_key < key) {
+                merged.push(entry);
+            }
+            entries.next_if(|(entry_key, _)| *entry_key == key);
+            if let Some(number) = change {
+                merged.push((key, number));
+            }
+        }
+        merged.extend(entries);
+
+        self.0 = merged;
     }
 
     pub fn get(&self, document: DocumentId, field: FieldId) -> Option<Number> {
-        self.0.get(&(document, field)).cloned()
+        self.position(document, field).ok().map(|pos| self.0[pos].1)
     }
 
     pub fn read_from_bin<R: Read>(reader: R) -> bincode::Result<RankedMap> {
-        bincode::deserialize_from(reader).map(RankedMap)
+        bincode::deserialize_from(reader)
     }
 
     pub fn write_to_bin<W: Write>(&self, writer: W) -> bincode::Result<()> {
         bincode::serialize_into(writer, &self.0)
     }
 }
+
+/// The ranked values inserted and removed by an update, in the order of the changes.
+#[derive(Debug, Default)]
+pub struct RankedMapChanges(Vec<((DocumentId, FieldId), Option<Number>)>);
+
+impl RankedMapChanges {
+    pub fn insert(&mut self, document: DocumentId, field: FieldId, number: Number) {
+        self.0.push(((document, field), Some(number)));
+    }
+
+    pub fn remove(&mut self, document: DocumentId, field: FieldId) {
+        self.0.push(((document, field), None));
+    }
+}
+
+impl<'de> Deserialize<'de> for RankedMap {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<RankedMap, D::Error> {
+        Vec::deserialize(deserializer).map(RankedMap::from_entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashMap;
+
+    use ordered_float::OrderedFloat;
+    use sdset::SetBuf;
+
+    #[test]
+    fn sorted_lookups() {
+        let mut ranked_map = RankedMap::default();
+        ranked_map.insert(DocumentId(2), FieldId(0), Number::Unsigned(20));
+        ranked_map.insert(DocumentId(0), FieldId(1), Number::Unsigned(1));
+        ranked_map.insert(DocumentId(0), FieldId(0), Number::Unsigned(0));
+        ranked_map.insert(DocumentId(2), FieldId(0), Number::Unsigned(21));
+
+        assert_eq!(ranked_map.len(), 3);
+        assert_eq!(ranked_map.get(DocumentId(0), FieldId(1)), Some(Number::Unsigned(1)));
+        assert_eq!(ranked_map.get(DocumentId(2), FieldId(0)), Some(Number::Unsigned(21)));
+        assert_eq!(ranked_map.get(DocumentId(1), FieldId(0)), None);
+
+        ranked_map.remove(DocumentId(0), FieldId(0));
+        assert_eq!(ranked_map.get(DocumentId(0), FieldId(0)), None);
+        assert_eq!(ranked_map.len(), 2);
+    }
+
+    #[test]
+    fn batched_changes_applied() {
+        let mut ranked_map = RankedMap::default();
+        ranked_map.insert(DocumentId(0), FieldId(0), Number::Unsigned(0));
+        ranked_map.insert(DocumentId(1), FieldId(0), Number::Unsigned(10));
+        ranked_map.insert(DocumentId(3), FieldId(0), Number::Unsigned(30));
+
+        let mut changes = RankedMapChanges::default();
+        changes.insert(DocumentId(2), FieldId(0), Number::Unsigned(20));
+        changes.insert(DocumentId(1), FieldId(0), Number::Unsigned(11));
+        changes.remove(DocumentId(0), FieldId(0));
+        changes.insert(DocumentId(4), FieldId(1), Number::Unsigned(40));
+        changes.remove(DocumentId(4), FieldId(1));
+        ranked_map.apply(changes);
+
+        let expected = [(1, 11), (2, 20), (3, 30)];
+        assert_eq!(ranked_map.len(), expected.len());
+        for (document, number) in &expected {
+            assert_eq!(ranked_map.get(DocumentId(*document), FieldId(0)), Some(Number::Unsigned(*number)));
+        }
+        assert_eq!(ranked_map.get(DocumentId(4), FieldId(1)), None);
+
+        ranked_map.remove_documents(&SetBuf::from_dirty(vec![DocumentId(1), DocumentId(3)]));
+        assert_eq!(ranked_map.len(), 1);
+        assert_eq!(ranked_map.get(DocumentId(2), FieldId(0)), Some(Number::Unsigned(20)));
+    }
+
+    #[test]
+    fn read_previous_map_format() {
+        let mut map = HashMap::new();
+        map.insert((DocumentId(3), FieldId(0)), Number::Float(OrderedFloat(3.5)));
+        map.insert((DocumentId(1), FieldId(2)), Number::Signed(-1));
+        let bytes = bincode::serialize(&map).unwrap();
+
+        let ranked_map = RankedMap::read_from_bin(bytes.as_slice()).unwrap();
+        assert_eq!(ranked_map.get(DocumentId(3), FieldId(0)), Some(Number::Float(OrderedFloat(3.5))));
+        assert_eq!(ranked_map.get(DocumentId(1), FieldId(2)), Some(Number::Signed(-1)));
+    }
+}
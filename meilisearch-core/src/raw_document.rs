@@ -13,6 +13,9 @@ pub struct RawDocument<'a, 'tag> {
     /// Does this document contains a field
     /// with one word that is exactly matching
     pub contains_one_word_field: bool,
+    /// The proximity of the query words, when it is known
+    /// without comparing the positions of the matches
+    pub proximity: Option<u16>,
 }
 
 impl<'a, 'tag> RawDocument<'a, 'tag> {
@@ -26,6 +29,11 @@ impl<'a, 'tag> RawDocument<'a, 'tag> {
             for bm in bare_matches.iter() {
                 let postings_list = &postings_lists[bm.postings_list];
 
+                // the postings lists are only rewritten when their attributes are reordered or removed
+                if postings_list.iter().all(|di| reordered_attrs.get(di.attribute) == Some(di.attribute)) {
+                    continue;
+                }
+
                 let mut rewritten = Vec::new();
                 for di in postings_list.iter() {
                     if let Some(attribute) = reordered_attrs.get(di.attribute) {
@@ -46,6 +54,7 @@ impl<'a, 'tag> RawDocument<'a, 'tag> {
             processed_matches: Vec::new(),
             processed_distances: Vec::new(),
             contains_one_word_field: false,
+            proximity: None,
         }
     }
 }
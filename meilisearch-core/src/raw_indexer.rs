@@ -1,7 +1,7 @@
 use std::collections::{BTreeMap, HashMap};
 use std::convert::TryFrom;
 
-use crate::{DocIndex, DocumentId};
+use crate::{DocIndex, DocumentId, NormalizationMode};
 use deunicode::deunicode_with_tofu;
 use meilisearch_schema::IndexedPos;
 use meilisearch_tokenizer::{is_cjk, SeqTokenizer, Token, Tokenizer};
@@ -11,9 +11,41 @@ const WORD_LENGTH_LIMIT: usize = 80;
 
 type Word = Vec<u8>; // TODO make it be a SmallVec
 
+/// The lengths of the n-grams indexed for the runs of CJK characters.
+const CJK_NGRAM_LENGTHS: [usize; 2] = [2, 3];
+
+/// The maximum number of tokens indexed, the following tokens are not indexed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenLimits {
+    pub max_tokens_per_field: usize,
+    pub max_tokens_per_document: usize,
+}
+
+impl Default for TokenLimits {
+    fn default() -> TokenLimits {
+        TokenLimits {
+            max_tokens_per_field: 1000,
+            max_tokens_per_document: 100_000,
+        }
+    }
+}
+
+/// The token limit that truncated the tokenization of a field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenLimitExceeded {
+    Field(usize),
+    Document(usize),
+}
+
 pub struct RawIndexer {
     word_limit: usize, // the maximum number of indexed words
+    token_limits: TokenLimits,
+    normalization_mode: NormalizationMode,
+    documents_tokens: HashMap<DocumentId, usize>,
+    token_limit_exceeded: Option<TokenLimitExceeded>,
     stop_words: fst::Set,
+    languages_stop_words: HashMap<String, fst::Set>,
+    documents_languages: HashMap<DocumentId, String>,
     words_doc_indexes: BTreeMap<Word, Vec<DocIndex>>,
     docs_words: HashMap<DocumentId, Vec<Word>>,
 }
@@ -21,6 +53,7 @@ pub struct RawIndexer {
 pub struct Indexed {
     pub words_doc_indexes: BTreeMap<Word, SetBuf<DocIndex>>,
     pub docs_words: HashMap<DocumentId, fst::Set>,
+    pub docs_word_counts: HashMap<DocumentId, BTreeMap<Word, u32>>,
 }
 
 impl RawIndexer {
@@ -31,27 +64,96 @@ impl RawIndexer {
     pub fn with_word_limit(stop_words: fst::Set, limit: usize) -> RawIndexer {
         RawIndexer {
             word_limit: limit,
+            token_limits: TokenLimits::default(),
+            normalization_mode: NormalizationMode::default(),
+            documents_tokens: HashMap::new(),
+            token_limit_exceeded: None,
             stop_words,
+            languages_stop_words: HashMap::new(),
+            documents_languages: HashMap::new(),
             words_doc_indexes: BTreeMap::new(),
             docs_words: HashMap::new(),
         }
     }
 
+    /// Sets the stop words of each language, they replace the default
+    /// stop words for the documents declaring one of these languages.
+    pub fn set_languages_stop_words(&mut self, languages_stop_words: HashMap<String, fst::Set>) {
+        self.languages_stop_words = languages_stop_words;
+    }
+
+    pub fn set_token_limits(&mut self, token_limits: TokenLimits) {
+        self.token_limits = token_limits;
+    }
+
+    pub fn set_normalization_mode(&mut self, normalization_mode: NormalizationMode) {
+        self.normalization_mode = normalization_mode;
+    }
+
+    /// Returns the limit that truncated the last field indexed, if any, and forgets it.
+    pub fn take_token_limit_exceeded(&mut self) -> Option<TokenLimitExceeded> {
+        self.token_limit_exceeded.take()
+    }
+
+    /// Returns the limit reached if one more token was indexed in the field.
+    fn token_limit_reached(&self, id: DocumentId, field_tokens: usize) -> Option<TokenLimitExceeded> {
+        let TokenLimits { max_tokens_per_field, max_tokens_per_document } = self.token_limits;
+        let document_tokens = self.documents_tokens.get(&id).copied().unwrap_or(0);
+
+        // the field limit is reported first, the next fields can still be indexed
+        if field_tokens >= max_tokens_per_field {
+            Some(TokenLimitExceeded::Field(max_tokens_per_field))
+        } else if document_tokens >= max_tokens_per_document {
+            Some(TokenLimitExceeded::Document(max_tokens_per_document))
+        } else {
+            None
+        }
+    }
+
+    /// Declares the language of a document, identified by its ISO 639-1 code.
+    pub fn set_document_language(&mut self, id: DocumentId, language: &str) {
+        self.documents_languages.insert(id, language.to_lowercase());
+    }
+
     pub fn index_text(&mut self, id: DocumentId, indexed_pos: IndexedPos, text: &str) -> usize {
         let mut number_of_words = 0;
 
+        let languages_stop_words = &self.languages_stop_words;
+        let stop_words = self.documents_languages.get(&id)
+            .and_then(|language| languages_stop_words.get(language))
+            .unwrap_or(&self.stop_words);
+
+        let mut cjk_ngrams = CjkNgramIndexer::default();
         for token in Tokenizer::new(text) {
+            if let Some(limit) = self.token_limit_reached(id, number_of_words) {
+                self.token_limit_exceeded = Some(limit);
+                break;
+            }
+
             let must_continue = index_token(
                 token,
                 id,
                 indexed_pos,
                 self.word_limit,
-                &self.stop_words,
+                self.normalization_mode,
+                stop_words,
                 &mut self.words_doc_indexes,
                 &mut self.docs_words,
             );
 
+            if must_continue {
+                cjk_ngrams.index_token(
+                    token,
+                    id,
+                    indexed_pos,
+                    stop_words,
+                    &mut self.words_doc_indexes,
+                    &mut self.docs_words,
+                );
+            }
+
             number_of_words += 1;
+            *self.documents_tokens.entry(id).or_insert(0) += 1;
 
             if !must_continue {
                 break;
@@ -65,18 +167,45 @@ impl RawIndexer {
     where
         I: IntoIterator<Item = &'a str>,
     {
+        let languages_stop_words = &self.languages_stop_words;
+        let stop_words = self.documents_languages.get(&id)
+            .and_then(|language| languages_stop_words.get(language))
+            .unwrap_or(&self.stop_words);
+
         let iter = iter.into_iter();
+        let mut number_of_words = 0;
+        let mut cjk_ngrams = CjkNgramIndexer::default();
         for token in SeqTokenizer::new(iter) {
+            if let Some(limit) = self.token_limit_reached(id, number_of_words) {
+                self.token_limit_exceeded = Some(limit);
+                break;
+            }
+
             let must_continue = index_token(
                 token,
                 id,
                 indexed_pos,
                 self.word_limit,
-                &self.stop_words,
+                self.normalization_mode,
+                stop_words,
                 &mut self.words_doc_indexes,
                 &mut self.docs_words,
             );
 
+            if must_continue {
+                cjk_ngrams.index_token(
+                    token,
+                    id,
+                    indexed_pos,
+                    stop_words,
+                    &mut self.words_doc_indexes,
+                    &mut self.docs_words,
+                );
+            }
+
+            number_of_words += 1;
+            *self.documents_tokens.entry(id).or_insert(0) += 1;
+
             if !must_continue {
                 break;
             }
@@ -90,10 +219,18 @@ impl RawIndexer {
             .map(|(word, indexes)| (word, SetBuf::from_dirty(indexes)))
             .collect();
 
+        let mut docs_word_counts = HashMap::new();
         let docs_words = self
             .docs_words
             .into_iter()
             .map(|(id, mut words)| {
+                // words are pushed once by occurrence, count them before deduplicating
+                let mut counts = BTreeMap::new();
+                for word in &words {
+                    *counts.entry(word.clone()).or_insert(0) += 1;
+                }
+                docs_word_counts.insert(id, counts);
+
                 words.sort_unstable();
                 words.dedup();
                 (id, fst::Set::from_iter(words).unwrap())
@@ -103,6 +240,75 @@ impl RawIndexer {
         Indexed {
             words_doc_indexes,
             docs_words,
+            docs_word_counts,
+        }
+    }
+}
+
+/// Indexes the overlapping bigrams and trigrams of the runs of CJK characters, each CJK
+/// character being tokenized as its own word. The n-grams are stored along the other words,
+/// at the position of their first character, the query tree searches the concatenation of
+/// the consecutive query words and therefore finds them.
+#[derive(Default)]
+struct CjkNgramIndexer<'a> {
+    run: Vec<Token<'a>>,
+}
+
+impl<'a> CjkNgramIndexer<'a> {
+    fn index_token(
+        &mut self,
+        token: Token<'a>,
+        id: DocumentId,
+        indexed_pos: IndexedPos,
+        stop_words: &fst::Set,
+        words_doc_indexes: &mut BTreeMap<Word, Vec<DocIndex>>,
+        docs_words: &mut HashMap<DocumentId, Vec<Word>>,
+    ) {
+        let mut chars = token.word.chars();
+        let is_cjk_char = match (chars.next(), chars.next()) {
+            (Some(c), None) => is_cjk(c),
+            _ => false,
+        };
+
+        if !is_cjk_char {
+            self.run.clear();
+            return;
+        }
+
+        // the run is broken by a separator between the characters
+        let follows_run = self.run.last().map_or(false, |last| {
+            last.word_index + 1 == token.word_index && last.char_index + 1 == token.char_index
+        });
+
+        if !follows_run {
+            self.run.clear();
+        }
+        self.run.push(token);
+
+        for &length in CJK_NGRAM_LENGTHS.iter() {
+            let start = match self.run.len().checked_sub(length) {
+                Some(start) => start,
+                None => continue,
+            };
+
+            let ngram = &self.run[start..];
+            let word: String = ngram.iter().map(|t| t.word).collect();
+            if stop_words.contains(&word) {
+                continue;
+            }
+
+            let token = Token { word: &word, ..ngram[0] };
+            if let Some(docindex) = token_to_docindex(id, indexed_pos, token) {
+                let word = word.into_bytes();
+                words_doc_indexes.entry(word.clone()).or_insert_with(Vec::new).push(docindex);
+                docs_words.entry(id).or_insert_with(Vec::new).push(word);
+            }
+        }
+
+        // only the last characters can start a new n-gram
+        let max_length = CJK_NGRAM_LENGTHS[CJK_NGRAM_LENGTHS.len() - 1];
+        if self.run.len() >= max_length {
+            self.run.remove(0);
         }
     }
 }
@@ -112,6 +318,7 @@ fn index_token(
     id: DocumentId,
     indexed_pos: IndexedPos,
     word_limit: usize,
+    normalization_mode: NormalizationMode,
     stop_words: &fst::Set,
     words_doc_indexes: &mut BTreeMap<Word, Vec<DocIndex>>,
     docs_words: &mut HashMap<DocumentId, Vec<Word>>,
@@ -129,7 +336,8 @@ fn index_token(
     if !stop_words.contains(&token.word) {
         match token_to_docindex(id, indexed_pos, token) {
             Some(docindex) => {
-                let word = Vec::from(token.word);
+                // the positions are the ones of the lowercased word, not of its normalized form
+                let word = Vec::from(normalization_mode.normalize_lowercased(lower.clone()));
 
                 if word.len() <= WORD_LENGTH_LIMIT {
                     words_doc_indexes
@@ -138,7 +346,7 @@ fn index_token(
                         .push(docindex);
                     docs_words.entry(id).or_insert_with(Vec::new).push(word);
 
-                    if !lower.contains(is_cjk) {
+                    if normalization_mode == NormalizationMode::Transliterate && !lower.contains(is_cjk) {
                         let unidecoded = deunicode_with_tofu(&lower, "");
                         if unidecoded != lower && !unidecoded.is_empty() {
                             let word = Vec::from(unidecoded);
@@ -225,6 +433,30 @@ mod tests {
             .is_some());
     }
 
+    #[test]
+    fn languages_stop_words() {
+        let mut indexer = RawIndexer::new(fst::Set::default());
+
+        let french = fst::Set::from_iter(vec!["de", "le"]).unwrap();
+        let mut languages = HashMap::new();
+        languages.insert("fr".to_string(), french);
+        indexer.set_languages_stop_words(languages);
+
+        let indexed_pos = IndexedPos(0);
+        indexer.set_document_language(DocumentId(0), "FR");
+        indexer.index_text(DocumentId(0), indexed_pos, "le chat de la voisine");
+        indexer.index_text(DocumentId(1), indexed_pos, "le chat");
+
+        let Indexed {
+            words_doc_indexes, ..
+        } = indexer.build();
+
+        let le = words_doc_indexes.get(&b"le"[..]).unwrap();
+        assert!(le.iter().all(|docindex| docindex.document_id == DocumentId(1)));
+        assert!(words_doc_indexes.get(&b"de"[..]).is_none());
+        assert_eq!(words_doc_indexes.get(&b"chat"[..]).unwrap().len(), 2);
+    }
+
     #[test]
     fn basic_stop_words() {
         let stop_words = sdset::SetBuf::from_dirty(vec!["l", "j", "ai", "de"]);
@@ -269,4 +501,80 @@ mod tests {
             .get(&"🇯🇵".to_owned().into_bytes())
             .is_some());
     }
+
+    #[test]
+    fn docs_word_counts() {
+        let mut indexer = RawIndexer::new(fst::Set::default());
+
+        let docid = DocumentId(0);
+        let indexed_pos = IndexedPos(0);
+        let text = "le chat et le chien";
+        indexer.index_text(docid, indexed_pos, text);
+
+        let Indexed {
+            docs_word_counts, ..
+        } = indexer.build();
+
+        let counts = docs_word_counts.get(&docid).unwrap();
+        assert_eq!(counts.get(&b"le"[..]), Some(&2));
+        assert_eq!(counts.get(&b"chat"[..]), Some(&1));
+        assert_eq!(counts.get(&b"chien"[..]), Some(&1));
+    }
+
+    #[test]
+    fn cjk_ngrams() {
+        let mut indexer = RawIndexer::new(fst::Set::default());
+
+        let docid = DocumentId(0);
+        let indexed_pos = IndexedPos(0);
+        let text = "hello 北京大学, 东京";
+        indexer.index_text(docid, indexed_pos, text);
+
+        let Indexed {
+            words_doc_indexes, docs_words, ..
+        } = indexer.build();
+
+        assert!(words_doc_indexes.get("北".as_bytes()).is_some());
+        assert!(words_doc_indexes.get("京大".as_bytes()).is_some());
+        assert!(words_doc_indexes.get("大学".as_bytes()).is_some());
+        assert!(words_doc_indexes.get("京大学".as_bytes()).is_some());
+        assert!(words_doc_indexes.get("东京".as_bytes()).is_some());
+        assert!(words_doc_indexes.get("北京大学".as_bytes()).is_none());
+        assert!(words_doc_indexes.get("学东".as_bytes()).is_none());
+
+        let docindexes = words_doc_indexes.get("北京大".as_bytes()).unwrap();
+        assert_eq!(docindexes[0].word_index, 1);
+        assert_eq!(docindexes[0].char_index, 6);
+        assert_eq!(docindexes[0].char_length, 3);
+
+        assert!(docs_words[&docid].contains("北京"));
+    }
+
+    #[test]
+    fn token_limits() {
+        let mut indexer = RawIndexer::new(fst::Set::default());
+        indexer.set_token_limits(TokenLimits { max_tokens_per_field: 3, max_tokens_per_document: 5 });
+
+        let docid = DocumentId(0);
+        indexer.index_text(docid, IndexedPos(0), "one two");
+        assert_eq!(indexer.take_token_limit_exceeded(), None);
+
+        indexer.index_text(docid, IndexedPos(1), "three four five six");
+        assert_eq!(indexer.take_token_limit_exceeded(), Some(TokenLimitExceeded::Field(3)));
+
+        indexer.index_text(docid, IndexedPos(2), "seven");
+        assert_eq!(indexer.take_token_limit_exceeded(), Some(TokenLimitExceeded::Document(5)));
+
+        indexer.index_text(DocumentId(1), IndexedPos(0), "eight");
+        assert_eq!(indexer.take_token_limit_exceeded(), None);
+
+        let Indexed {
+            words_doc_indexes, ..
+        } = indexer.build();
+
+        assert!(words_doc_indexes.get(&b"five"[..]).is_some());
+        assert!(words_doc_indexes.get(&b"six"[..]).is_none());
+        assert!(words_doc_indexes.get(&b"seven"[..]).is_none());
+        assert!(words_doc_indexes.get(&b"eight"[..]).is_some());
+    }
 }
@@ -0,0 +1,238 @@
+use std::ops::Deref;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard, TryLockError};
+use std::time::{Duration, Instant};
+
+use crate::database::MainT;
+
+/// Counters of the read transactions handed out by a [`ReadTxnPool`].
+///
+/// [`ReadTxnPool`]: struct.ReadTxnPool.html
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ReadTxnPoolStats {
+    /// The number of pooled transactions reused.
+    pub reused: u64,
+    /// The number of transactions created to fill an empty slot of the pool.
+    pub created: u64,
+    /// The number of pooled transactions replaced because they were too old
+    /// or because an update has been committed since they were created.
+    pub refreshed: u64,
+    /// The number of transactions created outside of the pool, all its slots were in use.
+    pub unpooled: u64,
+}
+
+struct PooledTxn {
+    txn: heed::RoTxn<MainT>,
+    created_at: Instant,
+    generation: u64,
+}
+
+// the main environment is opened with the MDB_NOTLS flag, the read transactions are not
+// bound to the thread that created them and the slot mutex gives an exclusive access.
+unsafe impl Send for PooledTxn {}
+
+/// A pool of main read transactions recycled across the searches, LMDB only has a
+/// limited number of reader slots (126 by default) and creating a transaction has a cost.
+///
+/// A pooled transaction reads the state of the database at the time it was created, it is
+/// refreshed when it is older than `max_age` or when the pool has been [invalidated].
+/// The environment must be opened with the `MDB_NOTLS` flag.
+///
+/// [invalidated]: struct.ReadTxnPool.html#method.invalidate
+pub struct ReadTxnPool {
+    env: heed::Env,
+    inner: Vec<Mutex<Option<PooledTxn>>>,
+    max_age: Duration,
+    generation: AtomicU64,
+    reused: AtomicU64,
+    created: AtomicU64,
+    refreshed: AtomicU64,
+    unpooled: AtomicU64,
+}
+
+impl ReadTxnPool {
+    pub fn new(env: heed::Env, size: usize, max_age: Duration) -> ReadTxnPool {
+        ReadTxnPool {
+            env,
+            inner: (0..size).map(|_| Mutex::new(None)).collect(),
+            max_age,
+            generation: AtomicU64::new(0),
+            reused: AtomicU64::new(0),
+            created: AtomicU64::new(0),
+            refreshed: AtomicU64::new(0),
+            unpooled: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns a read transaction of the first free slot of the pool,
+    /// a new transaction is created when all the slots are in use.
+    pub fn read_txn(&self) -> heed::Result<PooledReadTxn<'_>> {
+        // the generation is read before the transaction is created, a transaction
+        // created while the pool is invalidated is refreshed the next time
+        let generation = self.generation.load(Ordering::Acquire);
+
+        for slot in &self.inner {
+            let mut guard = match slot.try_lock() {
+                Ok(guard) => guard,
+                Err(TryLockError::Poisoned(error)) => error.into_inner(),
+                Err(TryLockError::WouldBlock) => continue,
+            };
+
+            let is_fresh = guard.as_ref().map(|pooled| {
+                pooled.generation == generation && pooled.created_at.elapsed() < self.max_age
+            });
+
+            let counter = match is_fresh {
+                Some(true) => {
+                    self.reused.fetch_add(1, Ordering::Relaxed);
+                    return Ok(PooledReadTxn(Inner::Pooled(guard, &self.generation)));
+                }
+                Some(false) => &self.refreshed,
+                None => &self.created,
+            };
+
+            // the previous transaction releases its reader slot before the new one is created
+            *guard = None;
+            let txn = self.env.typed_read_txn::<MainT>()?;
+            *guard = Some(PooledTxn { txn, created_at: Instant::now(), generation });
+            counter.fetch_add(1, Ordering::Relaxed);
+
+            return Ok(PooledReadTxn(Inner::Pooled(guard, &self.generation)));
+        }
+
+        self.unpooled.fetch_add(1, Ordering::Relaxed);
+        self.env.typed_read_txn::<MainT>().map(|txn| PooledReadTxn(Inner::Unpooled(txn)))
+    }
+
+    /// Marks all the pooled transactions as outdated, it must be called
+    /// after a write transaction has been committed.
+    ///
+    /// The idle transactions are dropped right away and the ones in use are dropped
+    /// when given back, an outdated snapshot must not prevent LMDB from reusing its pages.
+    pub fn invalidate(&self) {
+        self.generation.fetch_add(1, Ordering::AcqRel);
+
+        for slot in &self.inner {
+            match slot.try_lock() {
+                Ok(mut guard) => *guard = None,
+                Err(TryLockError::Poisoned(error)) => *error.into_inner() = None,
+                Err(TryLockError::WouldBlock) => (),
+            }
+        }
+    }
+
+    pub fn stats(&self) -> ReadTxnPoolStats {
+        ReadTxnPoolStats {
+            reused: self.reused.load(Ordering::Relaxed),
+            created: self.created.load(Ordering::Relaxed),
+            refreshed: self.refreshed.load(Ordering::Relaxed),
+            unpooled: self.unpooled.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// A read transaction handed out by a [`ReadTxnPool`], it is given
+/// back to the pool when dropped.
+///
+/// [`ReadTxnPool`]: struct.ReadTxnPool.html
+pub struct PooledReadTxn<'a>(Inner<'a>);
+
+enum Inner<'a> {
+    Pooled(MutexGuard<'a, Option<PooledTxn>>, &'a AtomicU64),
+    Unpooled(heed::RoTxn<MainT>),
+}
+
+impl Drop for PooledReadTxn<'_> {
+    fn drop(&mut self) {
+        if let Inner::Pooled(guard, generation) = &mut self.0 {
+            let generation = generation.load(Ordering::Acquire);
+            if guard.as_ref().map_or(false, |pooled| pooled.generation != generation) {
+                **guard = None;
+            }
+        }
+    }
+}
+
+impl Deref for PooledReadTxn<'_> {
+    type Target = heed::RoTxn<MainT>;
+
+    fn deref(&self) -> &heed::RoTxn<MainT> {
+        match &self.0 {
+            Inner::Pooled(guard, _) => &guard.as_ref().expect("pooled slots are filled").txn,
+            Inner::Unpooled(txn) => txn,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env(dir: &tempfile::TempDir) -> heed::Env {
+        let mut options = heed::EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024);
+        unsafe { options.flag(heed::flags::Flags::MdbNoTls) };
+        options.open(dir.path()).unwrap()
+    }
+
+    fn slots_filled(pool: &ReadTxnPool) -> usize {
+        pool.inner.iter().filter(|slot| slot.lock().unwrap().is_some()).count()
+    }
+
+    #[test]
+    fn transactions_recycled() {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = ReadTxnPool::new(env(&dir), 1, Duration::from_secs(60));
+
+        drop(pool.read_txn().unwrap());
+        drop(pool.read_txn().unwrap());
+        assert_eq!(pool.stats(), ReadTxnPoolStats { reused: 1, created: 1, refreshed: 0, unpooled: 0 });
+
+        // the only slot is in use
+        let first = pool.read_txn().unwrap();
+        let second = pool.read_txn().unwrap();
+        drop((first, second));
+        assert_eq!(pool.stats(), ReadTxnPoolStats { reused: 2, created: 1, refreshed: 0, unpooled: 1 });
+
+        // the outdated transaction is released when given back
+        let txn = pool.read_txn().unwrap();
+        pool.invalidate();
+        drop(txn);
+        drop(pool.read_txn().unwrap());
+        assert_eq!(pool.stats().refreshed, 0);
+        assert_eq!(pool.stats().created, 2);
+    }
+
+    #[test]
+    fn outdated_transactions_released() {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = ReadTxnPool::new(env(&dir), 2, Duration::from_secs(60));
+
+        drop(pool.read_txn().unwrap());
+        assert_eq!(slots_filled(&pool), 1);
+
+        // the idle slot is emptied right away
+        pool.invalidate();
+        assert_eq!(slots_filled(&pool), 0);
+
+        // the slot in use is emptied once given back
+        let first = pool.read_txn().unwrap();
+        let second = pool.read_txn().unwrap();
+        pool.invalidate();
+        drop((first, second));
+        assert_eq!(slots_filled(&pool), 0);
+
+        drop(pool.read_txn().unwrap());
+        assert_eq!(slots_filled(&pool), 1);
+    }
+
+    #[test]
+    fn old_transactions_refreshed() {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = ReadTxnPool::new(env(&dir), 2, Duration::from_secs(0));
+
+        drop(pool.read_txn().unwrap());
+        drop(pool.read_txn().unwrap());
+        assert_eq!(pool.stats(), ReadTxnPoolStats { reused: 0, created: 1, refreshed: 1, unpooled: 0 });
+    }
+}
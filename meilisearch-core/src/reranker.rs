@@ -0,0 +1,47 @@
+use crate::database::MainT;
+use crate::{store, Document};
+
+/// Reorders the documents returned by a search, after they have been sorted
+/// by the ranking rules and the requested range has been applied.
+pub trait Reranker {
+    fn rerank(&self, candidates: Vec<Document>) -> Vec<Document>;
+}
+
+/// Uses the boosts stored in the `DocumentBoosts` store to reorder the documents.
+///
+/// The score of a document is derived from its rank, `1 / (rank + 1)`, and is
+/// multiplied by its boost, the documents without boost keep their score.
+pub struct DocumentBoostReranker<'a> {
+    reader: &'a heed::RoTxn<MainT>,
+    boosts: store::DocumentBoosts,
+}
+
+impl<'a> DocumentBoostReranker<'a> {
+    pub fn new(reader: &'a heed::RoTxn<MainT>, boosts: store::DocumentBoosts) -> DocumentBoostReranker<'a> {
+        DocumentBoostReranker { reader, boosts }
+    }
+
+    fn boost(&self, document: &Document) -> f32 {
+        match self.boosts.boost(self.reader, document.id) {
+            Ok(boost) => boost.unwrap_or(1.0),
+            Err(e) => {
+                log::error!("could not read the boost of {:?}: {}", document.id, e);
+                1.0
+            }
+        }
+    }
+}
+
+impl Reranker for DocumentBoostReranker<'_> {
+    fn rerank(&self, candidates: Vec<Document>) -> Vec<Document> {
+        let mut scored: Vec<_> = candidates
+            .into_iter()
+            .enumerate()
+            .map(|(rank, document)| (self.boost(&document) / (rank + 1) as f32, document))
+            .collect();
+
+        // the sort is stable, the documents with the same score keep their rank
+        scored.sort_by(|(a, _), (b, _)| b.partial_cmp(a).unwrap_or(std::cmp::Ordering::Equal));
+        scored.into_iter().map(|(_, document)| document).collect()
+    }
+}
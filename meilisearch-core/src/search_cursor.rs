@@ -0,0 +1,106 @@
+use std::convert::TryInto;
+use std::str::FromStr;
+use std::{error, fmt};
+
+use crate::DocumentId;
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// The number of bytes of an encoded cursor, the document id followed by the position.
+const CURSOR_LEN: usize = 12;
+
+/// The position of the last document returned by a search, the next search
+/// given this cursor returns the documents ranked after this one.
+///
+/// It is displayed and parsed as an opaque URL-safe base64 token.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SearchCursor {
+    /// The last document returned.
+    pub document_id: DocumentId,
+    /// The number of documents returned up to the last one, the searches resume
+    /// at this offset when the last document does not match the query anymore.
+    pub position: u64,
+}
+
+impl SearchCursor {
+    pub fn new(document_id: DocumentId, position: u64) -> SearchCursor {
+        SearchCursor { document_id, position }
+    }
+}
+
+impl fmt::Display for SearchCursor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut bytes = [0; CURSOR_LEN];
+        bytes[..4].copy_from_slice(&self.document_id.0.to_be_bytes());
+        bytes[4..].copy_from_slice(&self.position.to_be_bytes());
+
+        for chunk in bytes.chunks(3) {
+            let n = chunk.iter().enumerate().fold(0u32, |n, (i, b)| n | u32::from(*b) << (16 - i * 8));
+            for i in 0..=chunk.len() {
+                let index = (n >> (18 - i * 6)) & 0x3f;
+                write!(f, "{}", ALPHABET[index as usize] as char)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for SearchCursor {
+    type Err = ParseSearchCursorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // the cursor bytes are a multiple of three, the token is never padded
+        if s.len() != CURSOR_LEN / 3 * 4 {
+            return Err(ParseSearchCursorError);
+        }
+
+        let mut bytes = Vec::with_capacity(CURSOR_LEN);
+        for chunk in s.as_bytes().chunks(4) {
+            let mut n = 0u32;
+            for c in chunk {
+                let value = ALPHABET.iter().position(|a| a == c).ok_or(ParseSearchCursorError)?;
+                n = n << 6 | value as u32;
+            }
+            bytes.extend_from_slice(&n.to_be_bytes()[1..]);
+        }
+
+        let document_id = u32::from_be_bytes(bytes[..4].try_into().unwrap());
+        let position = u64::from_be_bytes(bytes[4..].try_into().unwrap());
+
+        Ok(SearchCursor::new(DocumentId(document_id), position))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseSearchCursorError;
+
+impl fmt::Display for ParseSearchCursorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("invalid search cursor")
+    }
+}
+
+impl error::Error for ParseSearchCursorError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_round_trip() {
+        let cursor = SearchCursor::new(DocumentId(42), 1_000_000);
+        let token = cursor.to_string();
+        assert_eq!(token.len(), 16);
+        assert!(token.bytes().all(|b| ALPHABET.contains(&b)));
+        assert_eq!(token.parse::<SearchCursor>(), Ok(cursor));
+
+        let cursor = SearchCursor::new(DocumentId(u32::max_value()), u64::max_value());
+        assert_eq!(cursor.to_string(), "________________");
+        assert_eq!(cursor.to_string().parse::<SearchCursor>(), Ok(cursor));
+
+        assert_eq!(SearchCursor::new(DocumentId(0), 0).to_string(), "AAAAAAAAAAAAAAAA");
+        assert!("AAAAAAAAAAAAAAA".parse::<SearchCursor>().is_err());
+        assert!("AAAAAAAAAAAAAAA=".parse::<SearchCursor>().is_err());
+    }
+}
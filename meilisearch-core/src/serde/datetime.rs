@@ -0,0 +1,34 @@
+use chrono::{DateTime, NaiveDate};
+
+/// Parses an ISO 8601 date, either a full RFC 3339 date and time like `2024-01-15T10:00:00Z`
+/// or a calendar date like `2024-01-15`, and returns the number of seconds since
+/// the Unix epoch, the calendar dates being considered at midnight UTC.
+pub fn parse_datetime(value: &str) -> Option<f64> {
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(value) {
+        let seconds = datetime.timestamp() as f64;
+        let nanos = f64::from(datetime.timestamp_subsec_nanos()) / 1_000_000_000.0;
+        return Some(seconds + nanos);
+    }
+
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .map(|date| date.and_hms(0, 0, 0).timestamp() as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_iso_8601_dates() {
+        assert_eq!(parse_datetime("1970-01-01T00:00:00Z"), Some(0.0));
+        assert_eq!(parse_datetime("2024-01-15T10:00:00Z"), Some(1_705_312_800.0));
+        assert_eq!(parse_datetime("2024-01-15T11:00:00+01:00"), Some(1_705_312_800.0));
+        assert_eq!(parse_datetime("2024-01-15"), Some(1_705_276_800.0));
+        assert_eq!(parse_datetime("1970-01-01T00:00:00.5Z"), Some(0.5));
+
+        assert_eq!(parse_datetime("hello"), None);
+        assert_eq!(parse_datetime("2024"), None);
+        assert_eq!(parse_datetime("2024-13-01"), None);
+    }
+}
@@ -10,12 +10,14 @@ use serde_json::Error as SerdeJsonError;
 
 use crate::database::MainT;
 use crate::store::DocumentsFields;
+use crate::serde::{decrypt_field, field_json_bytes, FieldCipher, FieldCipherError};
 use crate::DocumentId;
 
 #[derive(Debug)]
 pub enum DeserializerError {
     SerdeJson(SerdeJsonError),
     Zlmdb(heed::Error),
+    FieldCipher(FieldCipherError),
     Custom(String),
 }
 
@@ -30,12 +32,22 @@ impl fmt::Display for DeserializerError {
         match self {
             DeserializerError::SerdeJson(e) => write!(f, "serde json related error: {}", e),
             DeserializerError::Zlmdb(e) => write!(f, "heed related error: {}", e),
+            DeserializerError::FieldCipher(e) => write!(f, "field encryption related error: {}", e),
             DeserializerError::Custom(s) => f.write_str(s),
         }
     }
 }
 
-impl Error for DeserializerError {}
+impl Error for DeserializerError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DeserializerError::SerdeJson(e) => Some(e),
+            DeserializerError::Zlmdb(e) => Some(e),
+            DeserializerError::FieldCipher(e) => Some(e),
+            DeserializerError::Custom(_) => None,
+        }
+    }
+}
 
 impl From<SerdeJsonError> for DeserializerError {
     fn from(error: SerdeJsonError) -> DeserializerError {
@@ -43,6 +55,12 @@ impl From<SerdeJsonError> for DeserializerError {
     }
 }
 
+impl From<FieldCipherError> for DeserializerError {
+    fn from(error: FieldCipherError) -> DeserializerError {
+        DeserializerError::FieldCipher(error)
+    }
+}
+
 impl From<heed::Error> for DeserializerError {
     fn from(error: heed::Error) -> DeserializerError {
         DeserializerError::Zlmdb(error)
@@ -55,6 +73,8 @@ pub struct Deserializer<'a> {
     pub documents_fields: DocumentsFields,
     pub schema: &'a Schema,
     pub fields: Option<&'a HashSet<FieldId>>,
+    /// Decrypts the encrypted fields, they can't be read without it.
+    pub cipher: Option<&'a FieldCipher>,
 }
 
 impl<'de, 'a, 'b> de::Deserializer<'de> for &'b mut Deserializer<'a> {
@@ -78,7 +98,7 @@ impl<'de, 'a, 'b> de::Deserializer<'de> for &'b mut Deserializer<'a> {
     where
         V: de::Visitor<'de>,
     {
-        let mut error = None;
+        let mut error: Option<DeserializerError> = None;
 
         let iter = self
             .documents_fields
@@ -87,7 +107,7 @@ impl<'de, 'a, 'b> de::Deserializer<'de> for &'b mut Deserializer<'a> {
                 let (attr, value) = match result {
                     Ok(value) => value,
                     Err(e) => {
-                        error = Some(e);
+                        error = Some(e.into());
                         return None;
                     }
                 };
@@ -95,7 +115,14 @@ impl<'de, 'a, 'b> de::Deserializer<'de> for &'b mut Deserializer<'a> {
                 let is_displayed = self.schema.is_displayed(attr);
                 if is_displayed && self.fields.map_or(true, |f| f.contains(&attr)) {
                     if let Some(attribute_name) = self.schema.name(attr) {
-                        let cursor = Cursor::new(value.to_owned());
+                        let value = match decrypt_field(self.cipher, value) {
+                            Ok(value) => value,
+                            Err(e) => {
+                                error = Some(e.into());
+                                return None;
+                            }
+                        };
+                        let cursor = Cursor::new(field_json_bytes(&value).to_owned());
                         let ioread = SerdeJsonIoRead::new(cursor);
                         let value = Value(SerdeJsonDeserializer::new(ioread));
 
@@ -121,7 +148,7 @@ impl<'de, 'a, 'b> de::Deserializer<'de> for &'b mut Deserializer<'a> {
         };
 
         match error.take() {
-            Some(error) => Err(error.into()),
+            Some(error) => Err(error),
             None => result,
         }
     }
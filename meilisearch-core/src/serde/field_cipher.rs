@@ -0,0 +1,108 @@
+use std::borrow::Cow;
+use std::{error, fmt};
+
+use aes_gcm::aead::generic_array::GenericArray;
+use aes_gcm::aead::{Aead, NewAead};
+use aes_gcm::Aes256Gcm;
+
+/// The byte prefixing the encrypted fields in the documents fields store,
+/// it is followed by the nonce and the encrypted JSON representation of the value.
+pub const ENCRYPTED_FIELD_BYTE: u8 = 2;
+
+const NONCE_LEN: usize = 12;
+
+/// Encrypts the values of the encrypted fields with AES-256-GCM, every value
+/// is encrypted with a new random nonce stored along with it.
+#[derive(Clone)]
+pub struct FieldCipher {
+    key: [u8; 32],
+}
+
+impl FieldCipher {
+    pub fn new(key: [u8; 32]) -> FieldCipher {
+        FieldCipher { key }
+    }
+
+    fn cipher(&self) -> Aes256Gcm {
+        Aes256Gcm::new(GenericArray::from_slice(&self.key))
+    }
+
+    pub fn encrypt(&self, plaintext: &[u8]) -> Vec<u8> {
+        let nonce: [u8; NONCE_LEN] = rand::random();
+        let ciphertext = self
+            .cipher()
+            .encrypt(GenericArray::from_slice(&nonce), plaintext)
+            .expect("the encryption of a field can't fail");
+
+        let mut bytes = Vec::with_capacity(1 + NONCE_LEN + ciphertext.len());
+        bytes.push(ENCRYPTED_FIELD_BYTE);
+        bytes.extend_from_slice(&nonce);
+        bytes.extend_from_slice(&ciphertext);
+        bytes
+    }
+
+    pub fn decrypt(&self, bytes: &[u8]) -> Result<Vec<u8>, FieldCipherError> {
+        if !is_encrypted_field(bytes) || bytes.len() < 1 + NONCE_LEN {
+            return Err(FieldCipherError::InvalidField);
+        }
+
+        let (nonce, ciphertext) = bytes[1..].split_at(NONCE_LEN);
+        self.cipher()
+            .decrypt(GenericArray::from_slice(nonce), ciphertext)
+            .map_err(|_| FieldCipherError::InvalidField)
+    }
+}
+
+/// Returns whether the stored bytes of a field are encrypted, the JSON
+/// representations and the booleans bytes never start with this byte.
+pub fn is_encrypted_field(bytes: &[u8]) -> bool {
+    bytes.first() == Some(&ENCRYPTED_FIELD_BYTE)
+}
+
+/// Returns the stored bytes of a field, decrypted if they are encrypted.
+pub fn decrypt_field<'a>(cipher: Option<&FieldCipher>, bytes: &'a [u8]) -> Result<Cow<'a, [u8]>, FieldCipherError> {
+    if !is_encrypted_field(bytes) {
+        return Ok(Cow::Borrowed(bytes));
+    }
+
+    match cipher {
+        Some(cipher) => cipher.decrypt(bytes).map(Cow::Owned),
+        None => Err(FieldCipherError::MissingKey),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldCipherError {
+    MissingKey,
+    InvalidField,
+}
+
+impl fmt::Display for FieldCipherError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FieldCipherError::MissingKey => f.write_str("a field is encrypted but no field encryption key is set"),
+            FieldCipherError::InvalidField => f.write_str("a field can't be decrypted with the field encryption key"),
+        }
+    }
+}
+
+impl error::Error for FieldCipherError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encrypt_decrypt() {
+        let cipher = FieldCipher::new([42; 32]);
+        let encrypted = cipher.encrypt(br#""secret""#);
+        assert!(is_encrypted_field(&encrypted));
+        assert_ne!(encrypted, cipher.encrypt(br#""secret""#));
+        assert_eq!(cipher.decrypt(&encrypted).unwrap(), br#""secret""#.to_vec());
+
+        let other = FieldCipher::new([43; 32]);
+        assert_eq!(other.decrypt(&encrypted), Err(FieldCipherError::InvalidField));
+        assert_eq!(decrypt_field(None, &encrypted), Err(FieldCipherError::MissingKey));
+        assert_eq!(decrypt_field(None, b"12").unwrap(), &b"12"[..]);
+    }
+}
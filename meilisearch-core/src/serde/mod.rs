@@ -1,6 +1,10 @@
+mod datetime;
 mod deserializer;
+mod field_cipher;
 
+pub use self::datetime::parse_datetime;
 pub use self::deserializer::{Deserializer, DeserializerError};
+pub use self::field_cipher::{decrypt_field, is_encrypted_field, FieldCipher, FieldCipherError, ENCRYPTED_FIELD_BYTE};
 
 use std::{error::Error, fmt};
 
@@ -10,6 +14,23 @@ use meilisearch_schema::Error as SchemaError;
 
 use crate::ParseNumberError;
 
+/// `null` document values are stored as zero-byte blobs in the documents fields store
+/// and booleans as a single `0` or `1` byte, this returns the JSON representation
+/// of a stored field, mapping these blobs to `null`, `false` and `true`.
+pub fn field_json_bytes(bytes: &[u8]) -> &[u8] {
+    match bytes {
+        [] => b"null",
+        [FALSE_FIELD_BYTE] => b"false",
+        [TRUE_FIELD_BYTE] => b"true",
+        bytes => bytes,
+    }
+}
+
+/// The bytes storing the boolean values in the documents fields store,
+/// they never are the whole JSON representation of a value.
+pub const FALSE_FIELD_BYTE: u8 = 0;
+pub const TRUE_FIELD_BYTE: u8 = 1;
+
 #[derive(Debug)]
 pub enum SerializerError {
     DocumentIdNotFound,
@@ -59,7 +80,17 @@ impl fmt::Display for SerializerError {
     }
 }
 
-impl Error for SerializerError {}
+impl Error for SerializerError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SerializerError::Zlmdb(e) => Some(e),
+            SerializerError::SerdeJson(e) => Some(e),
+            SerializerError::ParseNumber(e) => Some(e),
+            SerializerError::Schema(e) => Some(e),
+            _ => None,
+        }
+    }
+}
 
 impl From<String> for SerializerError {
     fn from(value: String) -> SerializerError {
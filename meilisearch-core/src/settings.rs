@@ -1,10 +1,12 @@
-use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::str::FromStr;
 use std::iter::IntoIterator;
 
 use serde::{Deserialize, Deserializer, Serialize};
 use once_cell::sync::Lazy;
 
+use crate::NormalizationMode;
+
 use self::RankingRule::*;
 
 pub const DEFAULT_RANKING_RULES: [RankingRule; 6] = [Typo, Words, Proximity, Attribute, WordsPosition, Exactness];
@@ -33,6 +35,14 @@ pub struct Settings {
     pub accept_new_fields: Option<Option<bool>>,
     #[serde(default, deserialize_with = "deserialize_some")]
     pub attributes_for_faceting: Option<Option<Vec<String>>>,
+    #[serde(default, deserialize_with = "deserialize_some", skip_serializing_if = "Option::is_none")]
+    pub attribute_weights: Option<Option<HashMap<String, f32>>>,
+    #[serde(default, deserialize_with = "deserialize_some", skip_serializing_if = "Option::is_none")]
+    pub max_documents: Option<Option<u64>>,
+    #[serde(default, deserialize_with = "deserialize_some", skip_serializing_if = "Option::is_none")]
+    pub encrypted_attributes: Option<Option<BTreeSet<String>>>,
+    #[serde(default, deserialize_with = "deserialize_some", skip_serializing_if = "Option::is_none")]
+    pub normalization_mode: Option<Option<NormalizationMode>>,
 }
 
 // Any value that is present is considered Some value, including null.
@@ -63,6 +73,11 @@ impl Settings {
             synonyms: settings.synonyms.into(),
             accept_new_fields: settings.accept_new_fields.into(),
             attributes_for_faceting: settings.attributes_for_faceting.into(),
+            attribute_weights: settings.attribute_weights.into(),
+            max_documents: settings.max_documents.into(),
+            encrypted_attributes: settings.encrypted_attributes.into(),
+            normalization_mode: settings.normalization_mode.into(),
+            actor: None,
         })
     }
 }
@@ -74,6 +89,12 @@ pub enum UpdateState<T> {
     Nothing,
 }
 
+impl<T> Default for UpdateState<T> {
+    fn default() -> UpdateState<T> {
+        UpdateState::Nothing
+    }
+}
+
 impl <T> From<Option<Option<T>>> for UpdateState<T> {
     fn from(opt: Option<Option<T>>) -> UpdateState<T> {
         match opt {
@@ -93,7 +114,7 @@ impl std::fmt::Display for RankingRuleConversionError {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum RankingRule {
     Typo,
     Words,
@@ -170,6 +191,21 @@ pub struct SettingsUpdate {
     pub synonyms: UpdateState<BTreeMap<String, Vec<String>>>,
     pub accept_new_fields: UpdateState<bool>,
     pub attributes_for_faceting: UpdateState<Vec<String>>,
+    /// The weights of the attributes, missing attributes have a weight of 1.0.
+    #[serde(default)]
+    pub attribute_weights: UpdateState<HashMap<String, f32>>,
+    /// The maximum number of documents the index can hold.
+    #[serde(default)]
+    pub max_documents: UpdateState<u64>,
+    /// The attributes stored encrypted and never indexed.
+    #[serde(default)]
+    pub encrypted_attributes: UpdateState<BTreeSet<String>>,
+    /// How the words are normalized, changing it reindexes the documents.
+    #[serde(default)]
+    pub normalization_mode: UpdateState<NormalizationMode>,
+    /// Who sent the update, recorded in the schema changelog.
+    #[serde(default)]
+    pub actor: Option<String>,
 }
 
 impl Default for SettingsUpdate {
@@ -184,6 +220,17 @@ impl Default for SettingsUpdate {
             synonyms: UpdateState::Nothing,
             accept_new_fields: UpdateState::Nothing,
             attributes_for_faceting: UpdateState::Nothing,
+            attribute_weights: UpdateState::Nothing,
+            max_documents: UpdateState::Nothing,
+            encrypted_attributes: UpdateState::Nothing,
+            normalization_mode: UpdateState::Nothing,
+            actor: None,
         }
     }
 }
+
+impl SettingsUpdate {
+    pub fn with_actor(self, actor: Option<String>) -> SettingsUpdate {
+        SettingsUpdate { actor, ..self }
+    }
+}
@@ -0,0 +1,175 @@
+//! A snapshot is a directory containing a compacted copy of the main and update
+//! LMDB environments along with a manifest listing the SHA-256 checksum of every file.
+//!
+//! ```text
+//! snapshot/
+//! ├── manifest.json
+//! ├── main/data.mdb
+//! └── update/data.mdb
+//! ```
+
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{Database, Error, MResult};
+
+const MANIFEST_NAME: &str = "manifest.json";
+const SNAPSHOT_VERSION: u32 = 1;
+const SNAPSHOT_FILES: [&str; 2] = ["main/data.mdb", "update/data.mdb"];
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SnapshotManifest {
+    version: u32,
+    /// The hex encoded SHA-256 checksums of the files, relative to the snapshot directory.
+    files: BTreeMap<String, String>,
+}
+
+fn file_checksum(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; 64 * 1024];
+
+    loop {
+        match file.read(&mut buffer)? {
+            0 => break,
+            n => hasher.input(&buffer[..n]),
+        }
+    }
+
+    Ok(format!("{:x}", hasher.result()))
+}
+
+/// Writes a snapshot of the database into a new directory.
+pub fn create_snapshot(database: &Database, snapshot_path: impl AsRef<Path>) -> MResult<()> {
+    let snapshot_path = snapshot_path.as_ref();
+    fs::create_dir_all(snapshot_path)?;
+
+    database.copy_and_compact_to_path(snapshot_path)?;
+
+    let mut files = BTreeMap::new();
+    for name in SNAPSHOT_FILES.iter() {
+        let checksum = file_checksum(&snapshot_path.join(name))?;
+        files.insert(name.to_string(), checksum);
+    }
+
+    let manifest = SnapshotManifest { version: SNAPSHOT_VERSION, files };
+    let manifest_file = File::create(snapshot_path.join(MANIFEST_NAME))?;
+    serde_json::to_writer_pretty(manifest_file, &manifest)?;
+
+    Ok(())
+}
+
+/// Replaces the database located at `db_path` by the content of the snapshot,
+/// the database must not be opened while it is restored.
+///
+/// The checksums of the snapshot files are validated before anything is touched, the files
+/// are copied next to the database which is then swapped with the copy by renaming the directories.
+pub fn restore_snapshot(db_path: impl AsRef<Path>, snapshot_path: impl AsRef<Path>) -> MResult<()> {
+    let db_path = db_path.as_ref();
+    let snapshot_path = snapshot_path.as_ref();
+
+    let manifest_file = File::open(snapshot_path.join(MANIFEST_NAME))?;
+    let manifest: SnapshotManifest = serde_json::from_reader(manifest_file)?;
+
+    if manifest.version != SNAPSHOT_VERSION {
+        let message = format!("unsupported snapshot version {}", manifest.version);
+        return Err(Error::InvalidSnapshot(message));
+    }
+
+    for name in SNAPSHOT_FILES.iter() {
+        let expected = match manifest.files.get(*name) {
+            Some(checksum) => checksum,
+            None => return Err(Error::InvalidSnapshot(format!("{} is missing from the manifest", name))),
+        };
+
+        if file_checksum(&snapshot_path.join(name))? != *expected {
+            return Err(Error::InvalidSnapshot(format!("{} checksum mismatch", name)));
+        }
+    }
+
+    let file_name = db_path.file_name().and_then(|n| n.to_str()).unwrap_or("data.ms");
+    let restore_path = db_path.with_file_name(format!("{}.restore", file_name));
+    let old_path = db_path.with_file_name(format!("{}.old", file_name));
+
+    if restore_path.exists() {
+        fs::remove_dir_all(&restore_path)?;
+    }
+
+    for name in SNAPSHOT_FILES.iter() {
+        let dest = restore_path.join(name);
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(snapshot_path.join(name), dest)?;
+    }
+
+    if db_path.exists() {
+        if old_path.exists() {
+            fs::remove_dir_all(&old_path)?;
+        }
+        fs::rename(db_path, &old_path)?;
+    }
+
+    fs::rename(&restore_path, db_path)?;
+
+    if old_path.exists() {
+        fs::remove_dir_all(&old_path)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::database::DatabaseOptions;
+
+    #[test]
+    fn snapshot_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let source_path = dir.path().join("source.ms");
+        let db_path = dir.path().join("data.ms");
+        let snapshot_path = dir.path().join("snapshot");
+
+        {
+            let database = Database::open_or_create(&source_path, DatabaseOptions::default()).unwrap();
+            database.create_index("movies").unwrap();
+            create_snapshot(&database, &snapshot_path).unwrap();
+            database.create_index("books").unwrap();
+        }
+
+        // heed keeps the environments opened until the end of the process, the snapshot
+        // is restored in place of a database that has not been opened by this test
+        fs::create_dir_all(db_path.join("main")).unwrap();
+        fs::write(db_path.join("main/data.mdb"), b"outdated").unwrap();
+
+        restore_snapshot(&db_path, &snapshot_path).unwrap();
+
+        let database = Database::open_or_create(&db_path, DatabaseOptions::default()).unwrap();
+        assert!(database.open_index("movies").is_some());
+        assert!(database.open_index("books").is_none());
+    }
+
+    #[test]
+    fn corrupted_snapshot() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("data.ms");
+        let snapshot_path = dir.path().join("snapshot");
+
+        {
+            let database = Database::open_or_create(&db_path, DatabaseOptions::default()).unwrap();
+            create_snapshot(&database, &snapshot_path).unwrap();
+        }
+
+        fs::write(snapshot_path.join("update/data.mdb"), b"corrupted").unwrap();
+
+        let result = restore_snapshot(&db_path, &snapshot_path);
+        assert_matches!(result, Err(Error::InvalidSnapshot(_)));
+    }
+}
@@ -0,0 +1,59 @@
+use std::borrow::Cow;
+
+use rust_stemmers::Algorithm;
+
+/// Reduces the normalized words of a query to their stem, the documents
+/// containing a word derived from the same stem also match the query.
+pub trait Stemmer {
+    fn stem<'a>(&self, word: &'a str) -> Cow<'a, str>;
+}
+
+/// The languages supported by the [`SnowballStemmer`].
+///
+/// [`SnowballStemmer`]: struct.SnowballStemmer.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StemmerLanguage {
+    English,
+    French,
+    German,
+    Spanish,
+    Italian,
+}
+
+/// Stems the words with the snowball algorithms of the `rust-stemmers` crate.
+pub struct SnowballStemmer {
+    inner: rust_stemmers::Stemmer,
+}
+
+impl SnowballStemmer {
+    pub fn new(language: StemmerLanguage) -> SnowballStemmer {
+        let algorithm = match language {
+            StemmerLanguage::English => Algorithm::English,
+            StemmerLanguage::French => Algorithm::French,
+            StemmerLanguage::German => Algorithm::German,
+            StemmerLanguage::Spanish => Algorithm::Spanish,
+            StemmerLanguage::Italian => Algorithm::Italian,
+        };
+
+        SnowballStemmer { inner: rust_stemmers::Stemmer::create(algorithm) }
+    }
+}
+
+impl Stemmer for SnowballStemmer {
+    fn stem<'a>(&self, word: &'a str) -> Cow<'a, str> {
+        self.inner.stem(word)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn english_words_stemmed() {
+        let stemmer = SnowballStemmer::new(StemmerLanguage::English);
+        assert_eq!(stemmer.stem("running"), "run");
+        assert_eq!(stemmer.stem("cats"), "cat");
+        assert_eq!(stemmer.stem("fish"), "fish");
+    }
+}
@@ -0,0 +1,51 @@
+//! Built-in stop words lists, identified by their ISO 639-1 language code.
+//!
+//! Documents can declare their language with the `_language` meta-field, the stop words
+//! registered for this language are then ignored while indexing these documents.
+
+/// The name of the meta-field declaring the language of a document.
+pub const LANGUAGE_FIELD: &str = "_language";
+
+pub const ENGLISH: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "but", "by", "for", "if", "in", "into", "is",
+    "it", "no", "not", "of", "on", "or", "such", "that", "the", "their", "then", "there",
+    "these", "they", "this", "to", "was", "will", "with",
+];
+
+pub const FRENCH: &[&str] = &[
+    "au", "aux", "avec", "ce", "ces", "dans", "de", "des", "du", "elle", "en", "et", "il",
+    "je", "la", "le", "les", "leur", "lui", "ma", "mais", "me", "mes", "mon", "ne", "nous",
+    "on", "ou", "par", "pas", "pour", "qu", "que", "qui", "sa", "se", "ses", "son", "sur",
+    "ta", "te", "tes", "ton", "tu", "un", "une", "vos", "votre", "vous",
+];
+
+pub const SPANISH: &[&str] = &[
+    "a", "al", "como", "con", "de", "del", "el", "en", "es", "la", "las", "lo", "los", "mas",
+    "me", "mi", "no", "o", "para", "pero", "por", "que", "se", "si", "su", "sus", "un", "una",
+    "y", "ya",
+];
+
+pub const GERMAN: &[&str] = &[
+    "aber", "als", "am", "an", "auch", "auf", "aus", "bei", "das", "dass", "dem", "den",
+    "der", "des", "die", "ein", "eine", "einem", "einen", "einer", "es", "für", "im", "in",
+    "ist", "mit", "nicht", "noch", "oder", "sich", "sie", "sind", "und", "von", "zu", "zum",
+    "zur",
+];
+
+pub const ITALIAN: &[&str] = &[
+    "a", "al", "alla", "che", "con", "da", "del", "della", "di", "e", "gli", "ha", "i", "il",
+    "in", "la", "le", "lo", "ma", "mi", "non", "per", "si", "su", "tra", "un", "una", "uno",
+];
+
+/// Returns the built-in stop words of the language corresponding to the given
+/// ISO 639-1 code, the languages supported are English, French, Spanish, German and Italian.
+pub fn builtin_stop_words(language: &str) -> Option<&'static [&'static str]> {
+    match language.to_lowercase().as_str() {
+        "en" => Some(ENGLISH),
+        "fr" => Some(FRENCH),
+        "es" => Some(SPANISH),
+        "de" => Some(GERMAN),
+        "it" => Some(ITALIAN),
+        _ => None,
+    }
+}
@@ -3,11 +3,20 @@ use crate::database::MainT;
 use crate::DocumentId;
 use heed::types::{ByteSlice, OwnedType};
 use heed::Result as ZResult;
+use std::collections::BTreeMap;
 use std::sync::Arc;
 
 #[derive(Copy, Clone)]
 pub struct DocsWords {
     pub(crate) docs_words: heed::Database<OwnedType<BEU32>, ByteSlice>,
+    pub(crate) docs_word_counts: heed::Database<ByteSlice, OwnedType<u32>>,
+}
+
+fn doc_word_key(document_id: DocumentId, word: &[u8]) -> Vec<u8> {
+    let mut key = Vec::with_capacity(4 + word.len());
+    key.extend_from_slice(&document_id.0.to_be_bytes());
+    key.extend_from_slice(word);
+    key
 }
 
 impl DocsWords {
@@ -22,13 +31,48 @@ impl DocsWords {
         self.docs_words.put(writer, &document_id, bytes)
     }
 
+    pub fn put_doc_word_counts(
+        self,
+        writer: &mut heed::RwTxn<MainT>,
+        document_id: DocumentId,
+        counts: &BTreeMap<Vec<u8>, u32>,
+    ) -> ZResult<()> {
+        for (word, count) in counts {
+            let key = doc_word_key(document_id, word);
+            self.docs_word_counts.put(writer, &key, count)?;
+        }
+        Ok(())
+    }
+
     pub fn del_doc_words(self, writer: &mut heed::RwTxn<MainT>, document_id: DocumentId) -> ZResult<bool> {
+        let prefix = document_id.0.to_be_bytes();
+        let mut keys = Vec::new();
+        for result in self.docs_word_counts.prefix_iter(writer, &prefix)? {
+            let (key, _) = result?;
+            keys.push(key.to_owned());
+        }
+        for key in keys {
+            self.docs_word_counts.delete(writer, &key)?;
+        }
+
         let document_id = BEU32::new(document_id.0);
         self.docs_words.delete(writer, &document_id)
     }
 
     pub fn clear(self, writer: &mut heed::RwTxn<MainT>) -> ZResult<()> {
-        self.docs_words.clear(writer)
+        self.docs_words.clear(writer)?;
+        self.docs_word_counts.clear(writer)
+    }
+
+    /// Returns the number of times the word appears in the indexed attributes of the document.
+    pub fn word_count_for_document(
+        self,
+        reader: &heed::RoTxn<MainT>,
+        document_id: DocumentId,
+        word: &str,
+    ) -> ZResult<Option<u32>> {
+        let key = doc_word_key(document_id, word.as_bytes());
+        self.docs_word_counts.get(reader, &key)
     }
 
     pub fn doc_words(
@@ -0,0 +1,34 @@
+use heed::types::OwnedType;
+use heed::Result as ZResult;
+
+use super::BEU32;
+use crate::database::MainT;
+use crate::DocumentId;
+
+/// Stores the boost of the documents, the factor applied to their score by
+/// the `DocumentBoostReranker`, the documents without boost have a boost of 1.0.
+#[derive(Copy, Clone)]
+pub struct DocumentBoosts {
+    pub(crate) document_boosts: heed::Database<OwnedType<BEU32>, OwnedType<f32>>,
+}
+
+impl DocumentBoosts {
+    pub fn put_boost(self, writer: &mut heed::RwTxn<MainT>, document_id: DocumentId, boost: f32) -> ZResult<()> {
+        let document_id = BEU32::new(document_id.0);
+        self.document_boosts.put(writer, &document_id, &boost)
+    }
+
+    pub fn delete_boost(self, writer: &mut heed::RwTxn<MainT>, document_id: DocumentId) -> ZResult<bool> {
+        let document_id = BEU32::new(document_id.0);
+        self.document_boosts.delete(writer, &document_id)
+    }
+
+    pub fn boost(self, reader: &heed::RoTxn<MainT>, document_id: DocumentId) -> ZResult<Option<f32>> {
+        let document_id = BEU32::new(document_id.0);
+        self.document_boosts.get(reader, &document_id)
+    }
+
+    pub fn clear(self, writer: &mut heed::RwTxn<MainT>) -> ZResult<()> {
+        self.document_boosts.clear(writer)
+    }
+}
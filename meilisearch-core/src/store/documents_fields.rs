@@ -1,10 +1,12 @@
 use heed::types::{ByteSlice, OwnedType};
 use crate::database::MainT;
 use heed::Result as ZResult;
-use meilisearch_schema::FieldId;
+use meilisearch_schema::{FieldId, Schema};
+use serde_json::Value;
 
 use super::DocumentFieldStoredKey;
-use crate::DocumentId;
+use crate::serde::{decrypt_field, field_json_bytes, FieldCipher};
+use crate::{DocumentId, MResult};
 
 #[derive(Copy, Clone)]
 pub struct DocumentsFields {
@@ -57,6 +59,38 @@ impl DocumentsFields {
         let iter = self.documents_fields.range(reader, &(start..=end))?;
         Ok(DocumentFieldsIter { iter })
     }
+
+    /// Returns the stored fields of a document as a JSON object keyed by the fields names,
+    /// only the given fields are returned if any. Returns `None` if the document does not exist.
+    pub fn document_as_json(
+        self,
+        reader: &heed::RoTxn<MainT>,
+        document_id: DocumentId,
+        schema: &Schema,
+        fields: Option<&[FieldId]>,
+        cipher: Option<&FieldCipher>,
+    ) -> MResult<Option<Value>>
+    {
+        let mut found = false;
+        let mut document = serde_json::Map::new();
+
+        for result in self.document_fields(reader, document_id)? {
+            let (field_id, bytes) = result?;
+            found = true;
+
+            if fields.map_or(false, |fields| !fields.contains(&field_id)) {
+                continue;
+            }
+
+            if let Some(name) = schema.name(field_id) {
+                let bytes = decrypt_field(cipher, bytes)?;
+                let value = serde_json::from_slice(field_json_bytes(&bytes))?;
+                document.insert(name.to_string(), value);
+            }
+        }
+
+        Ok(if found { Some(Value::Object(document)) } else { None })
+    }
 }
 
 pub struct DocumentFieldsIter<'txn> {
@@ -77,3 +111,31 @@ impl<'txn> Iterator for DocumentFieldsIter<'txn> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::update::test_helpers::{add_documents, with_index};
+
+    #[test]
+    fn document_as_json_with_fields_names() {
+        with_index(|writer, index| {
+            let document = serde_json::json!({ "id": 123, "name": "Marvin", "age": null });
+            add_documents(writer, index, 0, serde_json::json!([document.clone()]));
+
+            let schema = index.main.schema(writer).unwrap().unwrap();
+            let docid = index.main.external_to_internal_docid(writer, "123").unwrap().unwrap();
+
+            let json = index.documents_fields.document_as_json(writer, docid, &schema, None, None).unwrap();
+            assert_eq!(json, Some(document));
+
+            let name = schema.id("name").unwrap();
+            let json = index.documents_fields.document_as_json(writer, docid, &schema, Some(&[name]), None).unwrap();
+            assert_eq!(json, Some(serde_json::json!({ "name": "Marvin" })));
+
+            let json = index.documents_fields.document_as_json(writer, DocumentId(42), &schema, None, None).unwrap();
+            assert_eq!(json, None);
+        });
+    }
+}
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use super::DocumentFieldIndexedKey;
 use crate::database::MainT;
 use crate::DocumentId;
@@ -68,6 +70,30 @@ impl DocumentsFieldsCounts {
         })
     }
 
+    /// Returns at most `limit` documents ids, in ascending order, starting from `start` included.
+    pub fn documents_ids_from(
+        self,
+        reader: &heed::RoTxn<MainT>,
+        start: DocumentId,
+        limit: usize,
+    ) -> ZResult<Vec<DocumentId>> {
+        let start = DocumentFieldIndexedKey::new(start, IndexedPos::min());
+
+        let mut documents_ids = Vec::new();
+        for result in self.documents_fields_counts.range(reader, &(start..))? {
+            let (key, _) = result?;
+            let document_id = DocumentId(key.docid.get());
+            if documents_ids.last() != Some(&document_id) {
+                if documents_ids.len() == limit {
+                    break;
+                }
+                documents_ids.push(document_id);
+            }
+        }
+
+        Ok(documents_ids)
+    }
+
     pub fn all_documents_fields_counts<'txn>(
         self,
         reader: &'txn heed::RoTxn<MainT>,
@@ -75,6 +101,19 @@ impl DocumentsFieldsCounts {
         let iter = self.documents_fields_counts.iter(reader)?;
         Ok(AllDocumentsFieldsCountsIter { iter })
     }
+
+    /// Returns, for each indexed attribute, the number of documents containing it
+    /// and the total number of tokens it contributes to these documents.
+    pub fn fields_tokens_counts(self, reader: &heed::RoTxn<MainT>) -> ZResult<HashMap<IndexedPos, (u64, u64)>> {
+        let mut counts = HashMap::new();
+        for result in self.all_documents_fields_counts(reader)? {
+            let (_, indexed_pos, count) = result?;
+            let (documents, tokens) = counts.entry(indexed_pos).or_insert((0, 0));
+            *documents += 1;
+            *tokens += u64::from(count);
+        }
+        Ok(counts)
+    }
 }
 
 pub struct DocumentFieldsCountsIter<'txn> {
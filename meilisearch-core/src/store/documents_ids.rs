@@ -24,26 +24,32 @@ impl<'a> BytesDecode<'a> for DocumentsIds {
     }
 }
 
+/// Gives the ids available for new documents, the free ids are drained first,
+/// then the unused ids are found in the gaps of the used ids.
 pub struct DiscoverIds<'a> {
+    used_ids: &'a Set<DocumentId>,
     ids_iter: std::slice::Iter<'a, DocumentId>,
     left_id: Option<u32>,
     right_id: Option<u32>,
     available_range: std::ops::Range<u32>,
+    free_ids: Vec<DocumentId>,
+    drained: usize,
 }
 
-impl DiscoverIds<'_> {
-    pub fn new(ids: &Set<DocumentId>) -> DiscoverIds {
+impl<'a> DiscoverIds<'a> {
+    pub fn new(ids: &'a Set<DocumentId>) -> DiscoverIds<'a> {
+        DiscoverIds::with_free_ids(ids, Vec::new())
+    }
+
+    /// The free ids must be sorted, the ones that are used are skipped.
+    pub fn with_free_ids(ids: &'a Set<DocumentId>, free_ids: Vec<DocumentId>) -> DiscoverIds<'a> {
         let mut ids_iter = ids.iter();
         let right_id = ids_iter.next().map(|id| id.0);
         let available_range = 0..right_id.unwrap_or(u32::max_value());
-        DiscoverIds { ids_iter, left_id: None, right_id, available_range }
+        DiscoverIds { used_ids: ids, ids_iter, left_id: None, right_id, available_range, free_ids, drained: 0 }
     }
-}
-
-impl Iterator for DiscoverIds<'_> {
-    type Item = DocumentId;
 
-    fn next(&mut self) -> Option<Self::Item> {
+    fn next_gap_id(&mut self) -> Option<DocumentId> {
         loop {
             match self.available_range.next() {
                 // The available range gives us a new id, we return it.
@@ -73,3 +79,25 @@ impl Iterator for DiscoverIds<'_> {
         }
     }
 }
+
+impl Iterator for DiscoverIds<'_> {
+    type Item = DocumentId;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(id) = self.free_ids.get(self.drained).copied() {
+            self.drained += 1;
+            if self.used_ids.binary_search(&id).is_err() {
+                return Some(id);
+            }
+        }
+
+        // the free ids have all been drained, they are not given twice
+        loop {
+            let id = self.next_gap_id()?;
+            if self.free_ids.binary_search(&id).is_err() {
+                return Some(id);
+            }
+        }
+    }
+}
+
@@ -0,0 +1,48 @@
+use heed::types::{OwnedType, Unit};
+use heed::Result as ZResult;
+
+use super::BEU64;
+use crate::database::MainT;
+use crate::DocumentId;
+
+/// Stores the ids freed by the documents deletions, they are given
+/// to the next documents added before the gaps of the used ids.
+#[derive(Copy, Clone)]
+pub struct FreeList {
+    pub(crate) free_list: heed::Database<OwnedType<BEU64>, Unit>,
+}
+
+impl FreeList {
+    pub fn put_ids(self, writer: &mut heed::RwTxn<MainT>, ids: &[DocumentId]) -> ZResult<()> {
+        for id in ids {
+            self.free_list.put(writer, &BEU64::new(u64::from(id.0)), &())?;
+        }
+        Ok(())
+    }
+
+    pub fn delete_ids(self, writer: &mut heed::RwTxn<MainT>, ids: &[DocumentId]) -> ZResult<()> {
+        for id in ids {
+            self.free_list.delete(writer, &BEU64::new(u64::from(id.0)))?;
+        }
+        Ok(())
+    }
+
+    /// Returns the free ids in ascending order.
+    pub fn ids(self, reader: &heed::RoTxn<MainT>) -> ZResult<Vec<DocumentId>> {
+        self.first_ids(reader, usize::max_value())
+    }
+
+    /// Returns at most `count` of the smallest free ids in ascending order.
+    pub fn first_ids(self, reader: &heed::RoTxn<MainT>, count: usize) -> ZResult<Vec<DocumentId>> {
+        let mut ids = Vec::new();
+        for result in self.free_list.iter(reader)?.take(count) {
+            let (id, ()) = result?;
+            ids.push(DocumentId(id.get() as u32));
+        }
+        Ok(ids)
+    }
+
+    pub fn clear(self, writer: &mut heed::RwTxn<MainT>) -> ZResult<()> {
+        self.free_list.clear(writer)
+    }
+}
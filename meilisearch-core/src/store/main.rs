@@ -1,48 +1,164 @@
 use std::borrow::Cow;
+use std::cmp::Ordering;
 use std::sync::Arc;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
 use chrono::{DateTime, Utc};
 use heed::Result as ZResult;
-use heed::types::{ByteSlice, OwnedType, SerdeBincode, Str};
-use meilisearch_schema::{FieldId, Schema};
+use heed::types::{ByteSlice, OwnedType, SerdeBincode, SerdeJson, Str, Unit};
+use meilisearch_schema::{FieldId, LegacySchema, Schema};
 use meilisearch_types::DocumentId;
 use sdset::Set;
+use serde::{Deserialize, Serialize};
 
 use crate::database::MainT;
-use crate::RankedMap;
+use crate::{MResult, NormalizationMode, RankedMap};
 use crate::settings::RankingRule;
-use super::{CowSet, DocumentsIds};
+use super::{BEU64, CowSet, DocumentsIds};
 
+const ALL_STOP_WORDS_KEY: &str = "all-stop-words";
 const ATTRIBUTES_FOR_FACETING_KEY: &str = "attributes-for-faceting";
+const ATTRIBUTE_WEIGHTS_KEY: &str = "attribute-weights";
 const CREATED_AT_KEY: &str = "created-at";
 const CUSTOMS_KEY: &str = "customs";
 const DISTINCT_ATTRIBUTE_KEY: &str = "distinct-attribute";
 const EXTERNAL_DOCIDS_KEY: &str = "external-docids";
 const FIELDS_FREQUENCY_KEY: &str = "fields-frequency";
 const INTERNAL_DOCIDS_KEY: &str = "internal-docids";
+const LANGUAGES_STOP_WORDS_KEY: &str = "languages-stop-words";
+const MAX_DOCUMENTS_KEY: &str = "max-documents";
 const NAME_KEY: &str = "name";
+const NORMALIZATION_MODE_KEY: &str = "normalization-mode";
 const NUMBER_OF_DOCUMENTS_KEY: &str = "number-of-documents";
 const RANKED_MAP_KEY: &str = "ranked-map";
 const RANKING_RULES_KEY: &str = "ranking-rules";
+const REINDEX_CHECKPOINT_KEY: &str = "reindex-checkpoint";
 const SCHEMA_KEY: &str = "schema";
+const SHORT_PREFIXES_STALE_KEY: &str = "short-prefixes-stale";
+const SHORTEST_CACHED_PREFIX_LEN_KEY: &str = "shortest-cached-prefix-len";
 const STOP_WORDS_KEY: &str = "stop-words";
+const STORE_VERSION_KEY: &str = "store-version";
 const SYNONYMS_KEY: &str = "synonyms";
 const UPDATED_AT_KEY: &str = "updated-at";
 const WORDS_KEY: &str = "words";
+const WORDS_CHECKSUM_KEY: &str = "words-checksum";
+
+/// The version of the format of the data stored by the indexes, it must be
+/// increased each time a change makes the previously stored data unreadable.
+pub const STORE_VERSION: u32 = 2;
 
 pub type FreqsMap = HashMap<String, usize>;
 type SerdeFreqsMap = SerdeBincode<FreqsMap>;
 type SerdeDatetime = SerdeBincode<DateTime<Utc>>;
+type LanguagesStopWords = BTreeMap<String, BTreeSet<String>>;
+type AttributeWeights = HashMap<String, f32>;
+
+/// An entry of the schema changelog, recorded each time the schema of an index is modified.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SchemaChange {
+    /// The unix timestamp, in seconds, at which the change was applied.
+    pub timestamp: u64,
+    pub kind: SchemaChangeKind,
+    /// The API key or the JWT subject with which the change was requested, when known.
+    pub by: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum SchemaChangeKind {
+    #[serde(rename_all = "camelCase")]
+    PrimaryKeyUpdated { primary_key: String },
+    FieldsAdded { fields: Vec<String> },
+    SearchableAttributesUpdated { attributes: Vec<String> },
+    DisplayedAttributesUpdated { attributes: Vec<String> },
+    #[serde(rename_all = "camelCase")]
+    AcceptNewFieldsUpdated { accept_new_fields: bool },
+    AttributeRenamed { from: String, to: String },
+}
+
+impl SchemaChangeKind {
+    /// Returns the changes that turn the `old` schema into the `new` one,
+    /// a missing schema is considered as being an empty one.
+    pub fn between(old: Option<&Schema>, new: &Schema) -> Vec<SchemaChangeKind> {
+        let empty = Schema::new();
+        let old = old.unwrap_or(&empty);
+        let mut changes = Vec::new();
+
+        if let Some(primary_key) = new.primary_key() {
+            if old.primary_key() != Some(primary_key) {
+                let primary_key = primary_key.to_string();
+                changes.push(SchemaChangeKind::PrimaryKeyUpdated { primary_key });
+            }
+        }
+
+        // the renamed fields keep their id
+        let mut renamed: Vec<_> = new.names()
+            .filter(|name| !old.contains(name))
+            .filter_map(|name| old.name(new.id(name)?).map(|old_name| (old_name, name)))
+            .collect();
+        renamed.sort_unstable();
+        for (from, to) in renamed {
+            let (from, to) = (from.to_string(), to.to_string());
+            changes.push(SchemaChangeKind::AttributeRenamed { from, to });
+        }
+
+        let mut fields: Vec<_> = new.names()
+            .filter(|name| !old.contains(name))
+            .filter(|name| new.id(name).and_then(|id| old.name(id)).is_none())
+            .map(str::to_string)
+            .collect();
+        if !fields.is_empty() {
+            fields.sort_unstable();
+            changes.push(SchemaChangeKind::FieldsAdded { fields });
+        }
+
+        // compared by ids, the renamed attributes are not updated
+        if old.indexed() != new.indexed() {
+            let attributes = new.indexed_name().into_iter().map(str::to_string).collect();
+            changes.push(SchemaChangeKind::SearchableAttributesUpdated { attributes });
+        }
+
+        if old.displayed() != new.displayed() {
+            let mut attributes: Vec<_> = new.displayed_name().into_iter().map(str::to_string).collect();
+            attributes.sort_unstable();
+            changes.push(SchemaChangeKind::DisplayedAttributesUpdated { attributes });
+        }
+
+        if old.accept_new_fields() != new.accept_new_fields() {
+            let accept_new_fields = new.accept_new_fields();
+            changes.push(SchemaChangeKind::AcceptNewFieldsUpdated { accept_new_fields });
+        }
+
+        changes
+    }
+}
+
+/// The progress of a documents reindexation, stored after each chunk of documents
+/// and removed once all the documents have been reindexed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReindexCheckpoint {
+    pub last_processed_doc_id: u64,
+}
 
 #[derive(Copy, Clone)]
 pub struct Main {
     pub(crate) main: heed::PolyDatabase,
+    pub(crate) schema_changelog: heed::Database<OwnedType<BEU64>, SerdeJson<SchemaChange>>,
 }
 
 impl Main {
     pub fn clear(self, writer: &mut heed::RwTxn<MainT>) -> ZResult<()> {
-        self.main.clear(writer)
+        self.main.clear(writer)?;
+        self.schema_changelog.clear(writer)
+    }
+
+    pub fn put_store_version(self, writer: &mut heed::RwTxn<MainT>, version: u32) -> ZResult<()> {
+        self.main.put::<_, Str, OwnedType<u32>>(writer, STORE_VERSION_KEY, &version)
+    }
+
+    pub fn store_version(self, reader: &heed::RoTxn<MainT>) -> ZResult<Option<u32>> {
+        self.main.get::<_, Str, OwnedType<u32>>(reader, STORE_VERSION_KEY)
     }
 
     pub fn put_name(self, writer: &mut heed::RwTxn<MainT>, name: &str) -> ZResult<()> {
@@ -139,6 +255,46 @@ impl Main {
         self.main.put::<_, Str, ByteSlice>(writer, EXTERNAL_DOCIDS_KEY, external_docids.as_slice())
     }
 
+    /// Removes the given external docids in a single pass over the external docids fst,
+    /// the ids are sorted and deduplicated in place. Returns the number of removed entries.
+    pub fn batch_delete_external_docids(
+        self,
+        writer: &mut heed::RwTxn<MainT>,
+        external_docids: &mut Vec<String>,
+    ) -> ZResult<usize>
+    {
+        external_docids.sort_unstable();
+        external_docids.dedup();
+
+        let old_external_docids = self.external_docids(writer)?;
+        let deleted = external_docids.iter().filter(|id| old_external_docids.contains_key(id)).count();
+
+        let ids = fst::Map::from_iter(external_docids.iter().map(|k| (k, 0))).unwrap();
+        self.remove_external_docids(writer, &ids)?;
+
+        Ok(deleted)
+    }
+
+    /// Replaces the external docids by the ones yielded by the iterator, building the fst
+    /// in a single pass. The iterator must yield the external docids in lexicographic order
+    /// and without duplicates, an error is returned otherwise. Returns the number of inserted entries.
+    pub fn put_external_docids_from_sorted_iter<I>(self, writer: &mut heed::RwTxn<MainT>, iter: I) -> MResult<u64>
+    where
+        I: IntoIterator<Item = (String, DocumentId)>,
+    {
+        let mut build = fst::MapBuilder::memory();
+        let mut count = 0;
+        for (external_docid, internal_docid) in iter {
+            build.insert(external_docid, internal_docid.0 as u64)?;
+            count += 1;
+        }
+        let external_docids = build.into_inner()?;
+
+        self.main.put::<_, Str, ByteSlice>(writer, EXTERNAL_DOCIDS_KEY, external_docids.as_slice())?;
+
+        Ok(count)
+    }
+
     pub fn external_docids(self, reader: &heed::RoTxn<MainT>) -> ZResult<fst::Map> {
         match self.main.get::<_, Str, ByteSlice>(reader, EXTERNAL_DOCIDS_KEY)? {
             Some(bytes) => {
@@ -156,8 +312,94 @@ impl Main {
         Ok(external_ids.get(external_docid).map(|id| DocumentId(id as u32)))
     }
 
+    /// Returns the internal ids of the given external docids, in the same order.
+    ///
+    /// The external docids map is read once and scanned forward a single time,
+    /// from the smallest to the biggest of the requested docids.
+    pub fn external_to_internal_docids(
+        self,
+        reader: &heed::RoTxn<MainT>,
+        external_docids: &[&str],
+    ) -> ZResult<Vec<Option<DocumentId>>> {
+        use fst::{IntoStreamer, Streamer};
+
+        let mut results = vec![None; external_docids.len()];
+        let mut sorted: Vec<(usize, &str)> = external_docids.iter().copied().enumerate().collect();
+        sorted.sort_unstable_by_key(|&(_, docid)| docid);
+
+        let (first, last) = match (sorted.first(), sorted.last()) {
+            (Some(&(_, first)), Some(&(_, last))) => (first, last),
+            _ => return Ok(results),
+        };
+
+        let external_ids = self.external_docids(reader)?;
+        let mut stream = external_ids.range().ge(first).le(last).into_stream();
+        let mut requested = sorted.into_iter().peekable();
+
+        while let Some((key, id)) = stream.next() {
+            // skip the requested docids that are smaller than the current key, they do not exist
+            while let Some(&(i, docid)) = requested.peek() {
+                match docid.as_bytes().cmp(key) {
+                    Ordering::Less => { requested.next(); },
+                    Ordering::Equal => {
+                        results[i] = Some(DocumentId(id as u32));
+                        requested.next();
+                    },
+                    Ordering::Greater => break,
+                }
+            }
+
+            if requested.peek().is_none() {
+                break;
+            }
+        }
+
+        Ok(results)
+    }
+
+    /// Returns the external docids starting with `prefix` along with their internal ids,
+    /// in lexicographic order.
+    pub fn external_docids_with_prefix(
+        self,
+        reader: &heed::RoTxn<MainT>,
+        prefix: &str,
+    ) -> ZResult<Vec<(String, DocumentId)>> {
+        use fst::{IntoStreamer, Streamer};
+
+        let external_ids = self.external_docids(reader)?;
+        let mut stream = external_ids.range().ge(prefix).into_stream();
+
+        let mut docids = Vec::new();
+        while let Some((key, id)) = stream.next() {
+            let key = match std::str::from_utf8(key) {
+                Ok(key) => key,
+                Err(_) => continue,
+            };
+            if !key.starts_with(prefix) {
+                break;
+            }
+            docids.push((key.to_owned(), DocumentId(id as u32)));
+        }
+
+        Ok(docids)
+    }
+
     pub fn put_words_fst(self, writer: &mut heed::RwTxn<MainT>, fst: &fst::Set) -> ZResult<()> {
-        self.main.put::<_, Str, ByteSlice>(writer, WORDS_KEY, fst.as_fst().as_bytes())
+        self.put_words_fst_with_checksum(writer, fst).map(drop)
+    }
+
+    /// Stores the words fst along with an FNV-1a checksum of its bytes and returns the checksum,
+    /// callers can compare it with a cached one to avoid cloning the fst when nothing changed.
+    pub fn put_words_fst_with_checksum(self, writer: &mut heed::RwTxn<MainT>, fst: &fst::Set) -> ZResult<u64> {
+        let bytes = fst.as_fst().as_bytes();
+        let checksum = fnv1a(bytes);
+        self.main.put::<_, Str, ByteSlice>(writer, WORDS_KEY, bytes)?;
+        self.main.put::<_, Str, OwnedType<u64>>(writer, WORDS_CHECKSUM_KEY, &checksum)?;
+        Ok(checksum)
+    }
+
+    pub fn words_fst_checksum(self, reader: &heed::RoTxn<MainT>) -> ZResult<Option<u64>> {
+        self.main.get::<_, Str, OwnedType<u64>>(reader, WORDS_CHECKSUM_KEY)
     }
 
     pub unsafe fn static_words_fst(self, reader: &heed::RoTxn<MainT>) -> ZResult<Option<fst::Set>> {
@@ -183,7 +425,59 @@ impl Main {
         }
     }
 
+    /// Marks the short prefixes cache as out of sync with the words fst,
+    /// it stays marked until the background rebuild is done.
+    pub fn put_short_prefixes_stale(self, writer: &mut heed::RwTxn<MainT>) -> ZResult<()> {
+        self.main.put::<_, Str, Unit>(writer, SHORT_PREFIXES_STALE_KEY, &())
+    }
+
+    pub fn short_prefixes_stale(self, reader: &heed::RoTxn<MainT>) -> ZResult<bool> {
+        self.main.get::<_, Str, Unit>(reader, SHORT_PREFIXES_STALE_KEY).map(|o| o.is_some())
+    }
+
+    pub fn delete_short_prefixes_stale(self, writer: &mut heed::RwTxn<MainT>) -> ZResult<bool> {
+        self.main.delete::<_, Str>(writer, SHORT_PREFIXES_STALE_KEY)
+    }
+
+    pub fn put_shortest_cached_prefix_len(self, writer: &mut heed::RwTxn<MainT>, len: u64) -> ZResult<()> {
+        self.main.put::<_, Str, OwnedType<u64>>(writer, SHORTEST_CACHED_PREFIX_LEN_KEY, &len)
+    }
+
+    /// The length of the shortest prefixes stored in the prefix postings lists cache,
+    /// the indexes built before this length was stored cache the prefixes of one character.
+    pub fn shortest_cached_prefix_len(self, reader: &heed::RoTxn<MainT>) -> ZResult<usize> {
+        let len = self.main.get::<_, Str, OwnedType<u64>>(reader, SHORTEST_CACHED_PREFIX_LEN_KEY)?;
+        Ok(len.map_or(1, |len| len as usize))
+    }
+
+    pub fn put_reindex_checkpoint(self, writer: &mut heed::RwTxn<MainT>, checkpoint: ReindexCheckpoint) -> ZResult<()> {
+        self.main.put::<_, Str, SerdeBincode<ReindexCheckpoint>>(writer, REINDEX_CHECKPOINT_KEY, &checkpoint)
+    }
+
+    pub fn reindex_checkpoint(self, reader: &heed::RoTxn<MainT>) -> ZResult<Option<ReindexCheckpoint>> {
+        self.main.get::<_, Str, SerdeBincode<ReindexCheckpoint>>(reader, REINDEX_CHECKPOINT_KEY)
+    }
+
+    pub fn delete_reindex_checkpoint(self, writer: &mut heed::RwTxn<MainT>) -> ZResult<bool> {
+        self.main.delete::<_, Str>(writer, REINDEX_CHECKPOINT_KEY)
+    }
+
+    /// Stores the new schema and records the differences with the previous one in the changelog.
     pub fn put_schema(self, writer: &mut heed::RwTxn<MainT>, schema: &Schema) -> ZResult<()> {
+        self.put_schema_by(writer, schema, None)
+    }
+
+    /// Same as [`put_schema`], the changes being recorded as made by the given actor.
+    ///
+    /// [`put_schema`]: #method.put_schema
+    pub fn put_schema_by(self, writer: &mut heed::RwTxn<MainT>, schema: &Schema, by: Option<&str>) -> ZResult<()> {
+        let old_schema = self.schema(writer)?;
+        let timestamp = Utc::now().timestamp() as u64;
+        for kind in SchemaChangeKind::between(old_schema.as_ref(), schema) {
+            let by = by.map(str::to_string);
+            self.append_schema_change(writer, SchemaChange { timestamp, kind, by })?;
+        }
+
         self.main.put::<_, Str, SerdeBincode<Schema>>(writer, SCHEMA_KEY, schema)
     }
 
@@ -191,10 +485,36 @@ impl Main {
         self.main.get::<_, Str, SerdeBincode<Schema>>(reader, SCHEMA_KEY)
     }
 
+    /// Rewrites the schema stored in the format of the store versions preceding the 2nd in the
+    /// current format, the schema is not modified and nothing is recorded in the changelog.
+    pub fn migrate_legacy_schema(self, writer: &mut heed::RwTxn<MainT>) -> ZResult<()> {
+        match self.main.get::<_, Str, SerdeBincode<LegacySchema>>(writer, SCHEMA_KEY)? {
+            Some(schema) => self.main.put::<_, Str, SerdeBincode<Schema>>(writer, SCHEMA_KEY, &schema.into()),
+            None => Ok(()),
+        }
+    }
+
     pub fn delete_schema(self, writer: &mut heed::RwTxn<MainT>) -> ZResult<bool> {
         self.main.delete::<_, Str>(writer, SCHEMA_KEY)
     }
 
+    pub fn append_schema_change(self, writer: &mut heed::RwTxn<MainT>, change: SchemaChange) -> ZResult<()> {
+        let id = match self.schema_changelog.last(writer)? {
+            Some((last_id, _)) => last_id.get() + 1,
+            None => 0,
+        };
+        self.schema_changelog.put(writer, &BEU64::new(id), &change)
+    }
+
+    pub fn schema_changelog(self, reader: &heed::RoTxn<MainT>) -> ZResult<Vec<(u64, SchemaChange)>> {
+        let mut changes = Vec::new();
+        for result in self.schema_changelog.iter(reader)? {
+            let (id, change) = result?;
+            changes.push((id.get(), change));
+        }
+        Ok(changes)
+    }
+
     pub fn put_ranked_map(self, writer: &mut heed::RwTxn<MainT>, ranked_map: &RankedMap) -> ZResult<()> {
         self.main.put::<_, Str, SerdeBincode<RankedMap>>(writer, RANKED_MAP_KEY, &ranked_map)
     }
@@ -220,9 +540,10 @@ impl Main {
         }
     }
 
-    pub fn put_stop_words_fst(self, writer: &mut heed::RwTxn<MainT>, fst: &fst::Set) -> ZResult<()> {
+    pub fn put_stop_words_fst(self, writer: &mut heed::RwTxn<MainT>, fst: &fst::Set) -> MResult<()> {
         let bytes = fst.as_fst().as_bytes();
-        self.main.put::<_, Str, ByteSlice>(writer, STOP_WORDS_KEY, bytes)
+        self.main.put::<_, Str, ByteSlice>(writer, STOP_WORDS_KEY, bytes)?;
+        self.put_all_stop_words_fst(writer)
     }
 
     pub fn stop_words_fst(self, reader: &heed::RoTxn<MainT>) -> ZResult<Option<fst::Set>> {
@@ -237,6 +558,55 @@ impl Main {
         }
     }
 
+    /// Replaces the stop words of the given language, identified by its ISO 639-1 code.
+    /// These stop words are ignored while indexing the documents declaring this language
+    /// in their `_language` field and are removed from the queries on any document.
+    pub fn put_stop_words_for_language(
+        self,
+        writer: &mut heed::RwTxn<MainT>,
+        language: &str,
+        words: &[&str],
+    ) -> MResult<()>
+    {
+        let mut languages = self.languages_stop_words(writer)?;
+        let words = words.iter().map(|word| word.to_lowercase()).collect();
+        languages.insert(language.to_lowercase(), words);
+        self.main.put::<_, Str, SerdeBincode<LanguagesStopWords>>(writer, LANGUAGES_STOP_WORDS_KEY, &languages)?;
+        self.put_all_stop_words_fst(writer)
+    }
+
+    /// Returns the stop words of every language, indexed by their ISO 639-1 code.
+    pub fn languages_stop_words(self, reader: &heed::RoTxn<MainT>) -> ZResult<LanguagesStopWords> {
+        let languages = self.main.get::<_, Str, SerdeBincode<LanguagesStopWords>>(reader, LANGUAGES_STOP_WORDS_KEY)?;
+        Ok(languages.unwrap_or_default())
+    }
+
+    /// Stores the union of the index stop words and of the stop words of every language,
+    /// it is rebuilt each time one of them is updated.
+    fn put_all_stop_words_fst(self, writer: &mut heed::RwTxn<MainT>) -> MResult<()> {
+        let stop_words = self.stop_words_fst(writer)?.unwrap_or_default();
+        let mut words: BTreeSet<String> = self.languages_stop_words(writer)?.into_iter().flat_map(|(_, words)| words).collect();
+        words.extend(stop_words.stream().into_strs()?);
+
+        let fst = fst::Set::from_iter(words)?;
+        let bytes = fst.as_fst().as_bytes();
+        Ok(self.main.put::<_, Str, ByteSlice>(writer, ALL_STOP_WORDS_KEY, bytes)?)
+    }
+
+    /// Returns the union of the index stop words and of the stop words of every language.
+    pub fn all_stop_words_fst(self, reader: &heed::RoTxn<MainT>) -> ZResult<fst::Set> {
+        match self.main.get::<_, Str, ByteSlice>(reader, ALL_STOP_WORDS_KEY)? {
+            Some(bytes) => {
+                let len = bytes.len();
+                let bytes = Arc::new(bytes.to_owned());
+                let fst = fst::raw::Fst::from_shared_bytes(bytes, 0, len).unwrap();
+                Ok(fst::Set::from(fst))
+            }
+            // the indexes without languages stop words only have their own stop words
+            None => Ok(self.stop_words_fst(reader)?.unwrap_or_default()),
+        }
+    }
+
     pub fn put_number_of_documents<F>(self, writer: &mut heed::RwTxn<MainT>, f: F) -> ZResult<u64>
     where
         F: Fn(u64) -> u64,
@@ -257,6 +627,30 @@ impl Main {
         }
     }
 
+    pub fn max_documents(self, reader: &heed::RoTxn<MainT>) -> ZResult<Option<u64>> {
+        self.main.get::<_, Str, OwnedType<u64>>(reader, MAX_DOCUMENTS_KEY)
+    }
+
+    pub fn put_max_documents(self, writer: &mut heed::RwTxn<MainT>, limit: u64) -> ZResult<()> {
+        self.main.put::<_, Str, OwnedType<u64>>(writer, MAX_DOCUMENTS_KEY, &limit)
+    }
+
+    pub fn delete_max_documents(self, writer: &mut heed::RwTxn<MainT>) -> ZResult<bool> {
+        self.main.delete::<_, Str>(writer, MAX_DOCUMENTS_KEY)
+    }
+
+    pub fn normalization_mode(self, reader: &heed::RoTxn<MainT>) -> ZResult<Option<NormalizationMode>> {
+        self.main.get::<_, Str, SerdeBincode<NormalizationMode>>(reader, NORMALIZATION_MODE_KEY)
+    }
+
+    pub fn put_normalization_mode(self, writer: &mut heed::RwTxn<MainT>, mode: NormalizationMode) -> ZResult<()> {
+        self.main.put::<_, Str, SerdeBincode<NormalizationMode>>(writer, NORMALIZATION_MODE_KEY, &mode)
+    }
+
+    pub fn delete_normalization_mode(self, writer: &mut heed::RwTxn<MainT>) -> ZResult<bool> {
+        self.main.delete::<_, Str>(writer, NORMALIZATION_MODE_KEY)
+    }
+
     pub fn put_fields_frequency(
         self,
         writer: &mut heed::RwTxn<MainT>,
@@ -315,6 +709,18 @@ impl Main {
         self.main.delete::<_, Str>(writer, DISTINCT_ATTRIBUTE_KEY)
     }
 
+    pub fn attribute_weights(self, reader: &heed::RoTxn<MainT>) -> ZResult<Option<AttributeWeights>> {
+        self.main.get::<_, Str, SerdeBincode<AttributeWeights>>(reader, ATTRIBUTE_WEIGHTS_KEY)
+    }
+
+    pub fn put_attribute_weights(self, writer: &mut heed::RwTxn<MainT>, weights: &AttributeWeights) -> ZResult<()> {
+        self.main.put::<_, Str, SerdeBincode<AttributeWeights>>(writer, ATTRIBUTE_WEIGHTS_KEY, weights)
+    }
+
+    pub fn delete_attribute_weights(self, writer: &mut heed::RwTxn<MainT>) -> ZResult<bool> {
+        self.main.delete::<_, Str>(writer, ATTRIBUTE_WEIGHTS_KEY)
+    }
+
     pub fn put_customs(self, writer: &mut heed::RwTxn<MainT>, customs: &[u8]) -> ZResult<()> {
         self.main
             .put::<_, Str, ByteSlice>(writer, CUSTOMS_KEY, customs)
@@ -324,3 +730,111 @@ impl Main {
         self.main.get::<_, Str, ByteSlice>(reader, CUSTOMS_KEY)
     }
 }
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+    bytes.iter().fold(FNV_OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(FNV_PRIME)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::update::test_helpers::{add_documents, delete_documents, with_index};
+    use crate::Error;
+
+    #[test]
+    fn external_docids_from_sorted_iter() {
+        with_index(|writer, index| {
+            let docids = vec![
+                ("abc".to_string(), DocumentId(2)),
+                ("abd".to_string(), DocumentId(0)),
+                ("b".to_string(), DocumentId(1)),
+            ];
+            let count = index.main.put_external_docids_from_sorted_iter(writer, docids).unwrap();
+            assert_eq!(count, 3);
+            assert_eq!(index.main.external_to_internal_docid(writer, "abd").unwrap(), Some(DocumentId(0)));
+            assert_eq!(index.main.external_to_internal_docid(writer, "b").unwrap(), Some(DocumentId(1)));
+
+            // unsorted external docids are refused
+            let docids = vec![("b".to_string(), DocumentId(1)), ("a".to_string(), DocumentId(0))];
+            let result = index.main.put_external_docids_from_sorted_iter(writer, docids);
+            assert_matches!(result, Err(Error::Fst(_)));
+        });
+    }
+
+    #[test]
+    fn external_to_internal_docids_batch() {
+        with_index(|writer, index| {
+            let docids = vec![
+                ("abc".to_string(), DocumentId(2)),
+                ("abd".to_string(), DocumentId(0)),
+                ("b".to_string(), DocumentId(1)),
+                ("c".to_string(), DocumentId(3)),
+            ];
+            index.main.put_external_docids_from_sorted_iter(writer, docids).unwrap();
+
+            let results = index.main.external_to_internal_docids(writer, &["c", "aaa", "abd", "bb", "c", "abc"]).unwrap();
+            let expected = vec![
+                Some(DocumentId(3)),
+                None,
+                Some(DocumentId(0)),
+                None,
+                Some(DocumentId(3)),
+                Some(DocumentId(2)),
+            ];
+            assert_eq!(results, expected);
+
+            assert!(index.main.external_to_internal_docids(writer, &[]).unwrap().is_empty());
+        });
+    }
+
+    #[test]
+    fn external_docids_looked_up_by_prefix() {
+        with_index(|writer, index| {
+            add_documents(writer, index, 0, serde_json::json!([
+                { "id": "movie-2" }, { "id": "show-1" }, { "id": "mov" }, { "id": "movie-1" },
+            ]));
+
+            let external_docids = |prefix| -> Vec<String> {
+                let docids = index.main.external_docids_with_prefix(writer, prefix).unwrap();
+                docids.into_iter().map(|(external_docid, _)| external_docid).collect()
+            };
+
+            assert_eq!(external_docids("movie-"), vec!["movie-1", "movie-2"]);
+            assert_eq!(external_docids("mov"), vec!["mov", "movie-1", "movie-2"]);
+            assert_eq!(external_docids(""), vec!["mov", "movie-1", "movie-2", "show-1"]);
+            assert!(external_docids("shows").is_empty());
+
+            let (_, internal_docid) = index.main.external_docids_with_prefix(writer, "show").unwrap().remove(0);
+            assert_eq!(index.main.external_to_internal_docid(writer, "show-1").unwrap(), Some(internal_docid));
+        });
+    }
+
+    #[test]
+    fn external_docids_deleted_in_batch() {
+        with_index(|writer, index| {
+            add_documents(writer, index, 0, serde_json::json!([
+                { "id": "movie-1" }, { "id": "movie-2" }, { "id": "show-1" },
+            ]));
+
+            // the duplicates and the unknown ids are not counted
+            let mut external_docids = vec!["show-1", "movie-1", "unknown", "movie-1"].into_iter().map(String::from).collect();
+            let deleted = index.main.batch_delete_external_docids(writer, &mut external_docids).unwrap();
+            assert_eq!(deleted, 2);
+            assert_eq!(external_docids, vec!["movie-1", "show-1", "unknown"]);
+
+            let remaining = index.main.external_docids_with_prefix(writer, "").unwrap();
+            assert_eq!(remaining.len(), 1);
+            assert_eq!(remaining[0].0, "movie-2");
+
+            // the documents deletion removes their external ids
+            delete_documents(writer, index, 1, &["movie-2"]);
+            assert!(index.main.external_docids(writer).unwrap().is_empty());
+        });
+    }
+}
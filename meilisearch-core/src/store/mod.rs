@@ -1,9 +1,11 @@
 mod cow_set;
 mod docs_words;
+mod document_boosts;
 mod documents_ids;
 mod documents_fields;
 mod documents_fields_counts;
 mod facets;
+mod free_list;
 mod main;
 mod postings_lists;
 mod prefix_documents_cache;
@@ -11,37 +13,48 @@ mod prefix_postings_lists_cache;
 mod synonyms;
 mod updates;
 mod updates_results;
+mod word_frequency;
+mod word_prefix_pair_proximity_docids;
 
 pub use self::cow_set::CowSet;
 pub use self::docs_words::DocsWords;
+pub use self::document_boosts::DocumentBoosts;
 pub use self::documents_fields::{DocumentFieldsIter, DocumentsFields};
 pub use self::documents_fields_counts::{DocumentFieldsCountsIter, DocumentsFieldsCounts, DocumentsIdsIter};
 pub use self::documents_ids::{DocumentsIds, DiscoverIds};
 pub use self::facets::Facets;
-pub use self::main::Main;
-pub use self::postings_lists::PostingsLists;
+pub use self::free_list::FreeList;
+pub use self::main::{Main, ReindexCheckpoint, SchemaChange, SchemaChangeKind, STORE_VERSION};
+pub use self::postings_lists::{PostingEntry, PostingsLists, PostingsListIter};
 pub use self::prefix_documents_cache::PrefixDocumentsCache;
 pub use self::prefix_postings_lists_cache::PrefixPostingsListsCache;
 pub use self::synonyms::Synonyms;
 pub use self::updates::Updates;
-pub use self::updates_results::UpdatesResults;
+pub use self::updates_results::{CompactionStats, UpdatesResults};
+pub use self::word_frequency::WordFrequency;
+pub use self::word_prefix_pair_proximity_docids::WordPrefixPairProximityDocIds;
 
 use std::borrow::Cow;
 use std::collections::HashSet;
 use std::convert::TryInto;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::{mem, ptr};
 
 use heed::Result as ZResult;
+use fst::{IntoStreamer, Streamer};
 use heed::{BytesEncode, BytesDecode};
 use meilisearch_schema::{IndexedPos, FieldId};
 use sdset::{Set, SetBuf};
 use serde::de::{self, Deserialize};
 use zerocopy::{AsBytes, FromBytes};
 
+use crate::automaton::build_correction_dfa;
 use crate::criterion::Criteria;
+use crate::raw_indexer::TokenLimits;
 use crate::database::{MainT, UpdateT};
 use crate::database::{UpdateEvent, UpdateEventsEmitter};
-use crate::serde::Deserializer;
+use crate::serde::{decrypt_field, field_json_bytes, Deserializer, FieldCipher};
 use crate::settings::SettingsUpdate;
 use crate::{query_builder::QueryBuilder, update, DocIndex, DocumentId, Error, MResult};
 
@@ -166,6 +179,10 @@ fn main_name(name: &str) -> String {
     format!("store-{}", name)
 }
 
+fn schema_changelog_name(name: &str) -> String {
+    format!("store-{}-schema-changelog", name)
+}
+
 fn postings_lists_name(name: &str) -> String {
     format!("store-{}-postings-lists", name)
 }
@@ -186,6 +203,14 @@ fn docs_words_name(name: &str) -> String {
     format!("store-{}-docs-words", name)
 }
 
+fn docs_word_counts_name(name: &str) -> String {
+    format!("store-{}-docs-word-counts", name)
+}
+
+fn word_frequency_name(name: &str) -> String {
+    format!("store-{}-word-frequency", name)
+}
+
 fn prefix_documents_cache_name(name: &str) -> String {
     format!("store-{}-prefix-documents-cache", name)
 }
@@ -194,6 +219,18 @@ fn prefix_postings_lists_cache_name(name: &str) -> String {
     format!("store-{}-prefix-postings-lists-cache", name)
 }
 
+fn word_prefix_pair_proximity_docids_name(name: &str) -> String {
+    format!("store-{}-word-prefix-pair-proximity-docids", name)
+}
+
+fn document_boosts_name(name: &str) -> String {
+    format!("store-{}-document-boosts", name)
+}
+
+fn free_list_name(name: &str) -> String {
+    format!("store-{}-free-list", name)
+}
+
 fn updates_name(name: &str) -> String {
     format!("store-{}-updates", name)
 }
@@ -215,15 +252,36 @@ pub struct Index {
     pub facets: Facets,
     pub synonyms: Synonyms,
     pub docs_words: DocsWords,
+    pub word_frequency: WordFrequency,
     pub prefix_documents_cache: PrefixDocumentsCache,
     pub prefix_postings_lists_cache: PrefixPostingsListsCache,
+    pub word_prefix_pair_proximity_docids: WordPrefixPairProximityDocIds,
+    pub document_boosts: DocumentBoosts,
+    pub free_list: FreeList,
 
     pub updates: Updates,
     pub updates_results: UpdatesResults,
     pub(crate) updates_notifier: UpdateEventsEmitter,
+    pub(crate) read_only: Arc<AtomicBool>,
+    pub(crate) token_limits: TokenLimits,
+    pub(crate) checkpoint_interval: Option<usize>,
+    pub(crate) field_cipher: Option<FieldCipher>,
 }
 
 impl Index {
+    /// Returns `true` if the updates of this index must not be processed for now.
+    pub fn is_read_only(&self) -> bool {
+        self.read_only.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn set_read_only(&self, read_only: bool) {
+        self.read_only.store(read_only, Ordering::SeqCst);
+        // wake up the update loop, the updates enqueued in the meantime must be processed
+        if !read_only {
+            let _ = self.updates_notifier.send(UpdateEvent::NewUpdate);
+        }
+    }
+
     pub fn document<T: de::DeserializeOwned>(
         &self,
         reader: &heed::RoTxn<MainT>,
@@ -244,11 +302,41 @@ impl Index {
             documents_fields: self.documents_fields,
             schema: &schema,
             fields: attributes.as_ref(),
+            cipher: self.field_cipher.as_ref(),
         };
 
         Ok(Option::<T>::deserialize(&mut deserializer)?)
     }
 
+    /// Retrieves the documents with the given ids in the same order, the schema is read once
+    /// for all of them. The ids without document are skipped.
+    pub fn documents_batch<T: de::DeserializeOwned>(
+        &self,
+        reader: &heed::RoTxn<MainT>,
+        ids: &[DocumentId],
+    ) -> MResult<Vec<T>> {
+        let schema = self.main.schema(reader)?;
+        let schema = schema.ok_or(Error::SchemaMissing)?;
+
+        let mut documents = Vec::with_capacity(ids.len());
+        for document_id in ids.iter().copied() {
+            let mut deserializer = Deserializer {
+                document_id,
+                reader,
+                documents_fields: self.documents_fields,
+                schema: &schema,
+                fields: None,
+                cipher: self.field_cipher.as_ref(),
+            };
+
+            if let Some(document) = Option::<T>::deserialize(&mut deserializer)? {
+                documents.push(document);
+            }
+        }
+
+        Ok(documents)
+    }
+
     pub fn document_attribute<T: de::DeserializeOwned>(
         &self,
         reader: &heed::RoTxn<MainT>,
@@ -259,7 +347,10 @@ impl Index {
             .documents_fields
             .document_attribute(reader, document_id, attribute)?;
         match bytes {
-            Some(bytes) => Ok(Some(serde_json::from_slice(bytes)?)),
+            Some(bytes) => {
+                let bytes = decrypt_field(self.field_cipher.as_ref(), bytes)?;
+                Ok(Some(serde_json::from_slice(field_json_bytes(&bytes))?))
+            }
             None => Ok(None),
         }
     }
@@ -279,6 +370,20 @@ impl Index {
         }
     }
 
+    /// Returns the stored bytes of the attribute, decrypted if the attribute is encrypted.
+    /// The encrypted bytes of equal values differ, they can't be compared.
+    pub fn decrypted_document_attribute_bytes<'txn>(
+        &self,
+        reader: &'txn heed::RoTxn<MainT>,
+        document_id: DocumentId,
+        attribute: FieldId,
+    ) -> MResult<Option<Cow<'txn, [u8]>>> {
+        match self.document_attribute_bytes(reader, document_id, attribute)? {
+            Some(bytes) => Ok(Some(decrypt_field(self.field_cipher.as_ref(), bytes)?)),
+            None => Ok(None),
+        }
+    }
+
     pub fn customs_update(&self, writer: &mut heed::RwTxn<UpdateT>, customs: Vec<u8>) -> ZResult<u64> {
         let _ = self.updates_notifier.send(UpdateEvent::NewUpdate);
         update::push_customs_update(writer, self.updates, self.updates_results, customs)
@@ -381,44 +486,64 @@ pub fn create(
 ) -> MResult<Index> {
     // create all the store names
     let main_name = main_name(name);
+    let schema_changelog_name = schema_changelog_name(name);
     let postings_lists_name = postings_lists_name(name);
     let documents_fields_name = documents_fields_name(name);
     let documents_fields_counts_name = documents_fields_counts_name(name);
     let synonyms_name = synonyms_name(name);
     let docs_words_name = docs_words_name(name);
+    let docs_word_counts_name = docs_word_counts_name(name);
+    let word_frequency_name = word_frequency_name(name);
     let prefix_documents_cache_name = prefix_documents_cache_name(name);
     let prefix_postings_lists_cache_name = prefix_postings_lists_cache_name(name);
+    let word_prefix_pair_proximity_docids_name = word_prefix_pair_proximity_docids_name(name);
+    let document_boosts_name = document_boosts_name(name);
+    let free_list_name = free_list_name(name);
     let updates_name = updates_name(name);
     let updates_results_name = updates_results_name(name);
     let facets_name = facets_name(name);
 
     // open all the stores
     let main = env.create_poly_database(Some(&main_name))?;
+    let schema_changelog = env.create_database(Some(&schema_changelog_name))?;
     let postings_lists = env.create_database(Some(&postings_lists_name))?;
     let documents_fields = env.create_database(Some(&documents_fields_name))?;
     let documents_fields_counts = env.create_database(Some(&documents_fields_counts_name))?;
     let facets = env.create_database(Some(&facets_name))?;
     let synonyms = env.create_database(Some(&synonyms_name))?;
     let docs_words = env.create_database(Some(&docs_words_name))?;
+    let docs_word_counts = env.create_database(Some(&docs_word_counts_name))?;
+    let word_frequency = env.create_database(Some(&word_frequency_name))?;
     let prefix_documents_cache = env.create_database(Some(&prefix_documents_cache_name))?;
     let prefix_postings_lists_cache = env.create_database(Some(&prefix_postings_lists_cache_name))?;
+    let word_prefix_pair_proximity_docids = env.create_database(Some(&word_prefix_pair_proximity_docids_name))?;
+    let document_boosts = env.create_database(Some(&document_boosts_name))?;
+    let free_list = env.create_database(Some(&free_list_name))?;
     let updates = update_env.create_database(Some(&updates_name))?;
     let updates_results = update_env.create_database(Some(&updates_results_name))?;
 
     Ok(Index {
-        main: Main { main },
+        main: Main { main, schema_changelog },
         postings_lists: PostingsLists { postings_lists },
         documents_fields: DocumentsFields { documents_fields },
         documents_fields_counts: DocumentsFieldsCounts { documents_fields_counts },
         synonyms: Synonyms { synonyms },
-        docs_words: DocsWords { docs_words },
+        docs_words: DocsWords { docs_words, docs_word_counts },
+        word_frequency: WordFrequency { word_frequency },
         prefix_postings_lists_cache: PrefixPostingsListsCache { prefix_postings_lists_cache },
+        word_prefix_pair_proximity_docids: WordPrefixPairProximityDocIds { word_prefix_pair_proximity_docids },
+        document_boosts: DocumentBoosts { document_boosts },
+        free_list: FreeList { free_list },
         prefix_documents_cache: PrefixDocumentsCache { prefix_documents_cache },
         facets: Facets { facets },
 
         updates: Updates { updates },
         updates_results: UpdatesResults { updates_results },
         updates_notifier,
+        read_only: Arc::new(AtomicBool::new(false)),
+        token_limits: TokenLimits::default(),
+        checkpoint_interval: None,
+        field_cipher: None,
     })
 }
 
@@ -430,22 +555,29 @@ pub fn open(
 ) -> MResult<Option<Index>> {
     // create all the store names
     let main_name = main_name(name);
+    let schema_changelog_name = schema_changelog_name(name);
     let postings_lists_name = postings_lists_name(name);
     let documents_fields_name = documents_fields_name(name);
     let documents_fields_counts_name = documents_fields_counts_name(name);
     let synonyms_name = synonyms_name(name);
     let docs_words_name = docs_words_name(name);
+    let docs_word_counts_name = docs_word_counts_name(name);
+    let word_frequency_name = word_frequency_name(name);
     let prefix_documents_cache_name = prefix_documents_cache_name(name);
     let facets_name = facets_name(name);
     let prefix_postings_lists_cache_name = prefix_postings_lists_cache_name(name);
+    let word_prefix_pair_proximity_docids_name = word_prefix_pair_proximity_docids_name(name);
+    let document_boosts_name = document_boosts_name(name);
+    let free_list_name = free_list_name(name);
     let updates_name = updates_name(name);
     let updates_results_name = updates_results_name(name);
 
-    // open all the stores
+    // open all the stores, the ones added after the index was created are created empty
     let main = match env.open_poly_database(Some(&main_name))? {
         Some(main) => main,
         None => return Ok(None),
     };
+    let schema_changelog = env.create_database(Some(&schema_changelog_name))?;
     let postings_lists = match env.open_database(Some(&postings_lists_name))? {
         Some(postings_lists) => postings_lists,
         None => return Ok(None),
@@ -466,6 +598,8 @@ pub fn open(
         Some(docs_words) => docs_words,
         None => return Ok(None),
     };
+    let docs_word_counts = env.create_database(Some(&docs_word_counts_name))?;
+    let word_frequency = env.create_database(Some(&word_frequency_name))?;
     let prefix_documents_cache = match env.open_database(Some(&prefix_documents_cache_name))? {
         Some(prefix_documents_cache) => prefix_documents_cache,
         None => return Ok(None),
@@ -478,6 +612,9 @@ pub fn open(
         Some(prefix_postings_lists_cache) => prefix_postings_lists_cache,
         None => return Ok(None),
     };
+    let word_prefix_pair_proximity_docids = env.create_database(Some(&word_prefix_pair_proximity_docids_name))?;
+    let document_boosts = env.create_database(Some(&document_boosts_name))?;
+    let free_list = env.create_database(Some(&free_list_name))?;
     let updates = match update_env.open_database(Some(&updates_name))? {
         Some(updates) => updates,
         None => return Ok(None),
@@ -488,18 +625,26 @@ pub fn open(
     };
 
     Ok(Some(Index {
-        main: Main { main },
+        main: Main { main, schema_changelog },
         postings_lists: PostingsLists { postings_lists },
         documents_fields: DocumentsFields { documents_fields },
         documents_fields_counts: DocumentsFieldsCounts { documents_fields_counts },
         synonyms: Synonyms { synonyms },
-        docs_words: DocsWords { docs_words },
+        docs_words: DocsWords { docs_words, docs_word_counts },
+        word_frequency: WordFrequency { word_frequency },
         prefix_documents_cache: PrefixDocumentsCache { prefix_documents_cache },
         facets: Facets { facets },
         prefix_postings_lists_cache: PrefixPostingsListsCache { prefix_postings_lists_cache },
+        word_prefix_pair_proximity_docids: WordPrefixPairProximityDocIds { word_prefix_pair_proximity_docids },
+        document_boosts: DocumentBoosts { document_boosts },
+        free_list: FreeList { free_list },
         updates: Updates { updates },
         updates_results: UpdatesResults { updates_results },
         updates_notifier,
+        read_only: Arc::new(AtomicBool::new(false)),
+        token_limits: TokenLimits::default(),
+        checkpoint_interval: None,
+        field_cipher: None,
     }))
 }
 
@@ -515,9 +660,264 @@ pub fn clear(
     index.documents_fields_counts.clear(writer)?;
     index.synonyms.clear(writer)?;
     index.docs_words.clear(writer)?;
+    index.word_frequency.clear(writer)?;
     index.prefix_documents_cache.clear(writer)?;
     index.prefix_postings_lists_cache.clear(writer)?;
+    index.word_prefix_pair_proximity_docids.clear(writer)?;
+    index.document_boosts.clear(writer)?;
+    index.free_list.clear(writer)?;
     index.updates.clear(update_writer)?;
     index.updates_results.clear(update_writer)?;
     Ok(())
 }
+
+/// The number of entries stored in the stores of an index and the LMDB pages they use.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct IndexStats {
+    pub postings_lists_entries: u64,
+    pub documents_fields_entries: u64,
+    pub documents_fields_counts_entries: u64,
+    pub docs_words_entries: u64,
+    pub facets_entries: u64,
+    pub synonyms_entries: u64,
+    pub word_frequency_entries: u64,
+    pub prefix_postings_lists_cache_entries: u64,
+    /// The total number of entries of the stores above.
+    pub number_of_entries: u64,
+    /// The number of branch, leaf and overflow pages of the postings lists.
+    pub postings_list_pages: u64,
+    /// The number of branch, leaf and overflow pages of the documents fields.
+    pub documents_pages: u64,
+    /// The size of the `data.mdb` file of the environment, shared by all the indexes.
+    pub total_size_bytes: u64,
+}
+
+/// Reads the LMDB statistics of a database, heed doesn't expose `mdb_stat`.
+///
+/// heed 0.7 doesn't give access to the LMDB handles either: a read transaction only holds its
+/// transaction handle and a database only holds its dbi, which the assertions make sure of.
+fn database_stat<D: Copy>(reader: &heed::RoTxn<MainT>, database: D) -> MResult<lmdb_sys::MDB_stat> {
+    assert_eq!(mem::size_of::<heed::RoTxn<MainT>>(), mem::size_of::<*mut lmdb_sys::MDB_txn>());
+    assert_eq!(mem::size_of::<D>(), mem::size_of::<lmdb_sys::MDB_dbi>());
+
+    let txn: *mut lmdb_sys::MDB_txn = unsafe { mem::transmute_copy(reader) };
+    let dbi: lmdb_sys::MDB_dbi = unsafe { mem::transmute_copy(&database) };
+
+    let mut stat = mem::MaybeUninit::uninit();
+    match unsafe { lmdb_sys::mdb_stat(txn, dbi, stat.as_mut_ptr()) } {
+        0 => Ok(unsafe { stat.assume_init() }),
+        code => Err(heed::Error::Lmdb(heed::LmdbError::from_err_code(code)).into()),
+    }
+}
+
+/// The size of the used part of the data file of the environment of the transaction.
+fn environment_size(reader: &heed::RoTxn<MainT>, page_size: u64) -> MResult<u64> {
+    let txn: *mut lmdb_sys::MDB_txn = unsafe { mem::transmute_copy(reader) };
+
+    let mut info = mem::MaybeUninit::uninit();
+    match unsafe { lmdb_sys::mdb_env_info(lmdb_sys::mdb_txn_env(txn), info.as_mut_ptr()) } {
+        0 => Ok((unsafe { info.assume_init() }.me_last_pgno as u64 + 1) * page_size),
+        code => Err(heed::Error::Lmdb(heed::LmdbError::from_err_code(code)).into()),
+    }
+}
+
+fn pages(stat: &lmdb_sys::MDB_stat) -> u64 {
+    (stat.ms_branch_pages + stat.ms_leaf_pages + stat.ms_overflow_pages) as u64
+}
+
+/// Reads the LMDB statistics of the stores of an index, without iterating over their entries.
+pub fn stats(reader: &heed::RoTxn<MainT>, index: &Index) -> MResult<IndexStats> {
+    let postings_lists = database_stat(reader, index.postings_lists.postings_lists)?;
+    let documents_fields = database_stat(reader, index.documents_fields.documents_fields)?;
+    let documents_fields_counts = database_stat(reader, index.documents_fields_counts.documents_fields_counts)?;
+    let docs_words = database_stat(reader, index.docs_words.docs_words)?;
+    let facets = database_stat(reader, index.facets.facets)?;
+    let synonyms = database_stat(reader, index.synonyms.synonyms)?;
+    let word_frequency = database_stat(reader, index.word_frequency.word_frequency)?;
+    let prefix_postings_lists_cache = database_stat(reader, index.prefix_postings_lists_cache.prefix_postings_lists_cache)?;
+
+    let mut stats = IndexStats {
+        postings_lists_entries: postings_lists.ms_entries as u64,
+        documents_fields_entries: documents_fields.ms_entries as u64,
+        documents_fields_counts_entries: documents_fields_counts.ms_entries as u64,
+        docs_words_entries: docs_words.ms_entries as u64,
+        facets_entries: facets.ms_entries as u64,
+        synonyms_entries: synonyms.ms_entries as u64,
+        word_frequency_entries: word_frequency.ms_entries as u64,
+        prefix_postings_lists_cache_entries: prefix_postings_lists_cache.ms_entries as u64,
+        number_of_entries: 0,
+        postings_list_pages: pages(&postings_lists),
+        documents_pages: pages(&documents_fields),
+        total_size_bytes: environment_size(reader, postings_lists.ms_psize as u64)?,
+    };
+
+    stats.number_of_entries = stats.postings_lists_entries
+        + stats.documents_fields_entries
+        + stats.documents_fields_counts_entries
+        + stats.docs_words_entries
+        + stats.facets_entries
+        + stats.synonyms_entries
+        + stats.word_frequency_entries
+        + stats.prefix_postings_lists_cache_entries;
+
+    Ok(stats)
+}
+
+/// Suggests corrected spellings of a query word, the indexed words at most two typos away
+/// from it. The suggestions are ranked by the number of occurrences of the words, which is
+/// returned along with them, and at most `max_suggestions` of them are returned.
+pub fn suggest_corrections(
+    reader: &heed::RoTxn<MainT>,
+    index: &Index,
+    query_word: &str,
+    max_suggestions: usize,
+) -> MResult<Vec<(String, u32)>> {
+    let words = match index.main.words_fst(reader)? {
+        Some(words) => words,
+        None => return Ok(Vec::new()),
+    };
+
+    let normalization_mode = index.main.normalization_mode(reader)?.unwrap_or_default();
+    let query_word = normalization_mode.normalize(query_word);
+    let dfa = build_correction_dfa(&query_word);
+
+    let mut suggestions = Vec::new();
+    let mut stream = words.search(&dfa).into_stream();
+    while let Some(word) = stream.next() {
+        if word == query_word.as_bytes() {
+            continue;
+        }
+
+        if let Some(postings) = index.postings_lists.postings_list(reader, word)? {
+            let word = String::from_utf8_lossy(word).into_owned();
+            suggestions.push((word, postings.matches.len() as u32));
+        }
+    }
+
+    suggestions.sort_unstable_by(|(aw, ac), (bw, bc)| bc.cmp(ac).then_with(|| aw.cmp(bw)));
+    suggestions.truncate(max_suggestions);
+
+    Ok(suggestions)
+}
+
+/// The inconsistencies found between the words fst, the postings lists and the documents words.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct IntegrityReport {
+    /// The words of the words fst that have no postings list.
+    pub orphan_fst_words: Vec<String>,
+    /// The words that have a postings list but are missing from the words fst.
+    pub orphan_posting_lists: Vec<String>,
+    /// The documents that have no words stored in the docs words store.
+    pub missing_doc_words: Vec<DocumentId>,
+}
+
+impl IntegrityReport {
+    pub fn is_consistent(&self) -> bool {
+        self.orphan_fst_words.is_empty()
+            && self.orphan_posting_lists.is_empty()
+            && self.missing_doc_words.is_empty()
+    }
+}
+
+pub fn integrity_check(reader: &heed::RoTxn<MainT>, index: &Index) -> ZResult<IntegrityReport> {
+    let mut report = IntegrityReport::default();
+    let words = index.main.words_fst(reader)?.unwrap_or_default();
+
+    let mut stream = words.stream();
+    while let Some(word) = stream.next() {
+        if index.postings_lists.postings_list(reader, word)?.is_none() {
+            report.orphan_fst_words.push(String::from_utf8_lossy(word).into_owned());
+        }
+    }
+
+    for result in index.postings_lists.postings_lists.iter(reader)? {
+        let (word, _) = result?;
+        if !words.contains(word) {
+            report.orphan_posting_lists.push(String::from_utf8_lossy(word).into_owned());
+        }
+    }
+
+    for docid in index.main.internal_docids(reader)?.iter() {
+        if index.docs_words.doc_words(reader, *docid)?.is_none() {
+            report.missing_doc_words.push(*docid);
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    use serde_json::Value;
+
+    use crate::update::test_helpers::{add_documents, with_index};
+
+    #[test]
+    fn index_stats_count_entries() {
+        with_index(|writer, index| {
+            let stats = super::stats(writer, index).unwrap();
+            assert_eq!(stats.number_of_entries, 0);
+
+            add_documents(writer, index, 0, serde_json::json!([
+                { "id": 1, "title": "hello world" },
+                { "id": 2, "title": "hello" },
+            ]));
+
+            let stats = super::stats(writer, index).unwrap();
+            let words = index.main.words_fst(writer).unwrap().unwrap();
+            assert_eq!(stats.postings_lists_entries, words.len() as u64);
+            assert_eq!(stats.docs_words_entries, 2);
+            assert_eq!(stats.documents_fields_entries, 4);
+            assert!(stats.number_of_entries >= 8);
+            assert!(stats.postings_list_pages > 0);
+            assert!(stats.documents_pages > 0);
+            assert!(stats.total_size_bytes > 0);
+        });
+    }
+
+    #[test]
+    fn documents_batch_in_order() {
+        with_index(|writer, index| {
+            add_documents(writer, index, 0, serde_json::json!([
+                { "id": 1, "title": "alpha" },
+                { "id": 2, "title": "beta" },
+                { "id": 3, "title": "gamma" },
+            ]));
+
+            let ids: Vec<_> = index.main.external_to_internal_docids(writer, &["3", "1"])
+                .unwrap()
+                .into_iter()
+                .flatten()
+                .chain(Some(DocumentId(u32::max_value())))
+                .collect();
+
+            let documents: Vec<HashMap<String, Value>> = index.documents_batch(writer, &ids).unwrap();
+            let titles: Vec<_> = documents.iter().map(|document| document["title"].clone()).collect();
+            assert_eq!(titles, vec![serde_json::json!("gamma"), serde_json::json!("alpha")]);
+        });
+    }
+
+    #[test]
+    fn suggest_corrections_ranked_by_occurrences() {
+        with_index(|writer, index| {
+            add_documents(writer, index, 0, serde_json::json!([
+                { "id": 1, "title": "hello world" },
+                { "id": 2, "title": "Hello there" },
+                { "id": 3, "title": "hallo" },
+                { "id": 4, "title": "yellow" },
+            ]));
+
+            let suggestions = suggest_corrections(writer, index, "Helo", 5).unwrap();
+            assert_eq!(suggestions, vec![("hello".to_string(), 2), ("hallo".to_string(), 1)]);
+
+            let suggestions = suggest_corrections(writer, index, "helo", 1).unwrap();
+            assert_eq!(suggestions, vec![("hello".to_string(), 2)]);
+
+            let suggestions = suggest_corrections(writer, index, "hello", 5).unwrap();
+            assert_eq!(suggestions, vec![("hallo".to_string(), 1), ("yellow".to_string(), 1)]);
+        });
+    }
+}
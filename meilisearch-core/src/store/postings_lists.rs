@@ -2,11 +2,12 @@ use std::borrow::Cow;
 
 use heed::Result as ZResult;
 use heed::types::ByteSlice;
+use meilisearch_schema::IndexedPos;
 use sdset::{Set, SetBuf};
 use slice_group_by::GroupBy;
 
 use crate::database::MainT;
-use crate::DocIndex;
+use crate::{DocIndex, DocumentId};
 use crate::store::{Postings, PostingsCodec};
 
 #[derive(Copy, Clone)]
@@ -44,4 +45,89 @@ impl PostingsLists {
     ) -> ZResult<Option<Postings<'txn>>> {
         self.postings_lists.get(reader, word)
     }
+
+    /// Returns an iterator over the structured entries of the postings list of the given word.
+    pub fn postings_list_iter<'txn>(
+        self,
+        reader: &'txn heed::RoTxn<MainT>,
+        word: &[u8],
+    ) -> ZResult<Option<PostingsListIter<'txn>>> {
+        let postings = self.postings_list(reader, word)?;
+        Ok(postings.map(|postings| PostingsListIter::new(postings.matches, true)))
+    }
+}
+
+/// A decoded entry of a postings list, the location of a word in a document.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PostingEntry {
+    pub document_id: DocumentId,
+    pub attribute: IndexedPos,
+    pub position: u32,
+    /// Whether the word is exactly the one stored in the list, entries
+    /// coming from the prefix postings lists cache are not exact.
+    pub is_exact: bool,
+}
+
+pub struct PostingsListIter<'txn> {
+    matches: Cow<'txn, Set<DocIndex>>,
+    offset: usize,
+    is_exact: bool,
+}
+
+impl<'txn> PostingsListIter<'txn> {
+    pub(crate) fn new(matches: Cow<'txn, Set<DocIndex>>, is_exact: bool) -> PostingsListIter<'txn> {
+        PostingsListIter { matches, offset: 0, is_exact }
+    }
+}
+
+impl Iterator for PostingsListIter<'_> {
+    type Item = PostingEntry;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let doc_index = self.matches.get(self.offset)?;
+        self.offset += 1;
+
+        Some(PostingEntry {
+            document_id: doc_index.document_id,
+            attribute: IndexedPos(doc_index.attribute),
+            position: u32::from(doc_index.word_index),
+            is_exact: self.is_exact,
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.matches.len() - self.offset;
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for PostingsListIter<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use crate::update::test_helpers::{add_documents, with_index};
+
+    #[test]
+    fn postings_list_iter_yields_structured_entries() {
+        with_index(|writer, index| {
+            add_documents(writer, index, 0, serde_json::json!([
+                { "id": 1, "title": "the little prince", "description": "a prince" },
+                { "id": 2, "title": "prince of persia" },
+            ]));
+
+            let postings = index.postings_lists.postings_list(writer, b"prince").unwrap().unwrap();
+            let entries: Vec<_> = index.postings_lists.postings_list_iter(writer, b"prince").unwrap().unwrap().collect();
+            assert_eq!(entries.len(), postings.matches.len());
+            assert_eq!(entries.len(), 3);
+
+            for (entry, doc_index) in entries.iter().zip(postings.matches.iter()) {
+                assert_eq!(entry.document_id, doc_index.document_id);
+                assert_eq!(entry.attribute.0, doc_index.attribute);
+                assert_eq!(entry.position, u32::from(doc_index.word_index));
+                assert!(entry.is_exact);
+            }
+
+            assert!(index.postings_lists.postings_list_iter(writer, b"king").unwrap().is_none());
+        });
+    }
 }
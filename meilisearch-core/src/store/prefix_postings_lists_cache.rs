@@ -7,7 +7,7 @@ use slice_group_by::GroupBy;
 
 use crate::database::MainT;
 use crate::DocIndex;
-use crate::store::{PostingsCodec, Postings};
+use crate::store::{PostingsCodec, Postings, PostingsListIter};
 
 #[derive(Copy, Clone)]
 pub struct PrefixPostingsListsCache {
@@ -42,4 +42,16 @@ impl PrefixPostingsListsCache {
     {
         self.prefix_postings_lists_cache.get(reader, &prefix)
     }
+
+    /// Returns an iterator over the entries of the cached postings list of the given prefix,
+    /// these entries are never exact matches.
+    pub fn prefix_postings_list_iter<'txn>(
+        self,
+        reader: &'txn heed::RoTxn<MainT>,
+        prefix: [u8; 4],
+    ) -> ZResult<Option<PostingsListIter<'txn>>>
+    {
+        let postings = self.prefix_postings_list(reader, prefix)?;
+        Ok(postings.map(|postings| PostingsListIter::new(postings.matches, false)))
+    }
 }
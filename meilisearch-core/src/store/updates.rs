@@ -59,6 +59,10 @@ impl Updates {
         }
     }
 
+    pub fn len(self, reader: &heed::RoTxn<UpdateT>) -> ZResult<usize> {
+        self.updates.len(reader)
+    }
+
     pub fn clear(self, writer: &mut heed::RwTxn<UpdateT>) -> ZResult<()> {
         self.updates.clear(writer)
     }
@@ -1,9 +1,20 @@
+use std::mem;
+
 use super::BEU64;
 use crate::database::UpdateT;
-use crate::update::ProcessedUpdateResult;
+use crate::update::{ProcessedUpdateResult, UpdateType};
 use heed::types::{OwnedType, SerdeJson};
 use heed::Result as ZResult;
 
+/// The outcome of a compaction of the updates results.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct CompactionStats {
+    /// The number of updates results that have been merged.
+    pub merged_results: usize,
+    /// The number of entries that now replace them.
+    pub compacted_entries: usize,
+}
+
 #[derive(Copy, Clone)]
 pub struct UpdatesResults {
     pub(crate) updates_results: heed::Database<OwnedType<BEU64>, SerdeJson<ProcessedUpdateResult>>,
@@ -39,7 +50,160 @@ impl UpdatesResults {
         self.updates_results.get(reader, &update_id)
     }
 
+    /// Replaces every run of adjacent, successfully processed, updates results
+    /// of the same type by a single entry stored under the id of the last update of the run.
+    pub fn compact_processed_updates(self, writer: &mut heed::RwTxn<UpdateT>) -> ZResult<CompactionStats> {
+        let mut runs = Vec::new();
+        let mut current: Vec<(u64, ProcessedUpdateResult)> = Vec::new();
+
+        for result in self.updates_results.iter(writer)? {
+            let (update_id, update_result) = result?;
+            let processed = update_result.error.is_none();
+            let extends = processed && current.last().map_or(false, |(_, last)| {
+                mem::discriminant(&last.update_type) == mem::discriminant(&update_result.update_type)
+            });
+
+            if !extends {
+                if current.len() > 1 {
+                    runs.push(mem::take(&mut current));
+                } else {
+                    current.clear();
+                }
+            }
+
+            if processed {
+                current.push((update_id.get(), update_result));
+            }
+        }
+
+        if current.len() > 1 {
+            runs.push(current);
+        }
+
+        let mut stats = CompactionStats::default();
+        for run in runs {
+            stats.merged_results += run.len();
+            stats.compacted_entries += 1;
+
+            for (update_id, _) in &run {
+                self.updates_results.delete(writer, &BEU64::new(*update_id))?;
+            }
+
+            let mut results = run.into_iter().map(|(_, result)| result);
+            let first = results.next().expect("a run contains at least two results");
+            let merged = results.fold(first, merge_processed_results);
+            self.put_update_result(writer, merged.update_id, &merged)?;
+        }
+
+        Ok(stats)
+    }
+
     pub fn clear(self, writer: &mut heed::RwTxn<UpdateT>) -> ZResult<()> {
         self.updates_results.clear(writer)
     }
 }
+
+fn merge_processed_results(merged: ProcessedUpdateResult, result: ProcessedUpdateResult) -> ProcessedUpdateResult {
+    use UpdateType::*;
+
+    let update_type = match (merged.update_type, result.update_type) {
+        (DocumentsAddition { number: a }, DocumentsAddition { number: b }) => DocumentsAddition { number: a + b },
+        (DocumentsPartial { number: a }, DocumentsPartial { number: b }) => DocumentsPartial { number: a + b },
+        (DocumentsDeletion { number: a }, DocumentsDeletion { number: b }) => DocumentsDeletion { number: a + b },
+        (_, update_type) => update_type,
+    };
+
+    let conflicts_resolved = match (merged.conflicts_resolved, result.conflicts_resolved) {
+        (None, None) => None,
+        (a, b) => Some(a.unwrap_or(0) + b.unwrap_or(0)),
+    };
+
+    let merged_count = merged.merged_count.unwrap_or(1) + result.merged_count.unwrap_or(1);
+    let first_id = merged.first_id.unwrap_or(merged.update_id);
+
+    let mut warnings = merged.warnings;
+    warnings.extend(result.warnings);
+
+    ProcessedUpdateResult {
+        update_id: result.update_id,
+        update_type,
+        error: None,
+        duration: merged.duration + result.duration,
+        enqueued_at: merged.enqueued_at,
+        processed_at: result.processed_at,
+        conflicts_resolved,
+        merged_count: Some(merged_count),
+        first_id: Some(first_id),
+        last_id: Some(result.update_id),
+        indexed_stats: None,
+        warnings,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::Utc;
+
+    use crate::database::{Database, DatabaseOptions};
+
+    #[test]
+    fn compact_adjacent_processed_updates() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let index = database.create_index("test").unwrap();
+
+        let result = |update_id, update_type, error: Option<&str>| ProcessedUpdateResult {
+            update_id,
+            update_type,
+            error: error.map(str::to_string),
+            duration: 1.0,
+            enqueued_at: Utc::now(),
+            processed_at: Utc::now(),
+            conflicts_resolved: None,
+            merged_count: None,
+            first_id: None,
+            last_id: None,
+            indexed_stats: None,
+            warnings: Vec::new(),
+        };
+
+        let mut writer = database.update_write_txn().unwrap();
+        let results = vec![
+            result(0, UpdateType::DocumentsAddition { number: 2 }, None),
+            result(1, UpdateType::DocumentsAddition { number: 3 }, None),
+            result(2, UpdateType::DocumentsAddition { number: 4 }, None),
+            result(3, UpdateType::DocumentsAddition { number: 1 }, Some("invalid document")),
+            result(4, UpdateType::DocumentsDeletion { number: 1 }, None),
+            result(5, UpdateType::ClearAll, None),
+            result(6, UpdateType::ClearAll, None),
+        ];
+        for result in &results {
+            index.updates_results.put_update_result(&mut writer, result.update_id, result).unwrap();
+        }
+
+        let stats = index.updates_results.compact_processed_updates(&mut writer).unwrap();
+        assert_eq!(stats, CompactionStats { merged_results: 5, compacted_entries: 2 });
+
+        assert!(index.updates_results.update_result(&writer, 0).unwrap().is_none());
+        let merged = index.updates_results.update_result(&writer, 2).unwrap().unwrap();
+        assert_matches!(merged.update_type, UpdateType::DocumentsAddition { number: 9 });
+        assert_eq!(merged.merged_count, Some(3));
+        assert_eq!(merged.first_id, Some(0));
+        assert_eq!(merged.last_id, Some(2));
+        assert!((merged.duration - 3.0).abs() < f64::EPSILON);
+
+        assert!(index.updates_results.update_result(&writer, 3).unwrap().unwrap().error.is_some());
+        assert!(index.updates_results.update_result(&writer, 4).unwrap().unwrap().merged_count.is_none());
+        let merged = index.updates_results.update_result(&writer, 6).unwrap().unwrap();
+        assert_eq!(merged.merged_count, Some(2));
+
+        // compacting again must not change anything
+        let stats = index.updates_results.compact_processed_updates(&mut writer).unwrap();
+        assert_eq!(stats, CompactionStats::default());
+
+        writer.abort();
+    }
+}
@@ -0,0 +1,61 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use heed::types::{OwnedType, Str};
+use heed::Result as ZResult;
+
+use crate::database::MainT;
+
+/// Stores the number of times each word appears across all the documents.
+#[derive(Copy, Clone)]
+pub struct WordFrequency {
+    pub(crate) word_frequency: heed::Database<Str, OwnedType<u64>>,
+}
+
+impl WordFrequency {
+    pub fn increase_frequency(self, writer: &mut heed::RwTxn<MainT>, word: &str, count: u64) -> ZResult<()> {
+        let frequency = self.frequency(writer, word)?.unwrap_or(0);
+        self.word_frequency.put(writer, word, &(frequency + count))
+    }
+
+    /// Decreases the frequency of the word, it is removed when it doesn't appear anymore.
+    pub fn decrease_frequency(self, writer: &mut heed::RwTxn<MainT>, word: &str, count: u64) -> ZResult<()> {
+        match self.frequency(writer, word)? {
+            Some(frequency) if frequency > count => self.word_frequency.put(writer, word, &(frequency - count)),
+            Some(_) => self.word_frequency.delete(writer, word).map(drop),
+            None => Ok(()),
+        }
+    }
+
+    pub fn frequency(self, reader: &heed::RoTxn<MainT>, word: &str) -> ZResult<Option<u64>> {
+        self.word_frequency.get(reader, word)
+    }
+
+    pub fn is_empty(self, reader: &heed::RoTxn<MainT>) -> ZResult<bool> {
+        self.word_frequency.first(reader).map(|entry| entry.is_none())
+    }
+
+    /// Returns the `k` most frequent words, the most frequent first.
+    pub fn most_common(self, reader: &heed::RoTxn<MainT>, k: usize) -> ZResult<Vec<(String, u64)>> {
+        let mut heap = BinaryHeap::with_capacity(k + 1);
+        for result in self.word_frequency.iter(reader)? {
+            let (word, frequency) = result?;
+            heap.push(Reverse((frequency, word)));
+            if heap.len() > k {
+                heap.pop();
+            }
+        }
+
+        let mut words: Vec<_> = heap
+            .into_iter()
+            .map(|Reverse((frequency, word))| (word.to_string(), frequency))
+            .collect();
+        words.sort_unstable_by(|(aw, af), (bw, bf)| bf.cmp(af).then_with(|| aw.cmp(bw)));
+
+        Ok(words)
+    }
+
+    pub fn clear(self, writer: &mut heed::RwTxn<MainT>) -> ZResult<()> {
+        self.word_frequency.clear(writer)
+    }
+}
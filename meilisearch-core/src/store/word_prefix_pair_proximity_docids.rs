@@ -0,0 +1,55 @@
+use std::borrow::Cow;
+
+use heed::types::ByteSlice;
+use heed::Result as ZResult;
+use sdset::Set;
+
+use crate::database::MainT;
+use crate::DocumentId;
+use super::CowSet;
+
+/// Stores the documents where a word is followed by a word starting with a short prefix,
+/// at a given proximity, to avoid intersecting the postings lists at search time.
+#[derive(Copy, Clone)]
+pub struct WordPrefixPairProximityDocIds {
+    pub(crate) word_prefix_pair_proximity_docids: heed::Database<ByteSlice, CowSet<DocumentId>>,
+}
+
+fn pair_key(word: &[u8], prefix: [u8; 4], proximity: u8) -> Vec<u8> {
+    let mut key = Vec::with_capacity(1 + prefix.len() + word.len());
+    key.push(proximity);
+    key.extend_from_slice(&prefix);
+    key.extend_from_slice(word);
+    key
+}
+
+impl WordPrefixPairProximityDocIds {
+    pub fn put_pair_docids(
+        self,
+        writer: &mut heed::RwTxn<MainT>,
+        word: &[u8],
+        prefix: [u8; 4],
+        proximity: u8,
+        docids: &Set<DocumentId>,
+    ) -> ZResult<()>
+    {
+        let key = pair_key(word, prefix, proximity);
+        self.word_prefix_pair_proximity_docids.put(writer, &key, docids)
+    }
+
+    pub fn clear(self, writer: &mut heed::RwTxn<MainT>) -> ZResult<()> {
+        self.word_prefix_pair_proximity_docids.clear(writer)
+    }
+
+    pub fn pair_docids<'txn>(
+        self,
+        reader: &'txn heed::RoTxn<MainT>,
+        word: &[u8],
+        prefix: [u8; 4],
+        proximity: u8,
+    ) -> ZResult<Option<Cow<'txn, Set<DocumentId>>>>
+    {
+        let key = pair_key(word, prefix, proximity);
+        self.word_prefix_pair_proximity_docids.get(reader, &key)
+    }
+}
@@ -0,0 +1,201 @@
+use std::ops::{Deref, DerefMut};
+
+use log::warn;
+
+use crate::database::MainT;
+use crate::MResult;
+
+/// A write transaction that must be explicitly committed or aborted.
+///
+/// LMDB aborts the write transactions that are dropped, this guard logs a warning
+/// when it happens to catch the successful updates that are silently rolled back.
+pub struct CoreTransaction<'env, T = ()> {
+    txn: Option<heed::RwTxn<'env, T>>,
+    env: Option<&'env heed::Env>,
+}
+
+impl<'env, T> CoreTransaction<'env, T> {
+    pub fn new(txn: heed::RwTxn<'env, T>) -> CoreTransaction<'env, T> {
+        CoreTransaction { txn: Some(txn), env: None }
+    }
+
+    /// Opens a write transaction of the environment that can be committed
+    /// and continued with a new transaction by the [`checkpoint`]s.
+    ///
+    /// [`checkpoint`]: trait.UpdateWriter.html#method.checkpoint
+    pub fn reopenable(env: &'env heed::Env) -> MResult<CoreTransaction<'env, T>> {
+        let txn = env.typed_write_txn::<T>()?;
+        Ok(CoreTransaction { txn: Some(txn), env: Some(env) })
+    }
+
+    pub fn commit(mut self) -> MResult<()> {
+        match self.txn.take() {
+            Some(txn) => Ok(txn.commit()?),
+            None => Ok(()),
+        }
+    }
+
+    pub fn abort(mut self) {
+        if let Some(txn) = self.txn.take() {
+            txn.abort();
+        }
+    }
+}
+
+impl<'env, T> Deref for CoreTransaction<'env, T> {
+    type Target = heed::RwTxn<'env, T>;
+
+    fn deref(&self) -> &Self::Target {
+        self.txn.as_ref().expect("transaction already consumed")
+    }
+}
+
+impl<'env, T> DerefMut for CoreTransaction<'env, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.txn.as_mut().expect("transaction already consumed")
+    }
+}
+
+/// A main write transaction the updates are applied with, the long running updates
+/// can commit what they have written so far and continue with a new transaction.
+pub trait UpdateWriter<'env> {
+    fn txn(&mut self) -> &mut heed::RwTxn<'env, MainT>;
+
+    /// Commits the transaction and continues with a new one, returns `false`
+    /// and does nothing if the transaction can not be reopened once committed.
+    fn checkpoint(&mut self) -> MResult<bool> {
+        Ok(false)
+    }
+
+    /// Aborts what has been written since the last checkpoint and continues with a new
+    /// transaction, returns `false` and does nothing if the transaction can not be reopened.
+    fn rollback(&mut self) -> MResult<bool> {
+        Ok(false)
+    }
+}
+
+impl<'env> UpdateWriter<'env> for heed::RwTxn<'env, MainT> {
+    fn txn(&mut self) -> &mut heed::RwTxn<'env, MainT> {
+        self
+    }
+}
+
+impl<'env> UpdateWriter<'env> for CoreTransaction<'env, MainT> {
+    fn txn(&mut self) -> &mut heed::RwTxn<'env, MainT> {
+        self
+    }
+
+    fn checkpoint(&mut self) -> MResult<bool> {
+        let env = match self.env {
+            Some(env) => env,
+            None => return Ok(false),
+        };
+
+        if let Some(txn) = self.txn.take() {
+            txn.commit()?;
+        }
+        self.txn = Some(env.typed_write_txn::<MainT>()?);
+
+        Ok(true)
+    }
+
+    fn rollback(&mut self) -> MResult<bool> {
+        let env = match self.env {
+            Some(env) => env,
+            None => return Ok(false),
+        };
+
+        if let Some(txn) = self.txn.take() {
+            txn.abort();
+        }
+        self.txn = Some(env.typed_write_txn::<MainT>()?);
+
+        Ok(true)
+    }
+}
+
+impl<'env, T> Drop for CoreTransaction<'env, T> {
+    fn drop(&mut self) {
+        if let Some(txn) = self.txn.take() {
+            warn!("write transaction dropped without being committed or aborted, it is rolled back");
+            txn.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tempfile::TempDir;
+
+    use crate::database::{Database, DatabaseOptions};
+
+    #[test]
+    fn only_committed_transactions_are_persisted() {
+        let dir = TempDir::new().unwrap();
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let index = database.create_index("test").unwrap();
+
+        let mut writer = CoreTransaction::new(database.main_write_txn().unwrap());
+        index.main.put_name(&mut writer, "committed").unwrap();
+        writer.commit().unwrap();
+
+        let mut writer = CoreTransaction::new(database.main_write_txn().unwrap());
+        index.main.put_name(&mut writer, "dropped").unwrap();
+        drop(writer);
+
+        let mut writer = CoreTransaction::new(database.main_write_txn().unwrap());
+        index.main.put_name(&mut writer, "aborted").unwrap();
+        writer.abort();
+
+        let reader = database.main_read_txn().unwrap();
+        assert_eq!(index.main.name(&reader).unwrap().as_deref(), Some("committed"));
+    }
+
+    #[test]
+    fn checkpoints_commit_the_transaction() {
+        use heed::types::Str;
+
+        let dir = TempDir::new().unwrap();
+        let env = heed::EnvOpenOptions::new().map_size(10 * 1024 * 1024).max_dbs(1).open(dir.path()).unwrap();
+        let store = env.create_poly_database(Some("test")).unwrap();
+
+        let mut writer = CoreTransaction::<MainT>::reopenable(&env).unwrap();
+        store.put::<_, Str, Str>(writer.txn(), "checkpointed", "value").unwrap();
+        assert!(writer.checkpoint().unwrap());
+        store.put::<_, Str, Str>(writer.txn(), "aborted", "value").unwrap();
+        writer.abort();
+
+        let reader = env.typed_read_txn::<MainT>().unwrap();
+        assert!(store.get::<_, Str, Str>(&reader, "checkpointed").unwrap().is_some());
+        assert!(store.get::<_, Str, Str>(&reader, "aborted").unwrap().is_none());
+
+        // the transactions not opened from an environment are never committed
+        let mut writer = CoreTransaction::new(env.typed_write_txn::<MainT>().unwrap());
+        assert!(!writer.checkpoint().unwrap());
+        writer.abort();
+    }
+
+    #[test]
+    fn rollbacks_abort_since_the_last_checkpoint() {
+        use heed::types::Str;
+
+        let dir = TempDir::new().unwrap();
+        let env = heed::EnvOpenOptions::new().map_size(10 * 1024 * 1024).max_dbs(1).open(dir.path()).unwrap();
+        let store = env.create_poly_database(Some("test")).unwrap();
+
+        let mut writer = CoreTransaction::<MainT>::reopenable(&env).unwrap();
+        store.put::<_, Str, Str>(writer.txn(), "checkpointed", "value").unwrap();
+        assert!(writer.checkpoint().unwrap());
+        store.put::<_, Str, Str>(writer.txn(), "rolled back", "value").unwrap();
+        assert!(writer.rollback().unwrap());
+        store.put::<_, Str, Str>(writer.txn(), "committed", "value").unwrap();
+        writer.commit().unwrap();
+
+        let reader = env.typed_read_txn::<MainT>().unwrap();
+        assert!(store.get::<_, Str, Str>(&reader, "checkpointed").unwrap().is_some());
+        assert!(store.get::<_, Str, Str>(&reader, "rolled back").unwrap().is_none());
+        assert!(store.get::<_, Str, Str>(&reader, "committed").unwrap().is_some());
+    }
+}
@@ -15,8 +15,12 @@ pub fn apply_clear_all(
     index.documents_fields_counts.clear(writer)?;
     index.postings_lists.clear(writer)?;
     index.docs_words.clear(writer)?;
+    index.word_frequency.clear(writer)?;
     index.prefix_documents_cache.clear(writer)?;
     index.prefix_postings_lists_cache.clear(writer)?;
+    index.word_prefix_pair_proximity_docids.clear(writer)?;
+    index.document_boosts.clear(writer)?;
+    index.free_list.clear(writer)?;
 
     Ok(())
 }
@@ -0,0 +1,49 @@
+use std::collections::HashSet;
+
+use serde_json::Value;
+
+use super::{Update, UpdateData};
+
+/// Keeps track of the documents modified by the documents additions processed
+/// while the updates queue is not empty.
+///
+/// The updates are always applied in submission order, a document modified by
+/// many of them ends up with the version of the last one, each of those overwrites
+/// is counted as a resolved conflict.
+#[derive(Debug, Default)]
+pub struct ConflictsTracker {
+    modified: HashSet<String>,
+}
+
+impl ConflictsTracker {
+    /// Returns the external ids of the documents modified by the update,
+    /// `None` if the update is not a documents addition.
+    pub fn documents_ids(update: &Update, primary_key: &str) -> Option<Vec<String>> {
+        match &update.data {
+            UpdateData::DocumentsAddition(documents) | UpdateData::DocumentsPartial(documents) => {
+                let mut ids: Vec<_> = documents
+                    .iter()
+                    .filter_map(|document| match document.get(primary_key) {
+                        Some(Value::Number(number)) => Some(number.to_string()),
+                        Some(Value::String(string)) => Some(string.clone()),
+                        _ => None,
+                    })
+                    .collect();
+                ids.sort_unstable();
+                ids.dedup();
+                Some(ids)
+            }
+            _ => None,
+        }
+    }
+
+    /// Registers the documents modified by an update and returns the number
+    /// of them that were already modified by a previous update.
+    pub fn register(&mut self, ids: Vec<String>) -> usize {
+        ids.into_iter().map(|id| self.modified.insert(id)).filter(|inserted| !inserted).count()
+    }
+
+    pub fn clear(&mut self) {
+        self.modified.clear()
+    }
+}
@@ -1,22 +1,98 @@
+use std::collections::hash_map::Entry;
 use std::collections::{HashMap, BTreeMap};
+use std::convert::TryFrom;
+use std::sync::Arc;
 
 use fst::{set::OpBuilder, SetBuilder};
 use indexmap::IndexMap;
-use meilisearch_schema::{Schema, FieldId};
+use meilisearch_schema::{Schema, FieldId, FieldType};
 use meilisearch_types::DocumentId;
 use sdset::{duo::Union, SetOperation};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 use crate::database::{MainT, UpdateT};
 use crate::database::{UpdateEvent, UpdateEventsEmitter};
 use crate::facets;
-use crate::raw_indexer::RawIndexer;
-use crate::serde::Deserializer;
-use crate::store::{self, DocumentsFields, DocumentsFieldsCounts, DiscoverIds};
-use crate::update::helpers::{index_value, value_to_number, extract_document_id};
-use crate::update::{apply_documents_deletion, compute_short_prefixes, next_update_id, Update};
-use crate::{Error, MResult, RankedMap};
+use crate::raw_indexer::{RawIndexer, TokenLimitExceeded};
+use crate::serde::{decrypt_field, field_json_bytes, parse_datetime, Deserializer, FieldCipher, FieldCipherError, FALSE_FIELD_BYTE, TRUE_FIELD_BYTE};
+use crate::stop_words::LANGUAGE_FIELD;
+use crate::store::{self, DocumentsFields, DocumentsFieldsCounts, DiscoverIds, ReindexCheckpoint};
+use crate::update::helpers::{discover_document_id, extract_document_user_id, index_value, merge_document, value_to_ranked_number};
+use crate::update::{apply_documents_deletion, next_update_id, IndexingHooks, IndexingProgress, Update};
+use crate::{Error, MResult, RankedMap, RankedMapChanges, UpdateWriter};
+
+/// Metrics about the postings lists and words fst written by a documents addition.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexedStats {
+    /// The number of distinct words indexed.
+    pub total_words_indexed: usize,
+    /// The number of words that were not in the words fst yet.
+    pub new_words_added: usize,
+    /// The number of already existing postings lists that have been updated.
+    pub posting_lists_updated: usize,
+    /// The number of words in the words fst before and after the update.
+    pub fst_size_before: usize,
+    pub fst_size_after: usize,
+}
+
+impl IndexedStats {
+    /// Combines the stats of two successive writes, the fst sizes are the ones
+    /// before the first write and after the second one.
+    fn merge(self, other: IndexedStats) -> IndexedStats {
+        IndexedStats {
+            total_words_indexed: self.total_words_indexed + other.total_words_indexed,
+            new_words_added: self.new_words_added + other.new_words_added,
+            posting_lists_updated: self.posting_lists_updated + other.posting_lists_updated,
+            fst_size_before: self.fst_size_before,
+            fst_size_after: other.fst_size_after,
+        }
+    }
+}
+
+/// A problem found in the documents of an addition that did not prevent it from being processed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Warning {
+    /// The value of a ranked field is not a number nor a date, the document
+    /// is ranked as if this value was zero.
+    #[serde(rename_all = "camelCase")]
+    TypeConflict {
+        field: String,
+        expected: String,
+        actual: String,
+        document_user_id: String,
+    },
+    /// The tokenization of a field stopped after the maximum number of tokens
+    /// of the field or of the document, the following tokens are not indexed.
+    #[serde(rename_all = "camelCase")]
+    TokenLimitExceeded {
+        field: String,
+        document_user_id: String,
+        /// The limit reached, `field` or `document`.
+        limit: String,
+        max_tokens: usize,
+    },
+}
+
+/// What a documents addition does with the documents sharing the same id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DuplicatePolicy {
+    /// The whole addition fails.
+    Reject,
+    /// The last document replaces the previous ones.
+    Overwrite,
+    /// The documents are merged in order, following the JSON merge patch semantics.
+    Merge,
+}
+
+impl Default for DuplicatePolicy {
+    fn default() -> DuplicatePolicy {
+        DuplicatePolicy::Overwrite
+    }
+}
 
 pub struct DocumentsAddition<D> {
     updates_store: store::Updates,
@@ -24,6 +100,7 @@ pub struct DocumentsAddition<D> {
     updates_notifier: UpdateEventsEmitter,
     documents: Vec<D>,
     is_partial: bool,
+    duplicate_policy: DuplicatePolicy,
 }
 
 impl<D> DocumentsAddition<D> {
@@ -38,6 +115,7 @@ impl<D> DocumentsAddition<D> {
             updates_notifier,
             documents: Vec::new(),
             is_partial: false,
+            duplicate_policy: DuplicatePolicy::default(),
         }
     }
 
@@ -52,6 +130,7 @@ impl<D> DocumentsAddition<D> {
             updates_notifier,
             documents: Vec::new(),
             is_partial: true,
+            duplicate_policy: DuplicatePolicy::default(),
         }
     }
 
@@ -59,6 +138,10 @@ impl<D> DocumentsAddition<D> {
         self.documents.push(document);
     }
 
+    pub fn duplicate_policy(&mut self, policy: DuplicatePolicy) {
+        self.duplicate_policy = policy;
+    }
+
     pub fn finalize(self, writer: &mut heed::RwTxn<UpdateT>) -> MResult<u64>
     where
         D: serde::Serialize,
@@ -70,6 +153,7 @@ impl<D> DocumentsAddition<D> {
             self.updates_results_store,
             self.documents,
             self.is_partial,
+            self.duplicate_policy,
         )?;
         Ok(update_id)
     }
@@ -87,6 +171,7 @@ pub fn push_documents_addition<D: serde::Serialize>(
     updates_results_store: store::UpdatesResults,
     addition: Vec<D>,
     is_partial: bool,
+    duplicate_policy: DuplicatePolicy,
 ) -> MResult<u64> {
     let mut values = Vec::with_capacity(addition.len());
     for add in addition {
@@ -102,6 +187,7 @@ pub fn push_documents_addition<D: serde::Serialize>(
     } else {
         Update::documents_addition(values)
     };
+    let update = update.with_duplicate_policy(duplicate_policy);
 
     updates_store.put_update(writer, last_update_id, &update)?;
 
@@ -112,16 +198,38 @@ fn index_document(
     writer: &mut heed::RwTxn<MainT>,
     documents_fields: DocumentsFields,
     documents_fields_counts: DocumentsFieldsCounts,
-    ranked_map: &mut RankedMap,
+    ranked_changes: &mut RankedMapChanges,
     indexer: &mut RawIndexer,
     schema: &Schema,
     field_id: FieldId,
     document_id: DocumentId,
     value: &Value,
+    cipher: Option<&FieldCipher>,
 ) -> MResult<()>
 {
-    let serialized = serde_json::to_vec(value)?;
-    documents_fields.put_document_field(writer, document_id, field_id, &serialized)?;
+    // the encrypted fields are only stored, their values are never indexed nor ranked
+    if schema.is_encrypted(field_id) {
+        let cipher = cipher.ok_or(FieldCipherError::MissingKey)?;
+        let encrypted = cipher.encrypt(&serde_json::to_vec(value)?);
+        documents_fields.put_document_field(writer, document_id, field_id, &encrypted)?;
+        ranked_changes.remove(document_id, field_id);
+        return Ok(());
+    }
+
+    // null values are stored as empty blobs, the field is present but never indexed nor ranked
+    if value.is_null() {
+        documents_fields.put_document_field(writer, document_id, field_id, &[])?;
+        return Ok(());
+    }
+
+    match value {
+        Value::Bool(false) => documents_fields.put_document_field(writer, document_id, field_id, &[FALSE_FIELD_BYTE])?,
+        Value::Bool(true) => documents_fields.put_document_field(writer, document_id, field_id, &[TRUE_FIELD_BYTE])?,
+        value => {
+            let serialized = serde_json::to_vec(value)?;
+            documents_fields.put_document_field(writer, document_id, field_id, &serialized)?;
+        }
+    }
 
     if let Some(indexed_pos) = schema.is_indexed(field_id) {
         let number_of_words = index_value(indexer, document_id, *indexed_pos, value);
@@ -136,29 +244,158 @@ fn index_document(
     }
 
     if schema.is_ranked(field_id) {
-        let number = value_to_number(value).unwrap_or_default();
-        ranked_map.insert(document_id, field_id, number);
+        let number = value_to_ranked_number(value).unwrap_or_default();
+        ranked_changes.insert(document_id, field_id, number);
     }
 
     Ok(())
 }
 
-pub fn apply_addition<'a, 'b>(
-    writer: &'a mut heed::RwTxn<'b, MainT>,
+fn json_type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Returns the expected and actual types of a value of a ranked field that can't be ranked.
+fn ranked_type_conflict(schema: &Schema, field_id: FieldId, value: &Value) -> Option<(&'static str, &'static str)> {
+    if value.is_null() || !schema.is_ranked(field_id) || schema.is_encrypted(field_id) || value_to_ranked_number(value).is_some() {
+        return None;
+    }
+
+    let expected = match schema.field_type(field_id) {
+        Some(FieldType::DateTime) => "date",
+        _ => "number",
+    };
+
+    Some((expected, json_type_name(value)))
+}
+
+/// Marks the boolean fields and the ranked fields containing ISO 8601 dates,
+/// the dates are ranked by timestamp.
+fn detect_field_type(schema: &mut Schema, field_id: FieldId, value: &Value) {
+    if value.is_boolean() && schema.field_type(field_id).is_none() {
+        schema.set_field_type(field_id, FieldType::Bool);
+    } else if schema.is_ranked(field_id)
+        && schema.field_type(field_id).is_none()
+        && value.as_str().and_then(parse_datetime).is_some()
+    {
+        schema.set_field_type(field_id, FieldType::DateTime);
+    }
+}
+
+/// Adds the documents to the index. When a checkpoint interval is set, the postings lists
+/// are committed every time this number of words has been written. The documents are
+/// complete at each checkpoint, an addition that fails replaces the documents committed
+/// by the ones they replaced.
+pub fn apply_addition<'a, 'b, W: UpdateWriter<'b>>(
+    writer: &'a mut W,
+    index: &store::Index,
+    mut new_documents: Vec<IndexMap<String, Value>>,
+    partial: bool,
+    duplicate_policy: DuplicatePolicy,
+    hooks: Option<Arc<dyn IndexingHooks>>,
+) -> MResult<(IndexedStats, Vec<Warning>)>
+{
+    if let Some(hooks) = &hooks {
+        for document in &mut new_documents {
+            hooks.before_index(document)?;
+        }
+    }
+
+    if index.checkpoint_interval.map_or(true, |interval| interval == 0) {
+        return add_documents(writer, index, new_documents, partial, duplicate_policy, hooks.as_deref());
+    }
+
+    let savepoint = DocumentsSavepoint::take(writer.txn(), index, &new_documents)?;
+
+    match add_documents(writer, index, new_documents, partial, duplicate_policy, hooks.as_deref()) {
+        Ok(result) => Ok(result),
+        Err(error) => {
+            // what has been written since the last checkpoint is discarded
+            // and the committed documents are restored in a new checkpoint
+            if writer.rollback()? {
+                savepoint.restore(writer.txn(), index)?;
+                writer.checkpoint()?;
+            }
+            Err(error)
+        }
+    }
+}
+
+/// The documents replaced by an addition, as they were before the addition,
+/// used to restore them if the addition fails after a checkpoint.
+struct DocumentsSavepoint {
+    /// The external ids of the documents the addition creates.
+    new_external_docids: Vec<String>,
+    /// The documents the addition replaces.
+    previous_documents: Vec<IndexMap<String, Value>>,
+}
+
+impl DocumentsSavepoint {
+    fn take(
+        reader: &heed::RoTxn<MainT>,
+        index: &store::Index,
+        documents: &[IndexMap<String, Value>],
+    ) -> MResult<DocumentsSavepoint> {
+        let schema = index.main.schema(reader)?.ok_or(Error::SchemaMissing)?;
+        let primary_key = schema.primary_key().ok_or(Error::MissingPrimaryKey)?;
+
+        let mut new_external_docids = Vec::new();
+        let mut previous_documents = Vec::new();
+        for document in documents {
+            let external_docid = extract_document_user_id(primary_key, document)?;
+            let previous = match index.main.external_to_internal_docid(reader, &external_docid)? {
+                Some(docid) => index.document(reader, None, docid)?,
+                None => None,
+            };
+
+            match previous {
+                Some(previous) => previous_documents.push(previous),
+                None => new_external_docids.push(external_docid),
+            }
+        }
+
+        Ok(DocumentsSavepoint { new_external_docids, previous_documents })
+    }
+
+    /// Removes the documents created by the addition and adds the replaced documents again,
+    /// they keep their internal ids and boosts.
+    fn restore(self, writer: &mut heed::RwTxn<MainT>, index: &store::Index) -> MResult<()> {
+        apply_documents_deletion(writer, index, self.new_external_docids)?;
+        if !self.previous_documents.is_empty() {
+            add_documents(writer, index, self.previous_documents, false, DuplicatePolicy::Overwrite, None)?;
+        }
+        Ok(())
+    }
+}
+
+fn add_documents<'a, 'b, W: UpdateWriter<'b>>(
+    writer: &'a mut W,
     index: &store::Index,
     new_documents: Vec<IndexMap<String, Value>>,
-    partial: bool
-) -> MResult<()>
+    partial: bool,
+    duplicate_policy: DuplicatePolicy,
+    hooks: Option<&dyn IndexingHooks>,
+) -> MResult<(IndexedStats, Vec<Warning>)>
 {
-    let mut schema = match index.main.schema(writer)? {
+    let txn = writer.txn();
+    let mut schema = match index.main.schema(txn)? {
         Some(schema) => schema,
         None => return Err(Error::SchemaMissing),
     };
 
     // Retrieve the documents ids related structures
-    let external_docids = index.main.external_docids(writer)?;
-    let internal_docids = index.main.internal_docids(writer)?;
-    let mut available_ids = DiscoverIds::new(&internal_docids);
+    let external_docids = index.main.external_docids(txn)?;
+    let internal_docids = index.main.internal_docids(txn)?;
+    // an addition never needs more free ids than it has documents
+    let free_ids = index.free_list.first_ids(txn, new_documents.len())?;
+    let mut available_ids = DiscoverIds::with_free_ids(&internal_docids, free_ids);
 
     let primary_key = schema.primary_key().ok_or(Error::MissingPrimaryKey)?;
 
@@ -166,26 +403,24 @@ pub fn apply_addition<'a, 'b>(
     let mut documents_additions = HashMap::new();
     let mut new_external_docids = BTreeMap::new();
     let mut new_internal_docids = Vec::with_capacity(new_documents.len());
+    let mut documents_user_ids = HashMap::new();
 
     for mut document in new_documents {
-        let (internal_docid, external_docid) =
-            extract_document_id(
-                &primary_key,
-                &document,
-                &external_docids,
-                &mut available_ids,
-            )?;
-
-        new_external_docids.insert(external_docid, internal_docid.0);
-        new_internal_docids.push(internal_docid);
+        // the documents sharing an id in this addition are given the same internal id
+        let external_docid = extract_document_user_id(&primary_key, &document)?;
+        let internal_docid = match new_external_docids.get(&external_docid) {
+            Some(docid) => DocumentId(*docid),
+            None => discover_document_id(&external_docid, &external_docids, &mut available_ids)?,
+        };
 
         if partial {
             let mut deserializer = Deserializer {
                 document_id: internal_docid,
-                reader: writer,
+                reader: txn,
                 documents_fields: index.documents_fields,
                 schema: &schema,
                 fields: None,
+                cipher: index.field_cipher.as_ref(),
             };
 
             let old_document = Option::<HashMap<String, Value>>::deserialize(&mut deserializer)?;
@@ -195,92 +430,190 @@ pub fn apply_addition<'a, 'b>(
                 }
             }
         }
-        documents_additions.insert(internal_docid, document);
+
+        match documents_additions.entry(internal_docid) {
+            Entry::Vacant(entry) => {
+                documents_user_ids.insert(internal_docid, external_docid.clone());
+                new_external_docids.insert(external_docid, internal_docid.0);
+                new_internal_docids.push(internal_docid);
+                entry.insert(document);
+            }
+            Entry::Occupied(mut entry) => match duplicate_policy {
+                DuplicatePolicy::Reject => return Err(Error::DuplicateDocumentId { user_id: external_docid }),
+                DuplicatePolicy::Overwrite => { entry.insert(document); },
+                DuplicatePolicy::Merge => merge_document(entry.get_mut(), document),
+            },
+        }
+    }
+
+    // the documents replaced by this addition are not counted against the limit
+    if let Some(limit) = index.main.max_documents(txn)? {
+        let current = index.main.number_of_documents(txn)?;
+        let attempted = new_external_docids.keys().filter(|id| !external_docids.contains_key(id)).count() as u64;
+        if current + attempted > limit {
+            return Err(Error::IndexDocumentLimitExceeded { current, limit, attempted });
+        }
+    }
+
+    // the boosts of the replaced documents are kept
+    let mut boosts = Vec::new();
+    for document_id in &new_internal_docids {
+        if let Some(boost) = index.document_boosts.boost(txn, *document_id)? {
+            boosts.push((*document_id, boost));
+        }
     }
 
     // 2. remove the documents postings lists
     let number_of_inserted_documents = documents_additions.len();
     let documents_ids = new_external_docids.iter().map(|(id, _)| id.clone()).collect();
-    apply_documents_deletion(writer, index, documents_ids)?;
+    apply_documents_deletion(txn, index, documents_ids)?;
 
-    let mut ranked_map = match index.main.ranked_map(writer)? {
+    for (document_id, boost) in boosts {
+        index.document_boosts.put_boost(txn, document_id, boost)?;
+    }
+
+    let mut ranked_map = match index.main.ranked_map(txn)? {
         Some(ranked_map) => ranked_map,
         None => RankedMap::default(),
     };
 
-    let stop_words = match index.main.stop_words_fst(writer)? {
+    let stop_words = match index.main.stop_words_fst(txn)? {
         Some(stop_words) => stop_words,
         None => fst::Set::default(),
     };
 
     // 3. index the documents fields in the stores
-    if let Some(attributes_for_facetting) = index.main.attributes_for_faceting(writer)? {
+    if let Some(attributes_for_facetting) = index.main.attributes_for_faceting(txn)? {
         let facet_map = facets::facet_map_from_docs(&schema, &documents_additions, attributes_for_facetting.as_ref())?;
-        index.facets.add(writer, facet_map)?;
+        index.facets.add(txn, facet_map)?;
     }
 
+    let languages_stop_words = languages_stop_words(txn, index, &stop_words)?;
     let mut indexer = RawIndexer::new(stop_words);
+    indexer.set_languages_stop_words(languages_stop_words);
+    indexer.set_token_limits(index.token_limits);
+    indexer.set_normalization_mode(index.main.normalization_mode(txn)?.unwrap_or_default());
+
+    let mut warnings = Vec::new();
+    let mut ranked_changes = RankedMapChanges::default();
 
     // For each document in this update
     for (document_id, document) in documents_additions {
+        if let Some(language) = document.get(LANGUAGE_FIELD).and_then(Value::as_str) {
+            indexer.set_document_language(document_id, language);
+        }
+
         // For each key-value pair in the document.
         for (attribute, value) in document {
             let field_id = schema.insert_and_index(&attribute)?;
+            detect_field_type(&mut schema, field_id, &value);
+            let document_user_id = || documents_user_ids.get(&document_id).cloned().unwrap_or_default();
+            if let Some((expected, actual)) = ranked_type_conflict(&schema, field_id, &value) {
+                warnings.push(Warning::TypeConflict {
+                    field: attribute.clone(),
+                    expected: expected.to_string(),
+                    actual: actual.to_string(),
+                    document_user_id: document_user_id(),
+                });
+            }
             index_document(
-                writer,
+                txn,
                 index.documents_fields,
                 index.documents_fields_counts,
-                &mut ranked_map,
+                &mut ranked_changes,
                 &mut indexer,
                 &schema,
                 field_id,
                 document_id,
                 &value,
+                index.field_cipher.as_ref(),
             )?;
+            if let Some(exceeded) = indexer.take_token_limit_exceeded() {
+                let (limit, max_tokens) = match exceeded {
+                    TokenLimitExceeded::Field(max_tokens) => ("field", max_tokens),
+                    TokenLimitExceeded::Document(max_tokens) => ("document", max_tokens),
+                };
+                warnings.push(Warning::TokenLimitExceeded {
+                    field: attribute.clone(),
+                    document_user_id: document_user_id(),
+                    limit: limit.to_string(),
+                    max_tokens,
+                });
+            }
         }
     }
 
-    write_documents_addition_index(
+    ranked_map.apply(ranked_changes);
+    index.main.put_schema(txn, &schema)?;
+
+    // the documents ids are written before the postings lists,
+    // the documents are complete at the checkpoints of the words
+    let new_external_docids = fst::Map::from_iter(new_external_docids.iter().map(|(ext, id)| (ext, *id as u64)))?;
+    let new_internal_docids = sdset::SetBuf::from_dirty(new_internal_docids);
+    index.main.merge_external_docids(txn, &new_external_docids)?;
+    index.main.merge_internal_docids(txn, &new_internal_docids)?;
+    // the replaced documents ids have been freed by their deletion, they are in use again
+    index.free_list.delete_ids(txn, &new_internal_docids)?;
+
+    let stats = write_documents_addition_index(
         writer,
         index,
         &ranked_map,
         number_of_inserted_documents,
         indexer,
+        index.checkpoint_interval,
+        hooks,
     )?;
 
-    index.main.put_schema(writer, &schema)?;
-
-    let new_external_docids = fst::Map::from_iter(new_external_docids.iter().map(|(ext, id)| (ext, *id as u64)))?;
-    let new_internal_docids = sdset::SetBuf::from_dirty(new_internal_docids);
-    index.main.merge_external_docids(writer, &new_external_docids)?;
-    index.main.merge_internal_docids(writer, &new_internal_docids)?;
+    if let Some(hooks) = &hooks {
+        for document_id in new_internal_docids.iter() {
+            hooks.after_index(*document_id)?;
+        }
+    }
 
-    Ok(())
+    Ok((stats, warnings))
 }
 
-pub fn apply_documents_partial_addition<'a, 'b>(
-    writer: &'a mut heed::RwTxn<'b, MainT>,
+pub fn apply_documents_partial_addition<'a, 'b, W: UpdateWriter<'b>>(
+    writer: &'a mut W,
     index: &store::Index,
     new_documents: Vec<IndexMap<String, Value>>,
-) -> MResult<()> {
-    apply_addition(writer, index, new_documents, true)
+    duplicate_policy: DuplicatePolicy,
+    hooks: Option<Arc<dyn IndexingHooks>>,
+) -> MResult<(IndexedStats, Vec<Warning>)> {
+    apply_addition(writer, index, new_documents, true, duplicate_policy, hooks)
 }
 
-pub fn apply_documents_addition<'a, 'b>(
-    writer: &'a mut heed::RwTxn<'b, MainT>,
+pub fn apply_documents_addition<'a, 'b, W: UpdateWriter<'b>>(
+    writer: &'a mut W,
     index: &store::Index,
     new_documents: Vec<IndexMap<String, Value>>,
-) -> MResult<()> {
-    apply_addition(writer, index, new_documents, false)
+    duplicate_policy: DuplicatePolicy,
+    hooks: Option<Arc<dyn IndexingHooks>>,
+) -> MResult<(IndexedStats, Vec<Warning>)> {
+    apply_addition(writer, index, new_documents, false, duplicate_policy, hooks)
 }
 
-pub fn reindex_all_documents(writer: &mut heed::RwTxn<MainT>, index: &store::Index) -> MResult<()> {
-    let schema = match index.main.schema(writer)? {
-        Some(schema) => schema,
-        None => return Err(Error::SchemaMissing),
-    };
+/// The number of documents reindexed between two checkpoints.
+const REINDEX_CHUNK_SIZE: usize = 10_000;
+
+/// Reindexes the documents chunk by chunk. When a checkpoint interval is set, the writer
+/// is committed after each chunk along with a checkpoint the update loop resumes from
+/// if the reindexation is interrupted.
+pub fn reindex_all_documents<'a, 'b, W: UpdateWriter<'b>>(
+    writer: &'a mut W,
+    index: &store::Index,
+) -> MResult<IndexedStats> {
+    clear_documents_index(writer.txn(), index)?;
 
-    let mut ranked_map = RankedMap::default();
+    // 3. reindex the documents chunk by chunk
+    reindex_documents_from(writer, index, Some(DocumentId(0)))
+}
+
+fn clear_documents_index(writer: &mut heed::RwTxn<MainT>, index: &store::Index) -> MResult<()> {
+    if index.main.schema(writer)?.is_none() {
+        return Err(Error::SchemaMissing);
+    }
 
     // 1. retrieve all documents ids
     let mut documents_ids_to_reindex = Vec::new();
@@ -291,88 +624,260 @@ pub fn reindex_all_documents(writer: &mut heed::RwTxn<MainT>, index: &store::Ind
 
     // 2. remove the documents posting lists
     index.main.put_words_fst(writer, &fst::Set::default())?;
-    index.main.put_ranked_map(writer, &ranked_map)?;
+    index.main.put_ranked_map(writer, &RankedMap::default())?;
     index.main.put_number_of_documents(writer, |_| 0)?;
+    index.main.delete_reindex_checkpoint(writer)?;
     index.facets.clear(writer)?;
     index.postings_lists.clear(writer)?;
     index.docs_words.clear(writer)?;
+    index.word_frequency.clear(writer)?;
+
+    if let Some(ref attributes_for_facetting) = index.main.attributes_for_faceting(writer)? {
+        let facet_map = facets::facet_map_from_docids(writer, &index, &documents_ids_to_reindex, &attributes_for_facetting)?;
+        index.facets.add(writer, facet_map)?;
+    }
+
+    Ok(())
+}
+
+/// Reindexes the documents that were not processed yet by an interrupted reindexation,
+/// according to the checkpoint stored in the main store. Does nothing if there is no checkpoint.
+///
+/// The checkpoint is written in the same transaction as the reindexed documents, it is
+/// committed with them by the checkpoints of the writer. It is called by the update loop
+/// before the updates are processed.
+pub fn resume_reindex_from_checkpoint<'a, 'b, W: UpdateWriter<'b>>(
+    writer: &'a mut W,
+    index: &store::Index,
+) -> MResult<()> {
+    let checkpoint = match index.main.reindex_checkpoint(writer.txn())? {
+        Some(checkpoint) => checkpoint,
+        None => return Ok(()),
+    };
+
+    // there is no document left when the last possible document id has been processed
+    let start = u32::try_from(checkpoint.last_processed_doc_id + 1).ok().map(DocumentId);
+    reindex_documents_from(writer, index, start)?;
+
+    Ok(())
+}
+
+fn reindex_documents_from<'a, 'b, W: UpdateWriter<'b>>(
+    writer: &'a mut W,
+    index: &store::Index,
+    mut start: Option<DocumentId>,
+) -> MResult<IndexedStats>
+{
+    let mut schema = match index.main.schema(writer.txn())? {
+        Some(schema) => schema,
+        None => return Err(Error::SchemaMissing),
+    };
+
+    let commit_chunks = index.checkpoint_interval.map_or(false, |interval| interval != 0);
 
+    let mut stats: Option<IndexedStats> = None;
+    while let Some(start_id) = start {
+        let documents_ids = index.documents_fields_counts.documents_ids_from(writer.txn(), start_id, REINDEX_CHUNK_SIZE)?;
+        let last_document_id = match documents_ids.last() {
+            Some(document_id) => *document_id,
+            None => break,
+        };
+
+        let chunk_stats = reindex_documents_chunk(writer.txn(), index, &mut schema, documents_ids)?;
+        stats = Some(match stats {
+            Some(stats) => stats.merge(chunk_stats),
+            None => chunk_stats,
+        });
+
+        let checkpoint = ReindexCheckpoint { last_processed_doc_id: u64::from(last_document_id.0) };
+        index.main.put_reindex_checkpoint(writer.txn(), checkpoint)?;
+
+        start = last_document_id.0.checked_add(1).map(DocumentId);
+
+        // the types detected so far are committed along with the checkpoint
+        if commit_chunks && start.is_some() {
+            index.main.put_schema(writer.txn(), &schema)?;
+            writer.checkpoint()?;
+        }
+    }
+
+    let writer = writer.txn();
+    index.main.delete_reindex_checkpoint(writer)?;
+    index.main.put_schema(writer, &schema)?;
+    index.main.put_short_prefixes_stale(writer)?;
+
+    Ok(stats.unwrap_or_default())
+}
+
+fn reindex_documents_chunk(
+    writer: &mut heed::RwTxn<MainT>,
+    index: &store::Index,
+    schema: &mut Schema,
+    documents_ids: Vec<DocumentId>,
+) -> MResult<IndexedStats>
+{
+    let language_field_id = schema.id(LANGUAGE_FIELD);
+
+    let mut ranked_map = index.main.ranked_map(writer)?.unwrap_or_default();
+    let number_of_inserted_documents = documents_ids.len();
     let stop_words = match index.main.stop_words_fst(writer)? {
         Some(stop_words) => stop_words,
         None => fst::Set::default(),
     };
 
-    let number_of_inserted_documents = documents_ids_to_reindex.len();
+    let languages_stop_words = languages_stop_words(writer, index, &stop_words)?;
     let mut indexer = RawIndexer::new(stop_words);
+    indexer.set_languages_stop_words(languages_stop_words);
+    indexer.set_token_limits(index.token_limits);
+    indexer.set_normalization_mode(index.main.normalization_mode(writer)?.unwrap_or_default());
     let mut ram_store = HashMap::new();
+    let mut ranked_changes = RankedMapChanges::default();
 
-    if let Some(ref attributes_for_facetting) = index.main.attributes_for_faceting(writer)? {
-        let facet_map = facets::facet_map_from_docids(writer, &index, &documents_ids_to_reindex, &attributes_for_facetting)?;
-        index.facets.add(writer, facet_map)?;
-    }
     // ^-- https://github.com/meilisearch/MeiliSearch/pull/631#issuecomment-626624470 --v
-    for document_id in documents_ids_to_reindex {
+    for document_id in documents_ids {
         for result in index.documents_fields.document_fields(writer, document_id)? {
             let (field_id, bytes) = result?;
-            let value: Value = serde_json::from_slice(bytes)?;
+            let bytes = decrypt_field(index.field_cipher.as_ref(), bytes)?;
+            let value: Value = serde_json::from_slice(field_json_bytes(&bytes))?;
+            if Some(field_id) == language_field_id {
+                if let Some(language) = value.as_str() {
+                    indexer.set_document_language(document_id, language);
+                }
+            }
             ram_store.insert((document_id, field_id), value);
         }
 
         // For each key-value pair in the document.
         for ((document_id, field_id), value) in ram_store.drain() {
+            detect_field_type(schema, field_id, &value);
             index_document(
                 writer,
                 index.documents_fields,
                 index.documents_fields_counts,
-                &mut ranked_map,
+                &mut ranked_changes,
                 &mut indexer,
-                &schema,
+                schema,
                 field_id,
                 document_id,
                 &value,
+                index.field_cipher.as_ref(),
             )?;
         }
     }
 
     // 4. write the new index in the main store
+    ranked_map.apply(ranked_changes);
+    // the reindexed documents are committed by chunks of documents
     write_documents_addition_index(
         writer,
         index,
         &ranked_map,
         number_of_inserted_documents,
         indexer,
-    )?;
-
-    index.main.put_schema(writer, &schema)?;
+        None,
+        None,
+    )
+}
 
-    Ok(())
+/// Returns the stop words of each language merged with the default stop words of the index.
+fn languages_stop_words(
+    writer: &heed::RwTxn<MainT>,
+    index: &store::Index,
+    stop_words: &fst::Set,
+) -> MResult<HashMap<String, fst::Set>>
+{
+    let mut languages = HashMap::new();
+    for (language, words) in index.main.languages_stop_words(writer)? {
+        let words = fst::Set::from_iter(words)?;
+        let op = OpBuilder::new().add(stop_words).add(&words).r#union();
+
+        let mut builder = SetBuilder::memory();
+        builder.extend_stream(op)?;
+        let words = builder.into_inner().and_then(fst::Set::from_bytes)?;
+        languages.insert(language, words);
+    }
+    Ok(languages)
 }
 
-pub fn write_documents_addition_index(
-    writer: &mut heed::RwTxn<MainT>,
+/// Writes the postings lists of the indexed documents. When a checkpoint interval is set,
+/// the words written so far are added to the words fst and committed every time this
+/// number of words has been written.
+pub fn write_documents_addition_index<'b, W: UpdateWriter<'b>>(
+    writer: &mut W,
     index: &store::Index,
     ranked_map: &RankedMap,
     number_of_inserted_documents: usize,
     indexer: RawIndexer,
-) -> MResult<()> {
+    checkpoint_interval: Option<usize>,
+    hooks: Option<&dyn IndexingHooks>,
+) -> MResult<IndexedStats> {
     let indexed = indexer.build();
     let mut delta_words_builder = SetBuilder::memory();
+    let mut stats = IndexedStats::default();
+    stats.fst_size_before = index.main.words_fst(writer.txn())?.map_or(0, |words| words.len());
+
+    // the words of the documents are written first, the documents committed
+    // at a checkpoint can be deleted before all their words are written
+    for (id, words) in indexed.docs_words {
+        index.docs_words.put_doc_words(writer.txn(), id, &words)?;
+    }
+
+    for (id, counts) in indexed.docs_word_counts {
+        index.docs_words.put_doc_word_counts(writer.txn(), id, &counts)?;
+    }
+
+    index.main.put_ranked_map(writer.txn(), ranked_map)?;
+    index.main.put_number_of_documents(writer.txn(), |old| old + number_of_inserted_documents as u64)?;
 
     for (word, delta_set) in indexed.words_doc_indexes {
         delta_words_builder.insert(&word).unwrap();
-
-        let set = match index.postings_lists.postings_list(writer, &word)? {
-            Some(postings) => Union::new(&postings.matches, &delta_set).into_set_buf(),
-            None => delta_set,
+        stats.total_words_indexed += 1;
+
+        let (set, previous_len) = match index.postings_lists.postings_list(writer.txn(), &word)? {
+            Some(postings) => {
+                stats.posting_lists_updated += 1;
+                let previous_len = postings.matches.len();
+                (Union::new(&postings.matches, &delta_set).into_set_buf(), previous_len)
+            },
+            None => (delta_set, 0),
         };
 
-        index.postings_lists.put_postings_list(writer, &word, &set)?;
-    }
+        index.postings_lists.put_postings_list(writer.txn(), &word, &set)?;
 
-    for (id, words) in indexed.docs_words {
-        index.docs_words.put_doc_words(writer, id, &words)?;
+        if let Ok(word) = std::str::from_utf8(&word) {
+            let count = set.len().saturating_sub(previous_len) as u64;
+            index.word_frequency.increase_frequency(writer.txn(), word, count)?;
+        }
+
+        // the words written so far are added to the words fst before being
+        // committed, the searches find the postings lists already written
+        let checkpoint_reached = checkpoint_interval
+            .map_or(false, |interval| interval != 0 && stats.total_words_indexed % interval == 0);
+
+        if checkpoint_reached {
+            let delta_words = std::mem::replace(&mut delta_words_builder, SetBuilder::memory());
+            merge_words_fst(writer.txn(), index, delta_words)?;
+
+            if writer.checkpoint()? {
+                if let Some(hooks) = hooks {
+                    hooks.progress(IndexingProgress::Checkpoint { words_written: stats.total_words_indexed })?;
+                }
+            }
+        }
     }
 
+    stats.fst_size_after = merge_words_fst(writer.txn(), index, delta_words_builder)?;
+    stats.new_words_added = stats.total_words_indexed - stats.posting_lists_updated;
+
+    Ok(stats)
+}
+
+/// Adds the words to the words fst of the index, returns the new number of words.
+fn merge_words_fst(
+    writer: &mut heed::RwTxn<MainT>,
+    index: &store::Index,
+    delta_words_builder: SetBuilder<Vec<u8>>,
+) -> MResult<usize> {
     let delta_words = delta_words_builder
         .into_inner()
         .and_then(fst::Set::from_bytes)
@@ -396,10 +901,6 @@ pub fn write_documents_addition_index(
     };
 
     index.main.put_words_fst(writer, &words)?;
-    index.main.put_ranked_map(writer, ranked_map)?;
-    index.main.put_number_of_documents(writer, |old| old + number_of_inserted_documents as u64)?;
 
-    compute_short_prefixes(writer, index)?;
-
-    Ok(())
+    Ok(words.len())
 }
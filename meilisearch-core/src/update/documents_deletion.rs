@@ -7,7 +7,7 @@ use crate::database::{MainT, UpdateT};
 use crate::database::{UpdateEvent, UpdateEventsEmitter};
 use crate::facets;
 use crate::store;
-use crate::update::{next_update_id, compute_short_prefixes, Update};
+use crate::update::{next_update_id, Update};
 use crate::{DocumentId, Error, MResult, RankedMap};
 
 pub struct DocumentsDeletion {
@@ -70,28 +70,25 @@ pub fn push_documents_deletion(
 pub fn apply_documents_deletion(
     writer: &mut heed::RwTxn<MainT>,
     index: &store::Index,
-    external_docids: Vec<String>,
+    mut external_docids: Vec<String>,
 ) -> MResult<()>
 {
-    let (external_docids, internal_docids) = {
-        let new_external_docids = SetBuf::from_dirty(external_docids);
+    let internal_docids = {
         let mut internal_docids = Vec::new();
 
         let old_external_docids = index.main.external_docids(writer)?;
-        for external_docid in new_external_docids.as_slice() {
+        for external_docid in &external_docids {
             if let Some(id) = old_external_docids.get(external_docid) {
                 internal_docids.push(DocumentId(id as u32));
             }
         }
 
-        let new_external_docids = fst::Map::from_iter(new_external_docids.into_iter().map(|k| (k, 0))).unwrap();
-        (new_external_docids, SetBuf::from_dirty(internal_docids))
+        SetBuf::from_dirty(internal_docids)
     };
 
-    let schema = match index.main.schema(writer)? {
-        Some(schema) => schema,
-        None => return Err(Error::SchemaMissing),
-    };
+    if index.main.schema(writer)?.is_none() {
+        return Err(Error::SchemaMissing);
+    }
 
     let mut ranked_map = match index.main.ranked_map(writer)? {
         Some(ranked_map) => ranked_map,
@@ -104,16 +101,11 @@ pub fn apply_documents_deletion(
         index.facets.remove(writer, facet_map)?;
     }
 
-    // collect the ranked attributes according to the schema
-    let ranked_fields = schema.ranked();
+    // remove all the ranked attributes of the documents from the ranked_map
+    ranked_map.remove_documents(&internal_docids);
 
     let mut words_document_ids = HashMap::new();
     for id in internal_docids.iter().cloned() {
-        // remove all the ranked attributes from the ranked_map
-        for ranked_attr in ranked_fields {
-            ranked_map.remove(id, *ranked_attr);
-        }
-
         if let Some(words) = index.docs_words.doc_words(writer, id)? {
             let mut stream = words.stream();
             while let Some(word) = stream.next() {
@@ -135,6 +127,11 @@ pub fn apply_documents_deletion(
             let op = DifferenceByKey::new(&postings.matches, &document_ids, |d| d.document_id, |id| *id);
             let doc_indexes = op.into_set_buf();
 
+            if let Ok(word) = std::str::from_utf8(&word) {
+                let count = (postings.matches.len() - doc_indexes.len()) as u64;
+                index.word_frequency.decrease_frequency(writer, word, count)?;
+            }
+
             if !doc_indexes.is_empty() {
                 index.postings_lists.put_postings_list(writer, &word, &doc_indexes)?;
             } else {
@@ -154,6 +151,7 @@ pub fn apply_documents_deletion(
     let deleted_documents_len = deleted_documents.len() as u64;
     for id in deleted_documents {
         index.docs_words.del_doc_words(writer, id)?;
+        index.document_boosts.delete_boost(writer, id)?;
     }
 
     let removed_words = fst::Set::from_iter(removed_words).unwrap();
@@ -179,10 +177,9 @@ pub fn apply_documents_deletion(
     index.main.put_number_of_documents(writer, |old| old - deleted_documents_len)?;
 
     // We apply the changes to the user and internal ids
-    index.main.remove_external_docids(writer, &external_docids)?;
+    index.main.batch_delete_external_docids(writer, &mut external_docids)?;
     index.main.remove_internal_docids(writer, &internal_docids)?;
-
-    compute_short_prefixes(writer, index)?;
+    index.free_list.put_ids(writer, &internal_docids)?;
 
     Ok(())
 }
@@ -4,11 +4,11 @@ use indexmap::IndexMap;
 use meilisearch_schema::IndexedPos;
 use meilisearch_types::DocumentId;
 use ordered_float::OrderedFloat;
-use serde_json::Value;
+use serde_json::{Map, Value};
 
 use crate::Number;
 use crate::raw_indexer::RawIndexer;
-use crate::serde::SerializerError;
+use crate::serde::{parse_datetime, SerializerError};
 use crate::store::DiscoverIds;
 
 /// Returns the number of words indexed or `None` if the type is unindexable.
@@ -95,6 +95,15 @@ pub fn value_to_number(value: &Value) -> Option<Number> {
     }
 }
 
+/// Converts a ranked value into a number, the ISO 8601 date strings being
+/// converted into the number of seconds since the Unix epoch.
+pub fn value_to_ranked_number(value: &Value) -> Option<Number> {
+    match value_to_number(value) {
+        Some(number) => Some(number),
+        None => value.as_str().and_then(parse_datetime).map(|ts| Number::Float(OrderedFloat(ts))),
+    }
+}
+
 /// Validates a string representation to be a correct document id and returns
 /// the corresponding id or generate a new one, this is the way we produce documents ids.
 pub fn discover_document_id(
@@ -116,6 +125,20 @@ pub fn discover_document_id(
     }
 }
 
+/// Extracts the user id of a document, the value of its primary key.
+pub fn extract_document_user_id(
+    primary_key: &str,
+    document: &IndexMap<String, Value>,
+) -> Result<String, SerializerError>
+{
+    match document.get(primary_key) {
+        Some(Value::Number(number)) => Ok(number.to_string()),
+        Some(Value::String(string)) => Ok(string.clone()),
+        Some(_) => Err(SerializerError::InvalidDocumentIdFormat),
+        None => Err(SerializerError::DocumentIdNotFound),
+    }
+}
+
 /// Extracts and validates the document id of a document.
 pub fn extract_document_id(
     primary_key: &str,
@@ -124,15 +147,38 @@ pub fn extract_document_id(
     available_docids: &mut DiscoverIds<'_>,
 ) -> Result<(DocumentId, String), SerializerError>
 {
-    match document.get(primary_key) {
-        Some(value) => {
-            let docid = match value {
-                Value::Number(number) => number.to_string(),
-                Value::String(string) => string.clone(),
-                _ => return Err(SerializerError::InvalidDocumentIdFormat),
-            };
-            discover_document_id(&docid, external_docids, available_docids).map(|id| (id, docid))
+    let docid = extract_document_user_id(primary_key, document)?;
+    discover_document_id(&docid, external_docids, available_docids).map(|id| (id, docid))
+}
+
+/// Applies the fields of the patch to the document following the JSON merge patch
+/// semantics (RFC 7396), the objects are merged and the null values remove the fields.
+pub fn merge_document(document: &mut IndexMap<String, Value>, patch: IndexMap<String, Value>) {
+    for (key, value) in patch {
+        if value.is_null() {
+            document.shift_remove(&key);
+        } else {
+            merge_value(document.entry(key).or_insert(Value::Null), value);
         }
-        None => Err(SerializerError::DocumentIdNotFound),
+    }
+}
+
+fn merge_value(target: &mut Value, patch: Value) {
+    match patch {
+        Value::Object(patch) => {
+            if !target.is_object() {
+                *target = Value::Object(Map::new());
+            }
+            if let Value::Object(target) = target {
+                for (key, value) in patch {
+                    if value.is_null() {
+                        target.remove(&key);
+                    } else {
+                        merge_value(target.entry(key).or_insert(Value::Null), value);
+                    }
+                }
+            }
+        }
+        patch => *target = patch,
     }
 }
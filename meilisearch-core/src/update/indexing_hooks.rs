@@ -0,0 +1,63 @@
+use chrono::Utc;
+use indexmap::IndexMap;
+use serde_json::Value;
+
+use crate::{DocumentId, MResult};
+
+/// The progress of a documents addition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexingProgress {
+    /// The postings lists of the first `words_written` distinct words of the addition
+    /// have been written and committed, along with the documents of the addition.
+    Checkpoint { words_written: usize },
+}
+
+/// Hooks called while documents are being indexed by a documents addition,
+/// returning an error aborts the whole update.
+pub trait IndexingHooks: Send + Sync {
+    /// Called on every document of the update before it is indexed,
+    /// the document can be modified (e.g. sanitized or enriched with new fields).
+    fn before_index(&self, _document: &mut IndexMap<String, Value>) -> MResult<()> {
+        Ok(())
+    }
+
+    /// Called once the document has been indexed.
+    fn after_index(&self, _document_id: DocumentId) -> MResult<()> {
+        Ok(())
+    }
+
+    /// Called every time a documents addition commits a chunk of documents.
+    fn progress(&self, _progress: IndexingProgress) -> MResult<()> {
+        Ok(())
+    }
+}
+
+impl<T: IndexingHooks + ?Sized> IndexingHooks for Box<T> {
+    fn before_index(&self, document: &mut IndexMap<String, Value>) -> MResult<()> {
+        (**self).before_index(document)
+    }
+
+    fn after_index(&self, document_id: DocumentId) -> MResult<()> {
+        (**self).after_index(document_id)
+    }
+
+    fn progress(&self, progress: IndexingProgress) -> MResult<()> {
+        (**self).progress(progress)
+    }
+}
+
+/// Injects the date at which the document was indexed into the `_indexed_at` field,
+/// formatted as an RFC 3339 string.
+pub struct TimestampHook;
+
+impl TimestampHook {
+    pub const FIELD_NAME: &'static str = "_indexed_at";
+}
+
+impl IndexingHooks for TimestampHook {
+    fn before_index(&self, document: &mut IndexMap<String, Value>) -> MResult<()> {
+        let now = Utc::now().to_rfc3339();
+        document.insert(TimestampHook::FIELD_NAME.to_string(), Value::String(now));
+        Ok(())
+    }
+}
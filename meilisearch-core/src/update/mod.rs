@@ -1,18 +1,26 @@
 mod clear_all;
+mod conflicts;
 mod customs_update;
 mod documents_addition;
 mod documents_deletion;
 mod settings_update;
 mod helpers;
+mod indexing_hooks;
+#[cfg(test)]
+pub(crate) mod test_helpers;
 
 pub use self::clear_all::{apply_clear_all, push_clear_all};
+pub use self::conflicts::ConflictsTracker;
 pub use self::customs_update::{apply_customs_update, push_customs_update};
-pub use self::documents_addition::{apply_documents_addition, apply_documents_partial_addition, DocumentsAddition};
+pub use self::documents_addition::{apply_documents_addition, apply_documents_partial_addition, resume_reindex_from_checkpoint, DocumentsAddition, DuplicatePolicy, IndexedStats, Warning};
 pub use self::documents_deletion::{apply_documents_deletion, DocumentsDeletion};
-pub use self::helpers::{index_value, value_to_string, value_to_number, discover_document_id, extract_document_id};
-pub use self::settings_update::{apply_settings_update, push_settings_update};
+pub use self::indexing_hooks::{IndexingHooks, IndexingProgress, TimestampHook};
+pub use self::helpers::{index_value, value_to_string, value_to_number, discover_document_id, extract_document_id, extract_document_user_id, merge_document};
+pub use self::settings_update::{apply_schema_rename, apply_schema_settings, apply_settings_update, push_settings_update};
 
 use std::cmp;
+use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::Instant;
 
 use chrono::{DateTime, Utc};
@@ -20,11 +28,12 @@ use fst::{IntoStreamer, Streamer};
 use heed::Result as ZResult;
 use indexmap::IndexMap;
 use log::debug;
-use sdset::Set;
+use sdset::{Set, SetBuf};
+use slice_group_by::GroupBy;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-use crate::{store, MResult};
+use crate::{store, DocumentId, Error, MResult, UpdateWriter};
 use crate::database::{MainT, UpdateT};
 use crate::settings::SettingsUpdate;
 
@@ -32,6 +41,9 @@ use crate::settings::SettingsUpdate;
 pub struct Update {
     data: UpdateData,
     enqueued_at: DateTime<Utc>,
+    /// How the documents additions handle the documents sharing the same id.
+    #[serde(default)]
+    duplicate_policy: DuplicatePolicy,
 }
 
 impl Update {
@@ -39,6 +51,7 @@ impl Update {
         Update {
             data: UpdateData::ClearAll,
             enqueued_at: Utc::now(),
+            duplicate_policy: DuplicatePolicy::default(),
         }
     }
 
@@ -46,6 +59,7 @@ impl Update {
         Update {
             data: UpdateData::Customs(data),
             enqueued_at: Utc::now(),
+            duplicate_policy: DuplicatePolicy::default(),
         }
     }
 
@@ -53,6 +67,7 @@ impl Update {
         Update {
             data: UpdateData::DocumentsAddition(documents),
             enqueued_at: Utc::now(),
+            duplicate_policy: DuplicatePolicy::default(),
         }
     }
 
@@ -60,6 +75,7 @@ impl Update {
         Update {
             data: UpdateData::DocumentsPartial(documents),
             enqueued_at: Utc::now(),
+            duplicate_policy: DuplicatePolicy::default(),
         }
     }
 
@@ -67,6 +83,7 @@ impl Update {
         Update {
             data: UpdateData::DocumentsDeletion(data),
             enqueued_at: Utc::now(),
+            duplicate_policy: DuplicatePolicy::default(),
         }
     }
 
@@ -74,8 +91,13 @@ impl Update {
         Update {
             data: UpdateData::Settings(data),
             enqueued_at: Utc::now(),
+            duplicate_policy: DuplicatePolicy::default(),
         }
     }
+
+    fn with_duplicate_policy(self, duplicate_policy: DuplicatePolicy) -> Update {
+        Update { duplicate_policy, ..self }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -131,6 +153,24 @@ pub struct ProcessedUpdateResult {
     pub duration: f64, // in seconds
     pub enqueued_at: DateTime<Utc>,
     pub processed_at: DateTime<Utc>,
+    /// The number of documents of this update that overwrote
+    /// the version of a previous update that was pending with it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub conflicts_resolved: Option<usize>,
+    /// Set when this result replaces several adjacent processed
+    /// updates results that have been compacted together.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub merged_count: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_id: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_id: Option<u64>,
+    /// The postings lists metrics of a documents addition.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub indexed_stats: Option<IndexedStats>,
+    /// The problems found in the documents that did not prevent the update from being processed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<Warning>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -203,22 +243,33 @@ pub fn next_update_id(
     Ok(new_update_id)
 }
 
-pub fn update_task<'a, 'b>(
-    writer: &'a mut heed::RwTxn<'b, MainT>,
+pub fn update_task<'a, 'b, W: UpdateWriter<'b>>(
+    writer: &'a mut W,
     index: &store::Index,
     update_id: u64,
     update: Update,
+    hooks: Option<Arc<dyn IndexingHooks>>,
 ) -> MResult<ProcessedUpdateResult> {
+    if index.is_read_only() {
+        return Err(Error::IndexReadOnly);
+    }
+
     debug!("Processing update number {}", update_id);
 
-    let Update { enqueued_at, data } = update;
+    let Update { enqueued_at, data, duplicate_policy } = update;
+
+    // documents updates modify the words fst, the short prefixes cache is marked
+    // as stale and rebuilt in the background once the update is committed
+    let mut needs_prefix_rebuild = false;
+    let mut indexed_stats = None;
+    let mut warnings = Vec::new();
 
     let (update_type, result, duration) = match data {
         UpdateData::ClearAll => {
             let start = Instant::now();
 
             let update_type = UpdateType::ClearAll;
-            let result = apply_clear_all(writer, index);
+            let result = apply_clear_all(writer.txn(), index);
 
             (update_type, result, start.elapsed())
         }
@@ -226,7 +277,7 @@ pub fn update_task<'a, 'b>(
             let start = Instant::now();
 
             let update_type = UpdateType::Customs;
-            let result = apply_customs_update(writer, index.main, &customs).map_err(Into::into);
+            let result = apply_customs_update(writer.txn(), index.main, &customs).map_err(Into::into);
 
             (update_type, result, start.elapsed())
         }
@@ -237,7 +288,12 @@ pub fn update_task<'a, 'b>(
                 number: documents.len(),
             };
 
-            let result = apply_documents_addition(writer, index, documents);
+            let result = apply_documents_addition(writer, index, documents, duplicate_policy, hooks)
+                .map(|(stats, documents_warnings)| {
+                    indexed_stats = Some(stats);
+                    warnings = documents_warnings;
+                });
+            needs_prefix_rebuild = true;
 
             (update_type, result, start.elapsed())
         }
@@ -248,7 +304,12 @@ pub fn update_task<'a, 'b>(
                 number: documents.len(),
             };
 
-            let result = apply_documents_partial_addition(writer, index, documents);
+            let result = apply_documents_partial_addition(writer, index, documents, duplicate_policy, hooks)
+                .map(|(stats, documents_warnings)| {
+                    indexed_stats = Some(stats);
+                    warnings = documents_warnings;
+                });
+            needs_prefix_rebuild = true;
 
             (update_type, result, start.elapsed())
         }
@@ -259,7 +320,8 @@ pub fn update_task<'a, 'b>(
                 number: documents.len(),
             };
 
-            let result = apply_documents_deletion(writer, index, documents);
+            let result = apply_documents_deletion(writer.txn(), index, documents);
+            needs_prefix_rebuild = true;
 
             (update_type, result, start.elapsed())
         }
@@ -280,6 +342,17 @@ pub fn update_task<'a, 'b>(
         }
     };
 
+    let (result, duration) = match result {
+        Ok(()) if needs_prefix_rebuild => {
+            let result = index.main.put_short_prefixes_stale(writer.txn()).map_err(Into::into);
+            (result, duration)
+        }
+        result => (result, duration),
+    };
+
+    // the last modification date is committed along with the update
+    let result = result.and_then(|()| index.main.put_updated_at(writer.txn()).map_err(Into::into));
+
     debug!(
         "Processed update number {} {:?} {:?}",
         update_id, update_type, result
@@ -292,11 +365,48 @@ pub fn update_task<'a, 'b>(
         duration: duration.as_secs_f64(),
         enqueued_at,
         processed_at: Utc::now(),
+        conflicts_resolved: None,
+        merged_count: None,
+        first_id: None,
+        last_id: None,
+        indexed_stats,
+        warnings,
     };
 
     Ok(status)
 }
 
+/// Rebuilds the short prefixes cache if it has been marked as stale,
+/// returns `true` if the cache has been rebuilt.
+pub(crate) fn rebuild_short_prefixes(writer: &mut heed::RwTxn<MainT>, index: &store::Index) -> MResult<bool> {
+    if !index.main.short_prefixes_stale(writer)? {
+        return Ok(false);
+    }
+
+    compute_short_prefixes(writer, index)?;
+    index.main.delete_short_prefixes_stale(writer)?;
+
+    Ok(true)
+}
+
+/// The number of unique words above which the prefixes of one character are cached.
+const ONE_CHAR_PREFIXES_MIN_WORDS: usize = 50_000;
+
+/// The number of unique words above which the prefixes of two characters are cached.
+const TWO_CHARS_PREFIXES_MIN_WORDS: usize = 5_000;
+
+/// Returns the length of the shortest prefixes worth caching for an index with this
+/// number of unique words, the small indexes scan their words fst fast enough.
+fn shortest_cached_prefix_len(words_count: usize) -> usize {
+    if words_count > ONE_CHAR_PREFIXES_MIN_WORDS {
+        1
+    } else if words_count > TWO_CHARS_PREFIXES_MIN_WORDS {
+        2
+    } else {
+        3
+    }
+}
+
 fn compute_short_prefixes(writer: &mut heed::RwTxn<MainT>, index: &store::Index) -> MResult<()> {
     // retrieve the words fst to compute all those prefixes
     let words_fst = match index.main.words_fst(writer)? {
@@ -308,7 +418,10 @@ fn compute_short_prefixes(writer: &mut heed::RwTxn<MainT>, index: &store::Index)
     let pplc_store = index.prefix_postings_lists_cache;
     pplc_store.clear(writer)?;
 
-    for prefix_len in 1..=2 {
+    let shortest_prefix_len = shortest_cached_prefix_len(words_fst.len());
+    index.main.put_shortest_cached_prefix_len(writer, shortest_prefix_len as u64)?;
+
+    for prefix_len in shortest_prefix_len..=2 {
         // compute prefixes and store those in the PrefixPostingsListsCache store.
         let mut previous_prefix: Option<([u8; 4], Vec<_>)> = None;
         let mut stream = words_fst.into_stream();
@@ -358,5 +471,606 @@ fn compute_short_prefixes(writer: &mut heed::RwTxn<MainT>, index: &store::Index)
         }
     }
 
+    compute_word_prefix_pairs(writer, index, &words_fst)
+}
+
+/// Computes the documents where a word is directly followed by a word
+/// starting with a short prefix and stores those in the WordPrefixPairProximityDocIds store.
+fn compute_word_prefix_pairs(
+    writer: &mut heed::RwTxn<MainT>,
+    index: &store::Index,
+    words_fst: &fst::Set,
+) -> MResult<()>
+{
+    let pairs_store = index.word_prefix_pair_proximity_docids;
+    pairs_store.clear(writer)?;
+
+    // retrieve the positions of all the words in the documents
+    let mut words = Vec::new();
+    let mut positions = Vec::new();
+    let mut stream = words_fst.into_stream();
+    while let Some(input) = stream.next() {
+        if let Some(postings_list) = index.postings_lists.postings_list(writer, input)? {
+            let word_id = words.len();
+            words.push(input.to_vec());
+            for m in postings_list.matches.iter() {
+                positions.push((m.document_id, m.attribute, m.word_index, word_id));
+            }
+        }
+    }
+
+    positions.sort_unstable();
+
+    // many words can be found at the same position, we pair all the words
+    // of a position with all the words of the following one
+    let mut pairs: HashMap<(usize, [u8; 4]), Vec<DocumentId>> = HashMap::new();
+    let groups: Vec<_> = positions.linear_group_by_key(|(d, a, w, _)| (*d, *a, *w)).collect();
+    for window in groups.windows(2) {
+        let (docid, attribute, word_index, _) = window[0][0];
+        let (next_docid, next_attribute, next_word_index, _) = window[1][0];
+        if docid != next_docid || attribute != next_attribute || word_index as u32 + 1 != next_word_index as u32 {
+            continue;
+        }
+
+        for &(_, _, _, word_id) in window[0] {
+            for &(_, _, _, next_word_id) in window[1] {
+                let next_word = &words[next_word_id];
+                for prefix_len in 1..=2 {
+                    // like in the prefix postings lists cache a word
+                    // is not considered as a prefix of itself
+                    if next_word.len() <= prefix_len { continue }
+
+                    let mut prefix = [0; 4];
+                    prefix[..prefix_len].copy_from_slice(&next_word[..prefix_len]);
+                    pairs.entry((word_id, prefix)).or_insert_with(Vec::new).push(docid);
+                }
+            }
+        }
+    }
+
+    for ((word_id, prefix), docids) in pairs {
+        let docids = SetBuf::from_dirty(docids);
+        pairs_store.put_pair_docids(writer, &words[word_id], prefix, 1, &docids)?;
+    }
+
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::test_helpers::{add_documents, documents, with_index, with_index_options};
+
+    use crate::bucket_sort::SortResult;
+    use crate::database::DatabaseOptions;
+    use crate::Schema;
+
+    #[test]
+    fn short_prefixes_marked_stale_by_documents_updates() {
+        with_index(|writer, index| {
+            let documents = |name: &str| documents(serde_json::json!([{ "id": 123, "name": name }]));
+
+            assert!(!index.main.short_prefixes_stale(writer).unwrap());
+
+            let update = Update::documents_addition(documents("Marvin"));
+            let result = update_task(writer, index, 0, update, None).unwrap();
+            assert!(result.error.is_none());
+            assert!(index.main.short_prefixes_stale(writer).unwrap());
+
+            // the prefixes are not computed by the update itself
+            let prefix = [b'm', 0, 0, 0];
+            assert!(index.prefix_postings_lists_cache.prefix_postings_list(writer, prefix).unwrap().is_none());
+
+            assert!(rebuild_short_prefixes(writer, index).unwrap());
+            assert!(!index.main.short_prefixes_stale(writer).unwrap());
+
+            // the index is too small for its short prefixes to be cached
+            assert_eq!(index.main.shortest_cached_prefix_len(writer).unwrap(), 3);
+            assert!(index.prefix_postings_lists_cache.prefix_postings_list(writer, prefix).unwrap().is_none());
+
+            // nothing to do when the cache is up to date
+            assert!(!rebuild_short_prefixes(writer, index).unwrap());
+
+            let update = Update::documents_deletion(vec!["123".to_string()]);
+            let result = update_task(writer, index, 1, update, None).unwrap();
+            assert!(result.error.is_none());
+            assert!(index.main.short_prefixes_stale(writer).unwrap());
+        });
+    }
+
+    #[test]
+    fn word_prefix_pairs_computed_with_short_prefixes() {
+        with_index(|writer, index| {
+            let document = serde_json::json!({ "id": 123, "name": "Marvin the paranoid android" });
+            add_documents(writer, index, 0, serde_json::json!([document]));
+            assert!(rebuild_short_prefixes(writer, index).unwrap());
+
+            let docid = index.main.external_to_internal_docid(writer, "123").unwrap().unwrap();
+            let pairs = index.word_prefix_pair_proximity_docids;
+
+            let docids = pairs.pair_docids(writer, b"marvin", [b't', 0, 0, 0], 1).unwrap().unwrap();
+            assert_eq!(docids.as_slice(), &[docid]);
+            let docids = pairs.pair_docids(writer, b"the", [b'p', b'a', 0, 0], 1).unwrap().unwrap();
+            assert_eq!(docids.as_slice(), &[docid]);
+
+            // the words are not adjacent
+            assert!(pairs.pair_docids(writer, b"marvin", [b'p', 0, 0, 0], 1).unwrap().is_none());
+        });
+    }
+
+    #[test]
+    fn word_frequency_maintained_by_documents_updates() {
+        with_index(|writer, index| {
+            let document = serde_json::json!({ "id": 123, "name": "Marvin the paranoid Marvin" });
+            let documents = documents(serde_json::json!([document]));
+
+            let update = Update::documents_addition(documents.clone());
+            let result = update_task(writer, index, 0, update, None).unwrap();
+            assert!(result.error.is_none());
+
+            let stats = result.indexed_stats.unwrap();
+            assert_eq!(stats.new_words_added, stats.total_words_indexed);
+            assert_eq!(stats.posting_lists_updated, 0);
+            assert_eq!(stats.fst_size_before, 0);
+            assert!(stats.fst_size_after > 0);
+
+            assert_eq!(index.word_frequency.frequency(writer, "marvin").unwrap(), Some(2));
+            assert_eq!(index.word_frequency.frequency(writer, "paranoid").unwrap(), Some(1));
+            let most_common = index.word_frequency.most_common(writer, 1).unwrap();
+            assert_eq!(most_common, vec![("marvin".to_string(), 2)]);
+
+            // replacing the document must not count its words twice
+            let update = Update::documents_addition(documents);
+            let result = update_task(writer, index, 1, update, None).unwrap();
+            assert!(result.error.is_none());
+            assert_eq!(index.word_frequency.frequency(writer, "marvin").unwrap(), Some(2));
+
+            let update = Update::documents_deletion(vec!["123".to_string()]);
+            let result = update_task(writer, index, 2, update, None).unwrap();
+            assert!(result.error.is_none());
+            assert_eq!(index.word_frequency.frequency(writer, "marvin").unwrap(), None);
+        });
+    }
+
+    #[test]
+    fn languages_stop_words_ignored_while_indexing() {
+        with_index(|writer, index| {
+            let french = crate::stop_words::builtin_stop_words("fr").unwrap();
+            index.main.put_stop_words_for_language(writer, "fr", french).unwrap();
+
+            add_documents(writer, index, 0, serde_json::json!([
+                { "id": 1, "title": "le petit prince", "_language": "fr" },
+                { "id": 2, "title": "le mans" },
+            ]));
+
+            let french_docid = index.main.external_to_internal_docid(writer, "1").unwrap().unwrap();
+            let postings = index.postings_lists.postings_list(writer, b"le").unwrap().unwrap();
+            assert!(postings.matches.iter().all(|m| m.document_id != french_docid));
+            assert!(index.postings_lists.postings_list(writer, b"petit").unwrap().is_some());
+
+            let stop_words = index.main.all_stop_words_fst(writer).unwrap();
+            assert!(stop_words.contains("le"));
+        });
+    }
+
+    #[test]
+    fn resume_reindex_skips_checkpointed_documents() {
+        with_index(|writer, index| {
+            add_documents(writer, index, 0, serde_json::json!([
+                { "id": 1, "title": "alpha" },
+                { "id": 2, "title": "beta" },
+                { "id": 3, "title": "gamma" },
+            ]));
+
+            documents_addition::reindex_all_documents(writer, index).unwrap();
+            assert!(index.main.reindex_checkpoint(writer).unwrap().is_none());
+            assert_eq!(index.main.number_of_documents(writer).unwrap(), 3);
+
+            // simulate a reindexation interrupted after the first document
+            let first = index.main.external_to_internal_docid(writer, "1").unwrap().unwrap();
+            index.postings_lists.clear(writer).unwrap();
+            index.main.put_words_fst(writer, &fst::Set::default()).unwrap();
+            let checkpoint = crate::store::ReindexCheckpoint { last_processed_doc_id: u64::from(first.0) };
+            index.main.put_reindex_checkpoint(writer, checkpoint).unwrap();
+
+            resume_reindex_from_checkpoint(writer, index).unwrap();
+            assert!(index.main.reindex_checkpoint(writer).unwrap().is_none());
+
+            let words = index.main.words_fst(writer).unwrap().unwrap();
+            let expected: Vec<_> = ["1", "2", "3"]
+                .iter()
+                .map(|id| index.main.external_to_internal_docid(writer, id).unwrap().unwrap())
+                .map(|docid| docid > first)
+                .collect();
+            let indexed: Vec<_> = ["alpha", "beta", "gamma"].iter().map(|word| words.contains(word)).collect();
+            assert_eq!(indexed, expected);
+
+            // without a checkpoint there is nothing to resume
+            resume_reindex_from_checkpoint(writer, index).unwrap();
+        });
+    }
+
+    #[test]
+    fn datetime_ranked_fields() {
+        use meilisearch_schema::FieldType;
+        use ordered_float::OrderedFloat;
+        use crate::Number;
+
+        with_index(|writer, index| {
+            let mut schema = Schema::with_primary_key("id");
+            schema.set_ranked("released").unwrap();
+            schema.set_ranked("rank").unwrap();
+            index.main.put_schema(writer, &schema).unwrap();
+
+            add_documents(writer, index, 0, serde_json::json!([
+                { "id": 1, "released": "2024-01-15T10:00:00Z", "rank": 3 },
+                { "id": 2, "released": "not a date", "rank": 4 },
+            ]));
+
+            let schema = index.main.schema(writer).unwrap().unwrap();
+            let released = schema.id("released").unwrap();
+            let rank = schema.id("rank").unwrap();
+            assert_eq!(schema.field_type(released), Some(FieldType::DateTime));
+            assert_eq!(schema.field_type(rank), None);
+
+            let ranked_map = index.main.ranked_map(writer).unwrap().unwrap();
+            let first = index.main.external_to_internal_docid(writer, "1").unwrap().unwrap();
+            let second = index.main.external_to_internal_docid(writer, "2").unwrap().unwrap();
+            assert_eq!(ranked_map.get(first, released), Some(Number::Float(OrderedFloat(1_705_312_800.0))));
+            assert_eq!(ranked_map.get(second, released), Some(Number::Null));
+        });
+    }
+
+    #[test]
+    fn max_documents_limit_enforced_on_addition() {
+        with_index(|writer, index| {
+            index.main.put_max_documents(writer, 2).unwrap();
+
+            add_documents(writer, index, 0, serde_json::json!([
+                { "id": 1, "title": "alpha" },
+                { "id": 2, "title": "beta" },
+            ]));
+
+            // replacing a document does not count against the limit
+            add_documents(writer, index, 1, serde_json::json!([{ "id": 1, "title": "gamma" }]));
+
+            let update = Update::documents_addition(documents(serde_json::json!([
+                { "id": 2, "title": "delta" },
+                { "id": 3, "title": "epsilon" },
+            ])));
+            let result = update_task(writer, index, 2, update, None).unwrap();
+            assert!(result.error.unwrap().contains("limit of 2"));
+
+            assert_eq!(index.main.number_of_documents(writer).unwrap(), 2);
+            assert!(index.main.external_to_internal_docid(writer, "3").unwrap().is_none());
+            let words = index.main.words_fst(writer).unwrap().unwrap();
+            assert!(words.contains("beta"));
+            assert!(!words.contains("delta"));
+        });
+    }
+
+    #[test]
+    fn boolean_fields_stored_as_bytes() {
+        use meilisearch_schema::FieldType;
+        use crate::Filter;
+
+        with_index(|writer, index| {
+            add_documents(writer, index, 0, serde_json::json!([
+                { "id": 1, "available": true },
+                { "id": 2, "available": false },
+            ]));
+
+            let schema = index.main.schema(writer).unwrap().unwrap();
+            let available = schema.id("available").unwrap();
+            assert_eq!(schema.field_type(available), Some(FieldType::Bool));
+
+            let first = index.main.external_to_internal_docid(writer, "1").unwrap().unwrap();
+            let second = index.main.external_to_internal_docid(writer, "2").unwrap().unwrap();
+
+            let bytes = index.document_attribute_bytes(writer, first, available).unwrap();
+            assert_eq!(bytes, Some(&[crate::serde::TRUE_FIELD_BYTE][..]));
+            let value: Option<Value> = index.document_attribute(writer, second, available).unwrap();
+            assert_eq!(value, Some(Value::Bool(false)));
+
+            let filter = Filter::parse("available = true", &schema).unwrap();
+            assert!(filter.test(writer, index, first).unwrap());
+            assert!(!filter.test(writer, index, second).unwrap());
+
+            let filter = Filter::parse("available != true", &schema).unwrap();
+            assert!(!filter.test(writer, index, first).unwrap());
+            assert!(filter.test(writer, index, second).unwrap());
+        });
+    }
+
+    #[test]
+    fn rename_attribute_without_reindex() {
+        use crate::settings::RankingRule;
+        use crate::store::SchemaChangeKind;
+
+        with_index(|writer, index| {
+            add_documents(writer, index, 0, serde_json::json!([
+                { "id": 1, "title": "hello world", "rank": 2 },
+                { "id": 2, "title": "hello", "rank": 1 },
+            ]));
+
+            index.main.put_ranking_rules(writer, &[RankingRule::Typo, RankingRule::Desc("rank".to_string())]).unwrap();
+            index.main.put_distinct_attribute(writer, "title").unwrap();
+            let postings_before = index.postings_lists.postings_list(writer, b"hello").unwrap().unwrap().matches.into_owned();
+
+            apply_schema_rename(writer, index, "title", "name").unwrap();
+            apply_schema_rename(writer, index, "rank", "score").unwrap();
+
+            let schema = index.main.schema(writer).unwrap().unwrap();
+            assert!(schema.id("title").is_none());
+            assert!(schema.is_indexed(schema.id("name").unwrap()).is_some());
+            assert!(schema.is_indexed(schema.id("score").unwrap()).is_some());
+
+            let docid = index.main.external_to_internal_docid(writer, "1").unwrap().unwrap();
+            let document: Option<IndexMap<String, Value>> = index.document(writer, None, docid).unwrap();
+            assert_eq!(document.unwrap()["name"], serde_json::json!("hello world"));
+
+            let postings_after = index.postings_lists.postings_list(writer, b"hello").unwrap().unwrap().matches.into_owned();
+            assert_eq!(postings_before, postings_after);
+
+            let ranking_rules = index.main.ranking_rules(writer).unwrap().unwrap();
+            assert_eq!(ranking_rules, vec![RankingRule::Typo, RankingRule::Desc("score".to_string())]);
+            assert_eq!(index.main.distinct_attribute(writer).unwrap().as_deref(), Some("name"));
+
+            let changelog = index.main.schema_changelog(writer).unwrap();
+            let (_, last_change) = changelog.last().unwrap();
+            assert_eq!(last_change.kind, SchemaChangeKind::AttributeRenamed { from: "rank".to_string(), to: "score".to_string() });
+
+            assert!(apply_schema_rename(writer, index, "name", "id").is_err());
+            assert!(apply_schema_rename(writer, index, "title", "label").is_err());
+        });
+    }
+
+    #[test]
+    fn schema_changes_recorded_with_their_actor() {
+        use crate::settings::UpdateState;
+        use crate::store::SchemaChangeKind;
+
+        with_index(|writer, index| {
+            let settings = SettingsUpdate {
+                searchable_attributes: UpdateState::Update(vec!["title".to_string()]),
+                ..SettingsUpdate::default()
+            };
+            let settings = settings.with_actor(Some("marvin".to_string()));
+            let result = update_task(writer, index, 0, Update::settings(settings), None).unwrap();
+            assert!(result.error.is_none());
+
+            let changelog = index.main.schema_changelog(writer).unwrap();
+            let (_, last_change) = changelog.last().unwrap();
+            assert_matches!(last_change.kind, SchemaChangeKind::SearchableAttributesUpdated { .. });
+            assert_eq!(last_change.by.as_deref(), Some("marvin"));
+
+            // the changes made by the documents additions have no actor
+            add_documents(writer, index, 1, serde_json::json!([{ "id": 1, "name": "Marvin" }]));
+            let new_changes = index.main.schema_changelog(writer).unwrap().split_off(changelog.len());
+            assert!(new_changes.iter().any(|(_, change)| matches!(change.kind, SchemaChangeKind::FieldsAdded { .. })));
+            assert!(new_changes.iter().all(|(_, change)| change.by.is_none()));
+        });
+    }
+
+    #[test]
+    fn documents_addition_reports_type_conflicts() {
+        with_index(|writer, index| {
+            let mut schema = Schema::with_primary_key("id");
+            schema.set_ranked("price").unwrap();
+            index.main.put_schema(writer, &schema).unwrap();
+
+            let result = add_documents(writer, index, 0, serde_json::json!([
+                { "id": 1, "price": 10 },
+                { "id": 2, "price": "12.5" },
+                { "id": 3, "price": "cheap" },
+                { "id": 4, "price": null },
+            ]));
+            assert_eq!(result.warnings, vec![Warning::TypeConflict {
+                field: "price".to_string(),
+                expected: "number".to_string(),
+                actual: "string".to_string(),
+                document_user_id: "3".to_string(),
+            }]);
+
+            let value = serde_json::to_value(&result).unwrap();
+            assert_eq!(value["warnings"][0]["type"], "typeConflict");
+            assert_eq!(value["warnings"][0]["documentUserId"], "3");
+        });
+    }
+
+    #[test]
+    fn documents_addition_reports_token_limits() {
+        use crate::raw_indexer::TokenLimits;
+
+        let token_limits = TokenLimits { max_tokens_per_field: 2, max_tokens_per_document: 10 };
+        let options = DatabaseOptions { token_limits, ..DatabaseOptions::default() };
+        with_index_options(options, |writer, index| {
+            let result = add_documents(writer, index, 0, serde_json::json!([{ "id": 1, "title": "hello big world", "tag": "blue" }]));
+            assert_eq!(result.warnings, vec![Warning::TokenLimitExceeded {
+                field: "title".to_string(),
+                document_user_id: "1".to_string(),
+                limit: "field".to_string(),
+                max_tokens: 2,
+            }]);
+
+            assert!(index.postings_lists.postings_list(writer, b"big").unwrap().is_some());
+            assert!(index.postings_lists.postings_list(writer, b"world").unwrap().is_none());
+            assert!(index.postings_lists.postings_list(writer, b"blue").unwrap().is_some());
+        });
+    }
+
+    #[test]
+    fn encrypted_fields_are_stored_encrypted_and_not_indexed() {
+        let options = DatabaseOptions { field_encryption_key: Some([7; 32]), ..DatabaseOptions::default() };
+        with_index_options(options, |writer, index| {
+            let mut schema = Schema::with_primary_key("id");
+            schema.set_encrypted("secret").unwrap();
+            index.main.put_schema(writer, &schema).unwrap();
+
+            add_documents(writer, index, 0, serde_json::json!([{ "id": 1, "title": "hello", "secret": "password" }]));
+
+            let schema = index.main.schema(writer).unwrap().unwrap();
+            let secret = schema.id("secret").unwrap();
+            let docid = index.main.external_to_internal_docid(writer, "1").unwrap().unwrap();
+
+            let bytes = index.document_attribute_bytes(writer, docid, secret).unwrap().unwrap();
+            assert!(crate::serde::is_encrypted_field(bytes));
+            assert!(!bytes.windows(8).any(|w| w == b"password"));
+
+            // the equal values can be compared once decrypted, the distinct attribute hashes them
+            let bytes = index.decrypted_document_attribute_bytes(writer, docid, secret).unwrap().unwrap();
+            assert_eq!(crate::serde::field_json_bytes(&bytes), br#""password""#);
+
+            let document: Option<IndexMap<String, Value>> = index.document(writer, None, docid).unwrap();
+            assert_eq!(document.unwrap()["secret"], "password");
+
+            assert!(index.postings_lists.postings_list(writer, b"password").unwrap().is_none());
+            assert!(index.postings_lists.postings_list(writer, b"hello").unwrap().is_some());
+        });
+    }
+
+    #[test]
+    fn short_prefixes_cached_depending_on_the_number_of_words() {
+        assert_eq!(shortest_cached_prefix_len(0), 3);
+        assert_eq!(shortest_cached_prefix_len(TWO_CHARS_PREFIXES_MIN_WORDS), 3);
+        assert_eq!(shortest_cached_prefix_len(TWO_CHARS_PREFIXES_MIN_WORDS + 1), 2);
+        assert_eq!(shortest_cached_prefix_len(ONE_CHAR_PREFIXES_MIN_WORDS), 2);
+        assert_eq!(shortest_cached_prefix_len(ONE_CHAR_PREFIXES_MIN_WORDS + 1), 1);
+
+        with_index(|writer, index| {
+            // as many unique words as needed for the prefixes of two characters to be cached
+            let words = (0..TWO_CHARS_PREFIXES_MIN_WORDS + 1).map(|i| {
+                let letters: String = [i / 676, i / 26 % 26, i % 26].iter().map(|l| (b'a' + *l as u8) as char).collect();
+                serde_json::json!({ "id": i, "name": format!("wo{}", letters) })
+            });
+
+            let update = Update::documents_addition(documents(Value::Array(words.collect())));
+            let result = update_task(writer, index, 0, update, None).unwrap();
+            assert!(result.error.is_none());
+            assert!(rebuild_short_prefixes(writer, index).unwrap());
+
+            assert_eq!(index.main.shortest_cached_prefix_len(writer).unwrap(), 2);
+            let pplc = index.prefix_postings_lists_cache;
+            assert!(pplc.prefix_postings_list(writer, [b'w', b'o', 0, 0]).unwrap().is_some());
+            assert!(pplc.prefix_postings_list(writer, [b'w', 0, 0, 0]).unwrap().is_none());
+
+            // the prefixes of one character are searched in the words fst
+            let mut builder = index.query_builder();
+            builder.with_min_prefix_len(1);
+            let SortResult { documents, .. } = builder.query(writer, "w", 0..20).unwrap();
+            assert_eq!(documents.len(), 20);
+        });
+    }
+
+    #[test]
+    fn duplicate_documents_handled_by_policy() {
+        with_index(|writer, index| {
+            let documents = || documents(serde_json::json!([
+                { "id": 1, "title": "alpha", "tags": { "color": "red", "size": "big" } },
+                { "id": 1, "tags": { "size": null, "shape": "round" }, "rank": 3 },
+            ]));
+
+            let update = Update::documents_addition(documents()).with_duplicate_policy(DuplicatePolicy::Reject);
+            let result = update_task(writer, index, 0, update, None).unwrap();
+            assert!(result.error.unwrap().contains("the document id 1 is present more than once"));
+            assert_eq!(index.main.number_of_documents(writer).unwrap(), 0);
+
+            let update = Update::documents_addition(documents());
+            let result = update_task(writer, index, 1, update, None).unwrap();
+            assert!(result.error.is_none());
+            assert_eq!(index.main.number_of_documents(writer).unwrap(), 1);
+            assert_eq!(index.main.internal_docids(writer).unwrap().len(), 1);
+
+            let docid = index.main.external_to_internal_docid(writer, "1").unwrap().unwrap();
+            let document: HashMap<String, Value> = index.document(writer, None, docid).unwrap().unwrap();
+            assert_eq!(document.get("title"), None);
+            assert_eq!(document.get("rank"), Some(&serde_json::json!(3)));
+
+            let update = Update::documents_addition(documents()).with_duplicate_policy(DuplicatePolicy::Merge);
+            let result = update_task(writer, index, 2, update, None).unwrap();
+            assert!(result.error.is_none());
+            assert_eq!(index.main.number_of_documents(writer).unwrap(), 1);
+
+            let docid = index.main.external_to_internal_docid(writer, "1").unwrap().unwrap();
+            let document: HashMap<String, Value> = index.document(writer, None, docid).unwrap().unwrap();
+            assert_eq!(document.get("title"), Some(&serde_json::json!("alpha")));
+            assert_eq!(document.get("tags"), Some(&serde_json::json!({ "color": "red", "shape": "round" })));
+            assert_eq!(document.get("rank"), Some(&serde_json::json!(3)));
+        });
+    }
+
+    #[test]
+    fn deleted_documents_ids_recycled() {
+        with_index(|writer, index| {
+            let documents = |ids: &[u32]| -> Vec<IndexMap<String, Value>> {
+                ids.iter().map(|id| {
+                    let mut document = IndexMap::new();
+                    document.insert("id".to_string(), serde_json::json!(id));
+                    document.insert("title".to_string(), serde_json::json!(format!("title {}", id)));
+                    document
+                }).collect()
+            };
+
+            let update = Update::documents_addition(documents(&[1, 2, 3, 4, 5]));
+            let result = update_task(writer, index, 0, update, None).unwrap();
+            assert!(result.error.is_none());
+
+            let update = Update::documents_deletion(vec!["2".to_string(), "4".to_string()]);
+            let result = update_task(writer, index, 1, update, None).unwrap();
+            assert!(result.error.is_none());
+            assert_eq!(index.free_list.ids(writer).unwrap(), vec![DocumentId(1), DocumentId(3)]);
+
+            // the id of a replaced document is not freed
+            let update = Update::documents_addition(documents(&[5]));
+            let result = update_task(writer, index, 2, update, None).unwrap();
+            assert!(result.error.is_none());
+            assert_eq!(index.free_list.ids(writer).unwrap(), vec![DocumentId(1), DocumentId(3)]);
+
+            let update = Update::documents_addition(documents(&[6, 7, 8]));
+            let result = update_task(writer, index, 3, update, None).unwrap();
+            assert!(result.error.is_none());
+            assert!(index.free_list.ids(writer).unwrap().is_empty());
+
+            let ids: Vec<_> = index.main.external_to_internal_docids(writer, &["6", "7", "8"])
+                .unwrap()
+                .into_iter()
+                .flatten()
+                .collect();
+            assert_eq!(ids, vec![DocumentId(1), DocumentId(3), DocumentId(5)]);
+        });
+    }
+
+    #[test]
+    fn normalization_mode_reindexes_documents() {
+        use crate::settings::UpdateState;
+        use crate::NormalizationMode;
+
+        with_index(|writer, index| {
+            // the new indexes use NFKC, the ones created before this setting have none
+            assert_eq!(index.main.normalization_mode(writer).unwrap(), Some(NormalizationMode::Nfkc));
+            index.main.delete_normalization_mode(writer).unwrap();
+
+            let document = serde_json::json!({ "id": 1, "title": "café \u{fb01}le" });
+            add_documents(writer, index, 0, serde_json::json!([document]));
+
+            // the words are transliterated without a normalization mode
+            let words = index.main.words_fst(writer).unwrap().unwrap();
+            assert!(words.contains("café"));
+            assert!(words.contains("cafe"));
+            assert!(words.contains("file"));
+
+            let settings = SettingsUpdate {
+                normalization_mode: UpdateState::Update(NormalizationMode::Nfkc),
+                ..SettingsUpdate::default()
+            };
+            let result = update_task(writer, index, 1, Update::settings(settings), None).unwrap();
+            assert!(result.error.is_none());
+
+            let words = index.main.words_fst(writer).unwrap().unwrap();
+            assert!(words.contains("café"));
+            assert!(!words.contains("cafe"));
+            assert!(words.contains("file"));
+            assert!(!words.contains("\u{fb01}le"));
+        });
+    }
+}
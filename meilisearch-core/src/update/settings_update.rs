@@ -9,7 +9,7 @@ use crate::database::{MainT, UpdateT};
 use crate::settings::{UpdateState, SettingsUpdate, RankingRule};
 use crate::update::documents_addition::reindex_all_documents;
 use crate::update::{next_update_id, Update};
-use crate::{store, MResult, Error};
+use crate::{store, MResult, Error, NormalizationMode, UpdateWriter};
 
 pub fn push_settings_update(
     writer: &mut heed::RwTxn<UpdateT>,
@@ -25,11 +25,26 @@ pub fn push_settings_update(
     Ok(last_update_id)
 }
 
-pub fn apply_settings_update(
-    writer: &mut heed::RwTxn<MainT>,
+/// Applies the settings and reindexes the documents if the settings require it,
+/// the reindexation can commit the writer by chunks.
+pub fn apply_settings_update<'a, 'b, W: UpdateWriter<'b>>(
+    writer: &'a mut W,
     index: &store::Index,
     settings: SettingsUpdate,
 ) -> MResult<()> {
+    if apply_settings(writer.txn(), index, settings)? {
+        reindex_all_documents(writer, index)?;
+    }
+
+    Ok(())
+}
+
+/// Returns whether the documents must be reindexed.
+fn apply_settings(
+    writer: &mut heed::RwTxn<MainT>,
+    index: &store::Index,
+    settings: SettingsUpdate,
+) -> MResult<bool> {
     let mut must_reindex = false;
 
     let mut schema = match index.main.schema(writer)? {
@@ -42,22 +57,15 @@ pub fn apply_settings_update(
         }
     };
 
+    apply_schema_settings(&mut schema, &settings)?;
+
     match settings.ranking_rules {
         UpdateState::Update(v) => {
-            let ranked_field: Vec<&str> = v.iter().filter_map(RankingRule::field).collect();
-            schema.update_ranked(&ranked_field)?;
-            for name in ranked_field {
-                if schema.accept_new_fields() {
-                    schema.set_indexed(name.as_ref())?;
-                    schema.set_displayed(name.as_ref())?;
-                }
-            }
             index.main.put_ranking_rules(writer, &v)?;
             must_reindex = true;
         },
         UpdateState::Clear => {
             index.main.delete_ranking_rules(writer)?;
-            schema.clear_ranked();
             must_reindex = true;
         },
         UpdateState::Nothing => (),
@@ -73,35 +81,50 @@ pub fn apply_settings_update(
         UpdateState::Nothing => (),
     }
 
-    match settings.accept_new_fields {
-        UpdateState::Update(v) => {
-            schema.set_accept_new_fields(v);
+    match settings.attribute_weights {
+        UpdateState::Update(weights) => {
+            index.main.put_attribute_weights(writer, &weights)?;
         },
         UpdateState::Clear => {
-            schema.set_accept_new_fields(true);
+            index.main.delete_attribute_weights(writer)?;
         },
         UpdateState::Nothing => (),
     }
 
-    match settings.searchable_attributes.clone() {
-        UpdateState::Update(v) => {
-            schema.update_indexed(v)?;
-            must_reindex = true;
+    match settings.max_documents {
+        UpdateState::Update(limit) => {
+            index.main.put_max_documents(writer, limit)?;
         },
         UpdateState::Clear => {
-            schema.set_all_fields_as_indexed();
-            must_reindex = true;
+            index.main.delete_max_documents(writer)?;
         },
         UpdateState::Nothing => (),
     }
-    match settings.displayed_attributes.clone() {
-        UpdateState::Update(v) => schema.update_displayed(v)?,
+
+    match settings.normalization_mode {
+        UpdateState::Update(mode) => {
+            index.main.put_normalization_mode(writer, mode)?;
+            must_reindex = true;
+        },
+        // the indexes are reset to the mode of the new indexes
         UpdateState::Clear => {
-            schema.set_all_fields_as_displayed();
+            index.main.put_normalization_mode(writer, NormalizationMode::Nfkc)?;
+            must_reindex = true;
         },
         UpdateState::Nothing => (),
     }
 
+    match settings.searchable_attributes {
+        UpdateState::Update(_) | UpdateState::Clear => must_reindex = true,
+        UpdateState::Nothing => (),
+    }
+
+    // the documents fields are encrypted or decrypted by the reindexation
+    match settings.encrypted_attributes {
+        UpdateState::Update(_) | UpdateState::Clear => must_reindex = true,
+        UpdateState::Nothing => (),
+    }
+
     match settings.attributes_for_faceting {
         UpdateState::Update(attrs) => {
             apply_attributes_for_faceting_update(writer, index, &mut schema, &attrs)?;
@@ -114,7 +137,7 @@ pub fn apply_settings_update(
         UpdateState::Nothing => (),
     }
 
-    index.main.put_schema(writer, &schema)?;
+    index.main.put_schema_by(writer, &schema, settings.actor.as_deref())?;
 
     match settings.stop_words {
         UpdateState::Update(stop_words) => {
@@ -136,8 +159,95 @@ pub fn apply_settings_update(
         UpdateState::Nothing => (),
     }
 
-    if must_reindex {
-        reindex_all_documents(writer, index)?;
+    Ok(must_reindex)
+}
+
+/// Renames an attribute without reindexing the documents, the documents fields and the
+/// postings lists refer to the attributes by their ids which are kept by the rename.
+/// The settings referring to the attribute by its name are updated.
+pub fn apply_schema_rename(
+    writer: &mut heed::RwTxn<MainT>,
+    index: &store::Index,
+    old_name: &str,
+    new_name: &str,
+) -> MResult<()> {
+    let schema = index.main.schema(writer)?.ok_or(Error::SchemaMissing)?;
+    let schema = schema.rename_attribute(old_name, new_name)?;
+    index.main.put_schema(writer, &schema)?;
+
+    if let Some(ranking_rules) = index.main.ranking_rules(writer)? {
+        let ranking_rules: Vec<_> = ranking_rules
+            .into_iter()
+            .map(|rule| match rule {
+                RankingRule::Asc(name) if name == old_name => RankingRule::Asc(new_name.to_string()),
+                RankingRule::Desc(name) if name == old_name => RankingRule::Desc(new_name.to_string()),
+                rule => rule,
+            })
+            .collect();
+        index.main.put_ranking_rules(writer, &ranking_rules)?;
+    }
+
+    if index.main.distinct_attribute(writer)?.as_deref() == Some(old_name) {
+        index.main.put_distinct_attribute(writer, new_name)?;
+    }
+
+    if let Some(mut weights) = index.main.attribute_weights(writer)? {
+        if let Some(weight) = weights.remove(old_name) {
+            weights.insert(new_name.to_string(), weight);
+            index.main.put_attribute_weights(writer, &weights)?;
+        }
+    }
+
+    if let Some(mut frequencies) = index.main.fields_frequency(writer)? {
+        if let Some(frequency) = frequencies.remove(old_name) {
+            frequencies.insert(new_name.to_string(), frequency);
+            index.main.put_fields_frequency(writer, &frequencies)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Applies the settings that only modify the schema: the ranked, searchable
+/// and displayed attributes and whether new fields are accepted.
+pub fn apply_schema_settings(schema: &mut Schema, settings: &SettingsUpdate) -> MResult<()> {
+    match &settings.ranking_rules {
+        UpdateState::Update(v) => {
+            let ranked_field: Vec<&str> = v.iter().filter_map(RankingRule::field).collect();
+            schema.update_ranked(&ranked_field)?;
+            for name in ranked_field {
+                if schema.accept_new_fields() {
+                    schema.set_indexed(name.as_ref())?;
+                    schema.set_displayed(name.as_ref())?;
+                }
+            }
+        },
+        UpdateState::Clear => schema.clear_ranked(),
+        UpdateState::Nothing => (),
+    }
+
+    match settings.accept_new_fields {
+        UpdateState::Update(v) => schema.set_accept_new_fields(v),
+        UpdateState::Clear => schema.set_accept_new_fields(true),
+        UpdateState::Nothing => (),
+    }
+
+    match &settings.searchable_attributes {
+        UpdateState::Update(v) => schema.update_indexed(v.clone())?,
+        UpdateState::Clear => schema.set_all_fields_as_indexed(),
+        UpdateState::Nothing => (),
+    }
+
+    match &settings.displayed_attributes {
+        UpdateState::Update(v) => schema.update_displayed(v)?,
+        UpdateState::Clear => schema.set_all_fields_as_displayed(),
+        UpdateState::Nothing => (),
+    }
+
+    match &settings.encrypted_attributes {
+        UpdateState::Update(v) => schema.update_encrypted(v)?,
+        UpdateState::Clear => schema.clear_encrypted(),
+        UpdateState::Nothing => (),
     }
 
     Ok(())
@@ -0,0 +1,75 @@
+//! The fixture of the tests indexing documents in a temporary database.
+
+use indexmap::IndexMap;
+use meilisearch_schema::Schema;
+use serde_json::Value;
+
+use super::{update_task, ProcessedUpdateResult, Update};
+use crate::database::{Database, DatabaseOptions, MainT};
+use crate::store::Index;
+
+/// Converts a JSON array of objects into the documents of an addition.
+pub fn documents(json: Value) -> Vec<IndexMap<String, Value>> {
+    match json {
+        Value::Array(documents) => documents.into_iter().map(|document| match document {
+            Value::Object(map) => map.into_iter().collect(),
+            _ => unreachable!(),
+        }).collect(),
+        _ => unreachable!(),
+    }
+}
+
+/// Runs `f` with a write transaction over a new index whose primary key is `id`,
+/// the transaction is aborted once `f` returns.
+pub fn with_index<F>(f: F)
+where
+    F: FnOnce(&mut heed::RwTxn<MainT>, &Index),
+{
+    with_index_options(DatabaseOptions::default(), f)
+}
+
+/// Same as [`with_index`], the database being opened with the given options.
+///
+/// [`with_index`]: fn.with_index.html
+pub fn with_index_options<F>(options: DatabaseOptions, f: F)
+where
+    F: FnOnce(&mut heed::RwTxn<MainT>, &Index),
+{
+    let dir = tempfile::tempdir().unwrap();
+
+    let database = Database::open_or_create(dir.path(), options).unwrap();
+    let index = database.create_index("test").unwrap();
+
+    let mut writer = database.main_write_txn().unwrap();
+    index.main.put_schema(&mut writer, &Schema::with_primary_key("id")).unwrap();
+
+    f(&mut writer, &index);
+
+    writer.abort();
+}
+
+/// Adds the documents of a JSON array to the index, the addition must succeed.
+pub fn add_documents(
+    writer: &mut heed::RwTxn<MainT>,
+    index: &Index,
+    update_id: u64,
+    json: Value,
+) -> ProcessedUpdateResult {
+    let update = Update::documents_addition(documents(json));
+    let result = update_task(writer, index, update_id, update, None).unwrap();
+    assert!(result.error.is_none(), "{:?}", result.error);
+    result
+}
+
+/// Deletes the documents of the given ids from the index, the deletion must succeed.
+pub fn delete_documents(
+    writer: &mut heed::RwTxn<MainT>,
+    index: &Index,
+    update_id: u64,
+    ids: &[&str],
+) -> ProcessedUpdateResult {
+    let update = Update::documents_deletion(ids.iter().map(ToString::to_string).collect());
+    let result = update_task(writer, index, update_id, update, None).unwrap();
+    assert!(result.error.is_none(), "{:?}", result.error);
+    result
+}
@@ -1,17 +1,29 @@
 use std::collections::HashMap;
 use std::ops::Deref;
-use std::sync::Arc;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
 
 use chrono::{DateTime, Utc};
 use heed::types::{SerdeBincode, Str};
 use log::error;
 use meilisearch_core::{Database, DatabaseOptions, Error as MError, MResult, MainT, UpdateT};
+use meilisearch_core::{ProcessedUpdateResult, UpdateType};
+use prometheus::{Histogram, HistogramOpts};
+use serde::{Deserialize, Serialize};
 use sha2::Digest;
 use sysinfo::Pid;
+use tokio::sync::Semaphore;
 
+use crate::helpers::fan_out::RemoteShard;
+use crate::helpers::search_cache::{LruSearchCache, RedisSearchCache, TieredSearchCache};
+use crate::helpers::{JwtValidator, SearchCache};
 use crate::index_update_callback;
-use crate::option::Opt;
+use crate::option::{CacheBackend, Opt};
 
+const API_KEYS_KEY: &str = "api-keys";
 const LAST_UPDATE_KEY: &str = "last-update";
 
 type SerdeDatetime = SerdeBincode<DateTime<Utc>>;
@@ -33,9 +45,49 @@ impl Deref for Data {
 pub struct DataInner {
     pub db: Arc<Database>,
     pub db_path: String,
-    pub api_keys: ApiKeys,
+    pub api_keys: Arc<RwLock<ApiKeys>>,
     pub server_pid: Pid,
     pub http_payload_size_limit: usize,
+    pub max_request_body_size_bytes: usize,
+    pub max_index_count: Option<usize>,
+    pub metrics: Arc<Metrics>,
+    pub metrics_auth_key: Option<String>,
+    pub prometheus_metrics: bool,
+    pub jwt: Option<Arc<JwtValidator>>,
+    pub base_path: String,
+    pub api_version_prefix: String,
+    pub min_prefix_len: usize,
+    pub readers_limiter: Option<Arc<IndexReadersLimiter>>,
+    pub search_cache: Option<Arc<dyn SearchCache>>,
+    pub remote_shards: Vec<RemoteShard>,
+}
+
+/// Limits the number of requests reading each index concurrently, every one
+/// of them holds a reader slot of the LMDB environment shared by all the indexes.
+pub struct IndexReadersLimiter {
+    max_readers: usize,
+    semaphores: Mutex<HashMap<String, Arc<Semaphore>>>,
+}
+
+impl IndexReadersLimiter {
+    pub fn new(max_readers: usize) -> IndexReadersLimiter {
+        IndexReadersLimiter { max_readers, semaphores: Mutex::new(HashMap::new()) }
+    }
+
+    /// Returns the semaphore a request must acquire a permit of before reading the index.
+    pub fn semaphore(&self, index_uid: &str) -> Arc<Semaphore> {
+        let mut semaphores = self.semaphores.lock().unwrap();
+        let max_readers = self.max_readers;
+        semaphores
+            .entry(index_uid.to_string())
+            .or_insert_with(|| Arc::new(Semaphore::new(max_readers)))
+            .clone()
+    }
+
+    /// Forgets the semaphore of a deleted index.
+    pub fn remove(&self, index_uid: &str) {
+        self.semaphores.lock().unwrap().remove(index_uid);
+    }
 }
 
 #[derive(Clone)]
@@ -43,23 +95,222 @@ pub struct ApiKeys {
     pub public: Option<String>,
     pub private: Option<String>,
     pub master: Option<String>,
+    /// The keys replaced by a rotation, they are accepted until they expire.
+    pub rotated: Vec<RotatedKey>,
+    /// The random seeds the rotated keys are derived from along with the master key.
+    private_seed: Option<String>,
+    public_seed: Option<String>,
+}
+
+/// The keys that can be rotated, the master key is given by the options.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum KeyKind {
+    Private,
+    Public,
+}
+
+impl FromStr for KeyKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<KeyKind, String> {
+        match s {
+            "private" => Ok(KeyKind::Private),
+            "public" => Ok(KeyKind::Public),
+            _ => Err(format!("key {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RotatedKey {
+    pub kind: KeyKind,
+    /// Only the digest of the replaced key is kept, it is compared to the digest of the given keys.
+    pub digest: String,
+    pub valid_until: DateTime<Utc>,
+}
+
+/// The state of the keys persisted once one of them has been rotated, it is only used
+/// with the master key it has been generated for. No key is stored, the current keys
+/// are derived from the master key and the seeds.
+#[derive(Serialize, Deserialize)]
+struct StoredApiKeys {
+    master_digest: String,
+    private_seed: Option<String>,
+    public_seed: Option<String>,
+    rotated: Vec<RotatedKey>,
+}
+
+fn key_digest(key: &str) -> String {
+    format!("{:x}", sha2::Sha256::digest(key.as_bytes()))
+}
+
+fn derive_key(master_key: &str, kind: &str, seed: Option<&str>) -> String {
+    match seed {
+        Some(seed) => key_digest(&format!("{}-{}-{}", master_key, kind, seed)),
+        None => key_digest(&format!("{}-{}", master_key, kind)),
+    }
 }
 
 impl ApiKeys {
+    pub fn new(master: Option<String>) -> ApiKeys {
+        ApiKeys {
+            public: None,
+            private: None,
+            master,
+            rotated: Vec::new(),
+            private_seed: None,
+            public_seed: None,
+        }
+    }
+
     pub fn generate_missing_api_keys(&mut self) {
         if let Some(master_key) = &self.master {
             if self.private.is_none() {
-                let key = format!("{}-private", master_key);
-                let sha = sha2::Sha256::digest(key.as_bytes());
-                self.private = Some(format!("{:x}", sha));
+                self.private = Some(derive_key(master_key, "private", self.private_seed.as_deref()));
             }
             if self.public.is_none() {
-                let key = format!("{}-public", master_key);
-                let sha = sha2::Sha256::digest(key.as_bytes());
-                self.public = Some(format!("{:x}", sha));
+                self.public = Some(derive_key(master_key, "public", self.public_seed.as_deref()));
             }
         }
     }
+
+    pub fn is_master(&self, key: &str) -> bool {
+        self.master.as_deref() == Some(key)
+    }
+
+    /// Whether the key grants the rights of the private key, the master key grants all of them.
+    pub fn is_private(&self, key: &str) -> bool {
+        self.is_master(key) || self.private.as_deref() == Some(key) || self.is_rotated(KeyKind::Private, key)
+    }
+
+    pub fn is_public(&self, key: &str) -> bool {
+        self.is_private(key) || self.public.as_deref() == Some(key) || self.is_rotated(KeyKind::Public, key)
+    }
+
+    fn is_rotated(&self, kind: KeyKind, key: &str) -> bool {
+        if self.rotated.is_empty() {
+            return false;
+        }
+
+        let now = Utc::now();
+        let digest = key_digest(key);
+        self.rotated.iter().any(|rotated| rotated.kind == kind && rotated.digest == digest && rotated.valid_until > now)
+    }
+
+    /// Replaces the key by a new one derived from a random seed, the replaced key stays valid
+    /// during the grace period. Returns the new key along with the replaced one, if there was one.
+    pub fn rotate(&mut self, kind: KeyKind, grace_period: chrono::Duration) -> Option<(String, String, RotatedKey)> {
+        let master_key = self.master.as_deref()?;
+        let (slot, seed, name) = match kind {
+            KeyKind::Private => (&mut self.private, &mut self.private_seed, "private"),
+            KeyKind::Public => (&mut self.public, &mut self.public_seed, "public"),
+        };
+
+        let old_key = slot.take()?;
+        let random: [u8; 32] = rand::random();
+        let new_seed = format!("{:x}", sha2::Sha256::digest(&random));
+        let new_key = derive_key(master_key, name, Some(&new_seed));
+        *seed = Some(new_seed);
+        *slot = Some(new_key.clone());
+
+        let digest = key_digest(&old_key);
+        let valid_until = Utc::now() + grace_period;
+        // the key may have already been rotated back, only its last expiration is kept
+        self.rotated.retain(|rotated| rotated.digest != digest);
+        let rotated = RotatedKey { kind, digest, valid_until };
+        self.rotated.push(rotated.clone());
+
+        Some((new_key, old_key, rotated))
+    }
+
+    /// Forgets the rotated keys that expired, returns whether any has been removed.
+    pub fn remove_expired_keys(&mut self) -> bool {
+        let now = Utc::now();
+        let count = self.rotated.len();
+        self.rotated.retain(|rotated| rotated.valid_until > now);
+        self.rotated.len() != count
+    }
+}
+
+pub struct Metrics {
+    documents_indexed: AtomicU64,
+    indexing_duration_micros: AtomicU64,
+    updates_processed: AtomicU64,
+    updates_duration_micros: AtomicU64,
+    /// The durations histograms, with the default buckets of the Prometheus clients.
+    pub search_duration: Histogram,
+    pub indexing_duration: Histogram,
+}
+
+impl Default for Metrics {
+    fn default() -> Metrics {
+        // the names of the histograms are valid, their creation can't fail
+        let search_duration = HistogramOpts::new(
+            "meili_search_duration_seconds",
+            "The duration of the search requests in seconds.",
+        );
+        let indexing_duration = HistogramOpts::new(
+            "meili_indexing_duration_seconds",
+            "The duration of the documents additions in seconds.",
+        );
+
+        Metrics {
+            documents_indexed: AtomicU64::default(),
+            indexing_duration_micros: AtomicU64::default(),
+            updates_processed: AtomicU64::default(),
+            updates_duration_micros: AtomicU64::default(),
+            search_duration: Histogram::with_opts(search_duration).unwrap(),
+            indexing_duration: Histogram::with_opts(indexing_duration).unwrap(),
+        }
+    }
+}
+
+impl Metrics {
+    pub fn record_update(&self, status: &ProcessedUpdateResult) {
+        let duration_micros = (status.duration * 1_000_000.0) as u64;
+
+        self.updates_processed.fetch_add(1, Ordering::Relaxed);
+        self.updates_duration_micros.fetch_add(duration_micros, Ordering::Relaxed);
+
+        if status.error.is_some() {
+            return;
+        }
+
+        match status.update_type {
+            UpdateType::DocumentsAddition { number } | UpdateType::DocumentsPartial { number } => {
+                self.documents_indexed.fetch_add(number as u64, Ordering::Relaxed);
+                self.indexing_duration_micros.fetch_add(duration_micros, Ordering::Relaxed);
+                self.indexing_duration.observe(status.duration);
+            }
+            _ => (),
+        }
+    }
+
+    pub fn record_search(&self, duration: Duration) {
+        self.search_duration.observe(duration.as_secs_f64());
+    }
+
+    pub fn search_requests_total(&self) -> u64 {
+        self.search_duration.get_sample_count()
+    }
+
+    pub fn documents_indexed_per_second(&self) -> f64 {
+        let documents = self.documents_indexed.load(Ordering::Relaxed) as f64;
+        let micros = self.indexing_duration_micros.load(Ordering::Relaxed) as f64;
+        if micros == 0.0 { 0.0 } else { documents / (micros / 1_000_000.0) }
+    }
+
+    pub fn updates_processed_total(&self) -> u64 {
+        self.updates_processed.load(Ordering::Relaxed)
+    }
+
+    pub fn average_update_latency_ms(&self) -> f64 {
+        let updates = self.updates_processed.load(Ordering::Relaxed) as f64;
+        let micros = self.updates_duration_micros.load(Ordering::Relaxed) as f64;
+        if updates == 0.0 { 0.0 } else { micros / 1000.0 / updates }
+    }
 }
 
 impl DataInner {
@@ -70,6 +321,26 @@ impl DataInner {
         }
     }
 
+    /// The directory where the last exported compacted copy of the database is stored.
+    pub fn compacted_db_path(&self) -> PathBuf {
+        Path::new(&self.db_path).join("compacted")
+    }
+
+    /// The size, in bytes, of the main and update LMDB data files.
+    pub fn db_size_bytes(&self) -> u64 {
+        lmdb_files_size(Path::new(&self.db_path))
+    }
+
+    /// The size of the last compacted copy of the database, if any.
+    pub fn db_size_after_compaction_estimate_bytes(&self) -> Option<u64> {
+        let path = self.compacted_db_path();
+        if path.exists() {
+            Some(lmdb_files_size(&path))
+        } else {
+            None
+        }
+    }
+
     pub fn last_update(&self, reader: &heed::RoTxn<MainT>) -> MResult<Option<DateTime<Utc>>> {
         match self
             .db
@@ -88,6 +359,52 @@ impl DataInner {
             .map_err(Into::into)
     }
 
+    /// Rotates the private or public key, the keys are persisted to be kept across the restarts.
+    pub fn rotate_api_key(
+        &self,
+        kind: KeyKind,
+        grace_period: chrono::Duration,
+    ) -> MResult<Option<(String, String, RotatedKey)>> {
+        let mut api_keys = self.api_keys.write().unwrap();
+        let rotated = match api_keys.rotate(kind, grace_period) {
+            Some(rotated) => rotated,
+            None => return Ok(None),
+        };
+
+        self.put_api_keys(&api_keys)?;
+        Ok(Some(rotated))
+    }
+
+    /// Removes the rotated keys that expired, it is regularly called by a background thread.
+    pub fn remove_expired_api_keys(&self) -> MResult<()> {
+        let mut api_keys = self.api_keys.write().unwrap();
+        if api_keys.remove_expired_keys() {
+            self.put_api_keys(&api_keys)?;
+        }
+        Ok(())
+    }
+
+    fn put_api_keys(&self, api_keys: &ApiKeys) -> MResult<()> {
+        let master_digest = match &api_keys.master {
+            Some(master) => key_digest(master),
+            None => return Ok(()),
+        };
+
+        let stored = StoredApiKeys {
+            master_digest,
+            private_seed: api_keys.private_seed.clone(),
+            public_seed: api_keys.public_seed.clone(),
+            rotated: api_keys.rotated.clone(),
+        };
+
+        let mut writer = self.db.main_write_txn()?;
+        self.db
+            .common_store()
+            .put::<_, Str, SerdeBincode<StoredApiKeys>>(&mut writer, API_KEYS_KEY, &stored)?;
+        writer.commit()?;
+        Ok(())
+    }
+
     pub fn compute_stats(&self, writer: &mut heed::RwTxn<MainT>, index_uid: &str) -> MResult<()> {
         let index = match self.db.open_index(&index_uid) {
             Some(index) => index,
@@ -128,6 +445,44 @@ impl DataInner {
     }
 }
 
+/// Replaces the generated keys by the ones persisted by the previous rotations,
+/// if they have been generated for the same master key.
+fn load_api_keys(db: &Database, api_keys: &mut ApiKeys) -> MResult<()> {
+    let master_digest = match &api_keys.master {
+        Some(master) => key_digest(master),
+        None => return Ok(()),
+    };
+
+    let reader = db.main_read_txn()?;
+    let stored = db.common_store().get::<_, Str, SerdeBincode<StoredApiKeys>>(&reader, API_KEYS_KEY)?;
+    if let Some(stored) = stored.filter(|stored| stored.master_digest == master_digest) {
+        api_keys.private_seed = stored.private_seed;
+        api_keys.public_seed = stored.public_seed;
+        api_keys.rotated = stored.rotated;
+    }
+
+    Ok(())
+}
+
+fn search_cache(backend: CacheBackend, size: usize) -> Arc<dyn SearchCache> {
+    let local = LruSearchCache::new(size);
+    match backend {
+        CacheBackend::Memory => Arc::new(local),
+        CacheBackend::Redis(url) => {
+            let shared = RedisSearchCache::new(&url).unwrap();
+            Arc::new(TieredSearchCache::new(local, shared))
+        }
+    }
+}
+
+fn lmdb_files_size(path: &Path) -> u64 {
+    ["main", "update"]
+        .iter()
+        .filter_map(|env| std::fs::metadata(path.join(env).join("data.mdb")).ok())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
 impl Data {
     pub fn new(opt: Opt) -> Data {
         let db_path = opt.db_path.clone();
@@ -136,26 +491,58 @@ impl Data {
         let db_opt = DatabaseOptions {
             main_map_size: opt.main_map_size,
             update_map_size: opt.update_map_size,
+            token_limits: opt.token_limits(),
+            field_encryption_key: opt.field_encryption_key,
+            read_txn_pool_size: opt.read_txn_pool_size,
+            read_txn_max_age: Duration::from_millis(opt.read_txn_max_age_ms),
+            checkpoint_interval: opt.indexing_checkpoint_interval,
         };
 
         let http_payload_size_limit = opt.http_payload_size_limit;
+        let max_request_body_size_bytes = opt.max_request_body_size_bytes;
+        let max_index_count = opt.max_index_count;
+        let base_path = opt.base_path();
+        let api_version_prefix = opt.api_version_prefix();
+        let min_prefix_len = opt.min_prefix_len;
+        let readers_limiter = opt.max_concurrent_readers_per_index.map(|max| Arc::new(IndexReadersLimiter::new(max)));
+        let metrics_auth_key = opt.metrics_auth_key;
+        let prometheus_metrics = opt.enable_prometheus_metrics;
+        let jwt_audience = opt.jwt_audience;
+        let jwt = opt.jwt_issuer.map(|issuer| Arc::new(JwtValidator::new(issuer, jwt_audience)));
+        let cache_size = opt.cache_size;
+        let search_cache = opt.cache_backend.map(|backend| search_cache(backend, cache_size));
+        let remote_shards_api_key = opt.remote_shards_api_key;
+        let remote_shards = opt
+            .remote_shards
+            .into_iter()
+            .map(|url| RemoteShard { url, api_key: remote_shards_api_key.clone() })
+            .collect();
 
         let db = Arc::new(Database::open_or_create(opt.db_path, db_opt).unwrap());
 
-        let mut api_keys = ApiKeys {
-            master: opt.master_key,
-            private: None,
-            public: None,
-        };
+        let mut api_keys = ApiKeys::new(opt.master_key);
 
+        load_api_keys(&db, &mut api_keys).unwrap();
         api_keys.generate_missing_api_keys();
 
         let inner_data = DataInner {
             db: db.clone(),
             db_path,
-            api_keys,
+            api_keys: Arc::new(RwLock::new(api_keys)),
             server_pid,
             http_payload_size_limit,
+            max_request_body_size_bytes,
+            max_index_count,
+            metrics: Arc::new(Metrics::default()),
+            metrics_auth_key,
+            prometheus_metrics,
+            jwt,
+            base_path,
+            api_version_prefix,
+            min_prefix_len,
+            readers_limiter,
+            search_cache,
+            remote_shards,
         };
 
         let data = Data {
@@ -0,0 +1,120 @@
+//! Bootstraps a fresh database from a dump directory, containing one directory per index:
+//!
+//! ```text
+//! dump/
+//! └── movies/
+//!     ├── schema.json     {"primaryKey": "id", "settings": { ... }}
+//!     └── documents.jsonl one JSON document per line
+//! ```
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::sync::mpsc;
+
+use indexmap::IndexMap;
+use meilisearch_core::settings::Settings;
+use meilisearch_core::{Database, DatabaseOptions};
+use meilisearch_schema::Schema;
+use serde::Deserialize;
+use serde_json::Value;
+
+const SCHEMA_FILE_NAME: &str = "schema.json";
+const DOCUMENTS_FILE_NAME: &str = "documents.jsonl";
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct IndexDumpSchema {
+    primary_key: Option<String>,
+    #[serde(default)]
+    settings: Settings,
+}
+
+/// Creates the indexes of the dump in the empty database located at `db_path`,
+/// pushes their settings and documents and waits for all the updates to be processed.
+pub fn import_dump(
+    db_path: impl AsRef<Path>,
+    db_options: DatabaseOptions,
+    dump_path: impl AsRef<Path>,
+) -> Result<(), Box<dyn Error>> {
+    let db = Database::open_or_create(db_path, db_options)?;
+    if !db.indexes_uids().is_empty() {
+        return Err("a dump can only be imported into an empty database".into());
+    }
+
+    let (sender, receiver) = mpsc::channel();
+    db.set_update_callback(Box::new(move |index_uid, status| {
+        let _ = sender.send((index_uid.to_string(), status));
+    }));
+
+    let mut index_paths: Vec<_> = fs::read_dir(dump_path)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_dir())
+        .collect();
+    index_paths.sort();
+
+    let mut last_update_ids = HashMap::new();
+    for index_path in index_paths {
+        let index_uid = match index_path.file_name().and_then(|name| name.to_str()) {
+            Some(name) => name.to_string(),
+            None => continue,
+        };
+
+        let dump_schema: IndexDumpSchema = match File::open(index_path.join(SCHEMA_FILE_NAME)) {
+            Ok(file) => serde_json::from_reader(file)?,
+            Err(_) => IndexDumpSchema::default(),
+        };
+
+        let index = db.create_index(&index_uid)?;
+
+        if let Some(primary_key) = &dump_schema.primary_key {
+            let mut writer = db.main_write_txn()?;
+            index.main.put_schema(&mut writer, &Schema::with_primary_key(primary_key))?;
+            writer.commit()?;
+        }
+
+        let settings = dump_schema.settings.into_update().map_err(|e| e.to_string())?;
+
+        let mut documents_addition = index.documents_addition::<IndexMap<String, Value>>();
+        let documents_path = index_path.join(DOCUMENTS_FILE_NAME);
+        if documents_path.exists() {
+            let reader = BufReader::new(File::open(documents_path)?);
+            for line in reader.lines() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let document: IndexMap<String, Value> = serde_json::from_str(&line)?;
+                documents_addition.update_document(document);
+            }
+        }
+
+        let mut update_writer = db.update_write_txn()?;
+        index.settings_update(&mut update_writer, settings)?;
+        let update_id = documents_addition.finalize(&mut update_writer)?;
+        update_writer.commit()?;
+
+        last_update_ids.insert(index_uid, update_id);
+    }
+
+    // the updates of an index are processed in order, the import is done
+    // once the last update of every index has been processed
+    let mut errors = Vec::new();
+    while !last_update_ids.is_empty() {
+        let (index_uid, status) = receiver.recv()?;
+        if let Some(error) = &status.error {
+            errors.push(format!("index {} update {}: {}", index_uid, status.update_id, error));
+        }
+        if last_update_ids.get(&index_uid) == Some(&status.update_id) {
+            last_update_ids.remove(&index_uid);
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join(", ").into())
+    }
+}
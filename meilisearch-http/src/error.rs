@@ -2,9 +2,9 @@ use std::fmt;
 
 use actix_http::ResponseBuilder;
 use actix_web as aweb;
-use actix_web::http::StatusCode;
-use serde_json::json;
-use actix_web::error::JsonPayloadError;
+use actix_web::http::{header, StatusCode};
+use serde_json::{json, Value};
+use actix_web::error::{JsonPayloadError, PayloadError};
 
 #[derive(Debug)]
 pub enum ResponseError {
@@ -17,6 +17,7 @@ pub enum ResponseError {
     InvalidIndexUid,
     InvalidToken(String),
     Maintenance,
+    MaxIndexCountReached(usize),
     MissingAuthorizationHeader,
     MissingHeader(String),
     NotFound(String),
@@ -25,9 +26,12 @@ pub enum ResponseError {
     RetrieveDocument(u32, String),
     SearchDocuments(String),
     PayloadTooLarge,
+    PreconditionFailed(String),
     UnsupportedMediaType,
     FacetExpression(String),
     FacetCount(String),
+    IndexBusy(String),
+    InvalidRequestBody { message: String, unknown_fields: Vec<String>, missing_fields: Vec<String> },
 }
 
 pub enum FacetCountError {
@@ -123,6 +127,22 @@ impl ResponseError {
     pub fn search_documents(err: impl fmt::Display) -> ResponseError {
         ResponseError::SearchDocuments(err.to_string())
     }
+
+    pub fn index_busy(err: impl fmt::Display) -> ResponseError {
+        ResponseError::IndexBusy(err.to_string())
+    }
+
+    pub fn invalid_request_body(
+        err: impl fmt::Display,
+        unknown_fields: Vec<String>,
+        missing_fields: Vec<String>,
+    ) -> ResponseError {
+        ResponseError::InvalidRequestBody { message: err.to_string(), unknown_fields, missing_fields }
+    }
+
+    pub fn precondition_failed(err: impl fmt::Display) -> ResponseError {
+        ResponseError::PreconditionFailed(err.to_string())
+    }
 }
 
 impl fmt::Display for ResponseError {
@@ -137,6 +157,7 @@ impl fmt::Display for ResponseError {
             Self::InvalidIndexUid => f.write_str("Index must have a valid uid; Index uid can be of type integer or string only composed of alphanumeric characters, hyphens (-) and underscores (_)."),
             Self::InvalidToken(err) => write!(f, "Invalid API key: {}", err),
             Self::Maintenance => f.write_str("Server is in maintenance, please try again later"),
+            Self::MaxIndexCountReached(max) => write!(f, "Impossible to create index; the maximum number of indexes ({}) is reached", max),
             Self::FilterParsing(err) => write!(f, "parsing error: {}", err),
             Self::MissingAuthorizationHeader => f.write_str("You must have an authorization token"),
             Self::MissingHeader(header) => write!(f, "Header {} is missing", header),
@@ -146,17 +167,48 @@ impl fmt::Display for ResponseError {
             Self::SearchDocuments(err) => write!(f, "impossible to search documents; {}", err),
             Self::FacetExpression(e) => write!(f, "error parsing facet filter expression: {}", e),
             Self::PayloadTooLarge => f.write_str("Payload to large"),
+            Self::PreconditionFailed(err) => write!(f, "Precondition failed: {}", err),
             Self::UnsupportedMediaType => f.write_str("Unsupported media type"),
             Self::FacetCount(e) => write!(f, "error with facet count: {}", e),
+            Self::IndexBusy(index_uid) => write!(f, "Too many concurrent requests on index {}, please try again later", index_uid),
+            Self::InvalidRequestBody { message, .. } => f.write_str(message),
+        }
+    }
+}
+
+impl ResponseError {
+    /// A code identifying the error, only given to the errors clients may want to handle.
+    fn error_code(&self) -> Option<&'static str> {
+        match self {
+            Self::MaxIndexCountReached(_) => Some("max_index_count_reached"),
+            Self::PreconditionFailed(_) => Some("precondition_failed"),
+            Self::IndexBusy(_) => Some("index_busy"),
+            Self::InvalidRequestBody { .. } => Some("invalid_request_body"),
+            _ => None,
+        }
+    }
+
+    /// The JSON body describing the error to the clients.
+    pub fn body(&self) -> Value {
+        let mut body = match self.error_code() {
+            Some(code) => json!({ "message": self.to_string(), "code": code }),
+            None => json!({ "message": self.to_string() }),
+        };
+        if let Self::InvalidRequestBody { unknown_fields, missing_fields, .. } = self {
+            body["unknownFields"] = json!(unknown_fields);
+            body["missingFields"] = json!(missing_fields);
         }
+        body
     }
 }
 
 impl aweb::error::ResponseError for ResponseError {
     fn error_response(&self) -> aweb::HttpResponse {
-        ResponseBuilder::new(self.status_code()).json(json!({
-            "message": self.to_string(),
-        }))
+        let mut response = ResponseBuilder::new(self.status_code());
+        if let Self::IndexBusy(_) = self {
+            response.header(header::RETRY_AFTER, "1");
+        }
+        response.json(self.body())
     }
 
     fn status_code(&self) -> StatusCode {
@@ -170,6 +222,7 @@ impl aweb::error::ResponseError for ResponseError {
             | Self::FacetExpression(_)
             | Self::SearchDocuments(_)
             | Self::FacetCount(_)
+            | Self::InvalidRequestBody { .. }
             | Self::FilterParsing(_) => StatusCode::BAD_REQUEST,
             Self::DocumentNotFound(_)
             | Self::IndexNotFound(_)
@@ -178,8 +231,11 @@ impl aweb::error::ResponseError for ResponseError {
             | Self::MissingHeader(_) => StatusCode::UNAUTHORIZED,
             Self::MissingAuthorizationHeader => StatusCode::FORBIDDEN,
             Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
-            Self::Maintenance => StatusCode::SERVICE_UNAVAILABLE,
+            Self::Maintenance
+            | Self::IndexBusy(_) => StatusCode::SERVICE_UNAVAILABLE,
+            Self::MaxIndexCountReached(_) => StatusCode::INSUFFICIENT_STORAGE,
             Self::PayloadTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            Self::PreconditionFailed(_) => StatusCode::PRECONDITION_FAILED,
             Self::UnsupportedMediaType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
         }
     }
@@ -206,6 +262,12 @@ impl From<meilisearch_core::FacetError> for ResponseError {
 impl From<meilisearch_core::Error> for ResponseError {
     fn from(err: meilisearch_core::Error) -> ResponseError {
         use meilisearch_core::pest_error::LineColLocation::*;
+
+        // std::backtrace is not stable yet, the backtrace is captured where the core
+        // error reaches the http layer and is only logged in debug builds.
+        #[cfg(debug_assertions)]
+        log::debug!("{}; backtrace:\n{:?}", err, backtrace::Backtrace::new());
+
         match err {
             meilisearch_core::Error::FilterParseError(e) => {
                 let (line, column) = match e.line_col {
@@ -224,7 +286,10 @@ impl From<meilisearch_core::Error> for ResponseError {
 
 impl From<meilisearch_schema::Error> for ResponseError {
     fn from(err: meilisearch_schema::Error) -> ResponseError {
-        ResponseError::Internal(err.to_string())
+        match err {
+            meilisearch_schema::Error::TooManyAttributes { .. } => ResponseError::BadRequest(err.to_string()),
+            _ => ResponseError::Internal(err.to_string()),
+        }
     }
 }
 
@@ -245,6 +310,7 @@ impl From<JsonPayloadError> for ResponseError {
         match err {
             JsonPayloadError::Deserialize(err) => ResponseError::BadRequest(format!("Invalid JSON: {}", err)),
             JsonPayloadError::Overflow => ResponseError::PayloadTooLarge,
+            JsonPayloadError::Payload(PayloadError::Overflow) => ResponseError::PayloadTooLarge,
             JsonPayloadError::ContentType => ResponseError::UnsupportedMediaType,
             JsonPayloadError::Payload(err) => ResponseError::BadRequest(format!("Problem while decoding the request: {}", err)),
         }
@@ -4,8 +4,10 @@ use std::rc::Rc;
 use std::task::{Context, Poll};
 
 use actix_service::{Service, Transform};
-use actix_web::{dev::ServiceRequest, dev::ServiceResponse, Error};
+use actix_web::error::BlockingError;
+use actix_web::{dev::Payload, dev::ServiceRequest, dev::ServiceResponse, web, Error, FromRequest, HttpMessage, HttpRequest};
 use futures::future::{err, ok, Future, Ready};
+use sha2::Digest;
 
 use crate::error::ResponseError;
 use crate::Data;
@@ -17,6 +19,38 @@ pub enum Authentication {
     Admin,
 }
 
+/// Who authenticated the request, the subject of its JWT or its API key, stored in the
+/// extensions of the request. The API keys are secrets, they are identified by the
+/// beginning of their SHA-256 digest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Actor(pub String);
+
+impl Actor {
+    fn api_key(key: &str) -> Actor {
+        let digest = format!("{:x}", sha2::Sha256::digest(key.as_bytes()));
+        Actor(format!("key:{}", &digest[..8]))
+    }
+
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+/// The actor is missing when the server has no master key nor JWT issuer
+/// or when the JWT of the request has no subject.
+impl FromRequest for Actor {
+    type Error = Error;
+    type Future = Ready<Result<Actor, Error>>;
+    type Config = ();
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        match req.extensions().get::<Actor>() {
+            Some(actor) => ok(actor.clone()),
+            None => err(ResponseError::internal("the request has no actor").into()),
+        }
+    }
+}
+
 impl<S: 'static, B> Transform<S> for Authentication
 where
     S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
@@ -64,10 +98,47 @@ where
         // it means that actix-web has an issue or someone changes the type `Data`.
         let data = req.app_data::<Data>().unwrap();
 
-        if data.api_keys.master.is_none() {
+        if data.api_keys.read().unwrap().master.is_none() && data.jwt.is_none() {
             return Box::pin(svc.call(req));
         }
 
+        if let Some(jwt) = data.jwt.clone() {
+            let bearer = req.headers()
+                .get("Authorization")
+                .and_then(|auth| auth.to_str().ok())
+                .and_then(|auth| {
+                    let mut parts = auth.splitn(2, ' ');
+                    match (parts.next(), parts.next()) {
+                        (Some("Bearer"), Some(token)) => Some(token.trim().to_string()),
+                        _ => None,
+                    }
+                });
+
+            if let Some(token) = bearer {
+                let acl = self.acl.clone();
+                let index_uid = req.match_info().get("index_uid").map(str::to_string);
+
+                return Box::pin(async move {
+                    let claims = web::block(move || jwt.validate(&token))
+                        .await
+                        .map_err(|e| match e {
+                            BlockingError::Error(e) => e,
+                            BlockingError::Canceled => ResponseError::internal("JWT validation canceled"),
+                        })?;
+
+                    if !claims.allows(&acl, index_uid.as_deref()) {
+                        return Err(ResponseError::invalid_token("the JWT does not grant access to this route").into());
+                    }
+
+                    if let Some(subject) = claims.sub {
+                        req.extensions_mut().insert(Actor(subject));
+                    }
+
+                    svc.call(req).await
+                });
+            }
+        }
+
         let auth_header = match req.headers().get("X-Meili-API-Key") {
             Some(auth) => match auth.to_str() {
                 Ok(auth) => auth,
@@ -78,20 +149,16 @@ where
             }
         };
 
+        let api_keys = data.api_keys.read().unwrap();
         let authenticated = match self.acl {
-            Authentication::Admin => data.api_keys.master.as_deref() == Some(auth_header),
-            Authentication::Private => {
-                data.api_keys.master.as_deref() == Some(auth_header)
-                    || data.api_keys.private.as_deref() == Some(auth_header)
-            }
-            Authentication::Public => {
-                data.api_keys.master.as_deref() == Some(auth_header)
-                    || data.api_keys.private.as_deref() == Some(auth_header)
-                    || data.api_keys.public.as_deref() == Some(auth_header)
-            }
+            Authentication::Admin => api_keys.is_master(auth_header),
+            Authentication::Private => api_keys.is_private(auth_header),
+            Authentication::Public => api_keys.is_public(auth_header),
         };
+        drop(api_keys);
 
         if authenticated {
+            req.extensions_mut().insert(Actor::api_key(auth_header));
             Box::pin(svc.call(req))
         } else {
             Box::pin(err(
@@ -0,0 +1,192 @@
+/// This middleware logs the requests in the same format as the default actix `Logger`
+/// but strips the configured base path from the logged paths.
+///
+/// Ex: with the `/search` base path
+///   GET /search/indexes/movies/documents
+///   is logged as : GET /indexes/movies/documents
+///
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use std::time::Instant;
+
+use actix_service::{Service, Transform};
+use actix_web::body::{BodySize, MessageBody, ResponseBody};
+use actix_web::web::Bytes;
+use actix_web::{dev::ServiceRequest, dev::ServiceResponse, Error as ActixError};
+use futures::future::{ok, Future, Ready};
+use log::info;
+
+use crate::helpers::request_id::REQUEST_ID_HEADER;
+
+pub struct BasePathLogger {
+    base_path: Rc<String>,
+}
+
+impl BasePathLogger {
+    pub fn new(base_path: &str) -> BasePathLogger {
+        BasePathLogger {
+            base_path: Rc::new(base_path.to_string()),
+        }
+    }
+}
+
+impl<S, B> Transform<S> for BasePathLogger
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = ActixError>,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<LoggedBody<B>>;
+    type Error = ActixError;
+    type InitError = ();
+    type Transform = BasePathLogging<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(BasePathLogging {
+            base_path: self.base_path.clone(),
+            service,
+        })
+    }
+}
+
+pub struct BasePathLogging<S> {
+    base_path: Rc<String>,
+    service: S,
+}
+
+impl<S, B> Service for BasePathLogging<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = ActixError>,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<LoggedBody<B>>;
+    type Error = ActixError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    fn poll_ready(&mut self, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        let start = Instant::now();
+        let remote_addr = req.connection_info().remote().unwrap_or("-").to_string();
+        let method = req.method().clone();
+        let version = req.version();
+        let header = |name: &str| {
+            req.headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .unwrap_or("-")
+                .to_string()
+        };
+        let referer = header("referer");
+        let user_agent = header("user-agent");
+
+        let path = req.path();
+        let path = if !self.base_path.is_empty() && path.starts_with(self.base_path.as_str()) {
+            match &path[self.base_path.len()..] {
+                "" => "/".to_string(),
+                stripped if stripped.starts_with('/') => stripped.to_string(),
+                _ => path.to_string(),
+            }
+        } else {
+            path.to_string()
+        };
+
+        let path = match req.query_string() {
+            "" => path,
+            query => format!("{}?{}", path, query),
+        };
+
+        let fut = self.service.call(req);
+
+        Box::pin(async move {
+            let res = fut.await?;
+            let request_id = res
+                .headers()
+                .get(REQUEST_ID_HEADER)
+                .and_then(|value| value.to_str().ok())
+                .unwrap_or("-")
+                .to_string();
+            let status = res.status().as_u16();
+
+            // the request is logged once its body has been sent, with the size of the body
+            let line = move |size: usize| {
+                format!(
+                    "{} \"{} {} {:?}\" {} {} \"{}\" \"{}\" {:.6} {}",
+                    remote_addr,
+                    method,
+                    path,
+                    version,
+                    status,
+                    size,
+                    referer,
+                    user_agent,
+                    start.elapsed().as_secs_f64(),
+                    request_id,
+                )
+            };
+
+            Ok(res.map_body(move |_, body| {
+                ResponseBody::Body(LoggedBody { body, line: Some(Box::new(line)), size: 0 })
+            }))
+        })
+    }
+}
+
+/// Forwards the response body unchanged and counts its bytes,
+/// the request is logged when the body is dropped.
+pub struct LoggedBody<B> {
+    body: ResponseBody<B>,
+    line: Option<Box<dyn FnOnce(usize) -> String>>,
+    size: usize,
+}
+
+impl<B> Drop for LoggedBody<B> {
+    fn drop(&mut self) {
+        if let Some(line) = self.line.take() {
+            info!("{}", line(self.size));
+        }
+    }
+}
+
+impl<B: MessageBody> MessageBody for LoggedBody<B> {
+    fn size(&self) -> BodySize {
+        self.body.size()
+    }
+
+    fn poll_next(&mut self, cx: &mut Context) -> Poll<Option<Result<Bytes, ActixError>>> {
+        match self.body.poll_next(cx) {
+            Poll::Ready(Some(Ok(chunk))) => {
+                self.size += chunk.len();
+                Poll::Ready(Some(Ok(chunk)))
+            }
+            poll => poll,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use actix_web::{test, web, App, HttpResponse};
+
+    #[actix_rt::test]
+    async fn response_body_forwarded_unchanged() {
+        let app = App::new()
+            .wrap(BasePathLogger::new("/search"))
+            .route("/search/indexes", web::get().to(|| async { HttpResponse::Ok().body("[\"movies\"]") }));
+        let mut app = test::init_service(app).await;
+
+        let req = test::TestRequest::get().uri("/search/indexes").to_request();
+        let res = test::call_service(&mut app, req).await;
+        assert_eq!(res.response().body().size(), BodySize::Sized(10));
+        assert_eq!(test::read_body(res).await, Bytes::from_static(b"[\"movies\"]"));
+    }
+}
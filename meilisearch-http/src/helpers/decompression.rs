@@ -0,0 +1,127 @@
+/// This middleware decompresses the request bodies encoded with `gzip`, `br` or `deflate`,
+/// the encoding given by the `Content-Encoding` header, before they reach the handlers.
+/// The bodies decompressed to more than the limit are rejected, as the other encodings are.
+///
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use actix_service::{Service, Transform};
+use actix_web::dev::{Decompress, Payload, ServiceRequest, ServiceResponse};
+use actix_web::error::PayloadError;
+use actix_web::http::{header, ContentEncoding};
+use actix_web::web::Bytes;
+use actix_web::{Error as ActixError, HttpMessage};
+use futures::future::{ok, Either, Ready};
+use futures::stream::Stream;
+
+use crate::error::ResponseError;
+
+pub struct Decompression {
+    limit: usize,
+}
+
+impl Decompression {
+    pub fn new(limit: usize) -> Decompression {
+        Decompression { limit }
+    }
+}
+
+impl<S, B> Transform<S> for Decompression
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = ActixError>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = ActixError;
+    type InitError = ();
+    type Transform = DecompressionMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(DecompressionMiddleware { service, limit: self.limit })
+    }
+}
+
+pub struct DecompressionMiddleware<S> {
+    service: S,
+    limit: usize,
+}
+
+fn content_encoding(value: &str) -> Option<ContentEncoding> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "gzip" | "x-gzip" => Some(ContentEncoding::Gzip),
+        "br" => Some(ContentEncoding::Br),
+        "deflate" => Some(ContentEncoding::Deflate),
+        "identity" => Some(ContentEncoding::Identity),
+        _ => None,
+    }
+}
+
+impl<S, B> Service for DecompressionMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = ActixError>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = ActixError;
+    type Future = Either<S::Future, Ready<Result<Self::Response, Self::Error>>>;
+
+    fn poll_ready(&mut self, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: ServiceRequest) -> Self::Future {
+        let encoding = req
+            .headers()
+            .get(header::CONTENT_ENCODING)
+            .map(|value| value.to_str().ok().and_then(content_encoding));
+
+        let encoding = match encoding {
+            None | Some(Some(ContentEncoding::Identity)) => return Either::Left(self.service.call(req)),
+            Some(Some(encoding)) => encoding,
+            Some(None) => return Either::Right(ok(req.error_response(ResponseError::UnsupportedMediaType))),
+        };
+
+        // the extractors must not decompress the body again, and its length is unknown
+        req.headers_mut().remove(header::CONTENT_ENCODING);
+        req.headers_mut().remove(header::CONTENT_LENGTH);
+
+        let payload = LimitedPayload {
+            inner: Decompress::new(req.take_payload(), encoding),
+            remaining: self.limit,
+        };
+        req.set_payload(Payload::Stream(Box::pin(payload)));
+
+        Either::Left(self.service.call(req))
+    }
+}
+
+/// A decompressed payload, it fails once more than the limit has been decompressed.
+struct LimitedPayload<S> {
+    inner: S,
+    remaining: usize,
+}
+
+impl<S> Stream for LimitedPayload<S>
+where
+    S: Stream<Item = Result<Bytes, PayloadError>> + Unpin,
+{
+    type Item = Result<Bytes, PayloadError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        match Pin::new(&mut self.inner).poll_next(cx) {
+            Poll::Ready(Some(Ok(bytes))) => {
+                if bytes.len() > self.remaining {
+                    return Poll::Ready(Some(Err(PayloadError::Overflow)));
+                }
+                self.remaining -= bytes.len();
+                Poll::Ready(Some(Ok(bytes)))
+            }
+            poll => poll,
+        }
+    }
+}
@@ -0,0 +1,286 @@
+//! Searches an index spread over many MeiliSearch instances (shards) and merges the results.
+//!
+//! Every shard returns the ranking score of its hits, the merged hits are sorted by comparing
+//! these scores, the shards being ranked by the same ranking rules. The ties are broken by the
+//! shards order, the local index coming first.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use actix_web::web;
+use futures::future::{self, Future};
+use serde::{Deserialize, Serialize};
+
+use crate::error::ResponseError;
+use crate::helpers::meilisearch::{SearchHit, SearchResult};
+
+/// The header of the searches sent to the remote shards, they only search their own index.
+pub const SHARD_REQUEST_HEADER: &str = "X-Meili-Shard-Request";
+
+/// The time after which a shard that doesn't respond is considered in error.
+const SHARD_TIMEOUT_MS: u64 = 10_000;
+
+/// The query parameters handled by the fan-out, the other ones are forwarded to the shards.
+const FAN_OUT_PARAMETERS: [&str; 3] = ["offset", "limit", "rankingScore"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteShard {
+    /// The url of the MeiliSearch instance with its API version prefix, e.g. `http://127.0.0.1:7700/v1`.
+    pub url: String,
+    pub api_key: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FanOutQuery {
+    pub q: String,
+    pub offset: usize,
+    pub limit: usize,
+    /// Whether the ranking scores of the hits are returned.
+    pub ranking_score: bool,
+    /// The search parameters, as received by the search route.
+    pub query_string: String,
+}
+
+/// Sends the search query to all the remote shards concurrently and executes it on the local
+/// index meanwhile, every shard is asked for its first `offset + limit` hits. The `local_search`
+/// function receives this number of hits and must return their ranking scores.
+pub fn fan_out_search<F>(
+    remote_shards: &[RemoteShard],
+    index_uid: &str,
+    query: &FanOutQuery,
+    local_search: F,
+) -> impl Future<Output = Result<SearchResult, ResponseError>>
+where
+    F: FnOnce(usize) -> Result<SearchResult, ResponseError>,
+{
+    let shard_limit = query.offset + query.limit;
+    let query_string = shard_query_string(&query.query_string, shard_limit);
+
+    let remote_results: Vec<_> = remote_shards
+        .iter()
+        .cloned()
+        .map(|shard| {
+            let index_uid = index_uid.to_string();
+            let query_string = query_string.clone();
+            web::block(move || search_remote_shard(&shard, &index_uid, &query_string))
+        })
+        .collect();
+
+    let local_result = local_search(shard_limit);
+
+    let query = query.clone();
+    async move {
+        let mut results = Vec::with_capacity(remote_results.len() + 1);
+        results.push(local_result?);
+
+        for result in future::join_all(remote_results).await {
+            results.push(result.map_err(ResponseError::internal)?);
+        }
+
+        Ok(merge_shard_results(results, &query))
+    }
+}
+
+/// The forwarded search parameters, asking for the first `limit` hits with their ranking score.
+fn shard_query_string(query_string: &str, limit: usize) -> String {
+    let mut params: Vec<_> = query_string
+        .split('&')
+        .filter(|param| !param.is_empty())
+        .filter(|param| {
+            let name = param.split('=').next().unwrap_or_default();
+            !FAN_OUT_PARAMETERS.contains(&name)
+        })
+        .map(ToString::to_string)
+        .collect();
+
+    params.push("offset=0".to_string());
+    params.push(format!("limit={}", limit));
+    params.push("rankingScore=true".to_string());
+    params.join("&")
+}
+
+fn search_remote_shard(shard: &RemoteShard, index_uid: &str, query_string: &str) -> Result<SearchResult, String> {
+    let url = format!("{}/indexes/{}/search?{}", shard.url.trim_end_matches('/'), index_uid, query_string);
+    let mut request = ureq::get(&url);
+    request
+        .set(SHARD_REQUEST_HEADER, "true")
+        .timeout_connect(SHARD_TIMEOUT_MS)
+        .timeout_read(SHARD_TIMEOUT_MS);
+
+    if let Some(api_key) = &shard.api_key {
+        request.set("X-Meili-API-Key", api_key);
+    }
+
+    let response = request.call();
+    if !response.ok() {
+        let status = response.status();
+        let body = response.into_string().unwrap_or_default();
+        return Err(format!("shard {} responded with {}: {}", shard.url, status, body));
+    }
+
+    let body = response
+        .into_string()
+        .map_err(|e| format!("shard {} response read failed: {}", shard.url, e))?;
+
+    serde_json::from_str(&body).map_err(|e| format!("shard {} invalid response: {}", shard.url, e))
+}
+
+/// Compares the ranking scores of two hits, the hits without score are ranked last.
+fn compare_ranking_scores(lhs: &SearchHit, rhs: &SearchHit) -> Ordering {
+    match (&lhs.ranking_score, &rhs.ranking_score) {
+        (Some(lhs), Some(rhs)) => lhs
+            .iter()
+            .zip(rhs)
+            .map(|(lhs, rhs)| lhs.partial_cmp(rhs).unwrap_or(Ordering::Equal))
+            .find(|ordering| *ordering != Ordering::Equal)
+            .unwrap_or(Ordering::Equal),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+/// Merges the results of the shards, the best scored hits are kept.
+fn merge_shard_results(results: Vec<SearchResult>, query: &FanOutQuery) -> SearchResult {
+    let nb_hits = results.iter().map(|r| r.nb_hits).sum();
+    let exhaustive_nb_hits = results.iter().all(|r| r.exhaustive_nb_hits);
+    let processing_time_ms = results.iter().map(|r| r.processing_time_ms).max().unwrap_or(0);
+
+    let mut facets: Option<HashMap<String, HashMap<String, usize>>> = None;
+    let mut hits = Vec::new();
+    let mut results = results.into_iter();
+
+    // the explanations are the ones of the local index
+    let local = results.next();
+    let (stopped_words, explain) = match &local {
+        Some(local) => (local.stopped_words.clone(), local.explain.clone()),
+        None => (None, None),
+    };
+
+    for result in local.into_iter().chain(results) {
+        if let Some(result_facets) = result.facets {
+            let facets = facets.get_or_insert_with(HashMap::new);
+            for (attribute, counts) in result_facets {
+                let attribute_counts = facets.entry(attribute).or_insert_with(HashMap::new);
+                for (value, count) in counts {
+                    *attribute_counts.entry(value).or_insert(0) += count;
+                }
+            }
+        }
+        hits.extend(result.hits);
+    }
+
+    // the sort is stable, the hits with the same score stay in the shards order
+    hits.sort_by(compare_ranking_scores);
+
+    let hits = hits
+        .into_iter()
+        .skip(query.offset)
+        .take(query.limit)
+        .map(|mut hit| {
+            if !query.ranking_score {
+                hit.ranking_score = None;
+            }
+            hit
+        })
+        .collect();
+
+    SearchResult {
+        hits,
+        offset: query.offset,
+        limit: query.limit,
+        nb_hits,
+        exhaustive_nb_hits,
+        processing_time_ms,
+        query: query.q.clone(),
+        facets,
+        stopped_words,
+        explain,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use indexmap::IndexMap;
+    use serde_json::json;
+
+    fn shard_result(hits: &[(u64, f64)], nb_hits: usize) -> SearchResult {
+        let hits = hits
+            .iter()
+            .map(|(id, score)| {
+                let mut document = IndexMap::new();
+                document.insert("id".to_string(), json!(id));
+                SearchHit {
+                    document,
+                    formatted: IndexMap::new(),
+                    matches_info: None,
+                    ranking_score: Some(vec![*score, 0.0]),
+                }
+            })
+            .collect();
+
+        SearchResult {
+            hits,
+            offset: 0,
+            limit: 20,
+            nb_hits,
+            exhaustive_nb_hits: false,
+            processing_time_ms: nb_hits,
+            query: "marvin".to_string(),
+            facets: None,
+            stopped_words: None,
+            explain: None,
+        }
+    }
+
+    fn fan_out_query(offset: usize, limit: usize) -> FanOutQuery {
+        FanOutQuery {
+            q: "marvin".to_string(),
+            offset,
+            limit,
+            ranking_score: false,
+            query_string: "q=marvin".to_string(),
+        }
+    }
+
+    #[test]
+    fn merge_sorts_hits_by_score() {
+        let results = vec![
+            shard_result(&[(1, -3.0), (2, -1.0), (3, 0.0)], 3),
+            shard_result(&[(10, -2.0), (20, -1.0)], 12),
+        ];
+
+        let merged = merge_shard_results(results, &fan_out_query(1, 3));
+
+        // the hits of the same score are in the shards order
+        let ids: Vec<_> = merged.hits.iter().map(|hit| hit.document["id"].clone()).collect();
+        assert_eq!(ids, vec![json!(10), json!(2), json!(20)]);
+        assert!(merged.hits.iter().all(|hit| hit.ranking_score.is_none()));
+        assert_eq!(merged.nb_hits, 15);
+        assert_eq!(merged.processing_time_ms, 12);
+        assert!(!merged.exhaustive_nb_hits);
+    }
+
+    #[test]
+    fn merge_sums_facets_counts() {
+        let mut local = shard_result(&[], 0);
+        local.facets = Some(vec![("genre".to_string(), vec![("sf".to_string(), 2)].into_iter().collect())].into_iter().collect());
+        let mut remote = shard_result(&[], 0);
+        remote.facets = Some(vec![("genre".to_string(), vec![("sf".to_string(), 3), ("horror".to_string(), 1)].into_iter().collect())].into_iter().collect());
+
+        let merged = merge_shard_results(vec![local, remote], &fan_out_query(0, 20));
+
+        let genres = &merged.facets.unwrap()["genre"];
+        assert_eq!(genres["sf"], 5);
+        assert_eq!(genres["horror"], 1);
+    }
+
+    #[test]
+    fn shard_query_string_asks_for_the_ranking_score() {
+        let query_string = shard_query_string("q=marvin&offset=10&limit=5&filters=a%3D1&rankingScore=false", 15);
+        assert_eq!(query_string, "q=marvin&filters=a%3D1&offset=0&limit=15&rankingScore=true");
+    }
+}
@@ -0,0 +1,211 @@
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use serde::Deserialize;
+
+use crate::error::ResponseError;
+use crate::helpers::Authentication;
+
+/// The duration during which the keys fetched from the JWKS endpoint are reused.
+const JWKS_CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// The minimum duration between two fetches of the JWKS, the tokens
+/// signed by an unknown key are refused meanwhile.
+const JWKS_MIN_REFETCH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// The connection and read timeouts of the JWKS endpoint requests, in milliseconds.
+const JWKS_TIMEOUT_MS: u64 = 5_000;
+
+#[derive(Debug, Clone, Deserialize)]
+struct Jwk {
+    kid: Option<String>,
+    kty: String,
+    n: Option<String>,
+    e: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Jwks {
+    keys: Vec<Jwk>,
+}
+
+/// The custom claims read from the tokens.
+///
+/// `allowed_indexes` restricts the indexes the token gives access to, all of them when missing,
+/// the routes that are not about a particular index are only allowed by the `*` wildcard,
+/// `actions` lists the access levels the token grants: `public`, `private` and `admin`.
+/// The `sub` registered claim identifies who made the changes requested with the token.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Claims {
+    #[serde(default)]
+    pub allowed_indexes: Option<Vec<String>>,
+    #[serde(default)]
+    pub actions: Vec<String>,
+    #[serde(default)]
+    pub sub: Option<String>,
+}
+
+impl Claims {
+    pub fn allows(&self, acl: &Authentication, index_uid: Option<&str>) -> bool {
+        let allowed_actions: &[&str] = match acl {
+            Authentication::Admin => &["admin"],
+            Authentication::Private => &["admin", "private"],
+            Authentication::Public => &["admin", "private", "public"],
+        };
+
+        let action_allowed = self.actions.iter().any(|action| allowed_actions.contains(&action.as_str()));
+
+        // the tokens limited to some indexes can't access the routes of no particular index
+        let index_allowed = match (&self.allowed_indexes, index_uid) {
+            (Some(indexes), Some(index_uid)) => indexes.iter().any(|uid| uid == "*" || uid == index_uid),
+            (Some(indexes), None) => indexes.iter().any(|uid| uid == "*"),
+            (None, _) => true,
+        };
+
+        action_allowed && index_allowed
+    }
+}
+
+/// Validates the JWT tokens against the keys published by the issuer
+/// at its `/.well-known/jwks.json` endpoint.
+pub struct JwtValidator {
+    issuer: String,
+    audience: Option<String>,
+    jwks: RwLock<Option<(Instant, Vec<Jwk>)>>,
+    /// When the JWKS was last fetched, successfully or not, only one fetch runs at a time.
+    last_fetch: Mutex<Option<Instant>>,
+}
+
+impl JwtValidator {
+    pub fn new(issuer: String, audience: Option<String>) -> JwtValidator {
+        JwtValidator { issuer, audience, jwks: RwLock::new(None), last_fetch: Mutex::new(None) }
+    }
+
+    fn jwks_url(&self) -> String {
+        format!("{}/.well-known/jwks.json", self.issuer.trim_end_matches('/'))
+    }
+
+    fn fetch_keys(&self) -> Result<Vec<Jwk>, ResponseError> {
+        let response = ureq::get(&self.jwks_url())
+            .timeout_connect(JWKS_TIMEOUT_MS)
+            .timeout_read(JWKS_TIMEOUT_MS)
+            .call();
+        if !response.ok() {
+            return Err(ResponseError::internal(format!("Impossible to retrieve the JWKS: {}", response.status_line())));
+        }
+
+        let body = response.into_string().map_err(ResponseError::internal)?;
+        let jwks: Jwks = serde_json::from_str(&body).map_err(ResponseError::internal)?;
+
+        Ok(jwks.keys)
+    }
+
+    fn cached_key(&self, kid: Option<&str>) -> Option<Jwk> {
+        match &*self.jwks.read().unwrap() {
+            Some((fetched_at, keys)) if fetched_at.elapsed() < JWKS_CACHE_TTL => find_key(keys, kid),
+            _ => None,
+        }
+    }
+
+    /// Returns the cached key with the given id, the keys are fetched again when the cache
+    /// is outdated or does not contain this key and the last fetch is old enough.
+    fn key(&self, kid: Option<&str>) -> Result<Jwk, ResponseError> {
+        if let Some(key) = self.cached_key(kid) {
+            return Ok(key);
+        }
+
+        // the requests waiting for the fetch in progress use the keys it retrieved
+        let mut last_fetch = self.last_fetch.lock().unwrap();
+        if let Some(key) = self.cached_key(kid) {
+            return Ok(key);
+        }
+
+        if last_fetch.map_or(false, |at| at.elapsed() < JWKS_MIN_REFETCH_INTERVAL) {
+            return Err(ResponseError::invalid_token("no matching key found in the JWKS"));
+        }
+
+        *last_fetch = Some(Instant::now());
+        let keys = self.fetch_keys()?;
+        let key = find_key(&keys, kid);
+        *self.jwks.write().unwrap() = Some((Instant::now(), keys));
+
+        key.ok_or_else(|| ResponseError::invalid_token("no matching key found in the JWKS"))
+    }
+
+    pub fn validate(&self, token: &str) -> Result<Claims, ResponseError> {
+        let header = decode_header(token).map_err(ResponseError::invalid_token)?;
+        match header.alg {
+            Algorithm::RS256 | Algorithm::RS384 | Algorithm::RS512 => (),
+            alg => return Err(ResponseError::invalid_token(format!("unsupported algorithm {:?}", alg))),
+        }
+
+        let jwk = self.key(header.kid.as_deref())?;
+        let (n, e) = match (&jwk.n, &jwk.e) {
+            (Some(n), Some(e)) => (n, e),
+            _ => return Err(ResponseError::invalid_token("invalid key found in the JWKS")),
+        };
+        let key = DecodingKey::from_rsa_components(n, e);
+
+        let mut validation = Validation::new(header.alg);
+        validation.iss = Some(self.issuer.clone());
+        if let Some(audience) = &self.audience {
+            validation.set_audience(&[audience]);
+        }
+
+        let data = decode::<Claims>(token, &key, &validation).map_err(ResponseError::invalid_token)?;
+        Ok(data.claims)
+    }
+}
+
+fn find_key(keys: &[Jwk], kid: Option<&str>) -> Option<Jwk> {
+    keys.iter()
+        .filter(|key| key.kty == "RSA")
+        .find(|key| kid.is_none() || key.kid.as_deref() == kid)
+        .cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn claims_allow_routes() {
+        let claims = Claims {
+            allowed_indexes: Some(vec!["movies".to_string()]),
+            actions: vec!["private".to_string()],
+            sub: None,
+        };
+
+        assert!(claims.allows(&Authentication::Public, Some("movies")));
+        assert!(claims.allows(&Authentication::Private, Some("movies")));
+        assert!(!claims.allows(&Authentication::Private, None));
+        assert!(!claims.allows(&Authentication::Admin, Some("movies")));
+        assert!(!claims.allows(&Authentication::Private, Some("books")));
+
+        let claims = Claims { allowed_indexes: Some(vec!["*".to_string()]), actions: vec!["private".to_string()], sub: None };
+        assert!(claims.allows(&Authentication::Private, None));
+
+        let claims = Claims { allowed_indexes: None, actions: vec!["public".to_string()], sub: None };
+        assert!(claims.allows(&Authentication::Public, Some("books")));
+        assert!(claims.allows(&Authentication::Public, None));
+        assert!(!claims.allows(&Authentication::Private, Some("books")));
+    }
+
+    #[test]
+    fn jwks_not_refetched_too_often() {
+        // nothing listens on this port, the fetch fails right away
+        let validator = JwtValidator::new("http://127.0.0.1:1".to_string(), None);
+
+        match validator.key(Some("unknown")) {
+            Err(ResponseError::Internal(_)) => (),
+            other => panic!("unexpected result {:?}", other.map(|key| key.kid)),
+        }
+
+        // the JWKS is not fetched again, the key is refused
+        match validator.key(Some("unknown")) {
+            Err(ResponseError::InvalidToken(_)) => (),
+            other => panic!("unexpected result {:?}", other.map(|key| key.kid)),
+        }
+    }
+}
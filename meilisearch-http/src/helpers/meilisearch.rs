@@ -8,8 +8,9 @@ use log::error;
 use meilisearch_core::Filter;
 use meilisearch_core::facets::FacetFilter;
 use meilisearch_core::criterion::*;
-use meilisearch_core::settings::RankingRule;
-use meilisearch_core::{Highlight, Index, MainT, RankedMap};
+use meilisearch_core::settings::{RankingRule, DEFAULT_RANKING_RULES};
+use meilisearch_core::update::value_to_string;
+use meilisearch_core::{stopped_words, AutomatonGenerationStats, Highlight, Index, MainT, RankedMap, DEFAULT_MIN_PREFIX_LEN};
 use meilisearch_schema::{FieldId, Schema};
 use meilisearch_tokenizer::is_cjk;
 use serde::{Deserialize, Serialize};
@@ -31,28 +32,56 @@ impl IndexSearchExt for Index {
             offset: 0,
             limit: 20,
             attributes_to_crop: None,
+            crop_strategy: CropStrategy::default(),
             attributes_to_retrieve: None,
             attributes_to_highlight: None,
             filters: None,
             matches: false,
             facet_filters: None,
             facets: None,
+            explain_stop_words: false,
+            explain: false,
+            ranking_score: false,
+            min_prefix_len: DEFAULT_MIN_PREFIX_LEN,
+            minimum_should_match: None,
         }
     }
 }
 
+/// How the cropped attributes are cut around their first match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CropStrategy {
+    /// Keeps the words around the match, up to the crop length of the attribute.
+    Word,
+    /// Keeps the whole sentence of the match, whatever the crop length.
+    Sentence,
+}
+
+impl Default for CropStrategy {
+    fn default() -> CropStrategy {
+        CropStrategy::Word
+    }
+}
+
 pub struct SearchBuilder<'a> {
     index: &'a Index,
     query: String,
     offset: usize,
     limit: usize,
     attributes_to_crop: Option<HashMap<String, usize>>,
+    crop_strategy: CropStrategy,
     attributes_to_retrieve: Option<HashSet<String>>,
     attributes_to_highlight: Option<HashSet<String>>,
     filters: Option<String>,
     matches: bool,
     facet_filters: Option<FacetFilter>,
-    facets: Option<Vec<(FieldId, String)>>
+    facets: Option<Vec<(FieldId, String)>>,
+    explain_stop_words: bool,
+    explain: bool,
+    ranking_score: bool,
+    min_prefix_len: usize,
+    minimum_should_match: Option<f32>,
 }
 
 impl<'a> SearchBuilder<'a> {
@@ -71,6 +100,11 @@ impl<'a> SearchBuilder<'a> {
         self
     }
 
+    pub fn crop_strategy(&mut self, value: CropStrategy) -> &SearchBuilder {
+        self.crop_strategy = value;
+        self
+    }
+
     pub fn attributes_to_retrieve(&mut self, value: HashSet<String>) -> &SearchBuilder {
         self.attributes_to_retrieve = Some(value);
         self
@@ -107,6 +141,31 @@ impl<'a> SearchBuilder<'a> {
         self
     }
 
+    pub fn explain_stop_words(&mut self) -> &SearchBuilder {
+        self.explain_stop_words = true;
+        self
+    }
+
+    pub fn explain(&mut self) -> &SearchBuilder {
+        self.explain = true;
+        self
+    }
+
+    pub fn ranking_score(&mut self) -> &SearchBuilder {
+        self.ranking_score = true;
+        self
+    }
+
+    pub fn min_prefix_len(&mut self, value: usize) -> &SearchBuilder {
+        self.min_prefix_len = value;
+        self
+    }
+
+    pub fn minimum_should_match(&mut self, value: f32) -> &SearchBuilder {
+        self.minimum_should_match = Some(value);
+        self
+    }
+
     pub fn search(self, reader: &heed::RoTxn<MainT>) -> Result<SearchResult, ResponseError> {
         let schema = self
             .index
@@ -142,7 +201,8 @@ impl<'a> SearchBuilder<'a> {
             if let Some(field_id) = schema.id(&field) {
                 let index = &self.index;
                 query_builder.with_distinct(1, move |id| {
-                    match index.document_attribute_bytes(reader, id, field_id) {
+                    // the encrypted values are decrypted, their encrypted bytes always differ
+                    match index.decrypted_document_attribute_bytes(reader, id, field_id) {
                         Ok(Some(bytes)) => {
                             let mut s = SipHasher::new();
                             bytes.hash(&mut s);
@@ -154,6 +214,11 @@ impl<'a> SearchBuilder<'a> {
             }
         }
 
+        query_builder.with_min_prefix_len(self.min_prefix_len);
+        if let Some(fraction) = self.minimum_should_match {
+            query_builder.with_minimum_should_match(fraction);
+        }
+
         query_builder.set_facet_filter(self.facet_filters);
         query_builder.set_facets(self.facets);
 
@@ -208,7 +273,7 @@ impl<'a> SearchBuilder<'a> {
 
             // Crops fields if needed
             if let Some(fields) = &self.attributes_to_crop {
-                crop_document(&mut formatted, &mut matches, &schema, fields);
+                crop_document(&mut formatted, &mut matches, &schema, fields, self.crop_strategy);
             }
 
             // Transform to readable matches
@@ -231,15 +296,31 @@ impl<'a> SearchBuilder<'a> {
                 document.retain(|key, _| attributes_to_retrieve.contains(&key.to_string()))
             }
 
+            let ranking_score = if self.ranking_score {
+                Some(doc.ranking_score.iter().map(|score| score.into_inner()).collect())
+            } else {
+                None
+            };
+
             let hit = SearchHit {
                 document,
                 formatted,
                 matches_info,
+                ranking_score,
             };
 
             hits.push(hit);
         }
 
+        let stopped_words = if self.explain_stop_words {
+            let stop_words = self.index.main.all_stop_words_fst(reader)?;
+            Some(stopped_words(&stop_words, &self.query))
+        } else {
+            None
+        };
+
+        let explain = if self.explain { Some(search_result.automaton_stats) } else { None };
+
         let results = SearchResult {
             hits,
             offset: self.offset,
@@ -249,6 +330,8 @@ impl<'a> SearchBuilder<'a> {
             processing_time_ms: time_ms,
             query: self.query.to_string(),
             facets: search_result.facets,
+            stopped_words,
+            explain,
         };
 
         Ok(results)
@@ -261,6 +344,13 @@ impl<'a> SearchBuilder<'a> {
         schema: &Schema,
     ) -> Result<Option<Criteria<'a>>, ResponseError> {
         let ranking_rules = self.index.main.ranking_rules(reader)?;
+        let attribute_weights = self.index.main.attribute_weights(reader)?.filter(|w| !w.is_empty());
+
+        // the default criteria do not know about the attribute weights
+        let ranking_rules = match (ranking_rules, &attribute_weights) {
+            (None, Some(_)) => Some(DEFAULT_RANKING_RULES.to_vec()),
+            (ranking_rules, _) => ranking_rules,
+        };
 
         if let Some(ranking_rules) = ranking_rules {
             let mut builder = CriteriaBuilder::with_capacity(7 + ranking_rules.len());
@@ -269,7 +359,10 @@ impl<'a> SearchBuilder<'a> {
                     RankingRule::Typo => builder.push(Typo),
                     RankingRule::Words => builder.push(Words),
                     RankingRule::Proximity => builder.push(Proximity),
-                    RankingRule::Attribute => builder.push(Attribute),
+                    RankingRule::Attribute => match &attribute_weights {
+                        Some(weights) => builder.push(WeightedAttribute::new(schema, weights)),
+                        None => builder.push(Attribute),
+                    },
                     RankingRule::WordsPosition => builder.push(WordsPosition),
                     RankingRule::Exactness => builder.push(Exactness),
                     RankingRule::Asc(field) => {
@@ -317,13 +410,17 @@ pub type MatchesInfos = HashMap<String, Vec<MatchPosition>>;
 pub struct SearchHit {
     #[serde(flatten)]
     pub document: IndexMap<String, Value>,
-    #[serde(rename = "_formatted", skip_serializing_if = "IndexMap::is_empty")]
+    #[serde(rename = "_formatted", default, skip_serializing_if = "IndexMap::is_empty")]
     pub formatted: IndexMap<String, Value>,
     #[serde(rename = "_matchesInfo", skip_serializing_if = "Option::is_none")]
     pub matches_info: Option<MatchesInfos>,
+    /// The scores given by the ranking rules, the hits of many indexes
+    /// ranked by the same rules are sorted by comparing them in order.
+    #[serde(rename = "_rankingScore", default, skip_serializing_if = "Option::is_none")]
+    pub ranking_score: Option<Vec<f64>>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SearchResult {
     pub hits: Vec<SearchHit>,
@@ -334,6 +431,10 @@ pub struct SearchResult {
     pub processing_time_ms: usize,
     pub query: String,
     pub facets: Option<HashMap<String, HashMap<String, usize>>>,
+    #[serde(rename = "_stoppedWords", default, skip_serializing_if = "Option::is_none")]
+    pub stopped_words: Option<Vec<String>>,
+    #[serde(rename = "_explain", default, skip_serializing_if = "Option::is_none")]
+    pub explain: Option<AutomatonGenerationStats>,
 }
 
 /// returns the start index and the length on the crop.
@@ -364,15 +465,35 @@ fn aligned_crop(text: &str, match_index: usize, context: usize) -> (usize, usize
     (start, end - start)
 }
 
+/// returns the start index and the length of the sentence of the match,
+/// the sentences end with a `.`, a `!` or a `?`.
+fn sentence_crop(text: &str, match_index: usize) -> (usize, usize) {
+    let is_sentence_end = |c: &char| ['.', '!', '?'].contains(c);
+
+    let chars: Vec<char> = text.chars().collect();
+    let match_index = match_index.min(chars.len());
+
+    let start = chars[..match_index].iter().rposition(is_sentence_end).map_or(0, |i| i + 1);
+    // skip whitespaces if any
+    let start = start + chars[start..].iter().take_while(|c| c.is_whitespace()).count();
+    let end = chars[match_index..].iter().position(is_sentence_end).map_or(chars.len(), |i| match_index + i + 1);
+
+    (start, end.saturating_sub(start))
+}
+
 fn crop_text(
     text: &str,
     matches: impl IntoIterator<Item = Highlight>,
     context: usize,
+    strategy: CropStrategy,
 ) -> (String, Vec<Highlight>) {
     let mut matches = matches.into_iter().peekable();
 
     let char_index = matches.peek().map(|m| m.char_index as usize).unwrap_or(0);
-    let (start, count) = aligned_crop(text, char_index, context);
+    let (start, count) = match strategy {
+        CropStrategy::Word => aligned_crop(text, char_index, context),
+        CropStrategy::Sentence => sentence_crop(text, char_index),
+    };
 
     // TODO do something about double allocation
     let text = text
@@ -400,6 +521,7 @@ fn crop_document(
     matches: &mut Vec<Highlight>,
     schema: &Schema,
     fields: &HashMap<String, usize>,
+    strategy: CropStrategy,
 ) {
     matches.sort_unstable_by_key(|m| (m.char_index, m.char_length));
 
@@ -416,7 +538,7 @@ fn crop_document(
 
         if let Some(Value::String(ref mut original_text)) = document.get_mut(field) {
             let (cropped_text, cropped_matches) =
-                crop_text(original_text, selected_matches, *length);
+                crop_text(original_text, selected_matches, *length, strategy);
 
             *original_text = cropped_text;
 
@@ -464,6 +586,69 @@ fn calculate_matches(
     matches_result
 }
 
+/// Surrounds the matched parts of the text with `<em>` tags, the matches
+/// positions are expressed in chars and relative to the start of the text.
+fn highlight_text(text: &str, matches: &[MatchPosition]) -> String {
+    let value: Vec<_> = text.chars().collect();
+    let mut highlighted_value = String::new();
+    let mut index = 0;
+
+    let longest_matches = matches
+        .linear_group_by_key(|m| m.start)
+        .map(|group| group.last().unwrap())
+        .filter(move |m| m.start >= index);
+
+    for m in longest_matches {
+        let before = value.get(index..m.start);
+        let highlighted = value.get(m.start..(m.start + m.length));
+        if let (Some(before), Some(highlighted)) = (before, highlighted) {
+            highlighted_value.extend(before);
+            highlighted_value.push_str("<em>");
+            highlighted_value.extend(highlighted);
+            highlighted_value.push_str("</em>");
+            index = m.start + m.length;
+        } else {
+            error!("value: {:?}; index: {:?}, match: {:?}", value, index, m);
+        }
+    }
+    highlighted_value.extend(value[index..].iter());
+    highlighted_value
+}
+
+/// Highlights every string element of the array individually, non-string elements are
+/// left unchanged. Arrays are indexed as their elements joined by `". "`, the matches
+/// positions are rebased on every element accordingly.
+fn highlight_array(array: &[Value], matches: &[MatchPosition], offset: &mut usize) -> Vec<Value> {
+    let separator_len = 2;
+    let mut highlighted = Vec::with_capacity(array.len());
+
+    for value in array {
+        match value {
+            Value::String(text) => {
+                let len = text.chars().count();
+                let element_matches: Vec<_> = matches
+                    .iter()
+                    .filter(|m| m.start >= *offset && m.start + m.length <= *offset + len)
+                    .map(|m| MatchPosition { start: m.start - *offset, length: m.length })
+                    .collect();
+
+                highlighted.push(Value::String(highlight_text(text, &element_matches)));
+                *offset += len;
+            }
+            Value::Array(inner) => {
+                highlighted.push(Value::Array(highlight_array(inner, matches, offset)));
+            }
+            other => {
+                *offset += value_to_string(other).chars().count();
+                highlighted.push(other.clone());
+            }
+        }
+        *offset += separator_len;
+    }
+
+    highlighted
+}
+
 fn calculate_highlights(
     document: &IndexMap<String, Value>,
     matches: &MatchesInfos,
@@ -473,32 +658,17 @@ fn calculate_highlights(
 
     for (attribute, matches) in matches.iter() {
         if attributes_to_highlight.contains(attribute) {
-            if let Some(Value::String(value)) = document.get(attribute) {
-                let value: Vec<_> = value.chars().collect();
-                let mut highlighted_value = String::new();
-                let mut index = 0;
-
-                let longest_matches = matches
-                    .linear_group_by_key(|m| m.start)
-                    .map(|group| group.last().unwrap())
-                    .filter(move |m| m.start >= index);
-
-                for m in longest_matches {
-                    let before = value.get(index..m.start);
-                    let highlighted = value.get(m.start..(m.start + m.length));
-                    if let (Some(before), Some(highlighted)) = (before, highlighted) {
-                        highlighted_value.extend(before);
-                        highlighted_value.push_str("<em>");
-                        highlighted_value.extend(highlighted);
-                        highlighted_value.push_str("</em>");
-                        index = m.start + m.length;
-                    } else {
-                        error!("value: {:?}; index: {:?}, match: {:?}", value, index, m);
-                    }
+            match document.get(attribute) {
+                Some(Value::String(value)) => {
+                    let highlighted_value = highlight_text(value, matches);
+                    highlight_result.insert(attribute.to_string(), Value::String(highlighted_value));
                 }
-                highlighted_value.extend(value[index..].iter());
-                highlight_result.insert(attribute.to_string(), Value::String(highlighted_value));
-            };
+                Some(Value::Array(array)) => {
+                    let highlighted_array = highlight_array(array, matches, &mut 0);
+                    highlight_result.insert(attribute.to_string(), Value::Array(highlighted_array));
+                }
+                _ => (),
+            }
         }
     }
     highlight_result
@@ -540,6 +710,27 @@ mod tests {
         assert_eq!("の", cropped);
     }
 
+    #[test]
+    fn sentence_crops() {
+        let text = "Il était une fois. Un jeune homme partit en voyage! Le reste est connu";
+
+        let (start, length) = sentence_crop(&text, 22);
+        let cropped: String = text.chars().skip(start).take(length).collect();
+        assert_eq!("Un jeune homme partit en voyage!", cropped);
+
+        let (start, length) = sentence_crop(&text, 0);
+        let cropped: String = text.chars().skip(start).take(length).collect();
+        assert_eq!("Il était une fois.", cropped);
+
+        // the last sentence is not terminated
+        let (start, length) = sentence_crop(&text, 55);
+        let cropped: String = text.chars().skip(start).take(length).collect();
+        assert_eq!("Le reste est connu", cropped);
+
+        let (matched, _) = crop_text(&text, vec![Highlight { attribute: 0, char_index: 22, char_length: 5 }], 2, CropStrategy::Sentence);
+        assert_eq!("Un jeune homme partit en voyage!", matched);
+    }
+
     #[test]
     fn calculate_matches() {
         let mut matches = Vec::new();
@@ -641,4 +832,33 @@ mod tests {
 
         assert_eq!(result, result_expected);
     }
+
+    #[test]
+    fn highlight_array_elements() {
+        let data = r#"{
+            "genres": ["Science fiction", 1951, "Fiction", null, ["Classic fiction"]]
+        }"#;
+
+        let document: IndexMap<String, Value> = serde_json::from_str(data).unwrap();
+        let mut attributes_to_highlight = HashSet::new();
+        attributes_to_highlight.insert("genres".to_string());
+
+        // the array is indexed as "Science fiction. 1951. Fiction. . Classic fiction. . "
+        let mut matches = HashMap::new();
+        let mut m = Vec::new();
+        m.push(MatchPosition { start: 8, length: 7 });
+        m.push(MatchPosition { start: 23, length: 7 });
+        m.push(MatchPosition { start: 42, length: 7 });
+        matches.insert("genres".to_string(), m);
+
+        let result = super::calculate_highlights(&document, &matches, &attributes_to_highlight);
+
+        let mut result_expected = IndexMap::new();
+        result_expected.insert(
+            "genres".to_string(),
+            serde_json::json!(["Science <em>fiction</em>", 1951, "<em>Fiction</em>", null, ["Classic <em>fiction</em>"]]),
+        );
+
+        assert_eq!(result, result_expected);
+    }
 }
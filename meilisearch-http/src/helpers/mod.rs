@@ -1,6 +1,19 @@
 pub mod authentication;
+pub mod base_path_logger;
+pub mod decompression;
+pub mod fan_out;
+pub mod jwt;
 pub mod meilisearch;
 pub mod normalize_slashes;
+pub mod request_id;
+pub mod search_cache;
+pub mod strict_json;
 
-pub use authentication::Authentication;
+pub use authentication::{Actor, Authentication};
+pub use base_path_logger::BasePathLogger;
+pub use decompression::Decompression;
+pub use jwt::JwtValidator;
 pub use normalize_slashes::NormalizeSlashes;
+pub use request_id::RequestId;
+pub use search_cache::SearchCache;
+pub use strict_json::StrictJson;
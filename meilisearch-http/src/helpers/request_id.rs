@@ -0,0 +1,128 @@
+/// This middleware gives an id to each request, the `X-Request-ID` header of the request
+/// or a random UUID when it is missing or invalid, and echoes it in the `X-Request-ID`
+/// header of the response.
+///
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use actix_service::{Service, Transform};
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::{dev::ServiceRequest, dev::ServiceResponse, Error as ActixError};
+use futures::future::{ok, Future, Ready};
+use rand::Rng;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// The maximum length of the request ids accepted from the clients.
+const MAX_REQUEST_ID_LEN: usize = 200;
+
+fn is_valid_request_id(id: &str) -> bool {
+    !id.is_empty() && id.len() <= MAX_REQUEST_ID_LEN && id.bytes().all(|b| b.is_ascii_graphic())
+}
+
+/// Generates a random (version 4) UUID.
+fn generate_request_id() -> String {
+    let mut bytes: [u8; 16] = rand::thread_rng().gen();
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..])
+}
+
+pub struct RequestId;
+
+impl<S, B> Transform<S> for RequestId
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = ActixError>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = ActixError;
+    type InitError = ();
+    type Transform = RequestIdentification<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(RequestIdentification { service })
+    }
+}
+
+pub struct RequestIdentification<S> {
+    service: S,
+}
+
+impl<S, B> Service for RequestIdentification<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = ActixError>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = ActixError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    fn poll_ready(&mut self, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        let request_id = req
+            .headers()
+            .get(REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .filter(|id| is_valid_request_id(id))
+            .map(|id| id.to_string())
+            .unwrap_or_else(generate_request_id);
+
+        let fut = self.service.call(req);
+
+        Box::pin(async move {
+            let mut res = fut.await?;
+            if let Ok(value) = HeaderValue::from_str(&request_id) {
+                res.headers_mut().insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+            }
+            Ok(res)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use actix_web::{test, web, App, HttpResponse};
+
+    #[test]
+    fn generated_request_ids_are_uuids() {
+        let id = generate_request_id();
+        assert_eq!(id.len(), 36);
+        assert_eq!(id.as_bytes()[14], b'4');
+        assert!(is_valid_request_id(&id));
+        assert_ne!(id, generate_request_id());
+
+        assert!(!is_valid_request_id(""));
+        assert!(!is_valid_request_id("with space"));
+        assert!(!is_valid_request_id(&"a".repeat(MAX_REQUEST_ID_LEN + 1)));
+    }
+
+    #[actix_rt::test]
+    async fn request_id_echoed_in_response() {
+        let app = App::new()
+            .wrap(RequestId)
+            .route("/", web::get().to(|| async { HttpResponse::Ok() }));
+        let mut app = test::init_service(app).await;
+
+        let req = test::TestRequest::get().uri("/").header(REQUEST_ID_HEADER, "my-request").to_request();
+        let res = test::call_service(&mut app, req).await;
+        assert_eq!(res.headers().get(REQUEST_ID_HEADER).unwrap(), "my-request");
+
+        let req = test::TestRequest::get().uri("/").to_request();
+        let res = test::call_service(&mut app, req).await;
+        let id = res.headers().get(REQUEST_ID_HEADER).unwrap().to_str().unwrap();
+        assert!(is_valid_request_id(id));
+    }
+}
@@ -0,0 +1,237 @@
+/// The caches of the search results, an in-process LRU cache for the single node deployments
+/// which can be backed by Redis to share the results between the nodes of a deployment.
+///
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use log::warn;
+use lru::LruCache;
+
+use crate::helpers::meilisearch::SearchResult;
+
+/// The results stored in Redis expire after this number of seconds, the results
+/// of the previous states of the indexes are never requested again.
+const REDIS_TTL_SECS: usize = 60 * 60;
+
+const REDIS_CONNECTION_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Once Redis could not be reached, the searches don't try to connect again before
+/// this interval elapsed, they are considered as cache misses.
+const REDIS_RECONNECT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// What the results of a search depend on, they are outdated once an update has been
+/// processed or once the index has been deleted and created again.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CacheKey {
+    pub index_uid: String,
+    pub created_at: Option<DateTime<Utc>>,
+    pub last_update_id: Option<u64>,
+    pub query_string: String,
+}
+
+impl CacheKey {
+    fn redis_key(&self) -> String {
+        format!(
+            "meilisearch:search:{}:{}:{}:{}",
+            self.index_uid,
+            self.created_at.map_or(0, |date| date.timestamp_nanos()),
+            self.last_update_id.map_or(0, |id| id + 1),
+            self.query_string,
+        )
+    }
+}
+
+pub trait SearchCache: Send + Sync {
+    fn get(&self, key: &CacheKey) -> Option<SearchResult>;
+    fn set(&self, key: CacheKey, value: SearchResult);
+}
+
+/// Keeps the results of the most recently executed searches in memory.
+pub struct LruSearchCache {
+    inner: Mutex<LruCache<CacheKey, SearchResult>>,
+}
+
+impl LruSearchCache {
+    pub fn new(capacity: usize) -> LruSearchCache {
+        LruSearchCache { inner: Mutex::new(LruCache::new(capacity)) }
+    }
+}
+
+impl SearchCache for LruSearchCache {
+    fn get(&self, key: &CacheKey) -> Option<SearchResult> {
+        self.inner.lock().unwrap().get(key).cloned()
+    }
+
+    fn set(&self, key: CacheKey, value: SearchResult) {
+        self.inner.lock().unwrap().put(key, value);
+    }
+}
+
+/// Stores the results in Redis, serialized in JSON. The Redis errors are logged and
+/// considered as cache misses, the searches are executed.
+///
+/// The queries are blocking, the cache must be used from a blocking thread.
+pub struct RedisSearchCache {
+    client: redis::Client,
+    state: Mutex<RedisState>,
+}
+
+#[derive(Default)]
+struct RedisState {
+    connection: Option<redis::Connection>,
+    /// When the connection failed, the next connection isn't tried before this instant.
+    retry_at: Option<Instant>,
+}
+
+impl RedisSearchCache {
+    pub fn new(url: &str) -> redis::RedisResult<RedisSearchCache> {
+        let client = redis::Client::open(url)?;
+        Ok(RedisSearchCache { client, state: Mutex::new(RedisState::default()) })
+    }
+
+    /// Returns `None` without querying Redis while the reconnection is delayed.
+    fn query<T: redis::FromRedisValue>(&self, cmd: &redis::Cmd) -> Option<redis::RedisResult<T>> {
+        let mut state = self.state.lock().unwrap();
+        if state.connection.is_none() {
+            if state.retry_at.map_or(false, |retry_at| Instant::now() < retry_at) {
+                return None;
+            }
+
+            match self.client.get_connection_with_timeout(REDIS_CONNECTION_TIMEOUT) {
+                Ok(connection) => {
+                    state.connection = Some(connection);
+                    state.retry_at = None;
+                }
+                Err(e) => {
+                    state.retry_at = Some(Instant::now() + REDIS_RECONNECT_INTERVAL);
+                    return Some(Err(e));
+                }
+            }
+        }
+
+        let result = cmd.query(state.connection.as_mut().unwrap());
+        // the connection is opened again by the next query
+        if result.is_err() {
+            state.connection = None;
+        }
+
+        Some(result)
+    }
+}
+
+impl SearchCache for RedisSearchCache {
+    fn get(&self, key: &CacheKey) -> Option<SearchResult> {
+        let value: Option<String> = match self.query(redis::cmd("GET").arg(key.redis_key()))? {
+            Ok(value) => value,
+            Err(e) => {
+                warn!("Impossible to retrieve the cached search results; {}", e);
+                None
+            }
+        };
+
+        value.and_then(|value| serde_json::from_str(&value).ok())
+    }
+
+    fn set(&self, key: CacheKey, value: SearchResult) {
+        let value = match serde_json::to_string(&value) {
+            Ok(value) => value,
+            Err(_) => return,
+        };
+
+        let mut cmd = redis::cmd("SET");
+        cmd.arg(key.redis_key()).arg(value).arg("EX").arg(REDIS_TTL_SECS);
+        if let Some(Err(e)) = self.query::<()>(&cmd) {
+            warn!("Impossible to cache the search results; {}", e);
+        }
+    }
+}
+
+/// Looks up the in-process cache first and then the shared one, the results
+/// found in the shared cache are also kept in the in-process one.
+pub struct TieredSearchCache {
+    local: LruSearchCache,
+    shared: RedisSearchCache,
+}
+
+impl TieredSearchCache {
+    pub fn new(local: LruSearchCache, shared: RedisSearchCache) -> TieredSearchCache {
+        TieredSearchCache { local, shared }
+    }
+}
+
+impl SearchCache for TieredSearchCache {
+    fn get(&self, key: &CacheKey) -> Option<SearchResult> {
+        if let Some(value) = self.local.get(key) {
+            return Some(value);
+        }
+
+        let value = self.shared.get(key)?;
+        self.local.set(key.clone(), value.clone());
+        Some(value)
+    }
+
+    fn set(&self, key: CacheKey, value: SearchResult) {
+        self.shared.set(key.clone(), value.clone());
+        self.local.set(key, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(query_string: &str, last_update_id: Option<u64>) -> CacheKey {
+        CacheKey {
+            index_uid: "movies".to_string(),
+            created_at: None,
+            last_update_id,
+            query_string: query_string.to_string(),
+        }
+    }
+
+    fn result(query: &str) -> SearchResult {
+        SearchResult {
+            hits: Vec::new(),
+            offset: 0,
+            limit: 20,
+            nb_hits: 0,
+            exhaustive_nb_hits: false,
+            processing_time_ms: 0,
+            query: query.to_string(),
+            facets: None,
+            stopped_words: None,
+            explain: None,
+        }
+    }
+
+    #[test]
+    fn least_recently_used_results_evicted() {
+        let cache = LruSearchCache::new(2);
+        cache.set(key("q=hello", None), result("hello"));
+        cache.set(key("q=world", None), result("world"));
+
+        // "hello" is used more recently than "world"
+        assert_eq!(cache.get(&key("q=hello", None)).unwrap().query, "hello");
+        cache.set(key("q=marvin", None), result("marvin"));
+
+        assert!(cache.get(&key("q=world", None)).is_none());
+        assert!(cache.get(&key("q=hello", None)).is_some());
+        assert!(cache.get(&key("q=hello", Some(0))).is_none());
+    }
+
+    #[test]
+    fn unreachable_redis_not_reconnected_every_search() {
+        // nothing listens on this port, the connection is refused
+        let cache = RedisSearchCache::new("redis://127.0.0.1:1").unwrap();
+        assert!(cache.get(&key("q=hello", None)).is_none());
+
+        let retry_at = cache.state.lock().unwrap().retry_at;
+        assert!(retry_at.is_some());
+
+        // the next searches don't try to connect until the interval elapsed
+        cache.set(key("q=hello", None), result("hello"));
+        assert!(cache.get(&key("q=hello", None)).is_none());
+        assert_eq!(cache.state.lock().unwrap().retry_at, retry_at);
+    }
+}
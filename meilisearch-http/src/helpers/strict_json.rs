@@ -0,0 +1,135 @@
+/// A JSON extractor for the request bodies, the bodies are deserialized in the types which
+/// deny the unknown fields and the rejected bodies are answered with all the unknown fields
+/// found at the top level of the body and the missing field serde stopped at.
+///
+use std::ops::Deref;
+
+use actix_web::dev::Payload;
+use actix_web::{web, Error as ActixError, FromRequest, HttpRequest};
+use futures::future::{FutureExt, LocalBoxFuture};
+use serde::de::DeserializeOwned;
+use serde_json::value::RawValue;
+use serde_json::Value;
+
+use crate::error::ResponseError;
+
+pub struct StrictJson<T>(pub T);
+
+impl<T> StrictJson<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for StrictJson<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: DeserializeOwned + 'static> FromRequest for StrictJson<T> {
+    type Error = ActixError;
+    type Future = LocalBoxFuture<'static, Result<Self, ActixError>>;
+    type Config = ();
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        // the payload limits and errors are the ones configured for the json bodies
+        let raw = web::Json::<Box<RawValue>>::from_request(req, payload);
+        async move {
+            let raw = raw.await?.into_inner();
+            from_raw_value(&raw).map(StrictJson).map_err(ActixError::from)
+        }
+        .boxed_local()
+    }
+}
+
+fn from_raw_value<T: DeserializeOwned>(raw: &RawValue) -> Result<T, ResponseError> {
+    match serde_json::from_str(raw.get()) {
+        Ok(value) => Ok(value),
+        Err(error) => {
+            let (unknown_fields, missing_fields) = invalid_fields::<T>(raw);
+            Err(ResponseError::invalid_request_body(format!("Invalid JSON: {}", error), unknown_fields, missing_fields))
+        }
+    }
+}
+
+/// Returns the unknown and missing fields of the body. Serde stops at the first invalid field,
+/// the body is deserialized again without each unknown field it reports.
+fn invalid_fields<T: DeserializeOwned>(raw: &RawValue) -> (Vec<String>, Vec<String>) {
+    let mut unknown_fields = Vec::new();
+    let mut missing_fields = Vec::new();
+
+    let mut body = match serde_json::from_str(raw.get()) {
+        Ok(body @ Value::Object(_)) => body,
+        _ => return (unknown_fields, missing_fields),
+    };
+
+    loop {
+        let message = match T::deserialize(&body) {
+            Ok(_) => break,
+            Err(error) => error.to_string(),
+        };
+
+        if let Some(field) = quoted_field(&message, "unknown field `") {
+            // the unknown fields of the nested objects are not removed
+            match body.as_object_mut().and_then(|object| object.remove(field)) {
+                Some(_) => unknown_fields.push(field.to_string()),
+                None => break,
+            }
+        } else {
+            if let Some(field) = quoted_field(&message, "missing field `") {
+                missing_fields.push(field.to_string());
+            }
+            break;
+        }
+    }
+
+    (unknown_fields, missing_fields)
+}
+
+fn quoted_field<'a>(message: &'a str, prefix: &str) -> Option<&'a str> {
+    let start = message.find(prefix)? + prefix.len();
+    let len = message[start..].find('`')?;
+    Some(&message[start..start + len])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde::Deserialize;
+
+    #[allow(dead_code)]
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "camelCase", deny_unknown_fields)]
+    struct Body {
+        source_index: String,
+        limit: Option<usize>,
+    }
+
+    fn raw_value(json: &str) -> Box<RawValue> {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn all_unknown_fields_reported() {
+        let raw = raw_value(r#"{ "sourceIndex": "movies", "foo": 1, "bar": true }"#);
+        let (unknown_fields, missing_fields) = invalid_fields::<Body>(&raw);
+        assert_eq!(unknown_fields, vec!["foo", "bar"]);
+        assert!(missing_fields.is_empty());
+
+        let raw = raw_value(r#"{ "foo": 1, "limit": 2 }"#);
+        let (unknown_fields, missing_fields) = invalid_fields::<Body>(&raw);
+        assert_eq!(unknown_fields, vec!["foo"]);
+        assert_eq!(missing_fields, vec!["sourceIndex"]);
+
+        let raw = raw_value(r#"{ "sourceIndex": "movies", "limit": 2 }"#);
+        assert!(from_raw_value::<Body>(&raw).is_ok());
+
+        let raw = raw_value(r#"[{ "foo": 1 }]"#);
+        let (unknown_fields, missing_fields) = invalid_fields::<Body>(&raw);
+        assert!(unknown_fields.is_empty() && missing_fields.is_empty());
+    }
+}
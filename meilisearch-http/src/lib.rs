@@ -1,6 +1,7 @@
 #![allow(clippy::or_fun_call)]
 
 pub mod data;
+pub mod dump;
 pub mod error;
 pub mod helpers;
 pub mod models;
@@ -9,8 +10,10 @@ pub mod routes;
 
 pub use self::data::Data;
 use self::error::json_error_handler;
+use self::helpers::Decompression;
 use actix_http::Error;
 use actix_service::ServiceFactory;
+use actix_web::middleware::{Condition, DefaultHeaders};
 use actix_web::{dev, web, App};
 use log::error;
 use meilisearch_core::ProcessedUpdateResult;
@@ -27,33 +30,52 @@ pub fn create_app(
     >,
     actix_http::body::Body,
 > {
+    let api_version = data.api_version_prefix.trim_start_matches('/').to_string();
+    let api_version_header = DefaultHeaders::new().header("Api-Version", api_version.as_str());
+
     App::new()
+        .wrap(Condition::new(!api_version.is_empty(), api_version_header))
+        .wrap(Decompression::new(data.max_request_body_size_bytes))
         .app_data(web::Data::new(data.clone()))
+        .app_data(web::PayloadConfig::new(data.max_request_body_size_bytes))
         .app_data(
             web::JsonConfig::default()
-                .limit(data.http_payload_size_limit)
+                .limit(data.http_payload_size_limit.min(data.max_request_body_size_bytes))
                 .content_type(|_mime| true) // Accept all mime types
                 .error_handler(|err, _req| json_error_handler(err).into()),
         )
-        .service(routes::load_html)
-        .service(routes::load_css)
-        .configure(routes::document::services)
-        .configure(routes::index::services)
-        .configure(routes::search::services)
-        .configure(routes::setting::services)
-        .configure(routes::stop_words::services)
-        .configure(routes::synonym::services)
-        .configure(routes::health::services)
-        .configure(routes::stats::services)
-        .configure(routes::key::services)
+        .service(
+            web::scope(&format!("{}{}", data.base_path, data.api_version_prefix))
+                .configure(routes::document::services)
+                .configure(routes::index::services)
+                .configure(routes::search::services)
+                .configure(routes::live_search::services)
+                .configure(routes::setting::services)
+                .configure(routes::stop_words::services)
+                .configure(routes::synonym::services)
+                .configure(routes::health::services)
+                .configure(routes::stats::services)
+                .configure(routes::key::services)
+                .configure(routes::openapi::services)
+                .configure(routes::options::services)
+        )
+        // the interface is not part of the API, it is not versioned
+        .service(
+            web::scope(&data.base_path)
+                .service(routes::load_html)
+                .service(routes::load_css)
+        )
+        .default_service(web::route().to(routes::redirect_to_versioned_api))
 }
 
 pub fn index_update_callback(index_uid: &str, data: &Data, status: ProcessedUpdateResult) {
+    data.metrics.record_update(&status);
+
     if status.error.is_some() {
         return;
     }
 
-    if let Some(index) = data.db.open_index(&index_uid) {
+    if data.db.open_index(&index_uid).is_some() {
         let db = &data.db;
         let mut writer = match db.main_write_txn() {
             Ok(writer) => writer,
@@ -71,10 +93,6 @@ pub fn index_update_callback(index_uid: &str, data: &Data, status: ProcessedUpda
             error!("Impossible to update last_update; {}", e)
         }
 
-        if let Err(e) = index.main.put_updated_at(&mut writer) {
-            error!("Impossible to update updated_at; {}", e)
-        }
-
         if let Err(e) = writer.commit() {
             error!("Impossible to get write_txn; {}", e);
         }
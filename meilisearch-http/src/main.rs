@@ -1,16 +1,23 @@
+use std::time::Duration;
 use std::{env, thread};
 
 use actix_cors::Cors;
 use actix_web::{middleware, HttpServer};
+use log::error;
 use main_error::MainError;
+use meilisearch_core::DatabaseOptions;
 use meilisearch_http::data::Data;
-use meilisearch_http::helpers::NormalizeSlashes;
-use meilisearch_http::option::Opt;
+use meilisearch_http::dump::import_dump;
+use meilisearch_http::helpers::{BasePathLogger, NormalizeSlashes, RequestId};
+use meilisearch_http::option::{Command, ListenAddr, Opt};
 use meilisearch_http::{create_app, index_update_callback};
 use structopt::StructOpt;
 
 mod analytics;
 
+/// How often the rotated API keys that expired are removed.
+const EXPIRED_KEYS_REMOVAL_INTERVAL: Duration = Duration::from_secs(60);
+
 #[cfg(target_os = "linux")]
 #[global_allocator]
 static ALLOC: jemallocator::Jemalloc = jemallocator::Jemalloc;
@@ -49,6 +56,27 @@ async fn main() -> Result<(), MainError> {
         _ => unreachable!(),
     }
 
+    match &opt.command {
+        Some(Command::ImportSnapshot { snapshot_path }) => {
+            meilisearch_core::snapshot::restore_snapshot(&opt.db_path, snapshot_path)?;
+            eprintln!("Snapshot {:?} imported into {:?}", snapshot_path, opt.db_path);
+            return Ok(());
+        }
+        Some(Command::ImportDump { dump_path }) => {
+            let db_options = DatabaseOptions {
+                main_map_size: opt.main_map_size,
+                update_map_size: opt.update_map_size,
+                token_limits: opt.token_limits(),
+                field_encryption_key: opt.field_encryption_key,
+                ..DatabaseOptions::default()
+            };
+            import_dump(&opt.db_path, db_options, dump_path)?;
+            eprintln!("Dump {:?} imported into {:?}", dump_path, opt.db_path);
+            return Ok(());
+        }
+        None => (),
+    }
+
     if !opt.no_analytics {
         thread::spawn(analytics::analytics_sender);
     }
@@ -60,27 +88,70 @@ async fn main() -> Result<(), MainError> {
         index_update_callback(name, &data_cloned, status);
     }));
 
+    let data_cloned = data.clone();
+    thread::spawn(move || loop {
+        thread::sleep(EXPIRED_KEYS_REMOVAL_INTERVAL);
+        if let Err(e) = data_cloned.remove_expired_api_keys() {
+            error!("Impossible to remove the expired API keys; {}", e);
+        }
+    });
+
     print_launch_resume(&opt, &data);
 
-    HttpServer::new(move || {
+    let base_path = opt.base_path();
+    let shutdown_timeout = opt.shutdown_timeout_secs;
+    let cors_max_age = opt.cors_max_age_secs;
+    let db = data.db.clone();
+
+    // on SIGTERM the server stops accepting connections and waits for the in-flight requests
+    let server = HttpServer::new(move || {
         create_app(&data)
             .wrap(
                 Cors::new()
                     .send_wildcard()
                     .allowed_header("x-meili-api-key")
+                    .max_age(cors_max_age)
                     .finish(),
             )
-            .wrap(middleware::Logger::default())
+            .wrap(RequestId)
+            .wrap(BasePathLogger::new(&base_path))
             .wrap(middleware::Compress::default())
             .wrap(NormalizeSlashes)
     })
-    .bind(opt.http_addr)?
-    .run()
-    .await?;
+    .shutdown_timeout(shutdown_timeout);
+
+    let server = match &opt.http_addr {
+        ListenAddr::Tcp(addr) => server.bind(*addr)?,
+        #[cfg(unix)]
+        ListenAddr::Unix(path) => {
+            remove_stale_socket(path)?;
+            server.bind_uds(path)?
+        }
+        #[cfg(not(unix))]
+        ListenAddr::Unix(_) => return Err("Unix domain sockets are not supported on this platform".into()),
+    };
+
+    server.run().await?;
+
+    // the updates being processed must be committed before exiting
+    if !db.close(Duration::from_secs(shutdown_timeout)) {
+        log::warn!("Some updates were still being processed after {}s, exiting anyway", shutdown_timeout);
+    }
 
     Ok(())
 }
 
+/// Removes the socket file left by a previous run, binding to it would fail otherwise.
+#[cfg(unix)]
+fn remove_stale_socket(path: &std::path::Path) -> std::io::Result<()> {
+    use std::os::unix::fs::FileTypeExt;
+
+    match std::fs::metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => std::fs::remove_file(path),
+        _ => Ok(()),
+    }
+}
+
 pub fn print_launch_resume(opt: &Opt, data: &Data) {
     let ascii_name = r#"
 888b     d888          d8b 888 d8b  .d8888b.                                    888
@@ -96,7 +167,11 @@ pub fn print_launch_resume(opt: &Opt, data: &Data) {
     eprintln!("{}", ascii_name);
 
     eprintln!("Database path:\t\t{:?}", opt.db_path);
-    eprintln!("Server listening on:\t{:?}", opt.http_addr);
+    eprintln!("Server listening on:\t{:?}", opt.http_addr.to_string());
+    if opt.base_path.is_some() {
+        eprintln!("Base path:\t\t{:?}", opt.base_path());
+    }
+    eprintln!("API version prefix:\t{:?}", opt.api_version_prefix());
     eprintln!("Environment:\t\t{:?}", opt.env);
     eprintln!("Commit SHA:\t\t{:?}", env!("VERGEN_SHA").to_string());
     eprintln!(
@@ -110,7 +185,7 @@ pub fn print_launch_resume(opt: &Opt, data: &Data) {
 
     eprintln!();
 
-    if data.api_keys.master.is_some() {
+    if data.api_keys.read().unwrap().master.is_some() {
         eprintln!("A Master Key has been set. Requests to MeiliSearch won't be authorized unless you provide an authentication key.");
     } else {
         eprintln!("No master key found; The server will accept unidentified requests. \
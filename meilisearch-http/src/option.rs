@@ -1,3 +1,9 @@
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::{fmt, io};
+
+use meilisearch_core::raw_indexer::TokenLimits;
 use structopt::StructOpt;
 
 const POSSIBLE_ENV: [&str; 2] = ["development", "production"];
@@ -8,9 +14,10 @@ pub struct Opt {
     #[structopt(long, env = "MEILI_DB_PATH", default_value = "./data.ms")]
     pub db_path: String,
 
-    /// The address on which the http server will listen.
+    /// The address on which the http server will listen,
+    /// a Unix domain socket path when prefixed by `unix:`, e.g. `unix:/run/meilisearch.sock`.
     #[structopt(long, env = "MEILI_HTTP_ADDR", default_value = "127.0.0.1:7700")]
-    pub http_addr: String,
+    pub http_addr: ListenAddr,
 
     /// The master key allowing you to do everything on the server.
     #[structopt(long, env = "MEILI_MASTER_KEY")]
@@ -38,4 +45,253 @@ pub struct Opt {
     /// The maximum size, in bytes, of accepted JSON payloads
     #[structopt(long, env = "MEILI_HTTP_PAYLOAD_SIZE_LIMIT", default_value = "10485760")] // 10MB
     pub http_payload_size_limit: usize,
+
+    /// The maximum size, in bytes, of any request body, larger bodies are rejected with a 413 error
+    #[structopt(long, env = "MEILI_MAX_REQUEST_BODY_SIZE_BYTES", default_value = "104857600")] // 100MB
+    pub max_request_body_size_bytes: usize,
+
+    /// The maximum number of indexes that can be created, unlimited by default.
+    #[structopt(long, env = "MEILI_MAX_INDEX_COUNT")]
+    pub max_index_count: Option<usize>,
+
+    /// The path prefix under which all the routes are served, e.g. `/search` when behind a reverse proxy.
+    #[structopt(long, env = "MEILI_BASE_PATH")]
+    pub base_path: Option<String>,
+
+    /// The key required to access the metrics route, if not set the private key is required.
+    #[structopt(long, env = "MEILI_METRICS_AUTH_KEY")]
+    pub metrics_auth_key: Option<String>,
+
+    /// Serves the metrics route in the Prometheus text format instead of JSON.
+    #[structopt(long, env = "MEILI_ENABLE_PROMETHEUS_METRICS")]
+    pub enable_prometheus_metrics: bool,
+
+    /// The maximum number of seconds to wait, on shutdown, for the in-flight requests
+    /// to be answered and then for the updates being processed to be committed.
+    #[structopt(long, env = "MEILI_SHUTDOWN_TIMEOUT_SECS", default_value = "30")]
+    pub shutdown_timeout_secs: u64,
+
+    /// The number of characters below which the words of the queries are not
+    /// searched as prefixes, only the words equal to them are matched.
+    #[structopt(long, env = "MEILI_MIN_PREFIX_LEN", default_value = "3")]
+    pub min_prefix_len: usize,
+
+    /// The maximum number of tokens indexed in a field of a document, the tokens
+    /// following them are not indexed and the update reports a warning.
+    #[structopt(long, env = "MEILI_MAX_TOKENS_PER_FIELD", default_value = "1000")]
+    pub max_tokens_per_field: usize,
+
+    /// The maximum number of tokens indexed in all the fields of a document.
+    #[structopt(long, env = "MEILI_MAX_TOKENS_PER_DOCUMENT", default_value = "100000")]
+    pub max_tokens_per_document: usize,
+
+    /// The issuer of the JWT tokens accepted in the `Authorization: Bearer` header,
+    /// the keys are retrieved from its `/.well-known/jwks.json` endpoint.
+    #[structopt(long, env = "MEILI_JWT_ISSUER")]
+    pub jwt_issuer: Option<String>,
+
+    /// The audience the JWT tokens must be issued for.
+    #[structopt(long, env = "MEILI_JWT_AUDIENCE")]
+    pub jwt_audience: Option<String>,
+
+    /// The path prefix versioning the API, the routes are served under it
+    /// and the unversioned paths are redirected to it, empty to serve the API unversioned.
+    #[structopt(long, env = "MEILI_API_VERSION_PREFIX", default_value = "/v1")]
+    pub api_version_prefix: String,
+
+    /// The number of seconds the browsers can cache the result of the CORS preflight requests.
+    #[structopt(long, env = "MEILI_CORS_MAX_AGE_SECS", default_value = "600")]
+    pub cors_max_age_secs: usize,
+
+    /// The maximum number of search requests reading an index concurrently, the requests
+    /// above it are rejected with a 503 error, unlimited by default.
+    #[structopt(long, env = "MEILI_MAX_CONCURRENT_READERS_PER_INDEX")]
+    pub max_concurrent_readers_per_index: Option<usize>,
+
+    /// The number of read transactions recycled by the search requests, none by default.
+    #[structopt(long, env = "MEILI_READ_TXN_POOL_SIZE", default_value = "0")]
+    pub read_txn_pool_size: usize,
+
+    /// The number of milliseconds after which a recycled read transaction is replaced,
+    /// the searches can miss the changes made more recently than that.
+    #[structopt(long, env = "MEILI_READ_TXN_MAX_AGE_MS", default_value = "1000")]
+    pub read_txn_max_age_ms: u64,
+
+    /// The number of words after which the documents additions commit the postings lists they
+    /// have written, the transactions of large additions stay small, the failed additions are rolled back.
+    #[structopt(long, env = "MEILI_INDEXING_CHECKPOINT_INTERVAL")]
+    pub indexing_checkpoint_interval: Option<usize>,
+
+    /// The key encrypting the values of the encrypted attributes, 64 hexadecimal characters.
+    #[structopt(long, env = "MEILI_FIELD_ENCRYPTION_KEY", parse(try_from_str = parse_field_encryption_key))]
+    pub field_encryption_key: Option<[u8; 32]>,
+
+    /// Caches the search results, `memory` to keep them in memory or a `redis://` URL to also share
+    /// them with the other instances through Redis, the results are not cached by default.
+    #[structopt(long, env = "MEILI_CACHE_BACKEND")]
+    pub cache_backend: Option<CacheBackend>,
+
+    /// The number of search results kept in memory by the cache.
+    #[structopt(long, env = "MEILI_CACHE_SIZE", default_value = "1000")]
+    pub cache_size: usize,
+
+    /// The URLs of the MeiliSearch instances holding the other shards of the indexes, comma separated
+    /// and with their API version prefix, e.g. `http://10.0.0.2:7700/v1`. The searches are sent
+    /// to all of them and their hits are merged with the local ones, none by default.
+    #[structopt(long, env = "MEILI_REMOTE_SHARDS", use_delimiter = true)]
+    pub remote_shards: Vec<String>,
+
+    /// The API key sent to the remote shards.
+    #[structopt(long, env = "MEILI_REMOTE_SHARDS_API_KEY")]
+    pub remote_shards_api_key: Option<String>,
+
+    #[structopt(subcommand)]
+    pub command: Option<Command>,
+}
+
+fn parse_field_encryption_key(s: &str) -> Result<[u8; 32], String> {
+    let error = || "the field encryption key must be 64 hexadecimal characters".to_string();
+    if s.len() != 64 || !s.is_ascii() {
+        return Err(error());
+    }
+
+    let mut key = [0; 32];
+    for (byte, hex) in key.iter_mut().zip(s.as_bytes().chunks(2)) {
+        let hex = std::str::from_utf8(hex).map_err(|_| error())?;
+        *byte = u8::from_str_radix(hex, 16).map_err(|_| error())?;
+    }
+
+    Ok(key)
+}
+
+/// The address the http server listens on, a TCP socket or a Unix domain socket.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ListenAddr {
+    Tcp(SocketAddr),
+    Unix(PathBuf),
+}
+
+impl FromStr for ListenAddr {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> io::Result<ListenAddr> {
+        if s.starts_with("unix:") {
+            let path = &s["unix:".len()..];
+            if path.is_empty() {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, "empty Unix socket path"));
+            }
+            return Ok(ListenAddr::Unix(PathBuf::from(path)));
+        }
+
+        // the host names are resolved, the first address is used
+        match s.to_socket_addrs()?.next() {
+            Some(addr) => Ok(ListenAddr::Tcp(addr)),
+            None => Err(io::Error::new(io::ErrorKind::InvalidInput, format!("could not resolve {}", s))),
+        }
+    }
+}
+
+impl fmt::Display for ListenAddr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ListenAddr::Tcp(addr) => write!(f, "{}", addr),
+            ListenAddr::Unix(path) => write!(f, "unix:{}", path.display()),
+        }
+    }
+}
+
+/// Where the search results are cached.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CacheBackend {
+    Memory,
+    /// The URL of the Redis server, the results are also kept in memory.
+    Redis(String),
+}
+
+impl FromStr for CacheBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<CacheBackend, String> {
+        if s == "memory" {
+            return Ok(CacheBackend::Memory);
+        }
+
+        match redis::Client::open(s) {
+            Ok(_) => Ok(CacheBackend::Redis(s.to_string())),
+            Err(_) => Err(format!("the cache backend must be `memory` or a Redis URL, not {:?}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, StructOpt)]
+pub enum Command {
+    /// Replaces the database by the content of a snapshot and exits.
+    ImportSnapshot {
+        /// The snapshot directory to import.
+        #[structopt(long)]
+        snapshot_path: PathBuf,
+    },
+
+    /// Creates the indexes of a dump, with their settings and documents, in an empty database and exits.
+    ImportDump {
+        /// The dump directory to import.
+        #[structopt(long)]
+        dump_path: PathBuf,
+    },
+}
+
+impl Opt {
+    pub fn token_limits(&self) -> TokenLimits {
+        TokenLimits {
+            max_tokens_per_field: self.max_tokens_per_field,
+            max_tokens_per_document: self.max_tokens_per_document,
+        }
+    }
+
+    /// Returns the base path with a leading slash and without a trailing one,
+    /// an empty string is returned when no base path is set.
+    pub fn base_path(&self) -> String {
+        match &self.base_path {
+            Some(path) => {
+                let path = path.trim_matches('/');
+                if path.is_empty() { String::new() } else { format!("/{}", path) }
+            }
+            None => String::new(),
+        }
+    }
+
+    /// Returns the API version prefix with a leading slash and without a trailing one,
+    /// an empty string is returned when the API is not versioned.
+    pub fn api_version_prefix(&self) -> String {
+        let prefix = self.api_version_prefix.trim_matches('/');
+        if prefix.is_empty() { String::new() } else { format!("/{}", prefix) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_listen_addr() {
+        let addr: ListenAddr = "127.0.0.1:7700".parse().unwrap();
+        assert_eq!(addr, ListenAddr::Tcp("127.0.0.1:7700".parse().unwrap()));
+        assert_eq!(addr.to_string(), "127.0.0.1:7700");
+
+        let addr: ListenAddr = "unix:/run/meilisearch.sock".parse().unwrap();
+        assert_eq!(addr, ListenAddr::Unix(PathBuf::from("/run/meilisearch.sock")));
+        assert_eq!(addr.to_string(), "unix:/run/meilisearch.sock");
+
+        assert!("unix:".parse::<ListenAddr>().is_err());
+        assert!("127.0.0.1".parse::<ListenAddr>().is_err());
+    }
+
+    #[test]
+    fn parse_encryption_key() {
+        let key = parse_field_encryption_key(&"0f".repeat(32)).unwrap();
+        assert_eq!(key, [0x0f; 32]);
+
+        assert!(parse_field_encryption_key(&"0f".repeat(31)).is_err());
+        assert!(parse_field_encryption_key(&"zz".repeat(32)).is_err());
+    }
 }
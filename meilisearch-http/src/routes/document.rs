@@ -1,19 +1,28 @@
 use std::collections::{BTreeSet, HashSet};
 
-use actix_web::{web, HttpResponse};
+use actix_web::web::Bytes;
+use actix_web::{web, HttpRequest, HttpResponse};
 use actix_web_macros::{delete, get, post, put};
+use futures::stream;
 use indexmap::IndexMap;
-use meilisearch_core::update;
+use meilisearch_core::update::{self, DuplicatePolicy};
+use meilisearch_core::{DocumentId, Index};
 use serde::Deserialize;
 use serde_json::Value;
 
 use crate::error::ResponseError;
 use crate::helpers::Authentication;
-use crate::routes::{IndexParam, IndexUpdateResponse};
+use crate::routes::{not_modified_since, revalidate, IndexParam, IndexUpdateResponse};
 use crate::Data;
 
 type Document = IndexMap<String, Value>;
 
+/// The id of the update enqueued by a document mutation.
+const LAST_UPDATE_ID_HEADER: &str = "X-Last-Update-Id";
+
+/// The document updates are rejected if an update newer than this one has been processed.
+const IF_UNMODIFIED_SINCE_HEADER: &str = "If-Unmodified-Since-Update-Id";
+
 #[derive(Deserialize)]
 struct DocumentParam {
     index_uid: String,
@@ -21,12 +30,15 @@ struct DocumentParam {
 }
 
 pub fn services(cfg: &mut web::ServiceConfig) {
-    cfg.service(get_document)
+    // must be registered before get_document, `stream` would be taken as a document id
+    cfg.service(stream_documents)
+        .service(get_document)
         .service(delete_document)
         .service(get_all_documents)
         .service(add_documents)
         .service(update_documents)
         .service(delete_documents)
+        .service(get_documents_batch)
         .service(clear_all_documents);
 }
 
@@ -77,7 +89,7 @@ async fn delete_document(
 
     update_writer.commit()?;
 
-    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+    Ok(update_accepted(update_id))
 }
 
 #[derive(Deserialize)]
@@ -90,6 +102,7 @@ struct BrowseQuery {
 
 #[get("/indexes/{index_uid}/documents", wrap = "Authentication::Public")]
 async fn get_all_documents(
+    req: HttpRequest,
     data: web::Data<Data>,
     path: web::Path<IndexParam>,
     params: web::Query<BrowseQuery>,
@@ -104,6 +117,11 @@ async fn get_all_documents(
 
     let reader = data.db.main_read_txn()?;
 
+    let updated_at = index.main.updated_at(&reader)?;
+    if let Some(updated_at) = updated_at.filter(|date| not_modified_since(&req, *date)) {
+        return Ok(revalidate(&mut HttpResponse::NotModified(), updated_at).finish());
+    }
+
     let documents_ids: Result<BTreeSet<_>, _> = index
         .documents_fields_counts
         .documents_ids(&reader)?
@@ -127,7 +145,130 @@ async fn get_all_documents(
         }
     }
 
-    Ok(HttpResponse::Ok().json(response))
+    let mut builder = HttpResponse::Ok();
+    if let Some(updated_at) = updated_at {
+        revalidate(&mut builder, updated_at);
+    }
+
+    Ok(builder.json(response))
+}
+
+/// The number of documents read in a single transaction while streaming.
+const STREAM_CHUNK_SIZE: usize = 100;
+
+struct DocumentsStream {
+    data: web::Data<Data>,
+    index: Index,
+    attributes: Option<HashSet<String>>,
+    next_id: Option<DocumentId>,
+    to_skip: usize,
+    remaining: usize,
+}
+
+impl DocumentsStream {
+    /// Reads the next chunk of documents in a short-lived transaction
+    /// and serializes them as NDJSON, one document by line.
+    fn next_chunk(&mut self) -> Result<Bytes, ResponseError> {
+        let start = match self.next_id {
+            Some(start) => start,
+            None => return Ok(Bytes::new()),
+        };
+
+        let reader = self.data.db.main_read_txn()?;
+        let documents_ids = self.index
+            .documents_fields_counts
+            .documents_ids_from(&reader, start, STREAM_CHUNK_SIZE)?;
+
+        self.next_id = match documents_ids.last() {
+            Some(last) if documents_ids.len() == STREAM_CHUNK_SIZE => last.0.checked_add(1).map(DocumentId),
+            _ => None,
+        };
+
+        let attributes: Option<HashSet<&str>> = self.attributes
+            .as_ref()
+            .map(|a| a.iter().map(String::as_str).collect());
+
+        let mut chunk = Vec::new();
+        for document_id in documents_ids {
+            if self.to_skip > 0 {
+                self.to_skip -= 1;
+                continue;
+            }
+            if self.remaining == 0 {
+                self.next_id = None;
+                break;
+            }
+
+            if let Some(document) = self.index.document::<Document>(&reader, attributes.as_ref(), document_id)? {
+                serde_json::to_writer(&mut chunk, &document).map_err(ResponseError::internal)?;
+                chunk.push(b'\n');
+                self.remaining -= 1;
+            }
+        }
+
+        Ok(Bytes::from(chunk))
+    }
+}
+
+/// Streams the documents as NDJSON, reading them by chunks to keep
+/// the memory usage independent of the number of documents.
+#[get("/indexes/{index_uid}/documents/stream", wrap = "Authentication::Public")]
+async fn stream_documents(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    params: web::Query<BrowseQuery>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let attributes = params
+        .attributes_to_retrieve
+        .as_ref()
+        .map(|a| a.split(',').map(str::to_string).collect());
+
+    let state = DocumentsStream {
+        data: data.clone(),
+        index,
+        attributes,
+        next_id: Some(DocumentId(0)),
+        to_skip: params.offset.unwrap_or(0),
+        remaining: params.limit.unwrap_or(usize::max_value()),
+    };
+
+    let stream = stream::unfold(state, |mut state| async move {
+        state.next_id?;
+        match state.next_chunk() {
+            Ok(chunk) => Some((Ok(chunk), state)),
+            Err(e) => {
+                state.next_id = None;
+                Some((Err(e), state))
+            }
+        }
+    });
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/x-ndjson")
+        .streaming(Box::pin(stream)))
+}
+
+fn update_accepted(update_id: u64) -> HttpResponse {
+    HttpResponse::Accepted()
+        .header(LAST_UPDATE_ID_HEADER, update_id.to_string())
+        .json(IndexUpdateResponse::with_id(update_id))
+}
+
+fn if_unmodified_since(req: &HttpRequest) -> Result<Option<u64>, ResponseError> {
+    match req.headers().get(IF_UNMODIFIED_SINCE_HEADER) {
+        Some(value) => value
+            .to_str()
+            .ok()
+            .and_then(|value| value.trim().parse().ok())
+            .map(Some)
+            .ok_or_else(|| ResponseError::bad_request(format!("Invalid {} header", IF_UNMODIFIED_SINCE_HEADER))),
+        None => Ok(None),
+    }
 }
 
 fn find_primary_key(document: &IndexMap<String, Value>) -> Option<String> {
@@ -143,6 +284,7 @@ fn find_primary_key(document: &IndexMap<String, Value>) -> Option<String> {
 #[serde(rename_all = "camelCase", deny_unknown_fields)]
 struct UpdateDocumentsQuery {
     primary_key: Option<String>,
+    duplicate_policy: Option<DuplicatePolicy>,
 }
 
 async fn update_multiple_documents(
@@ -151,6 +293,7 @@ async fn update_multiple_documents(
     params: web::Query<UpdateDocumentsQuery>,
     body: web::Json<Vec<Document>>,
     is_partial: bool,
+    unmodified_since: Option<u64>,
 ) -> Result<HttpResponse, ResponseError> {
     let index = data
         .db
@@ -192,11 +335,29 @@ async fn update_multiple_documents(
         document_addition.update_document(document);
     }
 
+    if let Some(policy) = params.duplicate_policy {
+        document_addition.duplicate_policy(policy);
+    }
+
     let mut update_writer = data.db.update_write_txn()?;
+
+    // the updates results are written in an update transaction, none can be processed meanwhile
+    if let Some(update_id) = unmodified_since {
+        let last_processed_id = index.updates_results.last_update(&update_writer)?.map(|(id, _)| id);
+        let last_enqueued_id = index.updates.last_update(&update_writer)?.map(|(id, _)| id);
+        let last_update_id = last_processed_id.max(last_enqueued_id);
+        if let Some(last_update_id) = last_update_id.filter(|id| *id > update_id) {
+            return Err(ResponseError::precondition_failed(format!(
+                "the update {} has been enqueued or processed since the update {}",
+                last_update_id, update_id,
+            )));
+        }
+    }
+
     let update_id = document_addition.finalize(&mut update_writer)?;
     update_writer.commit()?;
 
-    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+    Ok(update_accepted(update_id))
 }
 
 #[post("/indexes/{index_uid}/documents", wrap = "Authentication::Private")]
@@ -206,17 +367,19 @@ async fn add_documents(
     params: web::Query<UpdateDocumentsQuery>,
     body: web::Json<Vec<Document>>,
 ) -> Result<HttpResponse, ResponseError> {
-    update_multiple_documents(data, path, params, body, false).await
+    update_multiple_documents(data, path, params, body, false, None).await
 }
 
 #[put("/indexes/{index_uid}/documents", wrap = "Authentication::Private")]
 async fn update_documents(
+    req: HttpRequest,
     data: web::Data<Data>,
     path: web::Path<IndexParam>,
     params: web::Query<UpdateDocumentsQuery>,
     body: web::Json<Vec<Document>>,
 ) -> Result<HttpResponse, ResponseError> {
-    update_multiple_documents(data, path, params, body, true).await
+    let unmodified_since = if_unmodified_since(&req)?;
+    update_multiple_documents(data, path, params, body, true, unmodified_since).await
 }
 
 #[post(
@@ -246,7 +409,37 @@ async fn delete_documents(
 
     writer.commit()?;
 
-    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+    Ok(update_accepted(update_id))
+}
+
+/// Returns the documents with the given ids in the same order, the unknown ids are ignored.
+#[post(
+    "/indexes/{index_uid}/documents/batch-get",
+    wrap = "Authentication::Public"
+)]
+async fn get_documents_batch(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    body: web::Json<Vec<Value>>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let external_ids: Vec<String> = body.iter().map(update::value_to_string).collect();
+    let external_ids: Vec<&str> = external_ids.iter().map(String::as_str).collect();
+
+    let reader = data.db.main_read_txn()?;
+    let documents_ids: Vec<DocumentId> = index.main
+        .external_to_internal_docids(&reader, &external_ids)?
+        .into_iter()
+        .flatten()
+        .collect();
+
+    let response: Vec<Document> = index.documents_batch(&reader, &documents_ids)?;
+
+    Ok(HttpResponse::Ok().json(response))
 }
 
 #[delete("/indexes/{index_uid}/documents", wrap = "Authentication::Private")]
@@ -265,5 +458,5 @@ async fn clear_all_documents(
 
     writer.commit()?;
 
-    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+    Ok(update_accepted(update_id))
 }
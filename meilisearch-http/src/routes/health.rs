@@ -4,7 +4,7 @@ use heed::types::{Str, Unit};
 use serde::Deserialize;
 
 use crate::error::ResponseError;
-use crate::helpers::Authentication;
+use crate::helpers::{Authentication, StrictJson};
 use crate::Data;
 
 const UNHEALTHY_KEY: &str = "_is_unhealthy";
@@ -45,6 +45,7 @@ async fn set_unhealthy(data: web::Data<Data>) -> Result<HttpResponse, ResponseEr
 }
 
 #[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
 struct HealthBody {
     health: bool,
 }
@@ -52,7 +53,7 @@ struct HealthBody {
 #[put("/health", wrap = "Authentication::Private")]
 async fn change_healthyness(
     data: web::Data<Data>,
-    body: web::Json<HealthBody>,
+    body: StrictJson<HealthBody>,
 ) -> Result<HttpResponse, ResponseError> {
     if body.health {
         set_healthy(data).await
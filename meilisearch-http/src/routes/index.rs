@@ -1,13 +1,22 @@
-use actix_web::{web, HttpResponse};
+use std::collections::{BTreeMap, VecDeque};
+use std::time::Duration;
+
+use actix_rt::time::delay_for;
+use actix_web::web::Bytes;
+use actix_web::{web, HttpRequest, HttpResponse};
 use actix_web_macros::{delete, get, post, put};
 use chrono::{DateTime, Utc};
+use futures::stream;
 use log::error;
+use meilisearch_core::store::{self, SchemaChange};
+use meilisearch_core::{EnqueuedUpdateResult, Index, UpdateStatus};
 use rand::seq::SliceRandom;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 use crate::error::ResponseError;
-use crate::helpers::Authentication;
-use crate::routes::IndexParam;
+use crate::helpers::{Authentication, StrictJson};
+use crate::routes::{not_modified_since, revalidate, IndexParam};
 use crate::Data;
 
 pub fn services(cfg: &mut web::ServiceConfig) {
@@ -16,8 +25,12 @@ pub fn services(cfg: &mut web::ServiceConfig) {
         .service(create_index)
         .service(update_index)
         .service(delete_index)
+        // must be registered before get_update_status, `stream` would be taken as an update id
+        .service(stream_updates_status)
         .service(get_update_status)
-        .service(get_all_updates_status);
+        .service(get_all_updates_status)
+        .service(get_schema_changelog)
+        .service(get_integrity);
 }
 
 fn generate_uid() -> String {
@@ -95,6 +108,7 @@ async fn list_indexes(data: web::Data<Data>) -> Result<HttpResponse, ResponseErr
 
 #[get("/indexes/{index_uid}", wrap = "Authentication::Private")]
 async fn get_index(
+    req: HttpRequest,
     data: web::Data<Data>,
     path: web::Path<IndexParam>,
 ) -> Result<HttpResponse, ResponseError> {
@@ -121,6 +135,10 @@ async fn get_index(
             "Impossible to get the last update date of an index",
         ))?;
 
+    if not_modified_since(&req, updated_at) {
+        return Ok(revalidate(&mut HttpResponse::NotModified(), updated_at).finish());
+    }
+
     let primary_key = match index.main.schema(&reader) {
         Ok(Some(schema)) => match schema.primary_key() {
             Some(primary_key) => Some(primary_key.to_owned()),
@@ -129,7 +147,7 @@ async fn get_index(
         _ => None,
     };
 
-    Ok(HttpResponse::Ok().json(IndexResponse {
+    Ok(revalidate(&mut HttpResponse::Ok(), updated_at).json(IndexResponse {
         name,
         uid: path.index_uid.clone(),
         created_at,
@@ -149,7 +167,7 @@ struct IndexCreateRequest {
 #[post("/indexes", wrap = "Authentication::Private")]
 async fn create_index(
     data: web::Data<Data>,
-    body: web::Json<IndexCreateRequest>,
+    body: StrictJson<IndexCreateRequest>,
 ) -> Result<HttpResponse, ResponseError> {
     if let (None, None) = (body.name.clone(), body.uid.clone()) {
         return Err(ResponseError::bad_request(
@@ -176,6 +194,12 @@ async fn create_index(
         },
     };
 
+    if let Some(max_index_count) = data.max_index_count {
+        if data.db.indexes_uids().len() >= max_index_count {
+            return Err(ResponseError::MaxIndexCountReached(max_index_count));
+        }
+    }
+
     let created_index = data
         .db
         .create_index(&uid)
@@ -237,7 +261,7 @@ struct UpdateIndexResponse {
 async fn update_index(
     data: web::Data<Data>,
     path: web::Path<IndexParam>,
-    body: web::Json<IndexCreateRequest>,
+    body: StrictJson<IndexCreateRequest>,
 ) -> Result<HttpResponse, ResponseError> {
     let index = data
         .db
@@ -311,6 +335,10 @@ async fn delete_index(
 ) -> Result<HttpResponse, ResponseError> {
     data.db.delete_index(&path.index_uid)?;
 
+    if let Some(limiter) = &data.readers_limiter {
+        limiter.remove(&path.index_uid);
+    }
+
     Ok(HttpResponse::NoContent().finish())
 }
 
@@ -362,3 +390,198 @@ async fn get_all_updates_status(
 
     Ok(HttpResponse::Ok().json(response))
 }
+
+/// The interval at which the updates statuses are read while streaming them.
+const UPDATES_STREAM_INTERVAL: Duration = Duration::from_millis(200);
+
+struct UpdatesStream {
+    data: web::Data<Data>,
+    index_uid: String,
+    index: Index,
+    /// The lowest update id that is not processed yet.
+    next_id: u64,
+    /// The updates that are not processed yet and whether they were seen processing.
+    pending: BTreeMap<u64, (EnqueuedUpdateResult, bool)>,
+    events: VecDeque<Bytes>,
+    closed: bool,
+}
+
+fn update_event(event: &str, status: &UpdateStatus) -> Result<Bytes, ResponseError> {
+    let mut status = serde_json::to_value(status).map_err(ResponseError::internal)?;
+    if let Value::Object(map) = &mut status {
+        map.insert("status".to_string(), Value::from(event));
+    }
+    Ok(Bytes::from(format!("event: {}\ndata: {}\n\n", event, status)))
+}
+
+impl UpdatesStream {
+    /// Reads the statuses of the updates that are not processed yet, in a short-lived
+    /// transaction, and queues an event for each one that changed.
+    fn read_statuses(&mut self) -> Result<(), ResponseError> {
+        if self.data.db.open_index(&self.index_uid).is_none() {
+            self.closed = true;
+            return Ok(());
+        }
+
+        let reader = self.data.db.update_read_txn()?;
+        let last_enqueued = self.index.updates.last_update(&reader)?.map(|(id, _)| id);
+        let last_processed = self.index.updates_results.last_update(&reader)?.map(|(id, _)| id);
+        let last_id = match last_enqueued.max(last_processed) {
+            Some(last_id) => last_id,
+            None => return Ok(()),
+        };
+
+        for update_id in self.next_id..=last_id {
+            if let Some(result) = self.index.updates_results.update_result(&reader, update_id)? {
+                self.pending.remove(&update_id);
+                let (event, status) = if result.error.is_some() {
+                    ("failed", UpdateStatus::Failed { content: result })
+                } else {
+                    ("processed", UpdateStatus::Processed { content: result })
+                };
+                self.events.push_back(update_event(event, &status)?);
+            } else if !self.pending.contains_key(&update_id) {
+                if let Some(UpdateStatus::Enqueued { content }) = self.index.update_status(&reader, update_id)? {
+                    let status = UpdateStatus::Enqueued { content: content.clone() };
+                    self.events.push_back(update_event("enqueued", &status)?);
+                    self.pending.insert(update_id, (content, false));
+                }
+            }
+        }
+
+        self.next_id = self.pending.keys().next().copied().unwrap_or(last_id + 1);
+
+        // the updates are processed in order, the first pending one is being processed
+        if !self.index.is_read_only() {
+            if let Some((content, processing)) = self.pending.values_mut().next() {
+                if !*processing {
+                    *processing = true;
+                    let status = UpdateStatus::Enqueued { content: content.clone() };
+                    self.events.push_back(update_event("processing", &status)?);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn next_event(&mut self) -> Option<Result<Bytes, ResponseError>> {
+        loop {
+            if let Some(event) = self.events.pop_front() {
+                return Some(Ok(event));
+            }
+            if self.closed {
+                return None;
+            }
+
+            delay_for(UPDATES_STREAM_INTERVAL).await;
+            if let Err(e) = self.read_statuses() {
+                self.closed = true;
+                return Some(Err(e));
+            }
+        }
+    }
+}
+
+/// Streams the changes of the updates statuses as server-sent events, the events are named
+/// after the new status of the update (enqueued, processing, processed or failed).
+/// The updates that are not processed yet are sent when subscribing.
+#[get("/indexes/{index_uid}/updates/stream", wrap = "Authentication::Private")]
+async fn stream_updates_status(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let reader = data.db.update_read_txn()?;
+    let next_id = match index.updates_results.last_update(&reader)? {
+        Some((last_id, _)) => last_id + 1,
+        None => 0,
+    };
+    drop(reader);
+
+    let mut state = UpdatesStream {
+        data: data.clone(),
+        index_uid: path.index_uid.clone(),
+        index,
+        next_id,
+        pending: BTreeMap::new(),
+        events: VecDeque::new(),
+        closed: false,
+    };
+    state.read_statuses()?;
+
+    let stream = stream::unfold(state, |mut state| async move {
+        let event = state.next_event().await?;
+        Some((event, state))
+    });
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .header("Cache-Control", "no-cache")
+        .streaming(Box::pin(stream)))
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SchemaChangeResponse {
+    id: u64,
+    #[serde(flatten)]
+    change: SchemaChange,
+}
+
+#[get("/indexes/{index_uid}/schema/changelog", wrap = "Authentication::Private")]
+async fn get_schema_changelog(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let reader = data.db.main_read_txn()?;
+
+    let response: Vec<_> = index
+        .main
+        .schema_changelog(&reader)?
+        .into_iter()
+        .map(|(id, change)| SchemaChangeResponse { id, change })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(response))
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct IntegrityResponse {
+    is_consistent: bool,
+    orphan_fst_words: Vec<String>,
+    orphan_posting_lists: Vec<String>,
+    missing_doc_words: Vec<u32>,
+}
+
+#[get("/indexes/{index_uid}/integrity", wrap = "Authentication::Private")]
+async fn get_integrity(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let reader = data.db.main_read_txn()?;
+
+    let report = store::integrity_check(&reader, &index)?;
+
+    Ok(HttpResponse::Ok().json(IntegrityResponse {
+        is_consistent: report.is_consistent(),
+        orphan_fst_words: report.orphan_fst_words,
+        orphan_posting_lists: report.orphan_posting_lists,
+        missing_doc_words: report.missing_doc_words.into_iter().map(|id| id.0).collect(),
+    }))
+}
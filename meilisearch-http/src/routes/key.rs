@@ -1,26 +1,78 @@
 use actix_web::web;
 use actix_web::HttpResponse;
-use actix_web_macros::get;
-use serde::Serialize;
+use actix_web_macros::{get, post};
+use serde::{Deserialize, Serialize};
 
+use crate::data::{KeyKind, RotatedKey};
+use crate::error::ResponseError;
 use crate::helpers::Authentication;
 use crate::Data;
 
+/// The number of seconds the replaced keys stay valid by default.
+const DEFAULT_GRACE_PERIOD_SECS: u32 = 60 * 60;
+
 pub fn services(cfg: &mut web::ServiceConfig) {
-    cfg.service(list);
+    cfg.service(list).service(rotate);
 }
 
 #[derive(Serialize)]
 struct KeysResponse {
     private: Option<String>,
     public: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    rotated: Vec<RotatedKey>,
 }
 
 #[get("/keys", wrap = "Authentication::Admin")]
 async fn list(data: web::Data<Data>) -> HttpResponse {
-    let api_keys = data.api_keys.clone();
+    let mut api_keys = data.api_keys.read().unwrap().clone();
+    api_keys.remove_expired_keys();
     HttpResponse::Ok().json(KeysResponse {
         private: api_keys.private,
         public: api_keys.public,
+        rotated: api_keys.rotated,
     })
 }
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct RotateQuery {
+    grace_period: Option<u32>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RotateResponse {
+    key: String,
+    previous_key: PreviousKey,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PreviousKey {
+    key: String,
+    #[serde(flatten)]
+    rotated: RotatedKey,
+}
+
+/// Replaces the private or public key by a new one, the previous key is still accepted
+/// during the grace period, one hour by default, for the clients to be updated.
+#[post("/keys/{key}/rotate", wrap = "Authentication::Admin")]
+async fn rotate(
+    data: web::Data<Data>,
+    path: web::Path<String>,
+    params: web::Query<RotateQuery>,
+) -> Result<HttpResponse, ResponseError> {
+    let kind: KeyKind = path.parse().map_err(ResponseError::not_found)?;
+
+    let grace_period = params.grace_period.unwrap_or(DEFAULT_GRACE_PERIOD_SECS);
+    let grace_period = chrono::Duration::seconds(i64::from(grace_period));
+
+    match data.rotate_api_key(kind, grace_period)? {
+        Some((key, previous_key, rotated)) => Ok(HttpResponse::Ok().json(RotateResponse {
+            key,
+            previous_key: PreviousKey { key: previous_key, rotated },
+        })),
+        None => Err(ResponseError::bad_request("No master key has been set, the keys can not be rotated")),
+    }
+}
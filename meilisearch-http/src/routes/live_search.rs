@@ -0,0 +1,128 @@
+use actix::{Actor, ActorContext, ActorFuture, AsyncContext, StreamHandler, WrapFuture};
+use actix_web::error::BlockingError;
+use actix_web::{web, Error as ActixError, HttpRequest, HttpResponse};
+use actix_web_actors::ws;
+use actix_web_macros::get;
+
+use crate::error::ResponseError;
+use crate::helpers::meilisearch::SearchResult;
+use crate::helpers::Authentication;
+use crate::routes::search::{execute_search, SearchQuery};
+use crate::routes::IndexParam;
+use crate::Data;
+
+pub fn services(cfg: &mut web::ServiceConfig) {
+    cfg.service(live_search);
+}
+
+/// Upgrades the connection to a WebSocket, every text frame received is a JSON search request
+/// with the parameters of the search route and is answered with a frame of its results, or
+/// with the error body when it fails.
+#[get("/indexes/{index_uid}/search/ws", wrap = "Authentication::Public")]
+async fn live_search(
+    req: HttpRequest,
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    stream: web::Payload,
+) -> Result<HttpResponse, ActixError> {
+    if data.db.open_index(&path.index_uid).is_none() {
+        return Err(ResponseError::index_not_found(&path.index_uid).into());
+    }
+
+    let session = LiveSearch {
+        data,
+        index_uid: path.into_inner().index_uid,
+        searching: false,
+        next_query: None,
+    };
+
+    ws::start(session, &req, stream)
+}
+
+/// A live search session executes one search at a time. The queries received while a search
+/// is executed replace each other, the results of the running search are dropped and only the
+/// latest query is executed and answered.
+struct LiveSearch {
+    data: web::Data<Data>,
+    index_uid: String,
+    searching: bool,
+    next_query: Option<SearchQuery>,
+}
+
+impl LiveSearch {
+    fn search(&mut self, query: SearchQuery, ctx: &mut ws::WebsocketContext<Self>) {
+        self.searching = true;
+
+        let data = self.data.clone();
+        let index_uid = self.index_uid.clone();
+
+        let search = web::block(move || search_index(&data, &index_uid, &query))
+            .into_actor(self)
+            .map(|result, session, ctx| {
+                session.searching = false;
+                match session.next_query.take() {
+                    // the results are outdated, the latest query is answered instead
+                    Some(query) => session.search(query, ctx),
+                    None => ctx.text(results_frame(result)),
+                }
+            });
+
+        ctx.spawn(search);
+    }
+}
+
+impl Actor for LiveSearch {
+    type Context = ws::WebsocketContext<Self>;
+}
+
+impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for LiveSearch {
+    fn handle(&mut self, message: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
+        match message {
+            Ok(ws::Message::Text(text)) => match serde_json::from_str(&text) {
+                Ok(query) if self.searching => self.next_query = Some(query),
+                Ok(query) => self.search(query, ctx),
+                Err(error) => {
+                    let error = ResponseError::bad_request(format!("Invalid search request: {}", error));
+                    ctx.text(error.body().to_string());
+                }
+            },
+            Ok(ws::Message::Ping(bytes)) => ctx.pong(&bytes),
+            Ok(ws::Message::Close(reason)) => {
+                ctx.close(reason);
+                ctx.stop();
+            }
+            Ok(_) => (),
+            Err(_) => ctx.stop(),
+        }
+    }
+}
+
+fn search_index(data: &Data, index_uid: &str, query: &SearchQuery) -> Result<SearchResult, ResponseError> {
+    let index = data
+        .db
+        .open_index(index_uid)
+        .ok_or(ResponseError::index_not_found(index_uid))?;
+
+    // the searches of the sessions are limited like the other ones
+    let semaphore = data.readers_limiter.as_ref().map(|limiter| limiter.semaphore(index_uid));
+    let _permit = match &semaphore {
+        Some(semaphore) => Some(semaphore.try_acquire().map_err(|_| ResponseError::index_busy(index_uid))?),
+        None => None,
+    };
+
+    execute_search(data, &index, query)
+}
+
+fn results_frame(result: Result<SearchResult, BlockingError<ResponseError>>) -> String {
+    let body = result
+        .map_err(|error| match error {
+            BlockingError::Error(error) => error,
+            BlockingError::Canceled => ResponseError::internal("the search has been canceled"),
+        })
+        .and_then(|results| serde_json::to_value(results).map_err(ResponseError::internal));
+
+    match body {
+        Ok(body) => body.to_string(),
+        Err(error) => error.body().to_string(),
+    }
+}
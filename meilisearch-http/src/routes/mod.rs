@@ -1,10 +1,20 @@
-use actix_web::{get, HttpResponse};
+use std::time::SystemTime;
+
+use actix_web::dev::HttpResponseBuilder;
+use actix_web::http::header::{self, HttpDate};
+use actix_web::{get, web, HttpRequest, HttpResponse};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::Data;
+
 pub mod document;
 pub mod health;
 pub mod index;
 pub mod key;
+pub mod live_search;
+pub mod openapi;
+pub mod options;
 pub mod search;
 pub mod setting;
 pub mod stats;
@@ -28,6 +38,25 @@ impl IndexUpdateResponse {
     }
 }
 
+/// Returns whether the resource modified at the given date has not been modified
+/// since the date of the `If-Modified-Since` header of the request.
+pub fn not_modified_since(req: &HttpRequest, updated_at: DateTime<Utc>) -> bool {
+    req.headers()
+        .get(header::IF_MODIFIED_SINCE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<HttpDate>().ok())
+        // the HTTP dates are precise to the second
+        .map_or(false, |since| updated_at.timestamp() <= DateTime::<Utc>::from(SystemTime::from(since)).timestamp())
+}
+
+/// Gives the `Last-Modified` date of the resource to the response, the clients
+/// must revalidate their copy of the resource before they use it.
+pub fn revalidate(builder: &mut HttpResponseBuilder, updated_at: DateTime<Utc>) -> &mut HttpResponseBuilder {
+    builder
+        .header(header::LAST_MODIFIED, HttpDate::from(SystemTime::from(updated_at)).to_string())
+        .header(header::CACHE_CONTROL, "max-age=0, must-revalidate")
+}
+
 #[get("/")]
 pub async fn load_html() -> HttpResponse {
     HttpResponse::Ok()
@@ -41,3 +70,30 @@ pub async fn load_css() -> HttpResponse {
         .content_type("text/css; charset=utf-8")
         .body(include_str!("../../public/bulma.min.css").to_string())
 }
+
+/// Redirects the requests made to the unversioned paths of the API to the versioned ones,
+/// the other unknown paths are not found.
+pub async fn redirect_to_versioned_api(req: HttpRequest, data: web::Data<Data>) -> HttpResponse {
+    let prefix = data.api_version_prefix.as_str();
+    let path = req.path();
+
+    let unversioned = if path.starts_with(data.base_path.as_str()) {
+        &path[data.base_path.len()..]
+    } else {
+        return HttpResponse::NotFound().finish();
+    };
+
+    let versioned = unversioned == prefix || unversioned.starts_with(&format!("{}/", prefix));
+    if prefix.is_empty() || versioned {
+        return HttpResponse::NotFound().finish();
+    }
+
+    let location = match req.query_string() {
+        "" => format!("{}{}{}", data.base_path, prefix, unversioned),
+        query => format!("{}{}{}?{}", data.base_path, prefix, unversioned, query),
+    };
+
+    HttpResponse::PermanentRedirect()
+        .header(header::LOCATION, location)
+        .finish()
+}
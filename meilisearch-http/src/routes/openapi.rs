@@ -0,0 +1,149 @@
+use actix_web::web;
+use actix_web::HttpResponse;
+use actix_web_macros::get;
+use serde_json::Value;
+
+use crate::error::ResponseError;
+use crate::helpers::Authentication;
+use crate::Data;
+
+/// The hand-written description of the API, it must follow the routes,
+/// the `spec_follows_the_routes` test checks it against the `OPTIONS` inventory.
+const OPENAPI_SPEC: &str = include_str!("../../public/openapi.json");
+
+pub fn services(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_openapi_json).service(get_openapi_yaml);
+}
+
+/// Parses the specification and points its server to the prefix the API is served under.
+fn openapi_spec(data: &Data) -> Result<Value, ResponseError> {
+    let mut spec: Value = serde_json::from_str(OPENAPI_SPEC).map_err(ResponseError::internal)?;
+    let url = format!("{}{}", data.base_path, data.api_version_prefix);
+    spec["servers"] = serde_json::json!([{ "url": if url.is_empty() { "/".to_string() } else { url } }]);
+    Ok(spec)
+}
+
+#[get("/openapi.json", wrap = "Authentication::Public")]
+async fn get_openapi_json(data: web::Data<Data>) -> Result<HttpResponse, ResponseError> {
+    Ok(HttpResponse::Ok().json(openapi_spec(&data)?))
+}
+
+#[get("/openapi.yaml", wrap = "Authentication::Public")]
+async fn get_openapi_yaml(data: web::Data<Data>) -> Result<HttpResponse, ResponseError> {
+    let mut yaml = String::from("---\n");
+    write_yaml(&mut yaml, &openapi_spec(&data)?, 0);
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/x-yaml; charset=utf-8")
+        .body(yaml))
+}
+
+/// Writes a scalar as a YAML flow value, the JSON strings are valid YAML double-quoted strings.
+fn yaml_scalar(value: &Value) -> String {
+    match value {
+        Value::Array(_) => "[]".to_string(),
+        Value::Object(_) => "{}".to_string(),
+        other => other.to_string(),
+    }
+}
+
+fn is_block(value: &Value) -> bool {
+    match value {
+        Value::Array(array) => !array.is_empty(),
+        Value::Object(object) => !object.is_empty(),
+        _ => false,
+    }
+}
+
+/// Writes a JSON value as block YAML, the non-empty arrays and objects
+/// are nested under their key or dash, indented by two spaces.
+fn write_yaml(out: &mut String, value: &Value, indent: usize) {
+    let pad = " ".repeat(indent);
+    match value {
+        Value::Object(object) if !object.is_empty() => {
+            for (key, value) in object {
+                let key = Value::String(key.clone()).to_string();
+                if is_block(value) {
+                    out.push_str(&format!("{}{}:\n", pad, key));
+                    write_yaml(out, value, indent + 2);
+                } else {
+                    out.push_str(&format!("{}{}: {}\n", pad, key, yaml_scalar(value)));
+                }
+            }
+        }
+        Value::Array(array) if !array.is_empty() => {
+            for value in array {
+                if is_block(value) {
+                    out.push_str(&format!("{}-\n", pad));
+                    write_yaml(out, value, indent + 2);
+                } else {
+                    out.push_str(&format!("{}- {}\n", pad, yaml_scalar(value)));
+                }
+            }
+        }
+        scalar => out.push_str(&format!("{}{}\n", pad, yaml_scalar(scalar))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use crate::routes::options::ALLOWED_METHODS;
+
+    #[test]
+    fn spec_follows_the_routes() {
+        let spec: Value = serde_json::from_str(OPENAPI_SPEC).unwrap();
+
+        let documented: BTreeMap<String, BTreeSet<String>> = spec["paths"]
+            .as_object()
+            .unwrap()
+            .iter()
+            .map(|(path, operations)| {
+                let methods = operations.as_object().unwrap().keys().map(|m| m.to_uppercase()).collect();
+                (path.clone(), methods)
+            })
+            .collect();
+
+        let routes: BTreeMap<String, BTreeSet<String>> = ALLOWED_METHODS
+            .iter()
+            .map(|(path, methods)| {
+                let methods = methods
+                    .split(", ")
+                    .filter(|m| *m != "OPTIONS")
+                    .map(String::from)
+                    .collect();
+                (path.to_string(), methods)
+            })
+            .collect();
+
+        assert_eq!(documented, routes);
+    }
+
+    #[test]
+    fn json_to_yaml() {
+        let value = serde_json::json!({
+            "openapi": "3.0.3",
+            "paths": { "/health": { "get": { "tags": [], "required": true } } },
+            "servers": [{ "url": "/v1" }, "other"],
+        });
+
+        let mut yaml = String::new();
+        write_yaml(&mut yaml, &value, 0);
+
+        let expected = "\"openapi\": \"3.0.3\"
+\"paths\":
+  \"/health\":
+    \"get\":
+      \"tags\": []
+      \"required\": true
+\"servers\":
+  -
+    \"url\": \"/v1\"
+  - \"other\"
+";
+        assert_eq!(yaml, expected);
+    }
+}
@@ -0,0 +1,61 @@
+use actix_web::{guard, web, HttpResponse};
+
+/// The methods available on every path, answered to the `OPTIONS` requests.
+/// The more specific paths must come first, e.g. `delete-batch` before `{document_id}`.
+pub(crate) const ALLOWED_METHODS: &[(&str, &str)] = &[
+    ("/indexes", "GET, POST, OPTIONS"),
+    ("/indexes/{index_uid}", "GET, PUT, DELETE, OPTIONS"),
+    ("/indexes/{index_uid}/documents", "GET, POST, PUT, DELETE, OPTIONS"),
+    ("/indexes/{index_uid}/documents/batch-get", "POST, OPTIONS"),
+    ("/indexes/{index_uid}/documents/delete-batch", "POST, OPTIONS"),
+    ("/indexes/{index_uid}/documents/stream", "GET, OPTIONS"),
+    ("/indexes/{index_uid}/documents/{document_id}", "GET, DELETE, OPTIONS"),
+    ("/indexes/{index_uid}/integrity", "GET, OPTIONS"),
+    ("/indexes/{index_uid}/schema/changelog", "GET, OPTIONS"),
+    ("/indexes/{index_uid}/search", "GET, OPTIONS"),
+    ("/indexes/{index_uid}/search/ws", "GET, OPTIONS"),
+    ("/indexes/{index_uid}/settings", "GET, POST, DELETE, OPTIONS"),
+    ("/indexes/{index_uid}/settings/accept-new-fields", "GET, POST, OPTIONS"),
+    ("/indexes/{index_uid}/settings/attribute-weights", "GET, POST, DELETE, OPTIONS"),
+    ("/indexes/{index_uid}/settings/displayed-attributes", "GET, POST, DELETE, OPTIONS"),
+    ("/indexes/{index_uid}/settings/distinct-attribute", "GET, POST, DELETE, OPTIONS"),
+    ("/indexes/{index_uid}/settings/ranking-rules", "GET, POST, DELETE, OPTIONS"),
+    ("/indexes/{index_uid}/settings/searchable-attributes", "GET, POST, DELETE, OPTIONS"),
+    ("/indexes/{index_uid}/settings/stop-words", "GET, POST, DELETE, OPTIONS"),
+    ("/indexes/{index_uid}/settings/synonyms", "GET, POST, DELETE, OPTIONS"),
+    ("/indexes/{index_uid}/stats", "GET, OPTIONS"),
+    ("/indexes/{index_uid}/updates", "GET, OPTIONS"),
+    ("/indexes/{index_uid}/updates/stream", "GET, OPTIONS"),
+    ("/indexes/{index_uid}/updates/{update_id}", "GET, OPTIONS"),
+    ("/compacted-copy", "POST, OPTIONS"),
+    ("/health", "GET, PUT, OPTIONS"),
+    ("/keys", "GET, OPTIONS"),
+    ("/keys/{key}/rotate", "POST, OPTIONS"),
+    ("/metrics", "GET, OPTIONS"),
+    ("/openapi.json", "GET, OPTIONS"),
+    ("/openapi.yaml", "GET, OPTIONS"),
+    ("/stats", "GET, OPTIONS"),
+    ("/synonyms/sync", "POST, OPTIONS"),
+    ("/sys-info", "GET, OPTIONS"),
+    ("/sys-info/pretty", "GET, OPTIONS"),
+    ("/version", "GET, OPTIONS"),
+];
+
+/// Must be registered after the other services, the `OPTIONS`
+/// resources would otherwise shadow the routes of the same paths.
+pub fn services(cfg: &mut web::ServiceConfig) {
+    for &(path, methods) in ALLOWED_METHODS {
+        cfg.service(
+            web::resource(path)
+                .guard(guard::Options())
+                .to(move || allowed_methods_response(methods)),
+        );
+    }
+}
+
+async fn allowed_methods_response(methods: &'static str) -> HttpResponse {
+    HttpResponse::NoContent()
+        .header("Allow", methods)
+        .header("Access-Control-Allow-Methods", methods)
+        .finish()
+}
@@ -1,43 +1,58 @@
 use std::collections::{HashSet, HashMap};
+use std::hash::Hasher;
+use std::time::Instant;
 
 use log::warn;
+use actix_web::http::header;
 use actix_web::web;
-use actix_web::HttpResponse;
+use actix_web::{HttpRequest, HttpResponse};
 use actix_web_macros::get;
+use chrono::{DateTime, Utc};
 use serde::Deserialize;
 use serde_json::Value;
+use twox_hash::XxHash64;
 
 use crate::error::{ResponseError, FacetCountError};
-use crate::helpers::meilisearch::IndexSearchExt;
+use crate::helpers::fan_out::{fan_out_search, FanOutQuery, SHARD_REQUEST_HEADER};
+use crate::helpers::meilisearch::{CropStrategy, IndexSearchExt, SearchResult};
+use crate::helpers::search_cache::CacheKey;
 use crate::helpers::Authentication;
 use crate::routes::IndexParam;
 use crate::Data;
 
 use meilisearch_core::facets::FacetFilter;
+use meilisearch_core::Index;
 use meilisearch_schema::{Schema, FieldId};
 
 pub fn services(cfg: &mut web::ServiceConfig) {
     cfg.service(search_with_url_query);
 }
 
-#[derive(Deserialize)]
+#[derive(Clone, Deserialize)]
 #[serde(rename_all = "camelCase", deny_unknown_fields)]
-struct SearchQuery {
+pub(crate) struct SearchQuery {
     q: String,
     offset: Option<usize>,
     limit: Option<usize>,
     attributes_to_retrieve: Option<String>,
     attributes_to_crop: Option<String>,
     crop_length: Option<usize>,
+    crop_strategy: Option<CropStrategy>,
     attributes_to_highlight: Option<String>,
+    highlight_all_fields: Option<bool>,
     filters: Option<String>,
     matches: Option<bool>,
     facet_filters: Option<String>,
     facets: Option<String>,
+    explain_stop_words: Option<bool>,
+    explain: Option<bool>,
+    ranking_score: Option<bool>,
+    minimum_should_match: Option<f32>,
 }
 
 #[get("/indexes/{index_uid}/search", wrap = "Authentication::Public")]
 async fn search_with_url_query(
+    req: HttpRequest,
     data: web::Data<Data>,
     path: web::Path<IndexParam>,
     params: web::Query<SearchQuery>,
@@ -47,7 +62,95 @@ async fn search_with_url_query(
         .open_index(&path.index_uid)
         .ok_or(ResponseError::index_not_found(&path.index_uid))?;
 
+    // the permit is held until the search transactions are released
+    let semaphore = data.readers_limiter.as_ref().map(|limiter| limiter.semaphore(&path.index_uid));
+    let _permit = match &semaphore {
+        Some(semaphore) => Some(semaphore.try_acquire().map_err(|_| ResponseError::index_busy(&path.index_uid))?),
+        None => None,
+    };
+
+    // the searches sent by another shard are not sent to the remote shards again
+    if !data.remote_shards.is_empty() && !req.headers().contains_key(SHARD_REQUEST_HEADER) {
+        let query = FanOutQuery {
+            q: params.q.clone(),
+            offset: params.offset.unwrap_or(0),
+            limit: params.limit.unwrap_or(20),
+            ranking_score: params.ranking_score.unwrap_or(false),
+            query_string: req.query_string().to_string(),
+        };
+
+        let local_search = |limit| {
+            let mut params = params.clone();
+            params.offset = None;
+            params.limit = Some(limit);
+            params.ranking_score = Some(true);
+            execute_search(&data, &index, &params)
+        };
+
+        // the results depend on the other shards, they are neither tagged nor cached
+        let results = fan_out_search(&data.remote_shards, &path.index_uid, &query, local_search).await?;
+        return Ok(HttpResponse::Ok().json(results));
+    }
+
+    // the results can only change when an update is processed
+    let update_reader = data.db.update_read_txn()?;
+    let last_update_id = index.updates_results.last_update(&update_reader)?.map(|(id, _)| id);
+    update_reader.abort();
+
+    // a deleted and recreated index restarts its update ids, the pooled
+    // read transactions are kept for the searches themselves
     let reader = data.db.main_read_txn()?;
+    let created_at = index.main.created_at(&reader)?;
+    reader.abort();
+
+    let etag = search_etag(&path.index_uid, created_at, req.query_string(), last_update_id);
+    if etag_matches(&req, &etag) {
+        return Ok(HttpResponse::NotModified().header(header::ETAG, etag).finish());
+    }
+
+    let results = match data.search_cache.clone() {
+        Some(cache) => {
+            let key = CacheKey {
+                index_uid: path.index_uid.clone(),
+                created_at,
+                last_update_id,
+                query_string: req.query_string().to_string(),
+            };
+
+            // the shared cache queries Redis, it is not queried from the async workers
+            let start = Instant::now();
+            let cached = {
+                let (cache, key) = (cache.clone(), key.clone());
+                web::block(move || Ok::<_, ()>(cache.get(&key))).await.ok().flatten()
+            };
+
+            match cached {
+                Some(results) => {
+                    data.metrics.record_search(start.elapsed());
+                    results
+                }
+                None => {
+                    let results = execute_search(&data, &index, &params)?;
+                    let value = results.clone();
+                    let _ = web::block(move || {
+                        cache.set(key, value);
+                        Ok::<_, ()>(())
+                    })
+                    .await;
+                    results
+                }
+            }
+        }
+        None => execute_search(&data, &index, &params)?,
+    };
+
+    Ok(HttpResponse::Ok().header(header::ETAG, etag).json(results))
+}
+
+/// Executes the search described by the parameters, the search is recorded in the metrics.
+pub(crate) fn execute_search(data: &Data, index: &Index, params: &SearchQuery) -> Result<SearchResult, ResponseError> {
+    let start = Instant::now();
+    let reader = data.db.pooled_main_read_txn()?;
 
     let schema = index
         .main
@@ -55,6 +158,7 @@ async fn search_with_url_query(
         .ok_or(ResponseError::internal("Impossible to retrieve the schema"))?;
 
     let mut search_builder = index.new_search(params.q.clone());
+    search_builder.min_prefix_len(data.min_prefix_len);
 
     if let Some(offset) = params.offset {
         search_builder.offset(offset);
@@ -130,6 +234,10 @@ async fn search_with_url_query(
         }
 
         search_builder.attributes_to_crop(final_attributes);
+
+        if let Some(strategy) = params.crop_strategy {
+            search_builder.crop_strategy(strategy);
+        }
     }
 
     if let Some(attributes_to_highlight) = &params.attributes_to_highlight {
@@ -151,6 +259,12 @@ async fn search_with_url_query(
         search_builder.attributes_to_highlight(final_attributes);
     }
 
+    // highlight all the retrieved attributes, like `attributesToHighlight=*`
+    if let Some(true) = params.highlight_all_fields {
+        let final_attributes = restricted_attributes.iter().map(|attr| attr.to_string()).collect();
+        search_builder.attributes_to_highlight(final_attributes);
+    }
+
     if let Some(filters) = &params.filters {
         search_builder.filters(filters.to_string());
     }
@@ -161,7 +275,57 @@ async fn search_with_url_query(
         }
     }
 
-    Ok(HttpResponse::Ok().json(search_builder.search(&reader)?))
+    if let Some(true) = params.explain_stop_words {
+        search_builder.explain_stop_words();
+    }
+
+    if let Some(true) = params.explain {
+        search_builder.explain();
+    }
+
+    if let Some(true) = params.ranking_score {
+        search_builder.ranking_score();
+    }
+
+    if let Some(fraction) = params.minimum_should_match {
+        if !(0.0..=1.0).contains(&fraction) {
+            return Err(ResponseError::bad_parameter("minimumShouldMatch", "must be a fraction between 0 and 1"));
+        }
+        search_builder.minimum_should_match(fraction);
+    }
+
+    let results = search_builder.search(&reader)?;
+    data.metrics.record_search(start.elapsed());
+
+    Ok(results)
+}
+
+/// Computes the ETag of a search from its query parameters, the creation date of the index
+/// and the last update processed by the index.
+fn search_etag(
+    index_uid: &str,
+    created_at: Option<DateTime<Utc>>,
+    query_string: &str,
+    last_update_id: Option<u64>,
+) -> String {
+    let mut hasher = XxHash64::with_seed(0);
+    hasher.write(index_uid.as_bytes());
+    hasher.write_u8(0);
+    hasher.write_i64(created_at.map_or(0, |date| date.timestamp()));
+    hasher.write_u32(created_at.map_or(0, |date| date.timestamp_subsec_nanos()));
+    hasher.write(query_string.as_bytes());
+    hasher.write_u64(last_update_id.map_or(0, |id| id + 1));
+    format!("\"{:016x}\"", hasher.finish())
+}
+
+/// Returns whether the `If-None-Match` header of the request contains the given ETag.
+fn etag_matches(req: &HttpRequest, etag: &str) -> bool {
+    req.headers()
+        .get_all(header::IF_NONE_MATCH)
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|tag| tag.trim())
+        .any(|tag| tag == "*" || tag == etag || tag.trim_start_matches("W/") == etag)
 }
 
 /// Parses the incoming string into an array of attributes for which to return a count. It returns
@@ -1,10 +1,14 @@
 use actix_web::{web, HttpResponse};
 use actix_web_macros::{delete, get, post};
 use meilisearch_core::settings::{Settings, SettingsUpdate, UpdateState, DEFAULT_RANKING_RULES};
-use std::collections::{BTreeMap, BTreeSet, HashSet};
+use meilisearch_core::update::apply_schema_settings;
+use meilisearch_core::Index;
+use meilisearch_schema::{schema_diff, SchemaDiff};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 
 use crate::error::ResponseError;
-use crate::helpers::Authentication;
+use crate::helpers::{Actor, Authentication, StrictJson};
 use crate::routes::{IndexParam, IndexUpdateResponse};
 use crate::Data;
 
@@ -25,14 +29,61 @@ pub fn services(cfg: &mut web::ServiceConfig) {
         .service(update_displayed)
         .service(delete_displayed)
         .service(get_accept_new_fields)
-        .service(update_accept_new_fields);
+        .service(update_accept_new_fields)
+        .service(get_attribute_weights)
+        .service(update_attribute_weights)
+        .service(delete_attribute_weights);
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SettingsUpdateResponse {
+    update_id: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    schema_diff: Option<SchemaDiff>,
+}
+
+/// The weights must be positive numbers, a weight of 0 makes the matches in the attribute count for nothing.
+fn check_attribute_weights(settings: &SettingsUpdate) -> Result<(), ResponseError> {
+    if let UpdateState::Update(weights) = &settings.attribute_weights {
+        if let Some((name, weight)) = weights.iter().find(|(_, w)| !w.is_finite() || **w < 0.0) {
+            return Err(ResponseError::bad_request(format!(
+                "invalid weight {} for the attribute {}, weights must be positive numbers",
+                weight, name
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Computes the changes the settings update will make to the current schema of the index,
+/// letting the callers know if the documents are going to be reindexed.
+fn preview_schema_diff(
+    data: &Data,
+    index: &Index,
+    settings: &SettingsUpdate,
+) -> Result<Option<SchemaDiff>, ResponseError> {
+    let reader = data.db.main_read_txn()?;
+    let old_schema = match index.main.schema(&reader)? {
+        Some(schema) => schema,
+        None => return Ok(None),
+    };
+
+    // invalid settings are reported by the update itself
+    let mut new_schema = old_schema.clone();
+    if apply_schema_settings(&mut new_schema, settings).is_err() {
+        return Ok(None);
+    }
+
+    Ok(Some(schema_diff(&old_schema, &new_schema)))
 }
 
 #[post("/indexes/{index_uid}/settings", wrap = "Authentication::Private")]
 async fn update_all(
     data: web::Data<Data>,
     path: web::Path<IndexParam>,
-    body: web::Json<Settings>,
+    actor: Option<Actor>,
+    body: StrictJson<Settings>,
 ) -> Result<HttpResponse, ResponseError> {
     let index = data
         .db
@@ -44,10 +95,12 @@ async fn update_all(
         .into_inner()
         .into_update()
         .map_err(ResponseError::bad_request)?;
-    let update_id = index.settings_update(&mut writer, settings)?;
+    check_attribute_weights(&settings)?;
+    let schema_diff = preview_schema_diff(&data, &index, &settings)?;
+    let update_id = index.settings_update(&mut writer, settings.with_actor(actor.map(Actor::into_inner)))?;
     writer.commit()?;
 
-    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+    Ok(HttpResponse::Accepted().json(SettingsUpdateResponse { update_id, schema_diff }))
 }
 
 #[get("/indexes/{index_uid}/settings", wrap = "Authentication::Private")]
@@ -116,8 +169,19 @@ async fn get_all(
             .collect::<HashSet<String>>()
     });
 
+    // the encrypted attributes are only returned when there are some
+    let encrypted_attributes = schema
+        .as_ref()
+        .map(|s| s.encrypted_name().into_iter().map(str::to_string).collect::<BTreeSet<String>>())
+        .filter(|attributes| !attributes.is_empty())
+        .map(Some);
+
     let accept_new_fields = schema.map(|s| s.accept_new_fields());
 
+    let attribute_weights = index.main.attribute_weights(&reader)?.map(Some);
+    let max_documents = index.main.max_documents(&reader)?.map(Some);
+    let normalization_mode = index.main.normalization_mode(&reader)?.map(Some);
+
     let settings = Settings {
         ranking_rules: Some(Some(ranking_rules)),
         distinct_attribute: Some(distinct_attribute),
@@ -127,6 +191,10 @@ async fn get_all(
         synonyms: Some(Some(synonyms)),
         accept_new_fields: Some(accept_new_fields),
         attributes_for_faceting: Some(attributes_for_faceting),
+        attribute_weights,
+        max_documents,
+        encrypted_attributes,
+        normalization_mode,
     };
 
     Ok(HttpResponse::Ok().json(settings))
@@ -136,6 +204,7 @@ async fn get_all(
 async fn delete_all(
     data: web::Data<Data>,
     path: web::Path<IndexParam>,
+    actor: Option<Actor>,
 ) -> Result<HttpResponse, ResponseError> {
     let index = data
         .db
@@ -153,12 +222,18 @@ async fn delete_all(
         synonyms: UpdateState::Clear,
         accept_new_fields: UpdateState::Clear,
         attributes_for_faceting: UpdateState::Clear,
+        attribute_weights: UpdateState::Clear,
+        max_documents: UpdateState::Clear,
+        encrypted_attributes: UpdateState::Clear,
+        normalization_mode: UpdateState::Clear,
+        actor: actor.map(Actor::into_inner),
     };
 
+    let schema_diff = preview_schema_diff(&data, &index, &settings)?;
     let update_id = index.settings_update(&mut writer, settings)?;
     writer.commit()?;
 
-    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+    Ok(HttpResponse::Accepted().json(SettingsUpdateResponse { update_id, schema_diff }))
 }
 
 #[get(
@@ -193,6 +268,7 @@ async fn get_rules(
 async fn update_rules(
     data: web::Data<Data>,
     path: web::Path<IndexParam>,
+    actor: Option<Actor>,
     body: web::Json<Option<Vec<String>>>,
 ) -> Result<HttpResponse, ResponseError> {
     let index = data
@@ -207,10 +283,11 @@ async fn update_rules(
 
     let mut writer = data.db.update_write_txn()?;
     let settings = settings.into_update().map_err(ResponseError::bad_request)?;
-    let update_id = index.settings_update(&mut writer, settings)?;
+    let schema_diff = preview_schema_diff(&data, &index, &settings)?;
+    let update_id = index.settings_update(&mut writer, settings.with_actor(actor.map(Actor::into_inner)))?;
     writer.commit()?;
 
-    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+    Ok(HttpResponse::Accepted().json(SettingsUpdateResponse { update_id, schema_diff }))
 }
 
 #[delete(
@@ -220,6 +297,7 @@ async fn update_rules(
 async fn delete_rules(
     data: web::Data<Data>,
     path: web::Path<IndexParam>,
+    actor: Option<Actor>,
 ) -> Result<HttpResponse, ResponseError> {
     let index = data
         .db
@@ -232,11 +310,12 @@ async fn delete_rules(
         ..SettingsUpdate::default()
     };
 
-    let update_id = index.settings_update(&mut writer, settings)?;
+    let schema_diff = preview_schema_diff(&data, &index, &settings)?;
+    let update_id = index.settings_update(&mut writer, settings.with_actor(actor.map(Actor::into_inner)))?;
 
     writer.commit()?;
 
-    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+    Ok(HttpResponse::Accepted().json(SettingsUpdateResponse { update_id, schema_diff }))
 }
 
 #[get(
@@ -264,6 +343,7 @@ async fn get_distinct(
 async fn update_distinct(
     data: web::Data<Data>,
     path: web::Path<IndexParam>,
+    actor: Option<Actor>,
     body: web::Json<Option<String>>,
 ) -> Result<HttpResponse, ResponseError> {
     let index = data
@@ -278,10 +358,11 @@ async fn update_distinct(
 
     let mut writer = data.db.update_write_txn()?;
     let settings = settings.into_update().map_err(ResponseError::bad_request)?;
-    let update_id = index.settings_update(&mut writer, settings)?;
+    let schema_diff = preview_schema_diff(&data, &index, &settings)?;
+    let update_id = index.settings_update(&mut writer, settings.with_actor(actor.map(Actor::into_inner)))?;
     writer.commit()?;
 
-    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+    Ok(HttpResponse::Accepted().json(SettingsUpdateResponse { update_id, schema_diff }))
 }
 
 #[delete(
@@ -291,6 +372,7 @@ async fn update_distinct(
 async fn delete_distinct(
     data: web::Data<Data>,
     path: web::Path<IndexParam>,
+    actor: Option<Actor>,
 ) -> Result<HttpResponse, ResponseError> {
     let index = data
         .db
@@ -303,11 +385,12 @@ async fn delete_distinct(
         ..SettingsUpdate::default()
     };
 
-    let update_id = index.settings_update(&mut writer, settings)?;
+    let schema_diff = preview_schema_diff(&data, &index, &settings)?;
+    let update_id = index.settings_update(&mut writer, settings.with_actor(actor.map(Actor::into_inner)))?;
 
     writer.commit()?;
 
-    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+    Ok(HttpResponse::Accepted().json(SettingsUpdateResponse { update_id, schema_diff }))
 }
 
 #[get(
@@ -337,6 +420,7 @@ async fn get_searchable(
 async fn update_searchable(
     data: web::Data<Data>,
     path: web::Path<IndexParam>,
+    actor: Option<Actor>,
     body: web::Json<Option<Vec<String>>>,
 ) -> Result<HttpResponse, ResponseError> {
     let index = data
@@ -351,10 +435,11 @@ async fn update_searchable(
 
     let mut writer = data.db.update_write_txn()?;
     let settings = settings.into_update().map_err(ResponseError::bad_request)?;
-    let update_id = index.settings_update(&mut writer, settings)?;
+    let schema_diff = preview_schema_diff(&data, &index, &settings)?;
+    let update_id = index.settings_update(&mut writer, settings.with_actor(actor.map(Actor::into_inner)))?;
     writer.commit()?;
 
-    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+    Ok(HttpResponse::Accepted().json(SettingsUpdateResponse { update_id, schema_diff }))
 }
 
 #[delete(
@@ -364,6 +449,7 @@ async fn update_searchable(
 async fn delete_searchable(
     data: web::Data<Data>,
     path: web::Path<IndexParam>,
+    actor: Option<Actor>,
 ) -> Result<HttpResponse, ResponseError> {
     let index = data
         .db
@@ -376,10 +462,11 @@ async fn delete_searchable(
     };
 
     let mut writer = data.db.update_write_txn()?;
-    let update_id = index.settings_update(&mut writer, settings)?;
+    let schema_diff = preview_schema_diff(&data, &index, &settings)?;
+    let update_id = index.settings_update(&mut writer, settings.with_actor(actor.map(Actor::into_inner)))?;
     writer.commit()?;
 
-    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+    Ok(HttpResponse::Accepted().json(SettingsUpdateResponse { update_id, schema_diff }))
 }
 
 #[get(
@@ -411,6 +498,7 @@ async fn get_displayed(
 async fn update_displayed(
     data: web::Data<Data>,
     path: web::Path<IndexParam>,
+    actor: Option<Actor>,
     body: web::Json<Option<HashSet<String>>>,
 ) -> Result<HttpResponse, ResponseError> {
     let index = data
@@ -425,10 +513,11 @@ async fn update_displayed(
 
     let mut writer = data.db.update_write_txn()?;
     let settings = settings.into_update().map_err(ResponseError::bad_request)?;
-    let update_id = index.settings_update(&mut writer, settings)?;
+    let schema_diff = preview_schema_diff(&data, &index, &settings)?;
+    let update_id = index.settings_update(&mut writer, settings.with_actor(actor.map(Actor::into_inner)))?;
     writer.commit()?;
 
-    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+    Ok(HttpResponse::Accepted().json(SettingsUpdateResponse { update_id, schema_diff }))
 }
 
 #[delete(
@@ -438,6 +527,7 @@ async fn update_displayed(
 async fn delete_displayed(
     data: web::Data<Data>,
     path: web::Path<IndexParam>,
+    actor: Option<Actor>,
 ) -> Result<HttpResponse, ResponseError> {
     let index = data
         .db
@@ -450,10 +540,11 @@ async fn delete_displayed(
     };
 
     let mut writer = data.db.update_write_txn()?;
-    let update_id = index.settings_update(&mut writer, settings)?;
+    let schema_diff = preview_schema_diff(&data, &index, &settings)?;
+    let update_id = index.settings_update(&mut writer, settings.with_actor(actor.map(Actor::into_inner)))?;
     writer.commit()?;
 
-    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+    Ok(HttpResponse::Accepted().json(SettingsUpdateResponse { update_id, schema_diff }))
 }
 
 #[get(
@@ -484,6 +575,7 @@ async fn get_accept_new_fields(
 async fn update_accept_new_fields(
     data: web::Data<Data>,
     path: web::Path<IndexParam>,
+    actor: Option<Actor>,
     body: web::Json<Option<bool>>,
 ) -> Result<HttpResponse, ResponseError> {
     let index = data
@@ -498,7 +590,82 @@ async fn update_accept_new_fields(
 
     let mut writer = data.db.update_write_txn()?;
     let settings = settings.into_update().map_err(ResponseError::bad_request)?;
-    let update_id = index.settings_update(&mut writer, settings)?;
+    let schema_diff = preview_schema_diff(&data, &index, &settings)?;
+    let update_id = index.settings_update(&mut writer, settings.with_actor(actor.map(Actor::into_inner)))?;
+    writer.commit()?;
+
+    Ok(HttpResponse::Accepted().json(SettingsUpdateResponse { update_id, schema_diff }))
+}
+
+#[get(
+    "/indexes/{index_uid}/settings/attribute-weights",
+    wrap = "Authentication::Private"
+)]
+async fn get_attribute_weights(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+    let reader = data.db.main_read_txn()?;
+
+    let attribute_weights = index.main.attribute_weights(&reader)?.unwrap_or_default();
+
+    Ok(HttpResponse::Ok().json(attribute_weights))
+}
+
+#[post(
+    "/indexes/{index_uid}/settings/attribute-weights",
+    wrap = "Authentication::Private"
+)]
+async fn update_attribute_weights(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    actor: Option<Actor>,
+    body: web::Json<Option<HashMap<String, f32>>>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let settings = Settings {
+        attribute_weights: Some(body.into_inner()),
+        ..Settings::default()
+    };
+
+    let mut writer = data.db.update_write_txn()?;
+    let settings = settings.into_update().map_err(ResponseError::bad_request)?;
+    check_attribute_weights(&settings)?;
+    let update_id = index.settings_update(&mut writer, settings.with_actor(actor.map(Actor::into_inner)))?;
+    writer.commit()?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[delete(
+    "/indexes/{index_uid}/settings/attribute-weights",
+    wrap = "Authentication::Private"
+)]
+async fn delete_attribute_weights(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    actor: Option<Actor>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let settings = SettingsUpdate {
+        attribute_weights: UpdateState::Clear,
+        ..SettingsUpdate::default()
+    };
+
+    let mut writer = data.db.update_write_txn()?;
+    let update_id = index.settings_update(&mut writer, settings.with_actor(actor.map(Actor::into_inner)))?;
     writer.commit()?;
 
     Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
@@ -1,11 +1,15 @@
 use std::collections::HashMap;
+use std::fs;
 
+use actix_web::error::BlockingError;
 use actix_web::web;
-use actix_web::HttpResponse;
-use actix_web_macros::get;
+use actix_web::{HttpRequest, HttpResponse};
+use actix_web_macros::{get, post};
 use chrono::{DateTime, Utc};
 use log::error;
+use meilisearch_core::{Index, MainT};
 use pretty_bytes::converter::convert;
+use prometheus::{Encoder, IntCounter, IntCounterVec, IntGauge, Opts, Registry, TextEncoder};
 use serde::Serialize;
 use sysinfo::{NetworkExt, ProcessExt, ProcessorExt, System, SystemExt};
 use walkdir::WalkDir;
@@ -18,9 +22,11 @@ use crate::Data;
 pub fn services(cfg: &mut web::ServiceConfig) {
     cfg.service(index_stats)
         .service(get_stats)
+        .service(export_compacted_copy)
         .service(get_version)
         .service(get_sys_info)
-        .service(get_sys_info_pretty);
+        .service(get_sys_info_pretty)
+        .service(get_metrics);
 }
 
 #[derive(Serialize)]
@@ -29,6 +35,34 @@ struct IndexStatsResponse {
     number_of_documents: u64,
     is_indexing: bool,
     fields_frequency: HashMap<String, usize>,
+    field_distribution: HashMap<String, u64>,
+    avg_tokens_per_field: HashMap<String, f32>,
+}
+
+/// Aggregates the fields counts of the indexed fields, returns the number of documents
+/// containing each field and the average number of tokens it contributes to them.
+fn fields_histogram(
+    index: &Index,
+    reader: &heed::RoTxn<MainT>,
+) -> Result<(HashMap<String, u64>, HashMap<String, f32>), ResponseError>
+{
+    let schema = match index.main.schema(reader)? {
+        Some(schema) => schema,
+        None => return Ok(Default::default()),
+    };
+
+    let mut field_distribution = HashMap::new();
+    let mut avg_tokens_per_field = HashMap::new();
+    for (indexed_pos, (documents, tokens)) in index.documents_fields_counts.fields_tokens_counts(reader)? {
+        let name = match schema.indexed_pos_to_field_id(indexed_pos).and_then(|id| schema.name(id)) {
+            Some(name) => name.to_string(),
+            None => continue,
+        };
+        avg_tokens_per_field.insert(name.clone(), tokens as f32 / documents as f32);
+        field_distribution.insert(name, documents);
+    }
+
+    Ok((field_distribution, avg_tokens_per_field))
 }
 
 #[get("/indexes/{index_uid}/stats", wrap = "Authentication::Private")]
@@ -47,6 +81,8 @@ async fn index_stats(
 
     let fields_frequency = index.main.fields_frequency(&reader)?.unwrap_or_default();
 
+    let (field_distribution, avg_tokens_per_field) = fields_histogram(&index, &reader)?;
+
     let update_reader = data.db.update_read_txn()?;
 
     let is_indexing =
@@ -59,6 +95,8 @@ async fn index_stats(
         number_of_documents,
         is_indexing,
         fields_frequency,
+        field_distribution,
+        avg_tokens_per_field,
     }))
 }
 
@@ -66,6 +104,8 @@ async fn index_stats(
 #[serde(rename_all = "camelCase")]
 struct StatsResult {
     database_size: u64,
+    db_size_bytes: u64,
+    db_size_after_compaction_estimate_bytes: Option<u64>,
     last_update: Option<DateTime<Utc>>,
     indexes: HashMap<String, IndexStatsResponse>,
 }
@@ -86,6 +126,8 @@ async fn get_stats(data: web::Data<Data>) -> Result<HttpResponse, ResponseError>
 
                 let fields_frequency = index.main.fields_frequency(&reader)?.unwrap_or_default();
 
+                let (field_distribution, avg_tokens_per_field) = fields_histogram(&index, &reader)?;
+
                 let is_indexing = data.is_indexing(&update_reader, &index_uid)?.ok_or(
                     ResponseError::internal("Impossible to know if the database is indexing"),
                 )?;
@@ -94,6 +136,8 @@ async fn get_stats(data: web::Data<Data>) -> Result<HttpResponse, ResponseError>
                     number_of_documents,
                     is_indexing,
                     fields_frequency,
+                    field_distribution,
+                    avg_tokens_per_field,
                 };
                 index_list.insert(index_uid, response);
             }
@@ -115,11 +159,66 @@ async fn get_stats(data: web::Data<Data>) -> Result<HttpResponse, ResponseError>
 
     Ok(HttpResponse::Ok().json(StatsResult {
         database_size,
+        db_size_bytes: data.db_size_bytes(),
+        db_size_after_compaction_estimate_bytes: data.db_size_after_compaction_estimate_bytes(),
         last_update,
         indexes: index_list,
     }))
 }
 
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CompactedCopyResponse {
+    db_size_bytes: u64,
+    compacted_copy_size_bytes: u64,
+}
+
+/// Exports a compacted copy of the whole database, all the indexes share the same LMDB
+/// environments, in the `compacted` directory of the database path. The running database is
+/// not compacted nor replaced: to use the copy, stop the server and move the `main` and `update`
+/// directories of `compacted` in place of the ones of the database path. The updates processed
+/// after the export are not in the copy, they must be sent again.
+#[post("/compacted-copy", wrap = "Authentication::Private")]
+async fn export_compacted_copy(data: web::Data<Data>) -> Result<HttpResponse, ResponseError> {
+    // the copy takes as long as writing the entire database, it is done on a blocking thread
+    let inner_data = data.clone();
+    web::block(move || write_compacted_copy(&inner_data))
+        .await
+        .map_err(|e| match e {
+            BlockingError::Error(e) => e,
+            BlockingError::Canceled => ResponseError::internal("the export has been canceled"),
+        })?;
+
+    Ok(HttpResponse::Ok().json(CompactedCopyResponse {
+        db_size_bytes: data.db_size_bytes(),
+        compacted_copy_size_bytes: data.db_size_after_compaction_estimate_bytes().unwrap_or_default(),
+    }))
+}
+
+fn write_compacted_copy(data: &Data) -> Result<(), ResponseError> {
+    // the environments are copied and compacted in a temporary directory
+    // that is then renamed into the compacted one, replacing the previous copy
+    let compacted_path = data.compacted_db_path();
+    let tmp_path = compacted_path.with_extension("tmp");
+
+    if tmp_path.exists() {
+        fs::remove_dir_all(&tmp_path).map_err(ResponseError::internal)?;
+    }
+    fs::create_dir_all(&tmp_path).map_err(ResponseError::internal)?;
+
+    if let Err(e) = data.db.copy_and_compact_to_path(&tmp_path) {
+        let _ = fs::remove_dir_all(&tmp_path);
+        return Err(e.into());
+    }
+
+    if compacted_path.exists() {
+        fs::remove_dir_all(&compacted_path).map_err(ResponseError::internal)?;
+    }
+    fs::rename(&tmp_path, &compacted_path).map_err(ResponseError::internal)?;
+
+    Ok(())
+}
+
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 struct VersionResponse {
@@ -333,3 +432,114 @@ async fn get_sys_info_pretty(data: web::Data<Data>) -> HttpResponse {
 
     HttpResponse::Ok().json(info)
 }
+
+#[derive(Serialize)]
+struct MetricsResponse {
+    documents_indexed_per_second: f64,
+    updates_pending: usize,
+    updates_processed_total: u64,
+    average_update_latency_ms: f64,
+    read_txns_reused_total: u64,
+    read_txns_created_total: u64,
+    read_txns_refreshed_total: u64,
+    read_txns_unpooled_total: u64,
+}
+
+#[get("/metrics")]
+async fn get_metrics(
+    data: web::Data<Data>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ResponseError> {
+    // the metrics can be protected by their own key, the private key is required otherwise
+    let api_keys = data.api_keys.read().unwrap().clone();
+    if data.metrics_auth_key.is_some() || api_keys.master.is_some() {
+        let auth_header = req
+            .headers()
+            .get("X-Meili-API-Key")
+            .ok_or(ResponseError::MissingAuthorizationHeader)?
+            .to_str()
+            .map_err(|_| ResponseError::MissingAuthorizationHeader)?;
+
+        let allowed = match &data.metrics_auth_key {
+            Some(key) => key == auth_header,
+            None => api_keys.is_private(auth_header),
+        };
+
+        if !allowed {
+            return Err(ResponseError::invalid_token(auth_header));
+        }
+    }
+
+    if data.prometheus_metrics {
+        let reader = data.db.main_read_txn()?;
+
+        let mut documents_total = 0;
+        for index_uid in data.db.indexes_uids() {
+            if let Some(index) = data.db.open_index(&index_uid) {
+                documents_total += index.main.number_of_documents(&reader)?;
+            }
+        }
+
+        return Ok(HttpResponse::Ok()
+            .content_type("text/plain; version=0.0.4")
+            .body(prometheus_metrics(&data, documents_total).map_err(ResponseError::internal)?));
+    }
+
+    let update_reader = data.db.update_read_txn()?;
+
+    let mut updates_pending = 0;
+    for index_uid in data.db.indexes_uids() {
+        if let Some(index) = data.db.open_index(&index_uid) {
+            updates_pending += index.updates.len(&update_reader)?;
+        }
+    }
+
+    let read_txn_pool = data.db.read_txn_pool_stats();
+
+    Ok(HttpResponse::Ok().json(MetricsResponse {
+        documents_indexed_per_second: data.metrics.documents_indexed_per_second(),
+        updates_pending,
+        updates_processed_total: data.metrics.updates_processed_total(),
+        average_update_latency_ms: data.metrics.average_update_latency_ms(),
+        read_txns_reused_total: read_txn_pool.reused,
+        read_txns_created_total: read_txn_pool.created,
+        read_txns_refreshed_total: read_txn_pool.refreshed,
+        read_txns_unpooled_total: read_txn_pool.unpooled,
+    }))
+}
+
+/// Renders the metrics in the Prometheus text exposition format, the gauge and counters
+/// read at each scrape are registered along with the durations histograms.
+fn prometheus_metrics(data: &Data, documents_total: u64) -> prometheus::Result<String> {
+    let documents = IntGauge::new("meili_documents_total", "The number of documents in all the indexes.")?;
+    documents.set(documents_total as i64);
+
+    let search_requests = IntCounter::new("meili_search_requests_total", "The number of search requests answered.")?;
+    search_requests.inc_by(data.metrics.search_requests_total() as i64);
+
+    let read_txn_pool = data.db.read_txn_pool_stats();
+    let read_txns = IntCounterVec::new(
+        Opts::new("meili_read_txns_total", "The number of read transactions given to the searches."),
+        &["source"],
+    )?;
+    let sources = [
+        ("reused", read_txn_pool.reused),
+        ("created", read_txn_pool.created),
+        ("refreshed", read_txn_pool.refreshed),
+        ("unpooled", read_txn_pool.unpooled),
+    ];
+    for (source, count) in &sources {
+        read_txns.with_label_values(&[source]).inc_by(*count as i64);
+    }
+
+    let registry = Registry::new();
+    registry.register(Box::new(documents))?;
+    registry.register(Box::new(search_requests))?;
+    registry.register(Box::new(read_txns))?;
+    registry.register(Box::new(data.metrics.search_duration.clone()))?;
+    registry.register(Box::new(data.metrics.indexing_duration.clone()))?;
+
+    let mut out = Vec::new();
+    TextEncoder::new().encode(&registry.gather(), &mut out)?;
+    String::from_utf8(out).map_err(|e| prometheus::Error::Msg(e.to_string()))
+}
@@ -4,7 +4,7 @@ use meilisearch_core::settings::{SettingsUpdate, UpdateState};
 use std::collections::BTreeSet;
 
 use crate::error::ResponseError;
-use crate::helpers::Authentication;
+use crate::helpers::{Actor, Authentication};
 use crate::routes::{IndexParam, IndexUpdateResponse};
 use crate::Data;
 
@@ -38,6 +38,7 @@ async fn get(
 async fn update(
     data: web::Data<Data>,
     path: web::Path<IndexParam>,
+    actor: Option<Actor>,
     body: web::Json<BTreeSet<String>>,
 ) -> Result<HttpResponse, ResponseError> {
     let index = data
@@ -51,7 +52,7 @@ async fn update(
     };
 
     let mut writer = data.db.update_write_txn()?;
-    let update_id = index.settings_update(&mut writer, settings)?;
+    let update_id = index.settings_update(&mut writer, settings.with_actor(actor.map(Actor::into_inner)))?;
     writer.commit()?;
 
     Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
@@ -64,6 +65,7 @@ async fn update(
 async fn delete(
     data: web::Data<Data>,
     path: web::Path<IndexParam>,
+    actor: Option<Actor>,
 ) -> Result<HttpResponse, ResponseError> {
     let index = data
         .db
@@ -76,7 +78,7 @@ async fn delete(
     };
 
     let mut writer = data.db.update_write_txn()?;
-    let update_id = index.settings_update(&mut writer, settings)?;
+    let update_id = index.settings_update(&mut writer, settings.with_actor(actor.map(Actor::into_inner)))?;
     writer.commit()?;
 
     Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
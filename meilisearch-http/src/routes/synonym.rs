@@ -2,16 +2,35 @@ use std::collections::BTreeMap;
 
 use actix_web::{web, HttpResponse};
 use actix_web_macros::{delete, get, post};
-use indexmap::IndexMap;
 use meilisearch_core::settings::{SettingsUpdate, UpdateState};
+use meilisearch_core::{Index, MainT};
+use serde::Deserialize;
 
 use crate::error::ResponseError;
-use crate::helpers::Authentication;
+use crate::helpers::{Actor, Authentication, StrictJson};
 use crate::routes::{IndexParam, IndexUpdateResponse};
 use crate::Data;
 
 pub fn services(cfg: &mut web::ServiceConfig) {
-    cfg.service(get).service(update).service(delete);
+    cfg.service(get).service(update).service(delete).service(sync);
+}
+
+fn index_synonyms(index: &Index, reader: &heed::RoTxn<MainT>) -> Result<BTreeMap<String, Vec<String>>, ResponseError> {
+    let synonyms_fst = index.main.synonyms_fst(reader)?.unwrap_or_default();
+    let synonyms_list = synonyms_fst.stream().into_strs()?;
+
+    let mut synonyms = BTreeMap::new();
+    let synonyms_store = &index.synonyms;
+    for synonym in synonyms_list {
+        let alternative_list = synonyms_store.synonyms(reader, synonym.as_bytes())?;
+
+        if let Some(list) = alternative_list {
+            let list = list.stream().into_strs()?;
+            synonyms.insert(synonym, list);
+        }
+    }
+
+    Ok(synonyms)
 }
 
 #[get(
@@ -28,20 +47,7 @@ async fn get(
         .ok_or(ResponseError::index_not_found(&path.index_uid))?;
 
     let reader = data.db.main_read_txn()?;
-
-    let synonyms_fst = index.main.synonyms_fst(&reader)?.unwrap_or_default();
-    let synonyms_list = synonyms_fst.stream().into_strs()?;
-
-    let mut synonyms = IndexMap::new();
-    let index_synonyms = &index.synonyms;
-    for synonym in synonyms_list {
-        let alternative_list = index_synonyms.synonyms(&reader, synonym.as_bytes())?;
-
-        if let Some(list) = alternative_list {
-            let list = list.stream().into_strs()?;
-            synonyms.insert(synonym, list);
-        }
-    }
+    let synonyms = index_synonyms(&index, &reader)?;
 
     Ok(HttpResponse::Ok().json(synonyms))
 }
@@ -53,6 +59,7 @@ async fn get(
 async fn update(
     data: web::Data<Data>,
     path: web::Path<IndexParam>,
+    actor: Option<Actor>,
     body: web::Json<BTreeMap<String, Vec<String>>>,
 ) -> Result<HttpResponse, ResponseError> {
     let index = data
@@ -66,7 +73,7 @@ async fn update(
     };
 
     let mut writer = data.db.update_write_txn()?;
-    let update_id = index.settings_update(&mut writer, settings)?;
+    let update_id = index.settings_update(&mut writer, settings.with_actor(actor.map(Actor::into_inner)))?;
     writer.commit()?;
 
     Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
@@ -79,6 +86,7 @@ async fn update(
 async fn delete(
     data: web::Data<Data>,
     path: web::Path<IndexParam>,
+    actor: Option<Actor>,
 ) -> Result<HttpResponse, ResponseError> {
     let index = data
         .db
@@ -91,9 +99,60 @@ async fn delete(
     };
 
     let mut writer = data.db.update_write_txn()?;
-    let update_id = index.settings_update(&mut writer, settings)?;
+    let update_id = index.settings_update(&mut writer, settings.with_actor(actor.map(Actor::into_inner)))?;
 
     writer.commit()?;
 
     Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
 }
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SyncSynonyms {
+    source_index: String,
+    target_indexes: Vec<String>,
+}
+
+/// Replaces the synonyms of the target indexes by the ones of the source index.
+/// The settings updates of all the targets are enqueued in the same transaction,
+/// each index then processes its own update and swaps its synonyms atomically.
+#[post("/synonyms/sync", wrap = "Authentication::Private")]
+async fn sync(
+    data: web::Data<Data>,
+    body: StrictJson<SyncSynonyms>,
+    actor: Option<Actor>,
+) -> Result<HttpResponse, ResponseError> {
+    let body = body.into_inner();
+
+    let source = data
+        .db
+        .open_index(&body.source_index)
+        .ok_or(ResponseError::index_not_found(&body.source_index))?;
+
+    let mut targets = Vec::with_capacity(body.target_indexes.len());
+    for uid in body.target_indexes {
+        let index = data
+            .db
+            .open_index(&uid)
+            .ok_or(ResponseError::index_not_found(&uid))?;
+        targets.push((uid, index));
+    }
+
+    let reader = data.db.main_read_txn()?;
+    let synonyms = index_synonyms(&source, &reader)?;
+    reader.abort();
+
+    let mut writer = data.db.update_write_txn()?;
+    let mut updates = BTreeMap::new();
+    for (uid, index) in targets {
+        let settings = SettingsUpdate {
+            synonyms: UpdateState::Update(synonyms.clone()),
+            ..SettingsUpdate::default()
+        };
+        let update_id = index.settings_update(&mut writer, settings.with_actor(actor.clone().map(Actor::into_inner)))?;
+        updates.insert(uid, IndexUpdateResponse::with_id(update_id));
+    }
+    writer.commit()?;
+
+    Ok(HttpResponse::Accepted().json(updates))
+}
@@ -0,0 +1,62 @@
+use std::time::Duration;
+
+use actix_web::http::Method;
+use tokio::time::delay_for;
+
+mod common;
+
+#[actix_rt::test]
+async fn rotated_keys_valid_during_grace_period() {
+    let mut server = common::Server::with_opt("movies", |opt| opt.master_key = Some("masterKey".to_string()));
+
+    let (response, status_code) = server.request_with_key(Method::GET, "/keys", "masterKey").await;
+    assert_eq!(status_code, 200);
+    let old_key = response["private"].as_str().unwrap().to_string();
+
+    let (response, status_code) = server
+        .request_with_key(Method::POST, "/keys/private/rotate?gracePeriod=1", "masterKey")
+        .await;
+    assert_eq!(status_code, 200);
+    let new_key = response["key"].as_str().unwrap().to_string();
+    assert_ne!(new_key, old_key);
+    assert_eq!(response["previousKey"]["key"], old_key.as_str());
+    assert_eq!(response["previousKey"]["kind"], "private");
+
+    // both keys are accepted until the previous one expires
+    let (_response, status_code) = server.request_with_key(Method::GET, "/indexes", &old_key).await;
+    assert_eq!(status_code, 200);
+    let (_response, status_code) = server.request_with_key(Method::GET, "/indexes", &new_key).await;
+    assert_eq!(status_code, 200);
+
+    let (response, _status_code) = server.request_with_key(Method::GET, "/keys", "masterKey").await;
+    assert_eq!(response["private"], new_key.as_str());
+    // only the digest of the previous key is kept
+    assert_eq!(response["rotated"][0]["kind"], "private");
+    assert!(response["rotated"][0].get("key").is_none());
+    assert!(response["rotated"][0]["digest"].is_string());
+
+    delay_for(Duration::from_secs(2)).await;
+
+    let (_response, status_code) = server.request_with_key(Method::GET, "/indexes", &old_key).await;
+    assert_eq!(status_code, 401);
+    let (_response, status_code) = server.request_with_key(Method::GET, "/indexes", &new_key).await;
+    assert_eq!(status_code, 200);
+
+    let (response, _status_code) = server.request_with_key(Method::GET, "/keys", "masterKey").await;
+    assert!(response.get("rotated").is_none());
+}
+
+#[actix_rt::test]
+async fn only_private_and_public_keys_rotated() {
+    let mut server = common::Server::with_opt("movies", |opt| opt.master_key = Some("masterKey".to_string()));
+
+    let (_response, status_code) = server.request_with_key(Method::POST, "/keys/master/rotate", "masterKey").await;
+    assert_eq!(status_code, 404);
+
+    let (response, _status_code) = server.request_with_key(Method::GET, "/keys", "masterKey").await;
+    let private_key = response["private"].as_str().unwrap().to_string();
+
+    // the private key can not rotate the keys
+    let (_response, status_code) = server.request_with_key(Method::POST, "/keys/public/rotate", &private_key).await;
+    assert_eq!(status_code, 401);
+}
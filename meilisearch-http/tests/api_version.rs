@@ -0,0 +1,20 @@
+mod common;
+
+#[actix_rt::test]
+async fn unversioned_routes_are_redirected() {
+    let mut server = common::Server::with_uid("movies");
+
+    let (location, api_version, status_code) = server.unversioned_request("/indexes/movies/search?q=a").await;
+    assert_eq!(status_code, 308);
+    assert_eq!(location.as_deref(), Some("/v1/indexes/movies/search?q=a"));
+    assert_eq!(api_version.as_deref(), Some("v1"));
+
+    let (location, api_version, status_code) = server.unversioned_request("/v1/version").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(location, None);
+    assert_eq!(api_version.as_deref(), Some("v1"));
+
+    let (location, _api_version, status_code) = server.unversioned_request("/v1/unknown").await;
+    assert_eq!(status_code, 404);
+    assert_eq!(location, None);
+}
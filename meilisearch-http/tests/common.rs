@@ -3,11 +3,13 @@
 use serde_json::{json, Value};
 use std::time::Duration;
 
-use actix_web::{http::StatusCode, test};
+use actix_http::ws;
+use actix_web::{dev::Service, dev::ServiceResponse, http::Method, http::StatusCode, test, web::Bytes};
 use meilisearch_core::DatabaseOptions;
 use meilisearch_http::data::Data;
 use meilisearch_http::option::Opt;
 use meilisearch_http::helpers::NormalizeSlashes;
+use futures::{Sink, Stream};
 use tempdir::TempDir;
 use tokio::time::delay_for;
 
@@ -18,21 +20,51 @@ pub struct Server {
 
 impl Server {
     pub fn with_uid(uid: &str) -> Server {
+        Server::with_opt(uid, |_| ())
+    }
+
+    pub fn with_opt(uid: &str, configure: impl FnOnce(&mut Opt)) -> Server {
         let tmp_dir = TempDir::new("meilisearch").unwrap();
 
         let default_db_options = DatabaseOptions::default();
 
-        let opt = Opt {
+        let mut opt = Opt {
             db_path: tmp_dir.path().to_str().unwrap().to_string(),
-            http_addr: "127.0.0.1:7700".to_owned(),
+            http_addr: "127.0.0.1:7700".parse().unwrap(),
             master_key: None,
             env: "development".to_owned(),
             no_analytics: true,
             main_map_size: default_db_options.main_map_size,
             update_map_size: default_db_options.update_map_size,
             http_payload_size_limit: 10000000,
+            max_request_body_size_bytes: 104857600,
+            max_index_count: None,
+            base_path: None,
+            api_version_prefix: "/v1".to_string(),
+            metrics_auth_key: None,
+            shutdown_timeout_secs: 30,
+            // the fixtures search the single letters as prefixes
+            min_prefix_len: 1,
+            max_tokens_per_field: 1000,
+            max_tokens_per_document: 100_000,
+            enable_prometheus_metrics: false,
+            jwt_issuer: None,
+            jwt_audience: None,
+            cors_max_age_secs: 600,
+            max_concurrent_readers_per_index: None,
+            read_txn_pool_size: 0,
+            read_txn_max_age_ms: 1000,
+            indexing_checkpoint_interval: None,
+            field_encryption_key: None,
+            cache_backend: None,
+            cache_size: 1000,
+            remote_shards: Vec::new(),
+            remote_shards_api_key: None,
+            command: None,
         };
 
+        configure(&mut opt);
+
         let data = Data::new(opt.clone());
 
         Server {
@@ -109,6 +141,11 @@ impl Server {
     }
 
 
+    /// Makes the following requests target another index of the same database.
+    pub fn set_uid(&mut self, uid: &str) {
+        self.uid = uid.to_string();
+    }
+
     pub async fn wait_update_id(&mut self, update_id: u64) {
         loop {
             let (response, status_code) = self.get_update_status(update_id).await;
@@ -123,6 +160,28 @@ impl Server {
         }
     }
 
+    fn versioned(&self, url: &str) -> String {
+        format!("{}{}", self.data.api_version_prefix, url)
+    }
+
+    pub async fn unversioned_request(&mut self, url: &str) -> (Option<String>, Option<String>, StatusCode) {
+        eprintln!("unversioned_request: {}", url);
+
+        let mut app = test::init_service(meilisearch_http::create_app(&self.data).wrap(NormalizeSlashes)).await;
+
+        let req = test::TestRequest::get().uri(url).to_request();
+        let res = test::call_service(&mut app, req).await;
+        let status_code = res.status().clone();
+
+        let header = |name| {
+            res.headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(String::from)
+        };
+        (header("location"), header("api-version"), status_code)
+    }
+
     // Global Http request GET/POST/DELETE async or sync
 
     pub async fn get_request(&mut self, url: &str) -> (Value, StatusCode) {
@@ -130,7 +189,7 @@ impl Server {
 
         let mut app = test::init_service(meilisearch_http::create_app(&self.data).wrap(NormalizeSlashes)).await;
 
-        let req = test::TestRequest::get().uri(url).to_request();
+        let req = test::TestRequest::get().uri(&self.versioned(url)).to_request();
         let res = test::call_service(&mut app, req).await;
         let status_code = res.status().clone();
 
@@ -139,13 +198,66 @@ impl Server {
         (response, status_code)
     }
 
+    pub async fn get_request_raw(&mut self, url: &str) -> (String, StatusCode) {
+        eprintln!("get_request_raw: {}", url);
+
+        let mut app = test::init_service(meilisearch_http::create_app(&self.data).wrap(NormalizeSlashes)).await;
+
+        let req = test::TestRequest::get().uri(&self.versioned(url)).to_request();
+        let res = test::call_service(&mut app, req).await;
+        let status_code = res.status().clone();
+
+        let body = test::read_body(res).await;
+        (String::from_utf8(body.to_vec()).unwrap(), status_code)
+    }
+
+    pub async fn request_with_key(&mut self, method: Method, url: &str, key: &str) -> (Value, StatusCode) {
+        eprintln!("request_with_key: {} {}", method, url);
+
+        let mut app = test::init_service(meilisearch_http::create_app(&self.data).wrap(NormalizeSlashes)).await;
+
+        let req = test::TestRequest::default()
+            .method(method)
+            .uri(&self.versioned(url))
+            .header("X-Meili-API-Key", key)
+            .to_request();
+        // the authentication middleware refuses the invalid keys with an error
+        let (status_code, body) = match app.call(req).await {
+            Ok(res) => (res.status(), test::read_body(res).await),
+            Err(error) => (error.as_response_error().status_code(), Bytes::new()),
+        };
+
+        let response = serde_json::from_slice(&body).unwrap_or_default();
+        (response, status_code)
+    }
+
+    pub async fn options_request(&mut self, url: &str) -> (Option<String>, StatusCode) {
+        eprintln!("options_request: {}", url);
+
+        let mut app = test::init_service(meilisearch_http::create_app(&self.data).wrap(NormalizeSlashes)).await;
+
+        let req = test::TestRequest::default()
+            .method(Method::OPTIONS)
+            .uri(&self.versioned(url))
+            .to_request();
+        let res = test::call_service(&mut app, req).await;
+        let status_code = res.status().clone();
+
+        let allowed_methods = res
+            .headers()
+            .get("access-control-allow-methods")
+            .and_then(|value| value.to_str().ok())
+            .map(String::from);
+        (allowed_methods, status_code)
+    }
+
     pub async fn post_request(&mut self, url: &str, body: Value) -> (Value, StatusCode) {
         eprintln!("post_request: {}", url);
 
         let mut app = test::init_service(meilisearch_http::create_app(&self.data).wrap(NormalizeSlashes)).await;
 
         let req = test::TestRequest::post()
-            .uri(url)
+            .uri(&self.versioned(url))
             .set_json(&body)
             .to_request();
         let res = test::call_service(&mut app, req).await;
@@ -156,6 +268,25 @@ impl Server {
         (response, status_code)
     }
 
+    pub async fn post_request_encoded(&mut self, url: &str, body: Vec<u8>, encoding: &str) -> (Value, StatusCode) {
+        eprintln!("post_request_encoded: {} ({})", url, encoding);
+
+        let mut app = test::init_service(meilisearch_http::create_app(&self.data).wrap(NormalizeSlashes)).await;
+
+        let req = test::TestRequest::post()
+            .uri(&self.versioned(url))
+            .header("Content-Type", "application/json")
+            .header("Content-Encoding", encoding)
+            .set_payload(body)
+            .to_request();
+        let res = test::call_service(&mut app, req).await;
+        let status_code = res.status().clone();
+
+        let body = test::read_body(res).await;
+        let response = serde_json::from_slice(&body).unwrap_or_default();
+        (response, status_code)
+    }
+
     pub async fn post_request_async(&mut self, url: &str, body: Value) -> (Value, StatusCode) {
         eprintln!("post_request_async: {}", url);
 
@@ -174,7 +305,7 @@ impl Server {
         let mut app = test::init_service(meilisearch_http::create_app(&self.data).wrap(NormalizeSlashes)).await;
 
         let req = test::TestRequest::put()
-            .uri(url)
+            .uri(&self.versioned(url))
             .set_json(&body)
             .to_request();
         let res = test::call_service(&mut app, req).await;
@@ -185,6 +316,37 @@ impl Server {
         (response, status_code)
     }
 
+    /// Updates the documents with an `If-Unmodified-Since-Update-Id` header,
+    /// returns the `X-Last-Update-Id` header of the response.
+    pub async fn update_documents_if_unmodified_since(
+        &mut self,
+        body: Value,
+        update_id: &str,
+    ) -> (Value, Option<String>, StatusCode) {
+        let url = format!("/indexes/{}/documents", self.uid);
+        eprintln!("update_documents_if_unmodified_since: {}", url);
+
+        let mut app = test::init_service(meilisearch_http::create_app(&self.data).wrap(NormalizeSlashes)).await;
+
+        let req = test::TestRequest::put()
+            .uri(&self.versioned(&url))
+            .header("If-Unmodified-Since-Update-Id", update_id)
+            .set_json(&body)
+            .to_request();
+        let res = test::call_service(&mut app, req).await;
+        let status_code = res.status().clone();
+
+        let last_update_id = res
+            .headers()
+            .get("X-Last-Update-Id")
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string());
+
+        let body = test::read_body(res).await;
+        let response = serde_json::from_slice(&body).unwrap_or_default();
+        (response, last_update_id, status_code)
+    }
+
     pub async fn put_request_async(&mut self, url: &str, body: Value) -> (Value, StatusCode) {
         eprintln!("put_request_async: {}", url);
 
@@ -201,7 +363,7 @@ impl Server {
 
         let mut app = test::init_service(meilisearch_http::create_app(&self.data).wrap(NormalizeSlashes)).await;
 
-        let req = test::TestRequest::delete().uri(url).to_request();
+        let req = test::TestRequest::delete().uri(&self.versioned(url)).to_request();
         let res = test::call_service(&mut app, req).await;
         let status_code = res.status().clone();
 
@@ -256,11 +418,106 @@ impl Server {
         self.get_request(&url).await
     }
 
+    pub async fn search_with_etag(&mut self, query: &str, if_none_match: Option<&str>) -> (Option<String>, StatusCode) {
+        let url = format!("/indexes/{}/search?{}", self.uid, query);
+        eprintln!("search_with_etag: {}", url);
+
+        let mut app = test::init_service(meilisearch_http::create_app(&self.data).wrap(NormalizeSlashes)).await;
+
+        let mut req = test::TestRequest::get().uri(&self.versioned(&url));
+        if let Some(etag) = if_none_match {
+            req = req.header("If-None-Match", etag);
+        }
+        let res = test::call_service(&mut app, req.to_request()).await;
+        let status_code = res.status().clone();
+
+        let etag = res
+            .headers()
+            .get("ETag")
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string());
+        (etag, status_code)
+    }
+
+    pub async fn get_last_modified(&mut self, url: &str, if_modified_since: Option<&str>) -> (Option<String>, StatusCode) {
+        eprintln!("get_last_modified: {}", url);
+
+        let mut app = test::init_service(meilisearch_http::create_app(&self.data).wrap(NormalizeSlashes)).await;
+
+        let mut req = test::TestRequest::get().uri(&self.versioned(url));
+        if let Some(date) = if_modified_since {
+            req = req.header("If-Modified-Since", date);
+        }
+        let res = test::call_service(&mut app, req.to_request()).await;
+        let status_code = res.status().clone();
+
+        let last_modified = res
+            .headers()
+            .get("Last-Modified")
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string());
+        (last_modified, status_code)
+    }
+
+    pub async fn live_search_handshake(&mut self) -> StatusCode {
+        let url = format!("/indexes/{}/search/ws", self.uid);
+        eprintln!("live_search_handshake: {}", url);
+
+        let mut app = test::init_service(meilisearch_http::create_app(&self.data).wrap(NormalizeSlashes)).await;
+
+        let req = test::TestRequest::get()
+            .uri(&self.versioned(&url))
+            .header("Upgrade", "websocket")
+            .header("Connection", "upgrade")
+            .header("Sec-WebSocket-Version", "13")
+            .header("Sec-WebSocket-Key", "dGhlIHNhbXBsZSBub25jZQ==")
+            .to_request();
+        let res = test::call_service(&mut app, req).await;
+        res.status()
+    }
+
+    /// Starts a test server listening on a local port and returns it with the URL of the
+    /// versioned API, the server is stopped when it is dropped.
+    pub fn start(&self) -> (test::TestServer, String) {
+        let data = self.data.clone();
+        let srv = test::start(move || meilisearch_http::create_app(&data).wrap(NormalizeSlashes));
+        let url = srv.url(&self.versioned(""));
+        (srv, url)
+    }
+
+    /// Starts a test server and connects to the live search of the index,
+    /// the server is stopped when it is dropped.
+    pub async fn live_search_session(
+        &mut self,
+    ) -> (
+        test::TestServer,
+        impl Sink<ws::Message, Error = ws::ProtocolError> + Stream<Item = Result<ws::Frame, ws::ProtocolError>> + Unpin,
+    ) {
+        let url = format!("/indexes/{}/search/ws", self.uid);
+        eprintln!("live_search_session: {}", url);
+
+        let (mut srv, _) = self.start();
+        let framed = srv.ws_at(&self.versioned(&url)).await.unwrap();
+        (srv, framed)
+    }
+
     pub async fn get_all_updates_status(&mut self) -> (Value, StatusCode) {
         let url = format!("/indexes/{}/updates", self.uid);
         self.get_request(&url).await
     }
 
+    pub async fn stream_updates_status(&mut self) -> (ServiceResponse, StatusCode) {
+        let url = format!("/indexes/{}/updates/stream", self.uid);
+        eprintln!("stream_updates_status: {}", url);
+
+        let mut app = test::init_service(meilisearch_http::create_app(&self.data).wrap(NormalizeSlashes)).await;
+
+        let req = test::TestRequest::get().uri(&self.versioned(&url)).to_request();
+        let res = test::call_service(&mut app, req).await;
+        let status_code = res.status().clone();
+        (res, status_code)
+    }
+
     pub async fn get_update_status(&mut self, update_id: u64) -> (Value, StatusCode) {
         let url = format!("/indexes/{}/updates/{}", self.uid, update_id);
         self.get_request(&url).await
@@ -271,6 +528,11 @@ impl Server {
         self.get_request(&url).await
     }
 
+    pub async fn stream_documents(&mut self, query: &str) -> (String, StatusCode) {
+        let url = format!("/indexes/{}/documents/stream?{}", self.uid, query);
+        self.get_request_raw(&url).await
+    }
+
     pub async fn add_or_replace_multiple_documents(&mut self, body: Value) {
         let url = format!("/indexes/{}/documents", self.uid);
         self.post_request_async(&url, body).await;
@@ -312,6 +574,11 @@ impl Server {
         self.delete_request_async(&url).await
     }
 
+    pub async fn get_documents_batch(&mut self, body: Value) -> (Value, StatusCode) {
+        let url = format!("/indexes/{}/documents/batch-get", self.uid);
+        self.post_request(&url, body).await
+    }
+
     pub async fn delete_multiple_documents(&mut self, body: Value) {
         let url = format!("/indexes/{}/documents/delete-batch", self.uid);
         self.post_request_async(&url, body).await;
@@ -412,6 +679,21 @@ impl Server {
         self.post_request_async(&url, body).await;
     }
 
+    pub async fn get_attribute_weights(&mut self) -> (Value, StatusCode) {
+        let url = format!("/indexes/{}/settings/attribute-weights", self.uid);
+        self.get_request(&url).await
+    }
+
+    pub async fn update_attribute_weights(&mut self, body: Value) {
+        let url = format!("/indexes/{}/settings/attribute-weights", self.uid);
+        self.post_request_async(&url, body).await;
+    }
+
+    pub async fn update_attribute_weights_sync(&mut self, body: Value) -> (Value, StatusCode) {
+        let url = format!("/indexes/{}/settings/attribute-weights", self.uid);
+        self.post_request(&url, body).await
+    }
+
     pub async fn get_synonyms(&mut self) -> (Value, StatusCode) {
         let url = format!("/indexes/{}/settings/synonyms", self.uid);
         self.get_request(&url).await
@@ -427,6 +709,10 @@ impl Server {
         self.delete_request_async(&url).await
     }
 
+    pub async fn sync_synonyms(&mut self, body: Value) -> (Value, StatusCode) {
+        self.post_request("/synonyms/sync", body).await
+    }
+
     pub async fn get_stop_words(&mut self) -> (Value, StatusCode) {
         let url = format!("/indexes/{}/settings/stop-words", self.uid);
         self.get_request(&url).await
@@ -442,6 +728,16 @@ impl Server {
         self.delete_request_async(&url).await
     }
 
+    pub async fn get_schema_changelog(&mut self) -> (Value, StatusCode) {
+        let url = format!("/indexes/{}/schema/changelog", self.uid);
+        self.get_request(&url).await
+    }
+
+    pub async fn get_integrity(&mut self) -> (Value, StatusCode) {
+        let url = format!("/indexes/{}/integrity", self.uid);
+        self.get_request(&url).await
+    }
+
     pub async fn get_index_stats(&mut self) -> (Value, StatusCode) {
         let url = format!("/indexes/{}/stats", self.uid);
         self.get_request(&url).await
@@ -471,6 +767,10 @@ impl Server {
         self.get_request("/sys-info/pretty").await
     }
 
+    pub async fn get_metrics(&mut self) -> (Value, StatusCode) {
+        self.get_request("/metrics").await
+    }
+
     // Populate routes
 
     pub async fn populate_movies(&mut self) {
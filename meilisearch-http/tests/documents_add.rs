@@ -1,3 +1,7 @@
+use std::io::Write;
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
 use serde_json::json;
 
 mod common;
@@ -175,3 +179,143 @@ async fn check_add_documents_with_nested_sequence() {
     assert_eq!(status_code, 200);
     assert_eq!(response["hits"], body);
 }
+
+#[actix_rt::test]
+async fn add_documents_larger_than_max_request_body_size() {
+    let mut server = common::Server::with_opt("movies", |opt| opt.max_request_body_size_bytes = 64);
+
+    let body = json!({ "uid": "movies", "primaryKey": "id" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = json!([{ "id": 1, "title": "a title long enough to exceed the maximum size of the request body" }]);
+    let (response, status_code) = server.add_or_replace_multiple_documents_sync(body).await;
+    assert_eq!(status_code, 413);
+    assert!(response["message"].is_string());
+}
+
+#[actix_rt::test]
+async fn update_status_exposes_indexed_stats() {
+    let mut server = common::Server::with_uid("movies");
+
+    let body = json!({ "uid": "movies", "primaryKey": "id" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = json!([
+        { "id": 1, "title": "hello world" },
+        { "id": 2, "title": "hello there" },
+    ]);
+    let (response, status_code) = server.post_request("/indexes/movies/documents", body).await;
+    assert_eq!(status_code, 202);
+    let update_id = response["updateId"].as_u64().unwrap();
+    server.wait_update_id(update_id).await;
+
+    let (response, status_code) = server.get_update_status(update_id).await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["status"], "processed");
+
+    let stats = &response["indexedStats"];
+    assert_eq!(stats["postingListsUpdated"], 0);
+    assert_eq!(stats["newWordsAdded"], stats["totalWordsIndexed"]);
+    assert_eq!(stats["fstSizeBefore"], 0);
+    assert!(stats["fstSizeAfter"].as_u64().unwrap() > 0);
+}
+
+#[actix_rt::test]
+async fn stream_documents_as_ndjson() {
+    let mut server = common::Server::with_uid("test");
+
+    let body = json!({ "uid": "test", "primaryKey": "id" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body: Vec<_> = (0..250).map(|id| json!({ "id": id, "title": format!("title {}", id) })).collect();
+    server.add_or_replace_multiple_documents(json!(body)).await;
+
+    let (response, status_code) = server.stream_documents("").await;
+    assert_eq!(status_code, 200);
+    let documents: Vec<serde_json::Value> = response.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+    assert_eq!(documents.len(), 250);
+
+    let (response, status_code) = server.stream_documents("offset=120&limit=100&attributesToRetrieve=id").await;
+    assert_eq!(status_code, 200);
+    let documents: Vec<serde_json::Value> = response.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+    assert_eq!(documents.len(), 100);
+    assert!(documents.iter().all(|document| document.get("title").is_none()));
+}
+
+#[actix_rt::test]
+async fn update_documents_if_unmodified_since() {
+    let mut server = common::Server::with_uid("movies");
+    server.create_index(json!({ "uid": "movies", "primaryKey": "id" })).await;
+
+    let body = json!([{ "id": 1, "title": "Carol" }]);
+    let (response, last_update_id, status_code) = server.update_documents_if_unmodified_since(body, "0").await;
+    assert_eq!(status_code, 202);
+    let first_update_id = response["updateId"].as_u64().unwrap();
+    assert_eq!(last_update_id, Some(first_update_id.to_string()));
+    server.wait_update_id(first_update_id).await;
+
+    // no update has been processed since the first one
+    let body = json!([{ "id": 1, "title": "Wonder Woman" }]);
+    let update_id = first_update_id.to_string();
+    let (response, _, status_code) = server.update_documents_if_unmodified_since(body, &update_id).await;
+    assert_eq!(status_code, 202);
+    let second_update_id = response["updateId"].as_u64().unwrap();
+    server.wait_update_id(second_update_id).await;
+
+    // the second update has been processed since the first one
+    let body = json!([{ "id": 1, "title": "Mulan" }]);
+    let (response, last_update_id, status_code) = server.update_documents_if_unmodified_since(body, &update_id).await;
+    assert_eq!(status_code, 412);
+    assert_eq!(response["code"], "precondition_failed");
+    assert_eq!(last_update_id, None);
+
+    // an update has been enqueued since the second one, even if it isn't processed yet
+    let (_, status_code) = server.add_or_replace_multiple_documents_sync(json!([{ "id": 2, "title": "Mulan" }])).await;
+    assert_eq!(status_code, 202);
+    let body = json!([{ "id": 1, "title": "Mulan" }]);
+    let (_, _, status_code) = server
+        .update_documents_if_unmodified_since(body, &second_update_id.to_string())
+        .await;
+    assert_eq!(status_code, 412);
+
+    let body = json!([{ "id": 1, "title": "Mulan" }]);
+    let (_, _, status_code) = server.update_documents_if_unmodified_since(body, "latest").await;
+    assert_eq!(status_code, 400);
+
+    let (response, _) = server.get_document(1).await;
+    assert_eq!(response["title"], "Wonder Woman");
+}
+
+fn gzip(body: &serde_json::Value) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(body.to_string().as_bytes()).unwrap();
+    encoder.finish().unwrap()
+}
+
+#[actix_rt::test]
+async fn add_compressed_documents() {
+    let mut server = common::Server::with_opt("movies", |opt| opt.max_request_body_size_bytes = 1000);
+    server.create_index(json!({ "uid": "movies", "primaryKey": "id" })).await;
+
+    let body = json!([{ "id": 1, "title": "Carol" }]);
+    let (response, status_code) = server.post_request_encoded("/indexes/movies/documents", gzip(&body), "gzip").await;
+    assert_eq!(status_code, 202);
+    server.wait_update_id(response["updateId"].as_u64().unwrap()).await;
+
+    let (response, _) = server.get_document(1).await;
+    assert_eq!(response["title"], "Carol");
+
+    // the limit applies to the decompressed body
+    let body = json!([{ "id": 2, "title": "a".repeat(2000) }]);
+    let compressed = gzip(&body);
+    assert!(compressed.len() < 1000);
+    let (_, status_code) = server.post_request_encoded("/indexes/movies/documents", compressed, "gzip").await;
+    assert_eq!(status_code, 413);
+
+    let body = json!([{ "id": 3, "title": "Mulan" }]);
+    let (_, status_code) = server.post_request_encoded("/indexes/movies/documents", gzip(&body), "zstd").await;
+    assert_eq!(status_code, 415);
+}
@@ -0,0 +1,27 @@
+mod common;
+
+#[actix_rt::test]
+async fn get_documents_batch() {
+    let mut server = common::Server::with_uid("movies");
+    server.populate_movies().await;
+
+    let body = serde_json::json!([512200, 419704, 1, "181812"]);
+    let (response, status_code) = server.get_documents_batch(body).await;
+    assert_eq!(status_code, 200);
+
+    let ids: Vec<_> = response
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|document| document["id"].as_u64().unwrap())
+        .collect();
+    assert_eq!(ids, vec![512200, 419704, 181812]);
+}
+
+#[actix_rt::test]
+async fn get_documents_batch_unknown_index() {
+    let mut server = common::Server::with_uid("unknown");
+
+    let (_response, status_code) = server.get_documents_batch(serde_json::json!([1])).await;
+    assert_eq!(status_code, 404);
+}
@@ -0,0 +1,51 @@
+use std::fs;
+
+use meilisearch_core::{Database, DatabaseOptions};
+use meilisearch_http::dump::import_dump;
+use tempdir::TempDir;
+
+#[test]
+fn import_dump_into_empty_database() {
+    let tmp_dir = TempDir::new("meilisearch").unwrap();
+    let db_path = tmp_dir.path().join("data.ms");
+    let dump_path = tmp_dir.path().join("dump");
+
+    let movies_path = dump_path.join("movies");
+    fs::create_dir_all(&movies_path).unwrap();
+    fs::write(
+        movies_path.join("schema.json"),
+        r#"{ "primaryKey": "id", "settings": { "stopWords": ["the"] } }"#,
+    ).unwrap();
+    fs::write(
+        movies_path.join("documents.jsonl"),
+        "{ \"id\": 1, \"title\": \"Carol\" }\n\n{ \"id\": 2, \"title\": \"Wonder Woman\" }\n",
+    ).unwrap();
+
+    import_dump(&db_path, DatabaseOptions::default(), &dump_path).unwrap();
+
+    let db = Database::open_or_create(&db_path, DatabaseOptions::default()).unwrap();
+    let index = db.open_index("movies").unwrap();
+
+    let reader = db.main_read_txn().unwrap();
+    let schema = index.main.schema(&reader).unwrap().unwrap();
+    assert_eq!(schema.primary_key(), Some("id"));
+    assert_eq!(index.main.number_of_documents(&reader).unwrap(), 2);
+
+    let stop_words = index.main.stop_words_fst(&reader).unwrap().unwrap();
+    assert!(stop_words.contains("the"));
+}
+
+#[test]
+fn import_dump_into_non_empty_database() {
+    let tmp_dir = TempDir::new("meilisearch").unwrap();
+    let db_path = tmp_dir.path().join("data.ms");
+    let dump_path = tmp_dir.path().join("dump");
+    fs::create_dir_all(&dump_path).unwrap();
+
+    {
+        let db = Database::open_or_create(&db_path, DatabaseOptions::default()).unwrap();
+        db.create_index("movies").unwrap();
+    }
+
+    assert!(import_dump(&db_path, DatabaseOptions::default(), &dump_path).is_err());
+}
@@ -1,6 +1,8 @@
 use assert_json_diff::assert_json_eq;
 use serde_json::json;
 use serde_json::Value;
+use std::time::Duration;
+use tokio::time::delay_for;
 
 mod common;
 
@@ -680,3 +682,199 @@ async fn check_first_update_should_bring_up_processed_status_after_first_docs_ad
     assert_eq!(status_code, 200);
     assert_eq!(response[0]["status"], "processed");
 }
+
+#[actix_rt::test]
+async fn schema_changelog_records_schema_updates() {
+    let mut server = common::Server::with_uid("movies");
+
+    let body = json!({
+        "uid": "movies",
+        "primaryKey": "id",
+    });
+
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    server.update_accept_new_fields(json!(false)).await;
+
+    let (response, status_code) = server.get_schema_changelog().await;
+    assert_eq!(status_code, 200);
+
+    let changes = response.as_array().unwrap();
+    let ids: Vec<_> = changes.iter().map(|change| change["id"].as_u64().unwrap()).collect();
+    let types: Vec<_> = changes.iter().map(|change| change["kind"]["type"].as_str().unwrap()).collect();
+
+    assert_eq!(ids, vec![0, 1, 2, 3, 4]);
+    assert_eq!(types, vec![
+        "primaryKeyUpdated",
+        "fieldsAdded",
+        "searchableAttributesUpdated",
+        "displayedAttributesUpdated",
+        "acceptNewFieldsUpdated",
+    ]);
+    assert_eq!(changes[0]["kind"]["primaryKey"], json!("id"));
+    assert_eq!(changes[4]["kind"]["acceptNewFields"], json!(false));
+    assert!(changes.iter().all(|change| change["timestamp"].as_u64().is_some()));
+    assert!(changes.iter().all(|change| change["by"].is_null()));
+}
+
+#[actix_rt::test]
+async fn integrity_check_of_a_consistent_index() {
+    let mut server = common::Server::with_uid("movies");
+    server.populate_movies().await;
+
+    let (response, status_code) = server.get_integrity().await;
+    assert_eq!(status_code, 200);
+
+    let expected = json!({
+        "isConsistent": true,
+        "orphanFstWords": [],
+        "orphanPostingLists": [],
+        "missingDocWords": [],
+    });
+    assert_json_eq!(response, expected, ordered: false);
+}
+
+#[actix_rt::test]
+async fn create_index_beyond_max_index_count() {
+    let mut server = common::Server::with_opt("movies", |opt| opt.max_index_count = Some(1));
+
+    let (_response, status_code) = server.create_index(json!({ "uid": "movies" })).await;
+    assert_eq!(status_code, 201);
+
+    let (response, status_code) = server.create_index(json!({ "uid": "books" })).await;
+    assert_eq!(status_code, 507);
+    assert_eq!(response["code"], "max_index_count_reached");
+
+    let (response, _status_code) = server.list_indexes().await;
+    assert_eq!(response.as_array().unwrap().len(), 1);
+}
+
+#[actix_rt::test]
+async fn index_stats_fields_histogram() {
+    let mut server = common::Server::with_uid("test");
+
+    let body = json!({ "uid": "test", "primaryKey": "id" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = json!([
+        { "id": 1, "title": "hello world" },
+        { "id": 2, "title": "hello" },
+        { "id": 3 },
+    ]);
+    server.add_or_replace_multiple_documents(body).await;
+
+    let (response, status_code) = server.get_index_stats().await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["fieldDistribution"]["id"], 3);
+    assert_eq!(response["fieldDistribution"]["title"], 2);
+    assert_eq!(response["avgTokensPerField"]["title"], 1.5);
+    assert_eq!(response["avgTokensPerField"]["id"], 1.0);
+}
+
+#[actix_rt::test]
+async fn stream_updates_status_until_processed() {
+    use futures::StreamExt;
+    use std::time::Duration;
+
+    let mut server = common::Server::with_uid("movies");
+    server.create_index(json!({ "uid": "movies", "primaryKey": "id" })).await;
+
+    let (mut res, status_code) = server.stream_updates_status().await;
+    assert_eq!(status_code, 200);
+    assert_eq!(res.headers().get("content-type").unwrap(), "text/event-stream");
+
+    let (response, status_code) = server
+        .post_request("/indexes/movies/documents", json!([{ "id": 1, "title": "Carol" }]))
+        .await;
+    assert_eq!(status_code, 202);
+    let update_id = response["updateId"].as_u64().unwrap();
+
+    let mut body = res.take_body();
+    let mut events = String::new();
+    while !events.contains("event: processed") {
+        let chunk = tokio::time::timeout(Duration::from_secs(10), body.next())
+            .await
+            .expect("no update event received")
+            .unwrap()
+            .unwrap();
+        events.push_str(std::str::from_utf8(&chunk).unwrap());
+    }
+
+    let data = events
+        .lines()
+        .skip_while(|line| *line != "event: processed")
+        .nth(1)
+        .unwrap();
+    let status: Value = serde_json::from_str(&data["data: ".len()..]).unwrap();
+    assert_eq!(status["status"], "processed");
+    assert_eq!(status["updateId"], update_id);
+}
+
+#[actix_rt::test]
+async fn write_routes_report_invalid_fields() {
+    let mut server = common::Server::with_uid("movies");
+
+    let body = json!({
+        "uid": "movies",
+        "active": true,
+        "searchable": ["title"],
+    });
+    let (response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 400);
+    assert_eq!(response["code"], "invalid_request_body");
+    assert_eq!(response["unknownFields"], json!(["active", "searchable"]));
+    assert_eq!(response["missingFields"], json!([]));
+
+    let (response, status_code) = server.update_health(json!({ "healthy": true })).await;
+    assert_eq!(status_code, 400);
+    assert_eq!(response["unknownFields"], json!(["healthy"]));
+    assert_eq!(response["missingFields"], json!(["health"]));
+
+    let (_response, status_code) = server.create_index(json!({ "uid": "movies" })).await;
+    assert_eq!(status_code, 201);
+
+    let (response, status_code) = server.update_index(json!({ "primaryKey": "id", "uuid": "movies" })).await;
+    assert_eq!(status_code, 400);
+    assert_eq!(response["unknownFields"], json!(["uuid"]));
+    assert_eq!(response["missingFields"], json!([]));
+
+    let body = json!({ "rankingRules": ["typo"], "searchableAttribute": ["title"] });
+    let (response, status_code) = server.post_request("/indexes/movies/settings", body).await;
+    assert_eq!(status_code, 400);
+    assert_eq!(response["unknownFields"], json!(["searchableAttribute"]));
+    assert_eq!(response["missingFields"], json!([]));
+
+    let body = json!({ "sourceIndex": "movies", "target_indexes": ["movies"] });
+    let (response, status_code) = server.sync_synonyms(body).await;
+    assert_eq!(status_code, 400);
+    assert_eq!(response["unknownFields"], json!(["sourceIndex"]));
+    assert_eq!(response["missingFields"], json!(["source_index"]));
+}
+
+#[actix_rt::test]
+async fn index_resources_last_modified() {
+    let mut server = common::Server::with_uid("movies");
+    server.create_index(json!({ "uid": "movies", "primaryKey": "id" })).await;
+
+    for url in &["/indexes/movies", "/indexes/movies/documents"] {
+        let (last_modified, status_code) = server.get_last_modified(url, None).await;
+        assert_eq!(status_code, 200);
+        let last_modified = last_modified.unwrap();
+
+        let (_, status_code) = server.get_last_modified(url, Some(&last_modified)).await;
+        assert_eq!(status_code, 304);
+    }
+
+    let (last_modified, _status_code) = server.get_last_modified("/indexes/movies/documents", None).await;
+    let last_modified = last_modified.unwrap();
+
+    // the dates are precise to the second
+    delay_for(Duration::from_secs(1)).await;
+    server.add_or_replace_multiple_documents(json!([{ "id": 1, "title": "hello" }])).await;
+
+    let (new_last_modified, status_code) = server.get_last_modified("/indexes/movies/documents", Some(&last_modified)).await;
+    assert_eq!(status_code, 200);
+    assert_ne!(new_last_modified.unwrap(), last_modified);
+}
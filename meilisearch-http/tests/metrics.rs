@@ -0,0 +1,55 @@
+mod common;
+
+#[actix_rt::test]
+async fn get_metrics() {
+    let mut server = common::Server::test_server().await;
+
+    let (response, status_code) = server.get_metrics().await;
+    assert_eq!(status_code, 200);
+
+    assert_eq!(response["updates_pending"], 0);
+    assert!(response["updates_processed_total"].is_u64());
+    assert!(response["documents_indexed_per_second"].is_f64());
+    assert!(response["average_update_latency_ms"].is_f64());
+}
+
+#[actix_rt::test]
+async fn get_prometheus_metrics() {
+    let mut server = common::Server::with_opt("movies", |opt| opt.enable_prometheus_metrics = true);
+    server.populate_movies().await;
+
+    let (_response, status_code) = server.search("q=captain").await;
+    assert_eq!(status_code, 200);
+
+    let (response, status_code) = server.get_request_raw("/metrics").await;
+    assert_eq!(status_code, 200);
+
+    assert!(response.contains("# TYPE meili_documents_total gauge\n"));
+    assert!(response.lines().any(|line| line.starts_with("meili_documents_total ") && line != "meili_documents_total 0"));
+    assert!(response.contains("meili_search_requests_total 1\n"));
+    assert!(response.contains("meili_search_duration_seconds_bucket{le=\"+Inf\"} 1\n"));
+    assert!(response.contains("meili_search_duration_seconds_count 1\n"));
+    assert!(response.contains("# TYPE meili_indexing_duration_seconds histogram\n"));
+    assert!(response.contains("meili_indexing_duration_seconds_bucket{le=\"+Inf\"} "));
+}
+
+#[actix_rt::test]
+async fn get_read_txn_pool_metrics() {
+    let mut server = common::Server::with_opt("movies", |opt| {
+        opt.read_txn_pool_size = 2;
+        opt.read_txn_max_age_ms = 60_000;
+    });
+    server.populate_movies().await;
+
+    // the second search reuses the transaction of the first one
+    let (_response, status_code) = server.search("q=captain").await;
+    assert_eq!(status_code, 200);
+    let (_response, status_code) = server.search("q=iron").await;
+    assert_eq!(status_code, 200);
+
+    let (response, status_code) = server.get_metrics().await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["read_txns_created_total"], 1);
+    assert_eq!(response["read_txns_reused_total"], 1);
+    assert_eq!(response["read_txns_unpooled_total"], 0);
+}
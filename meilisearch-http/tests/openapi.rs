@@ -0,0 +1,17 @@
+mod common;
+
+#[actix_rt::test]
+async fn get_openapi_spec() {
+    let mut server = common::Server::with_uid("movies");
+
+    let (response, status_code) = server.get_request("/openapi.json").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["openapi"], "3.0.3");
+    assert_eq!(response["servers"][0]["url"], "/v1");
+    assert!(response["paths"]["/indexes/{index_uid}/search"]["get"].is_object());
+
+    let (response, status_code) = server.get_request_raw("/openapi.yaml").await;
+    assert_eq!(status_code, 200);
+    assert!(response.starts_with("---\n\"openapi\": \"3.0.3\"\n"));
+    assert!(response.contains("\n  \"/indexes/{index_uid}/search\":\n    \"get\":\n"));
+}
@@ -0,0 +1,28 @@
+mod common;
+
+#[actix_rt::test]
+async fn options_allowed_methods() {
+    let mut server = common::Server::with_uid("movies");
+
+    let (allowed_methods, status_code) = server.options_request("/indexes").await;
+    assert_eq!(status_code, 204);
+    assert_eq!(allowed_methods.as_deref(), Some("GET, POST, OPTIONS"));
+
+    let (allowed_methods, status_code) = server.options_request("/indexes/movies/documents/delete-batch").await;
+    assert_eq!(status_code, 204);
+    assert_eq!(allowed_methods.as_deref(), Some("POST, OPTIONS"));
+
+    let (allowed_methods, status_code) = server.options_request("/indexes/movies/documents/123").await;
+    assert_eq!(status_code, 204);
+    assert_eq!(allowed_methods.as_deref(), Some("GET, DELETE, OPTIONS"));
+
+    let (allowed_methods, _) = server.options_request("/keys").await;
+    assert_eq!(allowed_methods.as_deref(), Some("GET, OPTIONS"));
+
+    let (allowed_methods, _) = server.options_request("/health").await;
+    assert_eq!(allowed_methods.as_deref(), Some("GET, PUT, OPTIONS"));
+
+    // the other routes are not shadowed
+    let (_response, status_code) = server.get_health().await;
+    assert_eq!(status_code, 200);
+}
@@ -1,8 +1,11 @@
 use std::convert::Into;
 
+use actix_http::ws;
 use assert_json_diff::assert_json_eq;
+use futures::{stream, SinkExt, Stream, StreamExt};
 use serde_json::json;
 use serde_json::Value;
+use meilisearch_http::option::CacheBackend;
 
 mod common;
 
@@ -1407,3 +1410,182 @@ async fn highlight_cropped_text() {
         .unwrap()
         , &Value::String(expected_response.to_owned()));
 }
+
+#[actix_rt::test]
+async fn search_etag_not_modified() {
+    let mut server = common::Server::with_uid("test");
+
+    let body = json!({ "uid": "test", "primaryKey": "id" });
+    server.create_index(body).await;
+    server.add_or_replace_multiple_documents(json!([{ "id": 1, "title": "hello" }])).await;
+
+    let (etag, status_code) = server.search_with_etag("q=hello", None).await;
+    assert_eq!(status_code, 200);
+    let etag = etag.unwrap();
+
+    let (same_etag, status_code) = server.search_with_etag("q=hello", Some(&etag)).await;
+    assert_eq!(status_code, 304);
+    assert_eq!(same_etag.as_deref(), Some(etag.as_str()));
+
+    // another query has another etag
+    let (other_etag, status_code) = server.search_with_etag("q=world", Some(&etag)).await;
+    assert_eq!(status_code, 200);
+    assert_ne!(other_etag.as_deref(), Some(etag.as_str()));
+
+    // processing an update invalidates the etag
+    server.add_or_replace_multiple_documents(json!([{ "id": 2, "title": "hello world" }])).await;
+    let (new_etag, status_code) = server.search_with_etag("q=hello", Some(&etag)).await;
+    assert_eq!(status_code, 200);
+    assert_ne!(new_etag.as_deref(), Some(etag.as_str()));
+
+    // a recreated index processes the same update ids, its creation date changes the etag
+    let (etag, _status_code) = server.search_with_etag("q=hello", None).await;
+    let etag = etag.unwrap();
+    server.delete_index().await;
+    server.create_index(json!({ "uid": "test", "primaryKey": "id" })).await;
+    server.add_or_replace_multiple_documents(json!([{ "id": 1, "title": "hello" }])).await;
+    server.add_or_replace_multiple_documents(json!([{ "id": 2, "title": "hello world" }])).await;
+
+    let (new_etag, status_code) = server.search_with_etag("q=hello", Some(&etag)).await;
+    assert_eq!(status_code, 200);
+    assert_ne!(new_etag.as_deref(), Some(etag.as_str()));
+}
+
+#[actix_rt::test]
+async fn search_with_highlight_all_fields() {
+    let mut server = common::Server::with_uid("movies");
+    server.populate_movies().await;
+
+    let (expected, _status_code) = server.search("q=captain&limit=1&attributesToHighlight=*").await;
+    let (response, status_code) = server.search("q=captain&limit=1&highlightAllFields=true").await;
+    assert_eq!(status_code, 200);
+    assert_json_eq!(expected["hits"].clone(), response["hits"].clone(), ordered: false);
+    assert!(response["hits"][0]["_formatted"]["title"].as_str().unwrap().contains("<em>Captain</em>"));
+}
+
+#[actix_rt::test]
+async fn search_rejected_when_no_reader_is_available() {
+    let mut server = common::Server::with_opt("movies", |opt| opt.max_concurrent_readers_per_index = Some(0));
+    server.create_index(json!({ "uid": "movies" })).await;
+
+    let (response, status_code) = server.search("q=captain").await;
+    assert_eq!(status_code, 503);
+    assert_eq!(response["code"], "index_busy");
+}
+
+#[actix_rt::test]
+async fn search_fanned_out_to_remote_shards() {
+    let mut remote = common::Server::with_uid("movies");
+    remote.create_index(json!({ "uid": "movies", "primaryKey": "id" })).await;
+    remote.add_or_replace_multiple_documents(json!([
+        { "id": 1, "title": "Captain Marvel" },
+        { "id": 2, "title": "The Adventures of Captain Underpants" },
+    ])).await;
+    let (_srv, remote_url) = remote.start();
+
+    let mut server = common::Server::with_opt("movies", |opt| opt.remote_shards = vec![remote_url]);
+    server.create_index(json!({ "uid": "movies", "primaryKey": "id" })).await;
+    server.add_or_replace_multiple_documents(json!([
+        { "id": 3, "title": "Captain America" },
+        { "id": 4, "title": "Iron Man" },
+    ])).await;
+
+    let (response, status_code) = server.search("q=captain&limit=2&rankingScore=true").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["nbHits"], 3);
+
+    // the titles starting with the query word are ranked first, the ties in the shards order
+    let hits = response["hits"].as_array().unwrap();
+    let ids: Vec<_> = hits.iter().map(|hit| hit["id"].clone()).collect();
+    assert_eq!(ids, vec![json!(3), json!(1)]);
+    assert!(hits.iter().all(|hit| hit["_rankingScore"].is_array()));
+
+    let (response, _status_code) = server.search("q=captain&offset=2").await;
+    let hits = response["hits"].as_array().unwrap();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0]["id"], 2);
+    assert!(hits[0].get("_rankingScore").is_none());
+}
+
+#[actix_rt::test]
+async fn live_search_upgrades_to_websocket() {
+    let mut server = common::Server::with_uid("movies");
+    assert_eq!(server.live_search_handshake().await, 404);
+
+    server.create_index(json!({ "uid": "movies" })).await;
+    assert_eq!(server.live_search_handshake().await, 101);
+}
+
+async fn next_text_frame<S>(session: &mut S) -> Value
+where
+    S: Stream<Item = Result<ws::Frame, ws::ProtocolError>> + Unpin,
+{
+    match session.next().await {
+        Some(Ok(ws::Frame::Text(text))) => serde_json::from_slice(&text).unwrap(),
+        frame => panic!("expected a text frame, got {:?}", frame),
+    }
+}
+
+#[actix_rt::test]
+async fn live_search_answers_queries() {
+    let mut server = common::Server::with_uid("movies");
+    server.populate_movies().await;
+
+    let (_srv, mut session) = server.live_search_session().await;
+
+    // a query is answered with the results of the search route
+    let query = json!({ "q": "captain", "limit": 3 }).to_string();
+    session.send(ws::Message::Text(query)).await.unwrap();
+    let frame = next_text_frame(&mut session).await;
+
+    let (expected, _status_code) = server.search("q=captain&limit=3").await;
+    assert_eq!(frame["query"], "captain");
+    assert_json_eq!(expected["hits"].clone(), frame["hits"].clone(), ordered: false);
+
+    // the queries received while searching replace each other, only the latest one is answered
+    let queries = ["thor", "iron", "spider"].iter().map(|q| Ok(ws::Message::Text(json!({ "q": q }).to_string())));
+    session.send_all(&mut stream::iter(queries)).await.unwrap();
+    let frame = next_text_frame(&mut session).await;
+    assert_eq!(frame["query"], "spider");
+
+    // an invalid request is answered with an error frame, no outdated results precede it
+    session.send(ws::Message::Text("{".to_string())).await.unwrap();
+    let frame = next_text_frame(&mut session).await;
+    assert!(frame["message"].as_str().unwrap().starts_with("Invalid search request"));
+}
+
+#[actix_rt::test]
+async fn search_explained() {
+    let mut server = common::Server::with_uid("movies");
+    server.populate_movies().await;
+
+    let (response, status_code) = server.search("q=captain%20marvel&explain=true").await;
+    assert_eq!(status_code, 200);
+    // "captain", "marvel" and "captain marvel"
+    assert_eq!(response["_explain"]["ngramsExplored"], 3);
+    assert!(response["_explain"]["totalAutomatons"].as_u64().unwrap() > 0);
+
+    let (response, _status_code) = server.search("q=captain%20marvel").await;
+    assert!(response.get("_explain").is_none());
+}
+
+#[actix_rt::test]
+async fn cached_search_results_invalidated_by_updates() {
+    let mut server = common::Server::with_opt("test", |opt| opt.cache_backend = Some(CacheBackend::Memory));
+
+    let body = json!({ "uid": "test", "primaryKey": "id" });
+    server.create_index(body).await;
+    server.add_or_replace_multiple_documents(json!([{ "id": 1, "title": "hello" }])).await;
+
+    let (response, status_code) = server.search("q=hello").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["nbHits"], 1);
+
+    let (cached, _status_code) = server.search("q=hello").await;
+    assert_eq!(cached, response);
+
+    server.add_or_replace_multiple_documents(json!([{ "id": 2, "title": "hello world" }])).await;
+    let (response, status_code) = server.search("q=hello").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["nbHits"], 2);
+}
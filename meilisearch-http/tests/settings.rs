@@ -49,6 +49,7 @@ async fn write_all_and_delete() {
         },
         "attributesForFaceting": ["title"],
         "acceptNewFields": false,
+        "normalizationMode": "NFKC",
     });
 
 
@@ -122,6 +123,7 @@ async fn write_all_and_delete() {
         "synonyms": {},
         "attributesForFaceting": null,
         "acceptNewFields": true,
+        "normalizationMode": "NFKC",
     });
 
     assert_json_eq!(expect, response, ordered: false);
@@ -173,6 +175,7 @@ async fn write_all_and_update() {
         },
         "attributesForFaceting": ["title"],
         "acceptNewFields": false,
+        "normalizationMode": "NFKC",
     });
 
     server.update_all_settings(body.clone()).await;
@@ -252,7 +255,8 @@ async fn write_all_and_update() {
             "logan": ["wolverine", "xmen"],
         },
         "attributesForFaceting": ["title"],
-        "acceptNewFields": false
+        "acceptNewFields": false,
+        "normalizationMode": "NFKC"
     });
 
     assert_json_eq!(expected, response, ordered: false);
@@ -284,6 +288,7 @@ async fn test_default_settings() {
         "synonyms": {},
         "attributesForFaceting": null,
         "acceptNewFields": true,
+        "normalizationMode": "NFKC",
     });
 
     let (response, _status_code) = server.get_all_settings().await;
@@ -322,6 +327,7 @@ async fn test_default_settings_2() {
         "synonyms": {},
         "attributesForFaceting": null,
         "acceptNewFields": true,
+        "normalizationMode": "NFKC",
     });
 
     let (response, _status_code) = server.get_all_settings().await;
@@ -430,9 +436,104 @@ async fn write_setting_and_update_partial() {
         },
         "attributesForFaceting": null,
         "acceptNewFields": false,
+        "normalizationMode": "NFKC",
     });
 
     let (response, _status_code) = server.get_all_settings().await;
 
     assert_json_eq!(expected, response, ordered: false);
 }
+
+#[actix_rt::test]
+async fn settings_update_returns_the_schema_diff() {
+    let mut server = common::Server::with_uid("movies");
+    server.populate_movies().await;
+
+    let body = json!(["tagline", "title"]);
+    let (response, status_code) = server.post_request("/indexes/movies/settings/searchable-attributes", body).await;
+    assert_eq!(status_code, 202);
+    assert!(response["updateId"].as_u64().is_some());
+
+    let expected = json!({
+        "addedFields": [],
+        "removedFields": [],
+        "typeChangedFields": ["cast", "director", "genres", "overview", "producer", "production_companies"],
+        "rankChangedFields": ["tagline", "title"],
+    });
+    assert_json_eq!(response["schemaDiff"].clone(), expected, ordered: true);
+}
+
+#[actix_rt::test]
+async fn sync_synonyms_between_indexes() {
+    let mut server = common::Server::with_uid("master");
+
+    for uid in &["master", "shard1", "shard2"] {
+        let (_response, status_code) = server.create_index(json!({ "uid": uid })).await;
+        assert_eq!(status_code, 201);
+    }
+
+    let synonyms = json!({
+        "road": ["street", "avenue"],
+        "street": ["avenue"],
+    });
+    server.update_synonyms(synonyms.clone()).await;
+
+    let body = json!({
+        "source_index": "master",
+        "target_indexes": ["shard1", "shard2"],
+    });
+    let (response, status_code) = server.sync_synonyms(body).await;
+    assert_eq!(status_code, 202);
+
+    for uid in &["shard1", "shard2"] {
+        let update_id = response[uid]["updateId"].as_u64().unwrap();
+        server.set_uid(uid);
+        server.wait_update_id(update_id).await;
+
+        let (response, status_code) = server.get_synonyms().await;
+        assert_eq!(status_code, 200);
+        assert_json_eq!(synonyms.clone(), response, ordered: false);
+    }
+
+    let body = json!({
+        "source_index": "master",
+        "target_indexes": ["shard1", "unknown"],
+    });
+    let (_response, status_code) = server.sync_synonyms(body).await;
+    assert_eq!(status_code, 404);
+}
+
+#[actix_rt::test]
+async fn attribute_weights_boost_matches() {
+    let mut server = common::Server::with_uid("test");
+
+    let body = json!({ "uid": "test", "primaryKey": "id" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = json!([
+        { "id": 1, "title": "prince", "description": "a story" },
+        { "id": 2, "title": "a story", "description": "prince and prince" },
+    ]);
+    server.add_or_replace_multiple_documents(body).await;
+
+    let (response, _status_code) = server.search("q=prince").await;
+    let ids: Vec<_> = response["hits"].as_array().unwrap().iter().map(|hit| hit["id"].clone()).collect();
+    assert_eq!(ids, vec![json!(1), json!(2)]);
+
+    server.update_attribute_weights(json!({ "description": 5.0 })).await;
+
+    let (response, status_code) = server.get_attribute_weights().await;
+    assert_eq!(status_code, 200);
+    assert_json_eq!(json!({ "description": 5.0 }), response, ordered: false);
+
+    let (response, _status_code) = server.get_all_settings().await;
+    assert_json_eq!(json!({ "description": 5.0 }), response["attributeWeights"].clone(), ordered: false);
+
+    let (response, _status_code) = server.search("q=prince").await;
+    let ids: Vec<_> = response["hits"].as_array().unwrap().iter().map(|hit| hit["id"].clone()).collect();
+    assert_eq!(ids, vec![json!(2), json!(1)]);
+
+    let (_response, status_code) = server.update_attribute_weights_sync(json!({ "title": -1.0 })).await;
+    assert_eq!(status_code, 400);
+}
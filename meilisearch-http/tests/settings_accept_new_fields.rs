@@ -343,6 +343,7 @@ async fn accept_new_fields_does_not_take_into_account_the_primary_key() {
         "synonyms": {},
         "attributesForFaceting": null,
         "acceptNewFields": false,
+        "normalizationMode": "NFKC",
     });
 
     assert_json_eq!(response, expected, ordered: false);
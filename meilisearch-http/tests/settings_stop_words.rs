@@ -61,3 +61,18 @@ async fn add_documents_and_stop_words() {
 
     // assert!(!response["hits"].as_array().unwrap().is_empty());
 }
+
+#[actix_rt::test]
+async fn search_explaining_stop_words() {
+    let mut server = common::Server::with_uid("movies");
+    server.populate_movies().await;
+
+    let body = json!(["the", "of"]);
+    server.update_stop_words(body).await;
+
+    let (response, _status_code) = server.search("q=The%20mask%20of%20zorro&explainStopWords=true").await;
+    assert_eq!(response["_stoppedWords"], json!(["the", "of"]));
+
+    let (response, _status_code) = server.search("q=the%20mask").await;
+    assert!(response.get("_stoppedWords").is_none());
+}
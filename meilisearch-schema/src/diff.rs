@@ -0,0 +1,87 @@
+use std::collections::BTreeSet;
+
+use serde::Serialize;
+
+use crate::Schema;
+
+/// The changes between two schemas, grouped by the impact they have on the documents.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SchemaDiff {
+    /// The fields only known by the new schema.
+    pub added_fields: Vec<String>,
+    /// The fields only known by the old schema.
+    pub removed_fields: Vec<String>,
+    /// The fields that became, or are no more, searchable or displayed.
+    pub type_changed_fields: Vec<String>,
+    /// The fields that became, or are no more, ranked or that moved in the searchable attributes.
+    pub rank_changed_fields: Vec<String>,
+}
+
+impl SchemaDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added_fields.is_empty()
+            && self.removed_fields.is_empty()
+            && self.type_changed_fields.is_empty()
+            && self.rank_changed_fields.is_empty()
+    }
+}
+
+pub fn schema_diff(old: &Schema, new: &Schema) -> SchemaDiff {
+    let mut diff = SchemaDiff::default();
+    let names: BTreeSet<_> = old.names().chain(new.names()).collect();
+
+    for name in names {
+        let (old_id, new_id) = match (old.id(name), new.id(name)) {
+            (Some(old_id), Some(new_id)) => (old_id, new_id),
+            (None, _) => {
+                diff.added_fields.push(name.to_string());
+                continue;
+            }
+            (_, None) => {
+                diff.removed_fields.push(name.to_string());
+                continue;
+            }
+        };
+
+        let old_indexed = old.is_indexed(old_id);
+        let new_indexed = new.is_indexed(new_id);
+
+        if old_indexed.is_some() != new_indexed.is_some()
+            || old.is_displayed(old_id) != new.is_displayed(new_id)
+        {
+            diff.type_changed_fields.push(name.to_string());
+        }
+
+        if old.is_ranked(old_id) != new.is_ranked(new_id)
+            || (old_indexed.is_some() && new_indexed.is_some() && old_indexed != new_indexed)
+        {
+            diff.rank_changed_fields.push(name.to_string());
+        }
+    }
+
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_of_searchable_attributes() {
+        let mut old = Schema::with_primary_key("id");
+        old.insert_and_index("title").unwrap();
+        old.insert_and_index("overview").unwrap();
+
+        let mut new = old.clone();
+        new.update_indexed(vec!["overview", "title", "genre"]).unwrap();
+
+        let diff = schema_diff(&old, &new);
+        assert_eq!(diff.added_fields, vec!["genre".to_string()]);
+        assert!(diff.removed_fields.is_empty());
+        assert_eq!(diff.type_changed_fields, vec!["id".to_string()]);
+        assert_eq!(diff.rank_changed_fields, vec!["overview".to_string()]);
+
+        assert!(schema_diff(&old, &old).is_empty());
+    }
+}
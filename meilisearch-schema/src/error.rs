@@ -6,8 +6,10 @@ pub type SResult<T> = Result<T, Error>;
 #[derive(Debug)]
 pub enum Error {
     FieldNameNotFound(String),
+    FieldNameAlreadyPresent(String),
     PrimaryKeyAlreadyPresent,
     MaxFieldsLimitExceeded,
+    TooManyAttributes { current: usize, limit: u16 },
 }
 
 impl fmt::Display for Error {
@@ -15,8 +17,12 @@ impl fmt::Display for Error {
         use self::Error::*;
         match self {
             FieldNameNotFound(field) => write!(f, "The field {:?} doesn't exist", field),
+            FieldNameAlreadyPresent(field) => write!(f, "The field {:?} already exists", field),
             PrimaryKeyAlreadyPresent => write!(f, "The schema already have an primary key. It's impossible to update it"),
             MaxFieldsLimitExceeded => write!(f, "The maximum of possible reattributed field id has been reached"),
+            TooManyAttributes { current, limit } => {
+                write!(f, "The schema can not have more than {} attributes, it already has {}", limit, current)
+            }
         }
     }
 }
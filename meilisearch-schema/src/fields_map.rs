@@ -3,7 +3,7 @@ use std::collections::hash_map::Iter;
 
 use serde::{Deserialize, Serialize};
 
-use crate::{SResult, FieldId};
+use crate::{SResult, FieldId, Error};
 
 #[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct FieldsMap {
@@ -39,6 +39,17 @@ impl FieldsMap {
         self.name_map.remove(name);
     }
 
+    /// Gives a new name to a field, the field keeps its id.
+    pub fn rename(&mut self, old: &str, new: &str) -> SResult<FieldId> {
+        if self.name_map.contains_key(new) {
+            return Err(Error::FieldNameAlreadyPresent(new.to_string()));
+        }
+        let id = self.name_map.remove(old).ok_or_else(|| Error::FieldNameNotFound(old.to_string()))?;
+        self.name_map.insert(new.to_string(), id);
+        self.id_map.insert(id, new.to_string());
+        Ok(id)
+    }
+
     pub fn id(&self, name: &str) -> Option<FieldId> {
         self.name_map.get(name).copied()
     }
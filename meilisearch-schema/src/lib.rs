@@ -1,10 +1,12 @@
+mod diff;
 mod error;
 mod fields_map;
 mod schema;
 
+pub use diff::{schema_diff, SchemaDiff};
 pub use error::{Error, SResult};
 pub use fields_map::FieldsMap;
-pub use schema::Schema;
+pub use schema::{LegacySchema, Schema, DEFAULT_MAX_ATTRIBUTES};
 use serde::{Deserialize, Serialize};
 use zerocopy::{AsBytes, FromBytes};
 
@@ -37,6 +39,15 @@ impl Into<u16> for IndexedPos {
     }
 }
 
+/// The type detected for the values of a field, when they must be
+/// converted before being compared, e.g. the dates are ranked by timestamp.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum FieldType {
+    DateTime,
+    /// The field contains booleans, they are stored as a single byte.
+    Bool,
+}
+
 #[derive(Debug, Copy, Clone, Default, PartialOrd, Ord, PartialEq, Eq, Hash)]
 #[derive(Serialize, Deserialize)]
 #[derive(AsBytes, FromBytes)]
@@ -1,7 +1,15 @@
-use crate::{FieldsMap, FieldId, SResult, Error, IndexedPos};
+use crate::{FieldsMap, FieldId, FieldType, SResult, Error, IndexedPos};
 use serde::{Serialize, Deserialize};
 use std::collections::{HashMap, HashSet};
 
+/// The number of attributes a schema accepts by default, the documents of the schemas with
+/// more attributes than that have very large entries in the documents fields store.
+pub const DEFAULT_MAX_ATTRIBUTES: u16 = 1000;
+
+fn default_max_attributes() -> u16 {
+    DEFAULT_MAX_ATTRIBUTES
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Schema {
     fields_map: FieldsMap,
@@ -14,6 +22,52 @@ pub struct Schema {
     indexed_map: HashMap<FieldId, IndexedPos>,
 
     accept_new_fields: bool,
+
+    #[serde(default)]
+    fields_types: HashMap<FieldId, FieldType>,
+
+    /// The fields stored encrypted, they are never indexed.
+    #[serde(default)]
+    encrypted: HashSet<FieldId>,
+
+    /// The maximum number of attributes, the new attributes are refused once it is reached.
+    #[serde(default = "default_max_attributes")]
+    max_attributes: u16,
+}
+
+/// The schema as stored by the indexes of the previous store versions, it has no fields types,
+/// encrypted fields nor maximum number of attributes. Bincode does not know the names of the
+/// fields, the missing ones can't be defaulted and a `Schema` can't be read from this format.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LegacySchema {
+    fields_map: FieldsMap,
+
+    primary_key: Option<FieldId>,
+    ranked: HashSet<FieldId>,
+    displayed: HashSet<FieldId>,
+
+    indexed: Vec<FieldId>,
+    indexed_map: HashMap<FieldId, IndexedPos>,
+
+    accept_new_fields: bool,
+}
+
+impl From<LegacySchema> for Schema {
+    fn from(schema: LegacySchema) -> Schema {
+        let LegacySchema { fields_map, primary_key, ranked, displayed, indexed, indexed_map, accept_new_fields } = schema;
+        Schema {
+            fields_map,
+            primary_key,
+            ranked,
+            displayed,
+            indexed,
+            indexed_map,
+            accept_new_fields,
+            fields_types: HashMap::new(),
+            encrypted: HashSet::new(),
+            max_attributes: DEFAULT_MAX_ATTRIBUTES,
+        }
+    }
 }
 
 impl Schema {
@@ -26,6 +80,9 @@ impl Schema {
             indexed: Vec::new(),
             indexed_map: HashMap::new(),
             accept_new_fields: true,
+            fields_types: HashMap::new(),
+            encrypted: HashSet::new(),
+            max_attributes: DEFAULT_MAX_ATTRIBUTES,
         }
     }
 
@@ -49,6 +106,9 @@ impl Schema {
             indexed,
             indexed_map,
             accept_new_fields: true,
+            fields_types: HashMap::new(),
+            encrypted: HashSet::new(),
+            max_attributes: DEFAULT_MAX_ATTRIBUTES,
         }
     }
 
@@ -88,6 +148,18 @@ impl Schema {
     }
 
     pub fn insert(&mut self, name: &str) -> SResult<FieldId> {
+        match self.fields_map.id(name) {
+            Some(id) => Ok(id),
+            None => self.new_attribute(name),
+        }
+    }
+
+    fn new_attribute(&mut self, name: &str) -> SResult<FieldId> {
+        let current = self.fields_map.len();
+        let limit = self.max_attributes;
+        if current >= usize::from(limit) {
+            return Err(Error::TooManyAttributes { current, limit });
+        }
         self.fields_map.insert(name)
     }
 
@@ -101,7 +173,7 @@ impl Schema {
                     self.set_indexed(name)?;
                     self.set_displayed(name)
                 } else {
-                    self.fields_map.insert(name)
+                    self.new_attribute(name)
                 }
             }
         }
@@ -123,6 +195,14 @@ impl Schema {
         self.displayed.iter().filter_map(|a| self.name(*a)).collect()
     }
 
+    pub fn encrypted(&self) -> &HashSet<FieldId> {
+        &self.encrypted
+    }
+
+    pub fn encrypted_name(&self) -> HashSet<&str> {
+        self.encrypted.iter().filter_map(|a| self.name(*a)).collect()
+    }
+
     pub fn indexed(&self) -> &Vec<FieldId> {
         &self.indexed
     }
@@ -131,20 +211,46 @@ impl Schema {
         self.indexed.iter().filter_map(|a| self.name(*a)).collect()
     }
 
+    /// Returns the attributes that are indexed, in their indexed position order,
+    /// attributes that are only stored are never part of the matches.
+    pub fn searchable_attributes(&self) -> impl Iterator<Item = FieldId> + '_ {
+        self.indexed.iter().copied()
+    }
+
+    /// Returns a copy of this schema where the attribute has a new name, it keeps its id
+    /// so the documents fields and the matches referring to it don't have to be changed.
+    pub fn rename_attribute(&self, old_name: &str, new_name: &str) -> SResult<Schema> {
+        let mut schema = self.clone();
+        schema.fields_map.rename(old_name, new_name)?;
+        Ok(schema)
+    }
+
     pub fn set_ranked(&mut self, name: &str) -> SResult<FieldId> {
-        let id = self.fields_map.insert(name)?;
+        let id = self.insert(name)?;
         self.ranked.insert(id);
         Ok(id)
     }
 
     pub fn set_displayed(&mut self, name: &str) -> SResult<FieldId> {
-        let id = self.fields_map.insert(name)?;
+        let id = self.insert(name)?;
         self.displayed.insert(id);
         Ok(id)
     }
 
+    /// Marks the field as encrypted, a new field is displayed as the fields
+    /// the documents introduce are but is never indexed.
+    pub fn set_encrypted(&mut self, name: &str) -> SResult<FieldId> {
+        let is_new = !self.contains(name);
+        let id = self.insert(name)?;
+        if is_new && self.accept_new_fields {
+            self.displayed.insert(id);
+        }
+        self.encrypted.insert(id);
+        Ok(id)
+    }
+
     pub fn set_indexed(&mut self, name: &str) -> SResult<(FieldId, IndexedPos)> {
-        let id = self.fields_map.insert(name)?;
+        let id = self.insert(name)?;
         if let Some(indexed_pos) = self.indexed_map.get(&id) {
             return Ok((id, *indexed_pos))
         };
@@ -185,10 +291,22 @@ impl Schema {
         self.displayed.get(&id).is_some()
     }
 
+    pub fn is_encrypted(&self, id: FieldId) -> bool {
+        self.encrypted.contains(&id)
+    }
+
     pub fn is_indexed(&self, id: FieldId) -> Option<&IndexedPos> {
         self.indexed_map.get(&id)
     }
 
+    pub fn field_type(&self, id: FieldId) -> Option<FieldType> {
+        self.fields_types.get(&id).copied()
+    }
+
+    pub fn set_field_type(&mut self, id: FieldId, field_type: FieldType) {
+        self.fields_types.insert(id, field_type);
+    }
+
     pub fn indexed_pos_to_field_id<I: Into<IndexedPos>>(&self, pos: I) -> Option<FieldId> {
         let indexed_pos = pos.into().0 as usize;
         if indexed_pos < self.indexed.len() {
@@ -214,6 +332,18 @@ impl Schema {
         Ok(())
     }
 
+    pub fn update_encrypted<S: AsRef<str>>(&mut self, data: impl IntoIterator<Item = S>) -> SResult<()> {
+        self.encrypted.clear();
+        for name in data {
+            self.set_encrypted(name.as_ref())?;
+        }
+        Ok(())
+    }
+
+    pub fn clear_encrypted(&mut self) {
+        self.encrypted.clear();
+    }
+
     pub fn update_indexed<S: AsRef<str>>(&mut self, data: Vec<S>) -> SResult<()> {
         self.indexed.clear();
         self.indexed_map.clear();
@@ -249,4 +379,60 @@ impl Schema {
     pub fn set_accept_new_fields(&mut self, value: bool) {
         self.accept_new_fields = value;
     }
+
+    pub fn max_attributes(&self) -> u16 {
+        self.max_attributes
+    }
+
+    /// Changes the maximum number of attributes, the attributes already
+    /// present are kept even when there are more of them than the limit.
+    pub fn set_max_attributes(&mut self, limit: u16) {
+        self.max_attributes = limit;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy_schema_converted() {
+        let schema = Schema::with_primary_key("id");
+        let legacy = LegacySchema {
+            fields_map: schema.fields_map.clone(),
+            primary_key: schema.primary_key,
+            ranked: schema.ranked.clone(),
+            displayed: schema.displayed.clone(),
+            indexed: schema.indexed.clone(),
+            indexed_map: schema.indexed_map.clone(),
+            accept_new_fields: schema.accept_new_fields,
+        };
+
+        let bytes = bincode::serialize(&legacy).unwrap();
+        assert!(bincode::deserialize::<Schema>(&bytes).is_err());
+
+        let schema: Schema = bincode::deserialize::<LegacySchema>(&bytes).unwrap().into();
+        assert_eq!(schema.primary_key(), Some("id"));
+        assert_eq!(schema.max_attributes(), DEFAULT_MAX_ATTRIBUTES);
+        assert!(schema.is_displayed(schema.id("id").unwrap()));
+    }
+
+    #[test]
+    fn new_attributes_limited() {
+        let mut schema = Schema::with_primary_key("id");
+        schema.set_max_attributes(3);
+
+        schema.insert_and_index("title").unwrap();
+        schema.set_displayed("description").unwrap();
+
+        match schema.insert_and_index("release_date") {
+            Err(Error::TooManyAttributes { current, limit }) => assert_eq!((current, limit), (3, 3)),
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert!(!schema.contains("release_date"));
+
+        // the attributes already present can still be used
+        assert_eq!(schema.set_ranked("title").unwrap(), 1.into());
+        assert_eq!(schema.max_attributes(), 3);
+    }
 }